@@ -1,5 +1,5 @@
-use crate::utils::validate_url_security;
 use crate::utils::FileOrUrl;
+use crate::utils::{deprecation_marker, validate_url_security};
 use clap::{command, Parser, Subcommand};
 use codec::Decode;
 use color_eyre::eyre::eyre;
@@ -235,10 +235,13 @@ fn pallets_as_string(metadata: &Metadata) -> String {
         format!("There are no {pallet_placeholder}'s available.")
     } else {
         let mut output = format!("Available {pallet_placeholder}'s are:");
-        let mut strings: Vec<_> = metadata.pallets().map(|p| p.name()).collect();
+        let mut strings: Vec<_> = metadata
+            .pallets()
+            .map(|p| (p.name(), deprecation_marker(p.deprecation_info())))
+            .collect();
         strings.sort();
-        for pallet in strings {
-            write!(output, "\n    {}", pallet).unwrap();
+        for (pallet, deprecated) in strings {
+            write!(output, "\n    {}{}", pallet, deprecated).unwrap();
         }
         output
     }
@@ -250,10 +253,13 @@ pub fn runtime_apis_as_string(metadata: &Metadata) -> String {
         format!("There are no {runtime_api_placeholder}'s available.")
     } else {
         let mut output = format!("Available {runtime_api_placeholder}'s are:");
-        let mut strings: Vec<_> = metadata.runtime_api_traits().map(|p| p.name()).collect();
+        let mut strings: Vec<_> = metadata
+            .runtime_api_traits()
+            .map(|p| (p.name(), deprecation_marker(p.deprecation_info())))
+            .collect();
         strings.sort();
-        for api in strings {
-            write!(output, "\n    {}", api).unwrap();
+        for (api, deprecated) in strings {
+            write!(output, "\n    {}{}", api, deprecated).unwrap();
         }
         output
     }
@@ -16,8 +16,8 @@ use subxt::{
 };
 
 use crate::utils::{
-    fields_composite_example, fields_description, parse_string_into_scale_value, Indent,
-    SyntaxHighlight,
+    deprecation_marker, fields_composite_example, fields_description,
+    parse_string_into_scale_value, Indent, SyntaxHighlight,
 };
 
 #[derive(Debug, Clone, Args)]
@@ -40,7 +40,7 @@ pub fn explore_calls(
         get_calls_enum_type(pallet_metadata, metadata.types())?;
 
     let usage = || {
-        let calls = calls_to_string(calls_enum_type_def, pallet_name);
+        let calls = calls_to_string(calls_enum_type_def, pallet_metadata);
         formatdoc! {"
         Usage:
             subxt explore pallet {pallet_name} calls <CALL>
@@ -115,17 +115,32 @@ pub fn explore_calls(
     Ok(())
 }
 
-fn calls_to_string(pallet_calls: &TypeDefVariant<PortableForm>, pallet_name: &str) -> String {
+fn calls_to_string(
+    pallet_calls: &TypeDefVariant<PortableForm>,
+    pallet_metadata: PalletMetadata,
+) -> String {
+    let pallet_name = pallet_metadata.name();
     if pallet_calls.variants.is_empty() {
         return format!("No <CALL>'s available in the \"{pallet_name}\" pallet.");
     }
     let mut output = format!("Available <CALL>'s in the \"{pallet_name}\" pallet:");
 
-    let mut strings: Vec<_> = pallet_calls.variants.iter().map(|c| &c.name).collect();
+    let deprecation_info = pallet_metadata.call_deprecation_info();
+    let mut strings: Vec<_> = pallet_calls
+        .variants
+        .iter()
+        .map(|c| {
+            (
+                &c.name,
+                deprecation_marker(deprecation_info.variant(c.index)),
+            )
+        })
+        .collect();
     strings.sort();
-    for variant in strings {
+    for (variant, deprecated) in strings {
         output.push_str("\n    ");
         output.push_str(variant);
+        output.push_str(&deprecated);
     }
     output
 }
@@ -4,7 +4,9 @@ use indoc::{formatdoc, writedoc};
 use scale_typegen_description::type_description;
 use subxt::metadata::{types::PalletMetadata, Metadata};
 
-use crate::utils::{first_paragraph_of_docs, format_scale_value, Indent, SyntaxHighlight};
+use crate::utils::{
+    deprecation_marker, first_paragraph_of_docs, format_scale_value, Indent, SyntaxHighlight,
+};
 
 #[derive(Debug, Clone, Args)]
 pub struct ConstantsSubcommand {
@@ -86,11 +88,15 @@ fn constants_to_string(pallet_metadata: PalletMetadata, pallet_name: &str) -> St
         return format!("No <CONSTANT>'s available in the \"{pallet_name}\" pallet.");
     }
     let mut output = format!("Available <CONSTANT>'s in the \"{pallet_name}\" pallet:");
-    let mut strings: Vec<_> = pallet_metadata.constants().map(|c| c.name()).collect();
+    let mut strings: Vec<_> = pallet_metadata
+        .constants()
+        .map(|c| (c.name(), deprecation_marker(c.deprecation_info())))
+        .collect();
     strings.sort();
-    for constant in strings {
+    for (constant, deprecated) in strings {
         output.push_str("\n    ");
         output.push_str(constant);
+        output.push_str(&deprecated);
     }
     output
 }
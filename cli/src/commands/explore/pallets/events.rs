@@ -4,7 +4,7 @@ use indoc::{formatdoc, writedoc};
 use scale_info::{form::PortableForm, Variant};
 use subxt::metadata::{types::PalletMetadata, Metadata};
 
-use crate::utils::{fields_description, first_paragraph_of_docs, Indent};
+use crate::utils::{deprecation_marker, fields_description, first_paragraph_of_docs, Indent};
 
 #[derive(Debug, Clone, Args)]
 pub struct EventsSubcommand {
@@ -21,7 +21,7 @@ pub fn explore_events(
     let event_variants = pallet_metadata.event_variants().unwrap_or(&[]);
 
     let usage = || {
-        let events = events_to_string(event_variants, pallet_name);
+        let events = events_to_string(event_variants, pallet_metadata);
         formatdoc! {"
         Usage:
             subxt explore pallet {pallet_name} events <EVENT>
@@ -73,16 +73,30 @@ pub fn explore_events(
     Ok(())
 }
 
-fn events_to_string(event_variants: &[Variant<PortableForm>], pallet_name: &str) -> String {
+fn events_to_string(
+    event_variants: &[Variant<PortableForm>],
+    pallet_metadata: PalletMetadata,
+) -> String {
+    let pallet_name = pallet_metadata.name();
     if event_variants.is_empty() {
         return format!("No <EVENT>'s available in the \"{pallet_name}\" pallet.");
     }
     let mut output = format!("Available <EVENT>'s in the \"{pallet_name}\" pallet:");
-    let mut strings: Vec<_> = event_variants.iter().map(|c| &c.name).collect();
+    let deprecation_info = pallet_metadata.event_deprecation_info();
+    let mut strings: Vec<_> = event_variants
+        .iter()
+        .map(|c| {
+            (
+                &c.name,
+                deprecation_marker(deprecation_info.variant(c.index)),
+            )
+        })
+        .collect();
     strings.sort();
-    for event in strings {
+    for (event, deprecated) in strings {
         output.push_str("\n    ");
         output.push_str(event);
+        output.push_str(&deprecated);
     }
     output
 }
@@ -18,8 +18,8 @@ use subxt::{
 };
 
 use crate::utils::{
-    create_client, first_paragraph_of_docs, parse_string_into_scale_value, type_example, FileOrUrl,
-    Indent, SyntaxHighlight,
+    create_client, deprecation_marker, first_paragraph_of_docs, parse_string_into_scale_value,
+    type_example, FileOrUrl, Indent, SyntaxHighlight,
 };
 
 #[derive(Debug, Clone, Args)]
@@ -211,11 +211,11 @@ fn storage_entries_string(storage_metadata: &StorageMetadata, pallet_name: &str)
         let mut strings: Vec<_> = storage_metadata
             .entries()
             .iter()
-            .map(|s| s.name())
+            .map(|s| (s.name(), deprecation_marker(s.deprecation_info())))
             .collect();
         strings.sort();
-        for entry in strings {
-            write!(output, "\n    {}", entry).unwrap();
+        for (entry, deprecated) in strings {
+            write!(output, "\n    {}{}", entry, deprecated).unwrap();
         }
         output
     }
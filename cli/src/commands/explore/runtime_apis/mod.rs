@@ -1,6 +1,6 @@
 use crate::utils::{
-    create_client, fields_composite_example, fields_description, first_paragraph_of_docs,
-    parse_string_into_scale_value, FileOrUrl, Indent, SyntaxHighlight,
+    create_client, deprecation_marker, fields_composite_example, fields_description,
+    first_paragraph_of_docs, parse_string_into_scale_value, FileOrUrl, Indent, SyntaxHighlight,
 };
 
 use color_eyre::{
@@ -192,11 +192,15 @@ fn methods_to_string(runtime_api_metadata: &RuntimeApiMetadata<'_>) -> String {
     }
 
     let mut output = format!("Available <METHOD>'s available for the \"{api_name}\" runtime api:");
-    let mut strings: Vec<_> = runtime_api_metadata.methods().map(|e| e.name()).collect();
+    let mut strings: Vec<_> = runtime_api_metadata
+        .methods()
+        .map(|e| (e.name(), deprecation_marker(e.deprecation_info())))
+        .collect();
     strings.sort();
-    for variant in strings {
+    for (variant, deprecated) in strings {
         output.push_str("\n    ");
         output.push_str(variant);
+        output.push_str(&deprecated);
     }
     output
 }
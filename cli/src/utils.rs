@@ -12,6 +12,7 @@ use std::fmt::Display;
 use std::str::FromStr;
 use std::{fs, io::Read, path::PathBuf};
 use subxt::{OnlineClient, PolkadotConfig};
+use subxt_metadata::DeprecationStatus;
 
 use scale_value::Value;
 use subxt_codegen::fetch_metadata::{fetch_metadata_from_url, MetadataVersion, Url};
@@ -200,6 +201,18 @@ pub fn first_paragraph_of_docs(docs: &[String]) -> String {
     docs_str
 }
 
+/// A marker to append to deprecated items when listing them, or an empty string
+/// if the item is not deprecated.
+pub fn deprecation_marker(status: &DeprecationStatus) -> String {
+    match status {
+        DeprecationStatus::NotDeprecated => String::new(),
+        DeprecationStatus::DeprecatedWithoutNote => format!(" {}", "(deprecated)".yellow()),
+        DeprecationStatus::Deprecated { note, .. } => {
+            format!(" {}", format!("(deprecated: {note})").yellow())
+        }
+    }
+}
+
 pub trait Indent: ToString {
     fn indent(&self, indent: usize) -> String {
         let indent_str = " ".repeat(indent);
@@ -0,0 +1,71 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use heck::ToUpperCamelCase as _;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use scale_typegen::typegen::ir::ToTokensWithSettings;
+use scale_typegen::TypeGenerator;
+use subxt_metadata::PalletMetadata;
+
+use super::CodegenError;
+
+/// Generate type aliases for the associated types of the provided pallet's config.
+///
+/// The function creates a new module named `associated_types` under the pallet's module.
+/// ```ignore
+/// pub mod PalletName {
+///     pub mod associated_types {
+///         pub type Balance = ::core::primitive::u128;
+///     }
+/// }
+/// ```
+///
+/// Associated types are only present in V16 metadata and later, and so nothing is
+/// generated for older metadata.
+///
+/// # Arguments
+///
+/// - `type_gen` - [`scale_typegen::TypeGenerator`] that contains settings and all types from the runtime metadata.
+/// - `pallet` - Pallet metadata from which the associated types are generated.
+pub fn generate_associated_types(
+    type_gen: &TypeGenerator,
+    pallet: &PalletMetadata,
+) -> Result<TokenStream2, CodegenError> {
+    // Early return if the pallet has no associated types.
+    if pallet.associated_types().len() == 0 {
+        return Ok(quote!());
+    }
+
+    let aliases = pallet
+        .associated_types()
+        .map(|associated_type| {
+            let alias_name = format_ident!("{}", associated_type.name().to_upper_camel_case());
+            let ty = type_gen
+                .resolve_type_path(associated_type.ty())?
+                .to_token_stream(type_gen.settings());
+            let docs = associated_type.docs();
+            let docs = type_gen
+                .settings()
+                .should_gen_docs
+                .then_some(quote! { #( #[doc = #docs ] )* })
+                .unwrap_or_default();
+
+            Ok(quote! {
+                #docs
+                pub type #alias_name = #ty;
+            })
+        })
+        .collect::<Result<Vec<_>, CodegenError>>()?;
+
+    let types_mod_ident = type_gen.types_mod_ident();
+
+    Ok(quote! {
+        pub mod associated_types {
+            use super::#types_mod_ident;
+
+            #( #aliases )*
+        }
+    })
+}
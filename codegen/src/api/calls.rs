@@ -70,6 +70,9 @@ pub fn generate_calls(
             // Propagate the documentation just to `TransactionApi` methods, while
             // draining the documentation of inner call structures.
             let docs = &var.composite.docs;
+            let deprecated = super::generate_deprecated_attr(
+                pallet.call_deprecation_info().variant(var.variant_index),
+            );
 
             // this converts the composite into a full struct type. No Type Parameters needed here.
             let struct_def = type_gen
@@ -78,6 +81,7 @@ pub fn generate_calls(
             let alias_mod = var.type_alias_mod;
             // The call structure's documentation was stripped above.
             let call_struct = quote! {
+                #deprecated
                 #struct_def
                 #alias_mod
 
@@ -89,6 +93,7 @@ pub fn generate_calls(
 
             let client_fn = quote! {
                 #docs
+                #deprecated
                 pub fn #fn_name(
                     &self,
                     #( #call_fn_args, )*
@@ -66,8 +66,11 @@ pub fn generate_constants(
                 .then_some(quote! { #( #[doc = #docs ] )* })
                 .unwrap_or_default();
 
+            let deprecated = super::generate_deprecated_attr(constant.deprecation_info());
+
             Ok(quote! {
                 #docs
+                #deprecated
                 pub fn #fn_name(&self) -> #crate_path::constants::address::StaticAddress<#return_ty> {
                     #crate_path::constants::address::StaticAddress::new_static(
                         #pallet_name,
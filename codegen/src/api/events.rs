@@ -59,7 +59,11 @@ pub fn generate_events(
         let struct_def = type_gen
             .upcast_composite(&var.composite)
             .to_token_stream(type_gen.settings());
+        let deprecated = super::generate_deprecated_attr(
+            pallet.event_deprecation_info().variant(var.variant_index),
+        );
         quote! {
+            #deprecated
             #struct_def
             #alias_mod
 
@@ -4,6 +4,7 @@
 
 //! Generate code for submitting extrinsics and query storage of a Substrate runtime.
 
+mod associated_types;
 mod calls;
 mod constants;
 mod custom_values;
@@ -11,13 +12,14 @@ mod errors;
 mod events;
 mod runtime_apis;
 mod storage;
+mod view_functions;
 
 use scale_typegen::typegen::ir::type_ir::{CompositeFieldIR, CompositeIR, CompositeIRKind};
 use scale_typegen::typegen::ir::ToTokensWithSettings;
 use scale_typegen::typegen::type_params::TypeParameters;
 use scale_typegen::typegen::type_path::TypePath;
 use scale_typegen::TypeGenerator;
-use subxt_metadata::Metadata;
+use subxt_metadata::{DeprecationStatus, Metadata};
 use syn::{parse_quote, Ident};
 
 use crate::error::CodegenError;
@@ -43,7 +45,7 @@ impl RuntimeGenerator {
     ///
     /// Panics if the runtime metadata version is not supported.
     ///
-    /// Supported versions: v14 and v15.
+    /// Supported versions: v14, v15 and v16.
     pub fn new(mut metadata: Metadata) -> Self {
         scale_typegen::utils::ensure_unique_type_paths(metadata.types_mut());
         RuntimeGenerator { metadata }
@@ -171,6 +173,12 @@ impl RuntimeGenerator {
 
                 let errors = errors::generate_error_type_alias(&type_gen, pallet)?;
 
+                let associated_types =
+                    associated_types::generate_associated_types(&type_gen, pallet)?;
+
+                let view_functions_mod =
+                    view_functions::generate_view_functions(&type_gen, pallet, &crate_path)?;
+
                 Ok(quote! {
                     pub mod #mod_name {
                         use super::root_mod;
@@ -180,6 +188,8 @@ impl RuntimeGenerator {
                         #event
                         #storage_mod
                         #constants_mod
+                        #associated_types
+                        #view_functions_mod
                     }
                 })
             })
@@ -207,6 +217,17 @@ impl RuntimeGenerator {
             .filter_map(|(pallet, pallet_mod_name)| pallet.call_ty_id().map(|_| pallet_mod_name))
             .collect();
 
+        let pallets_with_view_functions: Vec<_> = pallets_with_mod_names
+            .iter()
+            .filter_map(|(pallet, pallet_mod_name)| {
+                pallet
+                    .view_functions()
+                    .next()
+                    .is_some()
+                    .then_some(pallet_mod_name)
+            })
+            .collect();
+
         let rust_items = item_mod_ir.rust_items();
 
         let apis_mod = runtime_apis::generate_runtime_apis(
@@ -233,7 +254,7 @@ impl RuntimeGenerator {
 
         Ok(quote! {
             #( #item_mod_attrs )*
-            #[allow(dead_code, unused_imports, non_camel_case_types)]
+            #[allow(dead_code, unused_imports, non_camel_case_types, deprecated)]
             #[allow(clippy::all)]
             #[allow(rustdoc::broken_intra_doc_links)]
             pub mod #mod_ident {
@@ -281,6 +302,10 @@ impl RuntimeGenerator {
                     runtime_apis::RuntimeApi
                 }
 
+                pub fn view_functions() -> ViewFunctionsApi {
+                    ViewFunctionsApi
+                }
+
                 #apis_mod
 
                 pub fn custom() -> CustomValuesApi {
@@ -316,6 +341,15 @@ impl RuntimeGenerator {
                     )*
                 }
 
+                pub struct ViewFunctionsApi;
+                impl ViewFunctionsApi {
+                    #(
+                        pub fn #pallets_with_view_functions(&self) -> #pallets_with_view_functions::view_functions::ViewFunctionsApi {
+                            #pallets_with_view_functions::view_functions::ViewFunctionsApi
+                        }
+                    )*
+                }
+
                 /// check whether the metadata provided is aligned with this statically generated code.
                 pub fn is_codegen_valid_for(metadata: &#crate_path::Metadata) -> bool {
                     let runtime_metadata_hash = metadata
@@ -366,6 +400,7 @@ where
             let type_alias_mod = generate_type_alias_mod(&mut composite, type_gen);
             Ok(StructFromVariant {
                 variant_name: var.name.to_string(),
+                variant_index: var.index,
                 composite,
                 type_alias_mod,
             })
@@ -375,10 +410,27 @@ where
 
 pub struct StructFromVariant {
     variant_name: String,
+    variant_index: u8,
     composite: CompositeIR,
     type_alias_mod: TokenStream2,
 }
 
+/// Generate a `#[deprecated]` attribute for an item with the given deprecation status, so that
+/// using it causes a compiler warning. Nothing is generated if the item is not deprecated.
+pub fn generate_deprecated_attr(status: &DeprecationStatus) -> TokenStream2 {
+    match status {
+        DeprecationStatus::NotDeprecated => quote!(),
+        DeprecationStatus::DeprecatedWithoutNote => quote!( #[deprecated] ),
+        DeprecationStatus::Deprecated {
+            note,
+            since: Some(since),
+        } => quote!( #[deprecated(since = #since, note = #note)] ),
+        DeprecationStatus::Deprecated { note, since: None } => {
+            quote!( #[deprecated(note = #note)] )
+        }
+    }
+}
+
 /// Modifies the composite, by replacing its types with references to the generated type alias module.
 /// Returns the TokenStream of the type alias module.
 ///
@@ -441,3 +493,111 @@ pub fn generate_type_alias_mod(
         #( #aliases )*
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::RuntimeGenerator;
+    use frame_metadata::v16;
+    use quote::quote;
+    use scale_info::{meta_type, TypeInfo};
+    use subxt_metadata::Metadata;
+
+    // V16 metadata doesn't contain the runtime type, and so it is derived from the path of this.
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum RuntimeCall {}
+
+    fn metadata_with_deprecated_items() -> Metadata {
+        let pallet = v16::PalletMetadata {
+            name: "Test",
+            storage: Some(v16::PalletStorageMetadata {
+                prefix: "Test",
+                entries: vec![v16::StorageEntryMetadata {
+                    name: "Value",
+                    modifier: v16::StorageEntryModifier::Optional,
+                    ty: v16::StorageEntryType::Plain(meta_type::<u32>()),
+                    default: vec![],
+                    docs: vec![],
+                    deprecation_info: v16::DeprecationStatus::Deprecated {
+                        note: "use NewValue instead",
+                        since: Some("v2"),
+                    },
+                }],
+            }),
+            calls: None,
+            event: None,
+            constants: vec![],
+            error: None,
+            associated_types: vec![],
+            view_functions: vec![],
+            index: 0,
+            docs: vec![],
+            deprecation_info: v16::DeprecationStatus::NotDeprecated,
+        };
+
+        let runtime_api = v16::RuntimeApiMetadata {
+            name: "TestApi",
+            methods: vec![v16::RuntimeApiMethodMetadata {
+                name: "old_method",
+                inputs: vec![],
+                output: meta_type::<u32>(),
+                docs: vec![],
+                deprecation_info: v16::DeprecationStatus::DeprecatedWithoutNote,
+            }],
+            docs: vec![],
+            version: 1,
+            deprecation_info: v16::DeprecationStatus::NotDeprecated,
+        };
+
+        v16::RuntimeMetadataV16::new(
+            vec![pallet],
+            v16::ExtrinsicMetadata {
+                versions: vec![4],
+                address_ty: meta_type::<()>(),
+                signature_ty: meta_type::<()>(),
+                transaction_extensions_by_version: Default::default(),
+                transaction_extensions: vec![],
+            },
+            vec![runtime_api],
+            v16::OuterEnums {
+                call_enum_ty: meta_type::<RuntimeCall>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            v16::CustomMetadata {
+                map: Default::default(),
+            },
+        )
+        .try_into()
+        .expect("can build valid metadata")
+    }
+
+    #[test]
+    fn deprecated_items_are_marked() {
+        let item_mod = syn::parse_quote!(
+            pub mod api {}
+        );
+        let generated = RuntimeGenerator::new(metadata_with_deprecated_items())
+            .generate_runtime(
+                item_mod,
+                Default::default(),
+                Default::default(),
+                syn::parse_str("::subxt_path").unwrap(),
+                false,
+            )
+            .expect("should be able to generate runtime")
+            .to_string();
+
+        let expected_storage_fn = quote!(
+            #[deprecated(since = "v2", note = "use NewValue instead")]
+            pub fn value(&self,)
+        );
+        assert!(generated.contains(&expected_storage_fn.to_string()));
+
+        let expected_runtime_api_fn = quote!(
+            #[deprecated]
+            pub fn old_method(&self,)
+        );
+        assert!(generated.contains(&expected_runtime_api_fn.to_string()));
+    }
+}
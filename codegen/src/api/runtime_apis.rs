@@ -133,8 +133,11 @@ fn generate_runtime_api(
                 ))
             };
 
+            let deprecated = super::generate_deprecated_attr(method.deprecation_info());
+
             let method = quote!(
                 #docs
+                #deprecated
                 pub fn #method_name(&self, #( #fn_params, )* ) -> #crate_path::runtime_api::payload::StaticPayload<types::#struct_name, types::#method_name::output::Output> {
                     #crate_path::runtime_api::payload::StaticPayload::new_static(
                         #trait_name_str,
@@ -176,7 +179,9 @@ fn generate_runtime_api(
     );
 
     // A getter for the `RuntimeApi` to get the trait structure.
+    let deprecated = super::generate_deprecated_attr(api.deprecation_info());
     let trait_getter = quote!(
+        #deprecated
         pub fn #trait_name_snake(&self) -> #trait_name_snake::#trait_name {
             #trait_name_snake::#trait_name
         }
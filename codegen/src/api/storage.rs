@@ -171,6 +171,8 @@ fn generate_storage_entry_fns(
         .then_some(quote! { #( #[doc = #docs ] )* })
         .unwrap_or_default();
 
+    let deprecated = super::generate_deprecated_attr(storage_entry.deprecation_info());
+
     let is_defaultable_type = match storage_entry.modifier() {
         StorageEntryModifier::Default => quote!(#crate_path::utils::Yes),
         StorageEntryModifier::Optional => quote!(()),
@@ -255,6 +257,7 @@ fn generate_storage_entry_fns(
 
         quote!(
             #docs
+            #deprecated
             pub fn #fn_name(
                 &self,
                 #(#key_args,)*
@@ -0,0 +1,188 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use std::collections::HashSet;
+
+use heck::ToSnakeCase as _;
+use heck::ToUpperCamelCase as _;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use scale_typegen::typegen::ir::ToTokensWithSettings;
+use scale_typegen::TypeGenerator;
+use subxt_metadata::PalletMetadata;
+
+use super::CodegenError;
+
+/// Generate view functions from the provided pallet's metadata.
+///
+/// The function creates a new module named `view_functions` under the pallet's module.
+/// ```ignore
+/// pub mod PalletName {
+///     pub mod view_functions {
+///     ...
+///     }
+/// }
+/// ```
+///
+/// The view functions are exposed via the `ViewFunctionsApi` wrapper. View functions
+/// are only present in V16 metadata and later, and so nothing is generated for older metadata.
+///
+/// # Arguments
+///
+/// - `type_gen` - [`scale_typegen::TypeGenerator`] that contains settings and all types from the runtime metadata.
+/// - `pallet` - Pallet metadata from which the view functions are generated.
+/// - `crate_path` - The crate path under which the `subxt-core` crate is located, e.g. `::subxt::ext::subxt_core` when using subxt as a dependency.
+pub fn generate_view_functions(
+    type_gen: &TypeGenerator,
+    pallet: &PalletMetadata,
+    crate_path: &syn::Path,
+) -> Result<TokenStream2, CodegenError> {
+    // Early return if the pallet has no view functions.
+    if pallet.view_functions().len() == 0 {
+        return Ok(quote!());
+    }
+
+    let types_mod_ident = type_gen.types_mod_ident();
+    let pallet_name = pallet.name();
+
+    let structs_and_fns = pallet
+        .view_functions()
+        .map(|view_function| {
+            let fn_name_str = view_function.name();
+            let fn_name = format_ident!("{}", fn_name_str.to_snake_case());
+
+            let docs = view_function.docs();
+            let docs: TokenStream2 = type_gen
+                .settings()
+                .should_gen_docs
+                .then_some(quote! { #( #[doc = #docs ] )* })
+                .unwrap_or_default();
+
+            let mut unique_names = HashSet::new();
+            let mut unique_aliases = HashSet::new();
+
+            let inputs = view_function
+                .inputs()
+                .enumerate()
+                .map(|(idx, input)| {
+                    // Parameter names can just be '_', but struct field names can't
+                    // just be an underscore, so fix any such names we find to work in structs.
+                    let mut name = input.name.trim_start_matches('_').to_string();
+                    if name.is_empty() {
+                        name = format!("_{}", idx);
+                    }
+                    while !unique_names.insert(name.clone()) {
+                        // Name is already used, append the index until it is unique.
+                        name = format!("{}_param{}", name, idx);
+                    }
+
+                    let mut alias = name.to_upper_camel_case();
+                    // Note: name is not empty.
+                    if alias.as_bytes()[0].is_ascii_digit() {
+                        alias = format!("Param{}", alias);
+                    }
+                    while !unique_aliases.insert(alias.clone()) {
+                        alias = format!("{}Param{}", alias, idx);
+                    }
+
+                    let (alias_name, name) = (format_ident!("{alias}"), format_ident!("{name}"));
+
+                    let ty = type_gen
+                        .resolve_type_path(input.ty)?
+                        .to_token_stream(type_gen.settings());
+                    let aliased_param = quote!( pub type #alias_name = #ty; );
+
+                    // Structures are placed on the same level as the alias module.
+                    let struct_ty_path = quote!( #fn_name::#alias_name );
+                    let struct_param = quote!(#name: #struct_ty_path);
+
+                    // Function parameters must be indented by `types`.
+                    let fn_param = quote!(#name: types::#struct_ty_path);
+                    Ok((fn_param, struct_param, name, aliased_param))
+                })
+                .collect::<Result<Vec<_>, CodegenError>>()?;
+
+            let fn_params = inputs.iter().map(|(fn_param, _, _, _)| fn_param);
+            let struct_params = inputs.iter().map(|(_, struct_param, _, _)| struct_param);
+            let param_names = inputs.iter().map(|(_, _, name, _)| name);
+            let type_aliases = inputs.iter().map(|(_, _, _, aliased_param)| aliased_param);
+
+            let output = type_gen
+                .resolve_type_path(view_function.output_ty())?
+                .to_token_stream(type_gen.settings());
+            let aliased_module = quote!(
+                pub mod #fn_name {
+                    use super::#types_mod_ident;
+
+                    #( #type_aliases )*
+
+                    // Guard the `Output` name against collisions by placing it in a dedicated module.
+                    pub mod output {
+                        use super::#types_mod_ident;
+                        pub type Output = #output;
+                    }
+                }
+            );
+
+            // A structure holding all of the parameters, which is used with metadata
+            // to encode them via `encode_as_fields_to`.
+            let derives = type_gen.settings().derives.default_derives();
+            let struct_name = format_ident!("{}", fn_name_str.to_upper_camel_case());
+            let struct_input = quote!(
+                #aliased_module
+
+                #derives
+                pub struct #struct_name {
+                    #( pub #struct_params, )*
+                }
+            );
+
+            let Some(view_function_hash) = pallet.view_function_hash(fn_name_str) else {
+                return Err(CodegenError::MissingViewFunctionMetadata(
+                    pallet_name.into(),
+                    fn_name_str.into(),
+                ));
+            };
+
+            let deprecated = super::generate_deprecated_attr(view_function.deprecation_info());
+
+            let view_fn = quote!(
+                #docs
+                #deprecated
+                pub fn #fn_name(&self, #( #fn_params, )* ) -> #crate_path::view_functions::payload::StaticPayload<types::#struct_name, types::#fn_name::output::Output> {
+                    #crate_path::view_functions::payload::StaticPayload::new_static(
+                        #pallet_name,
+                        #fn_name_str,
+                        types::#struct_name { #( #param_names, )* },
+                        [#(#view_function_hash,)*],
+                    )
+                }
+            );
+
+            Ok((struct_input, view_fn))
+        })
+        .collect::<Result<Vec<_>, CodegenError>>()?;
+
+    let structs = structs_and_fns.iter().map(|(struct_, _)| struct_);
+    let view_fns = structs_and_fns.iter().map(|(_, view_fn)| view_fn);
+
+    Ok(quote! {
+        pub mod view_functions {
+            use super::root_mod;
+            use super::#types_mod_ident;
+
+            pub struct ViewFunctionsApi;
+
+            impl ViewFunctionsApi {
+                #( #view_fns )*
+            }
+
+            pub mod types {
+                use super::#types_mod_ident;
+
+                #( #structs )*
+            }
+        }
+    })
+}
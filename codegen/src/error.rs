@@ -35,6 +35,9 @@ pub enum CodegenError {
     /// Metadata for call could not be found.
     #[error("Metadata for runtime API entry {0}_{1} could not be found. Make sure you are providing a valid substrate-based metadata")]
     MissingRuntimeApiMetadata(String, String),
+    /// Metadata for view function could not be found.
+    #[error("Metadata for view function {0}_{1} could not be found. Make sure you are providing a valid substrate-based metadata")]
+    MissingViewFunctionMetadata(String, String),
     /// Call variant must have all named fields.
     #[error("Call variant for type {0} must have all named fields. Make sure you are providing a valid substrate-based metadata")]
     InvalidCallVariant(u32),
@@ -8,21 +8,29 @@ use crate::config::signed_extensions::{
 use crate::config::SignedExtension;
 use crate::dynamic::Value;
 use crate::{config::Config, error::Error, Metadata};
+use alloc::vec::Vec;
 use scale_decode::DecodeAsType;
+use subxt_metadata::SignedExtensionMetadata;
 
 /// The signed extensions of an extrinsic.
 #[derive(Debug, Clone)]
 pub struct ExtrinsicSignedExtensions<'a, T: Config> {
     bytes: &'a [u8],
     metadata: &'a Metadata,
+    transaction_extension_version: u8,
     _marker: core::marker::PhantomData<T>,
 }
 
 impl<'a, T: Config> ExtrinsicSignedExtensions<'a, T> {
-    pub(crate) fn new(bytes: &'a [u8], metadata: &'a Metadata) -> Self {
+    pub(crate) fn new(
+        bytes: &'a [u8],
+        metadata: &'a Metadata,
+        transaction_extension_version: u8,
+    ) -> Self {
         Self {
             bytes,
             metadata,
+            transaction_extension_version,
             _marker: core::marker::PhantomData,
         }
     }
@@ -30,7 +38,19 @@ impl<'a, T: Config> ExtrinsicSignedExtensions<'a, T> {
     /// Returns an iterator over each of the signed extension details of the extrinsic.
     /// If the decoding of any signed extension fails, an error item is yielded and the iterator stops.
     pub fn iter(&self) -> impl Iterator<Item = Result<ExtrinsicSignedExtension<T>, Error>> {
-        let signed_extension_types = self.metadata.extrinsic().signed_extensions();
+        let signed_extension_types: Vec<&SignedExtensionMetadata> = match self
+            .metadata
+            .extrinsic()
+            .transaction_extensions_by_version(self.transaction_extension_version)
+        {
+            Some(exts) => exts.collect(),
+            None => self
+                .metadata
+                .extrinsic()
+                .signed_extensions()
+                .iter()
+                .collect(),
+        };
         let num_signed_extensions = signed_extension_types.len();
         let bytes = self.bytes;
         let mut index = 0;
@@ -42,7 +62,7 @@ impl<'a, T: Config> ExtrinsicSignedExtensions<'a, T> {
                 return None;
             }
 
-            let extension = &signed_extension_types[index];
+            let extension = signed_extension_types[index];
             let ty_id = extension.extra_ty();
             let cursor = &mut &bytes[byte_start_idx..];
             if let Err(err) = scale_decode::visitor::decode_with_visitor(
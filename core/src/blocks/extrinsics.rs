@@ -128,8 +128,12 @@ pub struct ExtrinsicDetails<T: Config> {
     index: u32,
     /// Extrinsic bytes.
     bytes: Arc<[u8]>,
+    /// The extrinsic version (4 or 5).
+    version: u8,
     /// Some if the extrinsic payload is signed.
     signed_details: Option<SignedExtrinsicDetails>,
+    /// Some if the extrinsic is a v5 "general" extrinsic.
+    general_details: Option<GeneralExtrinsicDetails>,
     /// The start index in the `bytes` from which the call is encoded.
     call_start_idx: usize,
     /// The pallet index.
@@ -153,6 +157,16 @@ pub struct SignedExtrinsicDetails {
     extra_end_idx: usize,
 }
 
+/// Details only available in v5 "general" extrinsics.
+struct GeneralExtrinsicDetails {
+    /// The version of the transaction extensions used.
+    transaction_extension_version: u8,
+    /// start index of the range in `bytes` of `ExtrinsicDetails` that encodes the transaction extensions.
+    extensions_start_idx: usize,
+    /// end index of the range in `bytes` of `ExtrinsicDetails` that encodes the transaction extensions.
+    extensions_end_idx: usize,
+}
+
 impl<T> ExtrinsicDetails<T>
 where
     T: Config,
@@ -165,25 +179,29 @@ where
         metadata: Metadata,
         ids: ExtrinsicPartTypeIds,
     ) -> Result<ExtrinsicDetails<T>, Error> {
-        const SIGNATURE_MASK: u8 = 0b1000_0000;
-        const VERSION_MASK: u8 = 0b0111_1111;
-        const LATEST_EXTRINSIC_VERSION: u8 = 4;
+        const SIGNED_MASK: u8 = 0b1000_0000;
+        const GENERAL_MASK: u8 = 0b0100_0000;
+        const VERSION_MASK: u8 = 0b0011_1111;
 
         // removing the compact encoded prefix:
         let bytes: Arc<[u8]> = strip_compact_prefix(extrinsic_bytes)?.1.into();
 
         // Extrinsic are encoded in memory in the following way:
-        //   - first byte: abbbbbbb (a = 0 for unsigned, 1 for signed, b = version)
-        //   - signature: [unknown TBD with metadata].
+        //   - first byte: abxxxxxx (a = 1 for signed, b = 1 for general, x = version)
+        //   - v4 signed: signature: [unknown TBD with metadata].
+        //   - v5 general: transaction extension version byte, then the transaction extensions.
         //   - extrinsic data
         let first_byte: u8 = Decode::decode(&mut &bytes[..])?;
 
         let version = first_byte & VERSION_MASK;
-        if version != LATEST_EXTRINSIC_VERSION {
-            return Err(BlockError::UnsupportedVersion(version).into());
-        }
+        let is_signed = first_byte & SIGNED_MASK != 0;
+        let is_general = first_byte & GENERAL_MASK != 0;
 
-        let is_signed = first_byte & SIGNATURE_MASK != 0;
+        // v4 extrinsics are unsigned or signed, and v5 extrinsics are bare or general.
+        match (version, is_signed, is_general) {
+            (4, _, false) | (5, false, _) => {}
+            _ => return Err(BlockError::UnsupportedVersion(version).into()),
+        }
 
         // Skip over the first byte which denotes the version and signing.
         let cursor = &mut &bytes[1..];
@@ -210,13 +228,16 @@ where
                 .map_err(scale_decode::Error::from)?;
                 let signature_end_idx = bytes.len() - cursor.len();
 
-                scale_decode::visitor::decode_with_visitor(
-                    cursor,
-                    ids.extra,
-                    metadata.types(),
-                    scale_decode::visitor::IgnoreVisitor::new(),
-                )
-                .map_err(scale_decode::Error::from)?;
+                // v4 extrinsics always use transaction extension version 0.
+                for extension in metadata.extrinsic().signed_extensions() {
+                    scale_decode::visitor::decode_with_visitor(
+                        cursor,
+                        extension.extra_ty(),
+                        metadata.types(),
+                        scale_decode::visitor::IgnoreVisitor::new(),
+                    )
+                    .map_err(scale_decode::Error::from)?;
+                }
                 let extra_end_idx = bytes.len() - cursor.len();
 
                 Ok(SignedExtrinsicDetails {
@@ -228,6 +249,37 @@ where
             })
             .transpose()?;
 
+        let general_details = is_general
+            .then(|| -> Result<GeneralExtrinsicDetails, Error> {
+                let transaction_extension_version: u8 = Decode::decode(cursor)?;
+                let extensions = metadata
+                    .extrinsic()
+                    .transaction_extensions_by_version(transaction_extension_version)
+                    .ok_or(BlockError::UnsupportedTransactionExtensionVersion(
+                        transaction_extension_version,
+                    ))?;
+
+                // Skip over each of the transaction extensions in turn.
+                let extensions_start_idx = bytes.len() - cursor.len();
+                for extension in extensions {
+                    scale_decode::visitor::decode_with_visitor(
+                        cursor,
+                        extension.extra_ty(),
+                        metadata.types(),
+                        scale_decode::visitor::IgnoreVisitor::new(),
+                    )
+                    .map_err(scale_decode::Error::from)?;
+                }
+                let extensions_end_idx = bytes.len() - cursor.len();
+
+                Ok(GeneralExtrinsicDetails {
+                    transaction_extension_version,
+                    extensions_start_idx,
+                    extensions_end_idx,
+                })
+            })
+            .transpose()?;
+
         let call_start_idx = bytes.len() - cursor.len();
 
         // Decode the pallet index, then the call variant.
@@ -239,7 +291,9 @@ where
         Ok(ExtrinsicDetails {
             index,
             bytes,
+            version,
             signed_details,
+            general_details,
             call_start_idx,
             pallet_index,
             variant_index,
@@ -248,22 +302,57 @@ where
         })
     }
 
-    /// Is the extrinsic signed?
+    /// Is the extrinsic signed? This is only the case for v4 signed extrinsics; v5 "general"
+    /// extrinsics may carry a signature in their transaction extensions instead.
     pub fn is_signed(&self) -> bool {
         self.signed_details.is_some()
     }
 
+    /// Is the extrinsic a v5 "general" extrinsic?
+    pub fn is_general(&self) -> bool {
+        self.general_details.is_some()
+    }
+
+    /// The extrinsic version (4 or 5).
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The version of the transaction extensions used by this extrinsic. This is always 0 for
+    /// v4 signed extrinsics, and is `None` for extrinsics without any transaction extensions.
+    pub fn transaction_extension_version(&self) -> Option<u8> {
+        self.extensions_range().map(|(version, _, _)| version)
+    }
+
+    // The transaction extension version and the range of `bytes` encoding
+    // the transaction extensions, if there are any.
+    fn extensions_range(&self) -> Option<(u8, usize, usize)> {
+        if let Some(signed) = &self.signed_details {
+            return Some((0, signed.signature_end_idx, signed.extra_end_idx));
+        }
+        self.general_details.as_ref().map(|general| {
+            (
+                general.transaction_extension_version,
+                general.extensions_start_idx,
+                general.extensions_end_idx,
+            )
+        })
+    }
+
     /// The index of the extrinsic in the block.
     pub fn index(&self) -> u32 {
         self.index
     }
 
     /// Return _all_ of the bytes representing this extrinsic, which include, in order:
-    /// - First byte: abbbbbbb (a = 0 for unsigned, 1 for signed, b = version)
+    /// - First byte: abxxxxxx (a = 1 for signed, b = 1 for general, x = version)
     /// - SignatureType (if the payload is signed)
     ///   - Address
     ///   - Signature
     ///   - Extra fields
+    /// - Transaction extensions (if the extrinsic is general)
+    ///   - Transaction extension version
+    ///   - Extra fields
     /// - Extrinsic call bytes
     pub fn bytes(&self) -> &[u8] {
         &self.bytes
@@ -316,18 +405,21 @@ where
     /// These bytes are the scale encoded `extra` fields of each signed extension in order of the signed extensions.
     /// They do *not* include the `additional` signed bytes that are used as part of the payload that is signed.
     ///
-    /// Note: Returns `None` if the extrinsic is not signed.
+    /// Note: Returns `None` if the extrinsic is neither signed nor general.
     pub fn signed_extensions_bytes(&self) -> Option<&[u8]> {
-        self.signed_details
-            .as_ref()
-            .map(|e| &self.bytes[e.signature_end_idx..e.extra_end_idx])
+        self.extensions_range()
+            .map(|(_, start, end)| &self.bytes[start..end])
     }
 
-    /// Returns `None` if the extrinsic is not signed.
+    /// Returns `None` if the extrinsic is neither signed nor general.
     pub fn signed_extensions(&self) -> Option<ExtrinsicSignedExtensions<'_, T>> {
-        let signed = self.signed_details.as_ref()?;
-        let extra_bytes = &self.bytes[signed.signature_end_idx..signed.extra_end_idx];
-        Some(ExtrinsicSignedExtensions::new(extra_bytes, &self.metadata))
+        let (version, start, end) = self.extensions_range()?;
+        let extra_bytes = &self.bytes[start..end];
+        Some(ExtrinsicSignedExtensions::new(
+            extra_bytes,
+            &self.metadata,
+            version,
+        ))
     }
 
     /// The index of the pallet that the extrinsic originated from.
@@ -441,8 +533,6 @@ pub struct ExtrinsicPartTypeIds {
     _call: u32,
     /// The signature of the extrinsic.
     signature: u32,
-    /// The extra parameters of the extrinsic.
-    extra: u32,
 }
 
 impl ExtrinsicPartTypeIds {
@@ -452,7 +542,6 @@ impl ExtrinsicPartTypeIds {
             address: metadata.extrinsic().address_ty(),
             _call: metadata.extrinsic().call_ty(),
             signature: metadata.extrinsic().signature_ty(),
-            extra: metadata.extrinsic().extra_ty(),
         })
     }
 }
@@ -547,6 +636,11 @@ mod tests {
 
     /// Build fake metadata consisting the types needed to represent an extrinsic.
     fn metadata() -> Metadata {
+        metadata_with_version(4)
+    }
+
+    /// Build fake metadata as above, supporting the given extrinsic version.
+    fn metadata_with_version(version: u8) -> Metadata {
         let pallets = vec![PalletMetadata {
             name: "Test",
             storage: None,
@@ -561,7 +655,7 @@ mod tests {
         }];
 
         let extrinsic = ExtrinsicMetadata {
-            version: 4,
+            version,
             signed_extensions: vec![],
             address_ty: meta_type::<()>(),
             call_ty: meta_type::<RuntimeCall>(),
@@ -706,4 +800,106 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn decode_v5_bare_extrinsic() {
+        let metadata = metadata_with_version(5);
+        let ids = ExtrinsicPartTypeIds::new(&metadata).unwrap();
+
+        let tx = crate::dynamic::tx(
+            "Test",
+            "TestCall",
+            vec![
+                Value::u128(10),
+                Value::bool(true),
+                Value::string("SomeValue"),
+            ],
+        );
+        let tx_encoded = crate::tx::create_unsigned::<SubstrateConfig, _>(&tx, &metadata)
+            .expect("Valid dynamic parameters are provided");
+
+        let extrinsic = ExtrinsicDetails::<SubstrateConfig>::decode_from(
+            0,
+            tx_encoded.encoded(),
+            metadata,
+            ids,
+        )
+        .expect("Valid extrinsic");
+
+        assert_eq!(extrinsic.bytes()[0], 5);
+        assert_eq!(extrinsic.version(), 5);
+        assert!(!extrinsic.is_signed());
+        assert!(!extrinsic.is_general());
+        assert_eq!(extrinsic.transaction_extension_version(), None);
+        assert!(extrinsic.signed_extensions_bytes().is_none());
+        assert_eq!(extrinsic.variant_name().unwrap(), "TestCall");
+    }
+
+    #[test]
+    fn decode_v5_general_extrinsic() {
+        let metadata = metadata_with_version(5);
+        let ids = ExtrinsicPartTypeIds::new(&metadata).unwrap();
+
+        let tx = crate::dynamic::tx(
+            "Test",
+            "TestCall",
+            vec![
+                Value::u128(10),
+                Value::bool(true),
+                Value::string("SomeValue"),
+            ],
+        );
+        let call_data = crate::tx::call_data(&tx, &metadata).unwrap();
+
+        // "general" + version 5, then transaction extension version 0 (which
+        // has no extensions in our metadata), and then the call data.
+        let mut tx_bytes = vec![0b0100_0101u8, 0];
+        tx_bytes.extend(&call_data);
+
+        let extrinsic =
+            ExtrinsicDetails::<SubstrateConfig>::decode_from(0, &tx_bytes.encode(), metadata, ids)
+                .expect("Valid extrinsic");
+
+        assert_eq!(extrinsic.version(), 5);
+        assert!(!extrinsic.is_signed());
+        assert!(extrinsic.is_general());
+        assert_eq!(extrinsic.transaction_extension_version(), Some(0));
+        assert_eq!(extrinsic.signed_extensions_bytes(), Some(&[][..]));
+        assert_eq!(extrinsic.call_bytes(), &call_data[..]);
+
+        let decoded_extrinsic = extrinsic
+            .as_extrinsic::<TestCallExtrinsic>()
+            .expect("can decode extrinsic to extrinsic variant")
+            .expect("value cannot be None");
+
+        assert_eq!(
+            decoded_extrinsic,
+            TestCallExtrinsic {
+                value: 10,
+                signed: true,
+                name: "SomeValue".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn unsupported_transaction_extension_version() {
+        let metadata = metadata_with_version(5);
+        let ids = ExtrinsicPartTypeIds::new(&metadata).unwrap();
+
+        // A general extrinsic using transaction extension version 1, which isn't in the metadata.
+        let result = ExtrinsicDetails::<SubstrateConfig>::decode_from(
+            0,
+            &vec![0b0100_0101u8, 1].encode(),
+            metadata,
+            ids,
+        );
+
+        assert_matches!(
+            result.err(),
+            Some(crate::Error::Block(
+                crate::error::BlockError::UnsupportedTransactionExtensionVersion(1)
+            ))
+        );
+    }
 }
@@ -2,9 +2,10 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-use super::signed_extensions::CheckNonceParams;
+use super::signed_extensions::{CheckMetadataHashParams, CheckNonceParams};
 use super::{signed_extensions, ExtrinsicParams};
-use super::{Config, Header};
+use super::{Config, Header, NonceManager};
+use alloc::string::String;
 
 /// The default [`super::ExtrinsicParams`] implementation understands common signed extensions
 /// and how to apply them to a given chain.
@@ -19,6 +20,7 @@ pub type DefaultExtrinsicParams<T> = signed_extensions::AnyOf<
         signed_extensions::ChargeAssetTxPayment<T>,
         signed_extensions::ChargeTransactionPayment,
         signed_extensions::CheckMetadataHash,
+        signed_extensions::VerifySignature<T>,
     ),
 >;
 
@@ -28,12 +30,14 @@ pub type DefaultExtrinsicParams<T> = signed_extensions::AnyOf<
 pub struct DefaultExtrinsicParamsBuilder<T: Config> {
     /// `None` means the tx will be immortal.
     mortality: Option<Mortality<T::Hash>>,
-    /// `None` means the nonce will be automatically set.
-    nonce: Option<u64>,
+    /// By default, the nonce will be automatically set.
+    nonce: CheckNonceParams,
     /// `None` means we'll use the native token.
     tip_of_asset_id: Option<T::AssetId>,
     tip: u128,
     tip_of: u128,
+    /// By default, no metadata hash is provided.
+    metadata_hash: CheckMetadataHashParams,
 }
 
 struct Mortality<Hash> {
@@ -53,7 +57,8 @@ impl<T: Config> Default for DefaultExtrinsicParamsBuilder<T> {
             tip: 0,
             tip_of: 0,
             tip_of_asset_id: None,
-            nonce: None,
+            nonce: CheckNonceParams::default(),
+            metadata_hash: CheckMetadataHashParams::disabled(),
         }
     }
 }
@@ -79,7 +84,15 @@ impl<T: Config> DefaultExtrinsicParamsBuilder<T> {
 
     /// Provide a specific nonce for the submitter of the extrinsic
     pub fn nonce(mut self, nonce: u64) -> Self {
-        self.nonce = Some(nonce);
+        self.nonce = CheckNonceParams::from_nonce(nonce);
+        self
+    }
+
+    /// Use the next nonce handed out by the given [`NonceManager`], rather than the account
+    /// nonce from the chain. This allows many transactions from the same account to be
+    /// created before any of them make it into a block.
+    pub fn nonce_manager(mut self, nonce_manager: NonceManager) -> Self {
+        self.nonce = CheckNonceParams::from_nonce_manager(nonce_manager);
         self
     }
 
@@ -121,6 +134,29 @@ impl<T: Config> DefaultExtrinsicParamsBuilder<T> {
         self
     }
 
+    /// Enable metadata hash checking, calculating the metadata hash from the current
+    /// metadata and spec version along with the chain details given here. This is
+    /// not applicable on chains which don't use the `CheckMetadataHash` signed extension.
+    pub fn metadata_hash(
+        mut self,
+        spec_name: impl Into<String>,
+        base58_prefix: u16,
+        decimals: u8,
+        token_symbol: impl Into<String>,
+    ) -> Self {
+        self.metadata_hash =
+            CheckMetadataHashParams::enabled(spec_name, base58_prefix, decimals, token_symbol);
+        self
+    }
+
+    /// Enable metadata hash checking, providing a metadata hash that has already been
+    /// calculated. This is not applicable on chains which don't use the `CheckMetadataHash`
+    /// signed extension.
+    pub fn metadata_hash_of(mut self, hash: [u8; 32]) -> Self {
+        self.metadata_hash = CheckMetadataHashParams::from_hash(hash);
+        self
+    }
+
     /// Build the extrinsic parameters.
     pub fn build(self) -> <DefaultExtrinsicParams<T> as ExtrinsicParams<T>>::Params {
         let check_mortality_params = if let Some(mortality) = self.mortality {
@@ -142,16 +178,15 @@ impl<T: Config> DefaultExtrinsicParamsBuilder<T> {
         let charge_transaction_params =
             signed_extensions::ChargeTransactionPaymentParams::tip(self.tip);
 
-        let check_nonce_params = CheckNonceParams(self.nonce);
-
         (
             (),
             (),
-            check_nonce_params,
+            self.nonce,
             (),
             check_mortality_params,
             charge_asset_tx_params,
             charge_transaction_params,
+            self.metadata_hash,
             (),
         )
     }
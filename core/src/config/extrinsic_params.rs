@@ -39,4 +39,30 @@ pub trait ExtrinsicParamsEncoder: 'static {
     /// sent along with the transaction, but are taken into account when
     /// signing it, meaning the client and node must agree on their values.
     fn encode_additional_to(&self, _v: &mut Vec<u8>) {}
+
+    /// This is expected to SCALE encode the "signed extra" parameters as they
+    /// contribute to the signer payload of a v5 "general" transaction. This is
+    /// the same as [`ExtrinsicParamsEncoder::encode_extra_to()`] by default. Only
+    /// the extensions after `VerifySignature`, which carries the signature, are
+    /// signed, and so this differs for `VerifySignature` and for collections of
+    /// extensions (like [`crate::config::signed_extensions::AnyOf`]) which contain it.
+    fn encode_signer_payload_extra_to(&self, v: &mut Vec<u8>) {
+        self.encode_extra_to(v);
+    }
+
+    /// This is expected to SCALE encode the "additional" parameters as they
+    /// contribute to the signer payload of a v5 "general" transaction. This is the
+    /// same as [`ExtrinsicParamsEncoder::encode_additional_to()`] by default, and
+    /// differs in the same cases as [`ExtrinsicParamsEncoder::encode_signer_payload_extra_to()`].
+    fn encode_signer_payload_additional_to(&self, v: &mut Vec<u8>) {
+        self.encode_additional_to(v);
+    }
+
+    /// This is expected to SCALE encode the "signed extra" parameters of a v5
+    /// "general" transaction, given the SCALE encoded account ID and signature that
+    /// it has been signed with. This is the same as
+    /// [`ExtrinsicParamsEncoder::encode_extra_to()`] by default.
+    fn encode_signed_extra_to(&self, _account_id: &[u8], _signature: &[u8], v: &mut Vec<u8>) {
+        self.encode_extra_to(v);
+    }
 }
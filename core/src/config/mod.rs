@@ -10,6 +10,7 @@
 
 mod default_extrinsic_params;
 mod extrinsic_params;
+mod nonce_manager;
 mod refine_params;
 
 pub mod polkadot;
@@ -17,7 +18,7 @@ pub mod signed_extensions;
 pub mod substrate;
 
 use crate::macros::cfg_substrate_compat;
-use codec::{Decode, Encode};
+use codec::{Compact, Decode, Encode};
 use core::fmt::Debug;
 use scale_decode::DecodeAsType;
 use scale_encode::EncodeAsType;
@@ -25,6 +26,7 @@ use serde::{de::DeserializeOwned, Serialize};
 
 pub use default_extrinsic_params::{DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder};
 pub use extrinsic_params::{ExtrinsicParams, ExtrinsicParamsEncoder};
+pub use nonce_manager::{ManagedNonce, NonceManager};
 pub use polkadot::{PolkadotConfig, PolkadotExtrinsicParams, PolkadotExtrinsicParamsBuilder};
 pub use refine_params::{RefineParams, RefineParamsData};
 pub use signed_extensions::SignedExtension;
@@ -122,6 +124,48 @@ pub trait Header: Sized + Encode + Decode {
     /// Return the block number of this header.
     fn number(&self) -> Self::Number;
 
+    /// Return the hash of the parent block.
+    ///
+    /// The default implementation assumes the standard Substrate header layout, where the
+    /// SCALE encoded header begins with the parent hash. Override this if your header differs.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation panics if the parent hash cannot be decoded from the start
+    /// of the encoded header.
+    fn parent_hash(&self) -> <Self::Hasher as Hasher>::Output
+    where
+        <Self::Hasher as Hasher>::Output: Decode,
+    {
+        let encoded = self.encode();
+        Decode::decode(&mut &*encoded).expect("header should begin with the parent hash")
+    }
+
+    /// Return the root of the state trie for this block, which storage proofs can be
+    /// verified against.
+    ///
+    /// The default implementation assumes the standard Substrate header layout, where the
+    /// SCALE encoded header begins with the parent hash, then the compact encoded block
+    /// number, then the state root. Override this if your header differs.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation panics if the state root cannot be decoded from the
+    /// encoded header.
+    fn state_root(&self) -> <Self::Hasher as Hasher>::Output
+    where
+        <Self::Hasher as Hasher>::Output: Decode,
+    {
+        let encoded = self.encode();
+        let (_parent_hash, _number, state_root) = <(
+            <Self::Hasher as Hasher>::Output,
+            Compact<u64>,
+            <Self::Hasher as Hasher>::Output,
+        )>::decode(&mut &*encoded)
+        .expect("header should begin with the parent hash, block number and state root");
+        state_root
+    }
+
     /// Hash this header.
     fn hash(&self) -> <Self::Hasher as Hasher>::Output {
         Self::Hasher::hash_of(self)
@@ -143,6 +187,14 @@ cfg_substrate_compat! {
             fn number(&self) -> Self::Number {
                 *self.number()
             }
+
+            fn parent_hash(&self) -> <Self::Hasher as Hasher>::Output {
+                *sp_runtime::traits::Header::parent_hash(self)
+            }
+
+            fn state_root(&self) -> <Self::Hasher as Hasher>::Output {
+                *sp_runtime::traits::Header::state_root(self)
+            }
         }
 
         impl<T: sp_runtime::traits::Hash> Hasher for T {
@@ -0,0 +1,159 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Hand out nonces for an account locally, so that many transactions can be created
+//! from it without waiting for each to make it into a block.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+// The value of the next nonce when we need to go back to the account nonce from the chain.
+const RESYNC: u64 = u64::MAX;
+
+/// Hands out increasing nonces for transactions from a single account.
+///
+/// Refining the transaction params normally sets the nonce to the account nonce from the
+/// chain, and so transactions which are created before earlier ones from the same account
+/// make it into a block end up with the same nonce. Instead, a [`NonceManager`] hands out
+/// the account nonce from the chain or one more than the last nonce it handed out, whichever
+/// is higher. Clones of it share the same nonce, and so it can be shared across tasks.
+///
+/// Pass this to [`super::DefaultExtrinsicParamsBuilder::nonce_manager()`] (or to
+/// [`super::signed_extensions::CheckNonceParams::from_nonce_manager()`]) to use it. The
+/// same [`NonceManager`] should only ever be used for transactions from one account.
+///
+/// Nonces which end up unused, for instance because the transaction could not be submitted,
+/// should be given back with [`NonceManager::release()`] so that no gap is left. `subxt`
+/// does this automatically for transactions that it creates.
+#[derive(Debug, Clone)]
+pub struct NonceManager {
+    next_nonce: Arc<AtomicU64>,
+}
+
+impl Default for NonceManager {
+    fn default() -> Self {
+        NonceManager {
+            next_nonce: Arc::new(AtomicU64::new(RESYNC)),
+        }
+    }
+}
+
+impl NonceManager {
+    /// Create a new [`NonceManager`]. The first nonce handed out is the account nonce
+    /// from the chain.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hand out the next nonce, given the account nonce from the chain. This is the higher of
+    /// the account nonce and one more than the last nonce handed out.
+    pub fn next_nonce(&self, account_nonce: u64) -> u64 {
+        let mut next_nonce = self.next_nonce.load(Ordering::Acquire);
+        loop {
+            let nonce = if next_nonce == RESYNC {
+                account_nonce
+            } else {
+                next_nonce.max(account_nonce)
+            };
+            match self.next_nonce.compare_exchange_weak(
+                next_nonce,
+                nonce.saturating_add(1),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return nonce,
+                Err(current) => next_nonce = current,
+            }
+        }
+    }
+
+    /// Give back a nonce which was handed out but won't be used. If it was the last nonce
+    /// handed out, it's handed out again next time. Otherwise, there is now a gap, and so
+    /// the next nonce handed out is the account nonce from the chain again.
+    pub fn release(&self, nonce: u64) {
+        let released = self.next_nonce.compare_exchange(
+            nonce.saturating_add(1),
+            nonce,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+        if released.is_err() {
+            self.resync();
+        }
+    }
+
+    /// Forget the nonces handed out so far, so that the next nonce handed out is the
+    /// account nonce from the chain.
+    pub fn resync(&self) {
+        self.next_nonce.store(RESYNC, Ordering::Release);
+    }
+}
+
+/// A nonce which was handed out by a [`NonceManager`] when refining some params.
+/// See [`super::RefineParams::managed_nonce()`].
+#[derive(Debug, Clone)]
+pub struct ManagedNonce {
+    nonce_manager: NonceManager,
+    nonce: u64,
+}
+
+impl ManagedNonce {
+    /// Create a new [`ManagedNonce`] from a nonce and the [`NonceManager`] that handed it out.
+    pub fn new(nonce_manager: NonceManager, nonce: u64) -> Self {
+        ManagedNonce {
+            nonce_manager,
+            nonce,
+        }
+    }
+
+    /// The nonce that was handed out.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Give the nonce back to the [`NonceManager`] that handed it out, because it won't
+    /// be used. See [`NonceManager::release()`].
+    pub fn release(&self) {
+        self.nonce_manager.release(self.nonce);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_out_increasing_nonces() {
+        let nonce_manager = NonceManager::new();
+        let shared = nonce_manager.clone();
+
+        assert_eq!(nonce_manager.next_nonce(5), 5);
+        assert_eq!(shared.next_nonce(5), 6);
+        assert_eq!(nonce_manager.next_nonce(5), 7);
+
+        // If the chain is ahead (perhaps transactions were sent from elsewhere), we skip ahead.
+        assert_eq!(shared.next_nonce(10), 10);
+        assert_eq!(nonce_manager.next_nonce(5), 11);
+    }
+
+    #[test]
+    fn released_nonces_are_handed_out_again() {
+        let nonce_manager = NonceManager::new();
+        assert_eq!(nonce_manager.next_nonce(5), 5);
+        assert_eq!(nonce_manager.next_nonce(5), 6);
+
+        // The last nonce handed out is simply handed out again.
+        nonce_manager.release(6);
+        assert_eq!(nonce_manager.next_nonce(5), 6);
+        assert_eq!(nonce_manager.next_nonce(5), 7);
+
+        // Releasing an earlier one leaves a gap, and so we go back to the chain.
+        nonce_manager.release(6);
+        assert_eq!(nonce_manager.next_nonce(6), 6);
+        assert_eq!(nonce_manager.next_nonce(6), 7);
+
+        nonce_manager.resync();
+        assert_eq!(nonce_manager.next_nonce(3), 3);
+    }
+}
@@ -4,6 +4,7 @@
 
 //! Refining params with values fetched from the chain
 
+use super::nonce_manager::ManagedNonce;
 use crate::Config;
 
 /// Data that can be used to refine the params of signed extensions.
@@ -45,6 +46,17 @@ pub trait RefineParams<T: Config> {
     /// Refine params to an extrinsic. There is usually some notion of 'the param is already set/unset' in types implementing this trait.
     /// The refinement should most likely not affect cases where a param is in a 'is already set by the user' state.
     fn refine(&mut self, _data: &RefineParamsData<T>) {}
+
+    /// Whether refining these params will hand out a nonce from a [`super::NonceManager`].
+    fn uses_nonce_manager(&self) -> bool {
+        false
+    }
+
+    /// If refining these params handed out a nonce from a [`super::NonceManager`], return it, so
+    /// that it can be given back if the transaction never makes it into a block.
+    fn managed_nonce(&self) -> Option<ManagedNonce> {
+        None
+    }
 }
 
 impl<T: Config> RefineParams<T> for () {}
@@ -57,6 +69,14 @@ macro_rules! impl_tuples {
                 $(self.$index.refine(data);)+
             }
 
+            fn uses_nonce_manager(&self) -> bool {
+                false $(|| self.$index.uses_nonce_manager())+
+            }
+
+            fn managed_nonce(&self) -> Option<ManagedNonce> {
+                None$(.or_else(|| self.$index.managed_nonce()))+
+            }
+
         }
     }
 }
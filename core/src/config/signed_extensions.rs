@@ -8,6 +8,7 @@
 //! when interacting with a chain.
 
 use super::extrinsic_params::ExtrinsicParams;
+use super::nonce_manager::{ManagedNonce, NonceManager};
 use super::refine_params::RefineParamsData;
 use super::RefineParams;
 use crate::client::ClientState;
@@ -17,6 +18,7 @@ use crate::utils::Era;
 use crate::Config;
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::vec::Vec;
 use codec::{Compact, Encode};
 use core::fmt::Debug;
@@ -24,6 +26,7 @@ use derive_where::derive_where;
 use hashbrown::HashMap;
 use scale_decode::DecodeAsType;
 use scale_info::PortableRegistry;
+use subxt_metadata::MetadataDigestInfo;
 
 /// A single [`SignedExtension`] has a unique name, but is otherwise the
 /// same as [`ExtrinsicParams`] in describing how to encode the extra and
@@ -42,27 +45,106 @@ pub trait SignedExtension<T: Config>: ExtrinsicParams<T> {
 
 /// The [`CheckMetadataHash`] signed extension.
 pub struct CheckMetadataHash {
-    // Eventually we might provide or calculate the metadata hash here,
-    // but for now we never provide a hash and so this is empty.
+    /// `None` means that metadata hash checking is disabled.
+    hash: Option<[u8; 32]>,
 }
 
+/// Parameters to configure the [`CheckMetadataHash`] signed extension. By
+/// default, no metadata hash is provided and so checking is disabled.
+#[derive(Debug, Clone, Default)]
+pub struct CheckMetadataHashParams(Option<CheckMetadataHashParamsInner>);
+
+#[derive(Debug, Clone)]
+enum CheckMetadataHashParamsInner {
+    /// Compute the hash from the metadata and these chain details.
+    Compute {
+        spec_name: String,
+        base58_prefix: u16,
+        decimals: u8,
+        token_symbol: String,
+    },
+    /// Use a hash that has already been computed.
+    Hash([u8; 32]),
+}
+
+impl CheckMetadataHashParams {
+    /// Don't provide a metadata hash, which disables metadata hash checking.
+    pub fn disabled() -> Self {
+        CheckMetadataHashParams(None)
+    }
+
+    /// Enable metadata hash checking. The metadata hash is calculated from the
+    /// current metadata and spec version along with the chain details given here.
+    ///
+    /// Calculating the hash involves merkleizing the metadata, which is relatively
+    /// expensive. When submitting many transactions, consider calculating it once via
+    /// [`subxt_metadata::Metadata::merkleize()`] and using [`CheckMetadataHashParams::from_hash()`].
+    pub fn enabled(
+        spec_name: impl Into<String>,
+        base58_prefix: u16,
+        decimals: u8,
+        token_symbol: impl Into<String>,
+    ) -> Self {
+        CheckMetadataHashParams(Some(CheckMetadataHashParamsInner::Compute {
+            spec_name: spec_name.into(),
+            base58_prefix,
+            decimals,
+            token_symbol: token_symbol.into(),
+        }))
+    }
+
+    /// Enable metadata hash checking, providing a metadata hash that has already
+    /// been calculated. The hash must match the current metadata or the transaction
+    /// will be rejected.
+    pub fn from_hash(hash: [u8; 32]) -> Self {
+        CheckMetadataHashParams(Some(CheckMetadataHashParamsInner::Hash(hash)))
+    }
+}
+
+impl<T: Config> RefineParams<T> for CheckMetadataHashParams {}
+
 impl<T: Config> ExtrinsicParams<T> for CheckMetadataHash {
-    type Params = ();
+    type Params = CheckMetadataHashParams;
 
-    fn new(_client: &ClientState<T>, _params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
-        Ok(CheckMetadataHash {})
+    fn new(client: &ClientState<T>, params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
+        let hash = match params.0 {
+            None => None,
+            Some(CheckMetadataHashParamsInner::Hash(hash)) => Some(hash),
+            Some(CheckMetadataHashParamsInner::Compute {
+                spec_name,
+                base58_prefix,
+                decimals,
+                token_symbol,
+            }) => {
+                let merkleized = client
+                    .metadata
+                    .merkleize()
+                    .map_err(|e| ExtrinsicParamsError::Custom(Box::new(e)))?;
+                let digest = merkleized.digest(MetadataDigestInfo {
+                    spec_version: client.runtime_version.spec_version,
+                    spec_name,
+                    base58_prefix,
+                    decimals,
+                    token_symbol,
+                });
+                Some(digest.hash())
+            }
+        };
+
+        Ok(CheckMetadataHash { hash })
     }
 }
 
 impl ExtrinsicParamsEncoder for CheckMetadataHash {
     fn encode_extra_to(&self, v: &mut Vec<u8>) {
-        // A single 0 byte in the TX payload indicates that the chain should
-        // _not_ expect any metadata hash to exist in the signer payload.
-        0u8.encode_to(v);
+        // A single byte in the TX payload indicates whether the chain should
+        // expect a metadata hash to exist in the signer payload.
+        let mode = if self.hash.is_some() { 1u8 } else { 0u8 };
+        mode.encode_to(v);
     }
     fn encode_additional_to(&self, v: &mut Vec<u8>) {
-        // We provide no metadata hash in the signer payload to align with the above.
-        None::<()>.encode_to(v);
+        // The metadata hash (if any) is only provided in the signer payload.
+        self.hash.encode_to(v);
     }
 }
 
@@ -95,6 +177,43 @@ impl CheckMetadataHashMode {
     }
 }
 
+/// The [`VerifySignature`] transaction extension. This carries the signature and the
+/// signing account of v5 "general" transactions. In v4 transactions, the signature is
+/// provided alongside the address instead, and this extension is always disabled.
+pub struct VerifySignature<T: Config>(core::marker::PhantomData<T>);
+
+impl<T: Config> ExtrinsicParams<T> for VerifySignature<T> {
+    type Params = ();
+
+    fn new(_client: &ClientState<T>, _params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
+        Ok(VerifySignature(core::marker::PhantomData))
+    }
+}
+
+impl<T: Config> ExtrinsicParamsEncoder for VerifySignature<T> {
+    fn encode_extra_to(&self, v: &mut Vec<u8>) {
+        // Without a signature, we encode the "Disabled" variant.
+        1u8.encode_to(v);
+    }
+    fn encode_signer_payload_extra_to(&self, _v: &mut Vec<u8>) {
+        // This extension holds the signature, so it isn't part of the signer payload.
+    }
+    fn encode_signer_payload_additional_to(&self, _v: &mut Vec<u8>) {}
+    fn encode_signed_extra_to(&self, account_id: &[u8], signature: &[u8], v: &mut Vec<u8>) {
+        // The "Signed" variant holds the signature followed by the account.
+        0u8.encode_to(v);
+        v.extend_from_slice(signature);
+        v.extend_from_slice(account_id);
+    }
+}
+
+impl<T: Config> SignedExtension<T> for VerifySignature<T> {
+    type Decoded = scale_value::Value;
+    fn matches(identifier: &str, _type_id: u32, _types: &PortableRegistry) -> bool {
+        identifier == "VerifySignature"
+    }
+}
+
 /// The [`CheckSpecVersion`] signed extension.
 pub struct CheckSpecVersion(u32);
 
@@ -127,7 +246,7 @@ impl<T: Config> ExtrinsicParams<T> for CheckNonce {
 
     fn new(_client: &ClientState<T>, params: Self::Params) -> Result<Self, ExtrinsicParamsError> {
         // If no nonce is set (nor by user nor refinement), use a nonce of 0.
-        let nonce = params.0.unwrap_or(0);
+        let nonce = params.nonce.unwrap_or(0);
         Ok(CheckNonce(Compact(nonce)))
     }
 }
@@ -145,16 +264,62 @@ impl<T: Config> SignedExtension<T> for CheckNonce {
     }
 }
 
-/// Params for [`CheckNonce`]
+/// Params for [`CheckNonce`]. By default, the nonce is set to the account nonce from the
+/// chain when the params are refined.
+///
+/// Prior to supporting [`NonceManager`]s, this was a tuple struct wrapping an `Option<u64>`.
+/// Use [`CheckNonceParams::from_nonce()`] in place of `CheckNonceParams(Some(nonce))`,
+/// [`CheckNonceParams::default()`] in place of `CheckNonceParams(None)`, and
+/// [`CheckNonceParams::nonce()`] in place of accessing the inner value.
 #[derive(Debug, Clone, Default)]
-pub struct CheckNonceParams(pub Option<u64>);
+pub struct CheckNonceParams {
+    nonce: Option<u64>,
+    nonce_manager: Option<NonceManager>,
+}
+
+impl CheckNonceParams {
+    /// Use the given nonce.
+    pub fn from_nonce(nonce: u64) -> Self {
+        CheckNonceParams {
+            nonce: Some(nonce),
+            nonce_manager: None,
+        }
+    }
+
+    /// Use the next nonce handed out by the given [`NonceManager`] when the params are refined.
+    pub fn from_nonce_manager(nonce_manager: NonceManager) -> Self {
+        CheckNonceParams {
+            nonce: None,
+            nonce_manager: Some(nonce_manager),
+        }
+    }
+
+    /// The nonce that will be used, if it has been set or the params have been refined.
+    pub fn nonce(&self) -> Option<u64> {
+        self.nonce
+    }
+}
 
 impl<T: Config> RefineParams<T> for CheckNonceParams {
     fn refine(&mut self, data: &RefineParamsData<T>) {
-        if self.0.is_none() {
-            self.0 = Some(data.account_nonce());
+        if self.nonce.is_none() {
+            let nonce = match &self.nonce_manager {
+                Some(nonce_manager) => nonce_manager.next_nonce(data.account_nonce()),
+                None => data.account_nonce(),
+            };
+            self.nonce = Some(nonce);
         }
     }
+
+    fn uses_nonce_manager(&self) -> bool {
+        self.nonce.is_none() && self.nonce_manager.is_some()
+    }
+
+    fn managed_nonce(&self) -> Option<ManagedNonce> {
+        let nonce_manager = self.nonce_manager.clone()?;
+        let nonce = self.nonce?;
+        Some(ManagedNonce::new(nonce_manager, nonce))
+    }
 }
 
 /// The [`CheckTxVersion`] signed extension.
@@ -436,6 +601,9 @@ impl<T: Config> SignedExtension<T> for ChargeTransactionPayment {
 /// is a sensible default, and allows for a single configuration to work across multiple chains.
 pub struct AnyOf<T, Params> {
     params: Vec<Box<dyn ExtrinsicParamsEncoder + Send + 'static>>,
+    // The index of the first of the params which is signed in v5 transactions, which is
+    // the first one after `VerifySignature` (or 0 if it's not present).
+    signer_payload_start: usize,
     _marker: core::marker::PhantomData<(T, Params)>,
 }
 
@@ -458,11 +626,22 @@ macro_rules! impl_tuples {
                 let metadata = &client.metadata;
                 let types = metadata.types();
 
+                // The signed extensions used by the transaction extension version that we'll
+                // build transactions with, in the order that they appear in the extrinsic.
+                let extension_version = crate::tx::transaction_extension_version(metadata);
+                let extensions: Vec<_> = match metadata
+                    .extrinsic()
+                    .transaction_extensions_by_version(extension_version)
+                {
+                    Some(exts) => exts.collect(),
+                    None => metadata.extrinsic().signed_extensions().iter().collect(),
+                };
+
                 // For each signed extension in the tuple, find the matching index in the metadata, if
                 // there is one, and add it to a map with that index as the key.
                 let mut exts_by_index = HashMap::new();
                 $({
-                    for (idx, e) in metadata.extrinsic().signed_extensions().iter().enumerate() {
+                    for (idx, e) in extensions.iter().enumerate() {
                         // Skip over any exts that have a match already:
                         if exts_by_index.contains_key(&idx) {
                             continue
@@ -479,7 +658,8 @@ macro_rules! impl_tuples {
 
                 // Next, turn these into an ordered vec, erroring if we haven't matched on any exts yet.
                 let mut params = Vec::new();
-                for (idx, e) in metadata.extrinsic().signed_extensions().iter().enumerate() {
+                let mut signer_payload_start = 0;
+                for (idx, e) in extensions.iter().enumerate() {
                     let Some(ext) = exts_by_index.remove(&idx) else {
                         if is_type_empty(e.extra_ty(), types) {
                             continue
@@ -488,10 +668,14 @@ macro_rules! impl_tuples {
                         }
                     };
                     params.push(ext);
+                    if e.identifier() == "VerifySignature" {
+                        signer_payload_start = params.len();
+                    }
                 }
 
                 Ok(AnyOf {
                     params,
+                    signer_payload_start,
                     _marker: core::marker::PhantomData
                 })
             }
@@ -512,6 +696,21 @@ macro_rules! impl_tuples {
                     ext.encode_additional_to(v);
                 }
             }
+            fn encode_signer_payload_extra_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params[self.signer_payload_start..] {
+                    ext.encode_signer_payload_extra_to(v);
+                }
+            }
+            fn encode_signer_payload_additional_to(&self, v: &mut Vec<u8>) {
+                for ext in &self.params[self.signer_payload_start..] {
+                    ext.encode_signer_payload_additional_to(v);
+                }
+            }
+            fn encode_signed_extra_to(&self, account_id: &[u8], signature: &[u8], v: &mut Vec<u8>) {
+                for ext in &self.params {
+                    ext.encode_signed_extra_to(account_id, signature, v);
+                }
+            }
         }
     }
 }
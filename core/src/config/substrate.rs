@@ -77,6 +77,7 @@ impl<N, H> Header for SubstrateHeader<N, H>
 where
     N: Copy + Into<u64> + Into<U256> + TryFrom<U256> + Encode,
     H: Hasher + Encode,
+    H::Output: Clone,
     SubstrateHeader<N, H>: Encode + Decode,
 {
     type Number = N;
@@ -84,6 +85,12 @@ where
     fn number(&self) -> Self::Number {
         self.number
     }
+    fn parent_hash(&self) -> H::Output {
+        self.parent_hash.clone()
+    }
+    fn state_root(&self) -> H::Output {
+        self.state_root.clone()
+    }
 }
 
 /// Generic header digest. From `sp_runtime::generic::digest`.
@@ -337,4 +344,31 @@ mod test {
             serde_json::from_str(numeric_block_number_json).expect("valid block header");
         assert_eq!(header.number(), 4);
     }
+
+    // Headers which don't override `parent_hash` and `state_root` fall back to
+    // decoding them from the standard Substrate header layout.
+    #[test]
+    fn default_header_methods_decode_substrate_layout() {
+        #[derive(Encode, Decode)]
+        struct DefaultHeader(SubstrateHeader<u32, BlakeTwo256>);
+
+        impl Header for DefaultHeader {
+            type Number = u32;
+            type Hasher = BlakeTwo256;
+            fn number(&self) -> Self::Number {
+                self.0.number
+            }
+        }
+
+        let header = DefaultHeader(SubstrateHeader {
+            parent_hash: H256::repeat_byte(1),
+            number: 1_000_000,
+            state_root: H256::repeat_byte(2),
+            extrinsics_root: H256::repeat_byte(3),
+            digest: Digest::default(),
+        });
+
+        assert_eq!(header.parent_hash(), H256::repeat_byte(1));
+        assert_eq!(header.state_root(), H256::repeat_byte(2));
+    }
 }
@@ -19,6 +19,8 @@ pub enum Error {
     Metadata(MetadataError),
     /// Storage address error.
     StorageAddress(StorageAddressError),
+    /// Error verifying a storage proof.
+    StorageProof(StorageProofError),
     /// Error decoding to a [`crate::dynamic::Value`].
     Decode(scale_decode::Error),
     /// Error encoding from a [`crate::dynamic::Value`].
@@ -27,6 +29,11 @@ pub enum Error {
     ExtrinsicParams(ExtrinsicParamsError),
     /// Block body error.
     Block(BlockError),
+    /// Error returned from a pallet view function call.
+    ViewFunction(ViewFunctionError),
+    /// A v5 transaction was signed with an address. These must be signed with an
+    /// account ID instead.
+    SignedWithAddress,
 }
 
 impl core::fmt::Display for Error {
@@ -35,10 +42,16 @@ impl core::fmt::Display for Error {
             Error::Codec(e) => write!(f, "Scale codec error: {e}"),
             Error::Metadata(e) => write!(f, "Metadata Error: {e}"),
             Error::StorageAddress(e) => write!(f, "Storage Error: {e}"),
+            Error::StorageProof(e) => write!(f, "Storage proof error: {e}"),
             Error::Decode(e) => write!(f, "Error decoding into dynamic value: {e}"),
             Error::Encode(e) => write!(f, "Error encoding from dynamic value: {e}"),
             Error::ExtrinsicParams(e) => write!(f, "Extrinsic params error: {e}"),
             Error::Block(e) => write!(f, "Error working with block_body: {}", e),
+            Error::ViewFunction(e) => write!(f, "View function error: {e}"),
+            Error::SignedWithAddress => write!(
+                f,
+                "v5 transactions must be signed with an account ID rather than an address"
+            ),
         }
     }
 }
@@ -48,11 +61,13 @@ impl std::error::Error for Error {}
 
 impl_from!(ExtrinsicParamsError => Error::ExtrinsicParams);
 impl_from!(BlockError => Error::Block);
+impl_from!(ViewFunctionError => Error::ViewFunction);
 impl_from!(MetadataError => Error::Metadata);
 impl_from!(scale_decode::Error => Error::Decode);
 impl_from!(scale_decode::visitor::DecodeError => Error::Decode);
 impl_from!(scale_encode::Error => Error::Encode);
 impl_from!(StorageAddressError => Error::StorageAddress);
+impl_from!(StorageProofError => Error::StorageProof);
 impl_from!(codec::Error => Error::Codec);
 
 /// Block error
@@ -63,6 +78,8 @@ pub enum BlockError {
     /// Unsupported signature.
     /// The extrinsic has an unsupported version.
     UnsupportedVersion(u8),
+    /// The extrinsic uses a transaction extension version that is not in the metadata.
+    UnsupportedTransactionExtensionVersion(u8),
     /// Decoding error.
     DecodingError(codec::Error),
 }
@@ -71,7 +88,8 @@ impl Display for BlockError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             BlockError::MissingType => write!(f, "Extrinsic type ID cannot be resolved with the provided metadata. Make sure this is a valid metadata"),
-            BlockError::UnsupportedVersion(_) => write!(f, "Unsupported extrinsic version, only version 4 is supported currently"),
+            BlockError::UnsupportedVersion(_) => write!(f, "Unsupported extrinsic version, only versions 4 and 5 are supported currently"),
+            BlockError::UnsupportedTransactionExtensionVersion(v) => write!(f, "Unsupported transaction extension version {v}, which is not in the metadata"),
             BlockError::DecodingError(e) => write!(f, "Cannot decode extrinsic: {e}"),
         }
     }
@@ -102,6 +120,8 @@ pub enum MetadataError {
     RuntimeTraitNotFound(String),
     /// Runtime method not found.
     RuntimeMethodNotFound(String),
+    /// View function not found.
+    ViewFunctionNotFound(String),
     /// Call type not found in metadata.
     CallTypeNotFoundInPallet(u8),
     /// Event type not found in metadata.
@@ -133,6 +153,9 @@ impl Display for MetadataError {
             MetadataError::RuntimeMethodNotFound(e) => {
                 write!(f, "Runtime method with name {e} not found")
             }
+            MetadataError::ViewFunctionNotFound(e) => {
+                write!(f, "View function with name {e} not found")
+            }
             MetadataError::CallTypeNotFoundInPallet(e) => {
                 write!(f, "Call type not found in pallet with index {e}")
             }
@@ -156,6 +179,36 @@ impl Display for MetadataError {
 #[cfg(feature = "std")]
 impl std::error::Error for MetadataError {}
 
+/// The error returned by the runtime when a pallet view function cannot be executed.
+#[derive(Clone, Debug, Eq, PartialEq, codec::Decode, codec::Encode)]
+pub enum ViewFunctionError {
+    /// View functions are not implemented for this runtime.
+    NotImplemented,
+    /// A view function with the given ID was not found.
+    NotFound([u8; 32]),
+    /// Failed to decode the view function input.
+    Codec,
+}
+
+impl Display for ViewFunctionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ViewFunctionError::NotImplemented => {
+                write!(f, "View functions are not implemented by the runtime")
+            }
+            ViewFunctionError::NotFound(id) => {
+                write!(f, "View function with ID 0x{} not found", hex::encode(id))
+            }
+            ViewFunctionError::Codec => {
+                write!(f, "The runtime could not decode the view function input")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ViewFunctionError {}
+
 /// Something went wrong trying to encode or decode a storage address.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -221,6 +274,34 @@ impl Display for StorageAddressError {
 #[cfg(feature = "std")]
 impl std::error::Error for StorageAddressError {}
 
+/// Something went wrong trying to verify a storage proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum StorageProofError {
+    /// The proof does not contain a node that is needed to look up the key,
+    /// starting from the state root.
+    IncompleteProof,
+    /// One of the nodes in the proof could not be decoded.
+    InvalidNode,
+}
+
+impl Display for StorageProofError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StorageProofError::IncompleteProof => write!(
+                f,
+                "The storage proof does not contain all of the trie nodes needed to look up the key from the state root"
+            ),
+            StorageProofError::InvalidNode => {
+                write!(f, "The storage proof contains a trie node that could not be decoded")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StorageProofError {}
+
 /// An error that can be emitted when trying to construct an instance of [`crate::config::ExtrinsicParams`],
 /// encode data from the instance, or match on signed extensions.
 #[derive(Debug)]
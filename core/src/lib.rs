@@ -17,6 +17,7 @@
 //! - [`tx`]: construct and sign transactions (extrinsics).
 //! - [`runtime_api`]: construct runtime API request payloads and decode the results you'd get back.
 //! - [`events`]: decode and explore events.
+//! - [`view_functions`]: construct pallet view function payloads and decode the results you'd get back.
 //!
 
 #![deny(missing_docs)]
@@ -39,6 +40,7 @@ pub mod runtime_api;
 pub mod storage;
 pub mod tx;
 pub mod utils;
+pub mod view_functions;
 
 pub use config::Config;
 pub use error::Error;
@@ -2,7 +2,8 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-//! Encode storage keys, decode storage values, and validate static storage addresses.
+//! Encode storage keys, decode storage values, validate static storage addresses, and verify
+//! storage proofs.
 //!
 //! # Example
 //!
@@ -45,6 +46,7 @@ mod storage_key;
 mod utils;
 
 pub mod address;
+pub mod proof;
 
 use crate::{error::MetadataError, metadata::DecodeWithMetadata, Error, Metadata};
 use address::Address;
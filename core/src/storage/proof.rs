@@ -0,0 +1,308 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Verify storage proofs against the state root of a block.
+//!
+//! A storage proof (such as the one returned from the `state_getReadProof` RPC method) is a set of
+//! encoded trie nodes. Starting from the state root found in a block header, we can follow these
+//! nodes to find the value at some storage key, or to show that no value exists at that key. Since
+//! each node is referenced by its hash, the proof can be obtained from an untrusted source.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use subxt_core::storage;
+//!
+//! // Given a block header, a proof for some storage address, and the metadata at that block:
+//! let value = storage::proof::verify::<PolkadotConfig, _>(
+//!     &address,
+//!     header.state_root,
+//!     &proof,
+//!     &metadata,
+//! )?;
+//!
+//! match value {
+//!     Some(value) => println!("Value: {value:?}"),
+//!     None => println!("No value exists at this address"),
+//! }
+//! ```
+
+use super::address::Address;
+use crate::config::{Config, Hasher};
+use crate::error::{Error, StorageProofError};
+use crate::Metadata;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use codec::{Compact, Decode};
+
+/// Verify a storage proof for the given storage `address` against the `state_root` of some block,
+/// and decode the value that it proves to exist. Returns `Ok(None)` if the proof shows that no value
+/// exists at the address; the default value for the address is not returned in this case.
+///
+/// The metadata is expected to be the metadata at the block that the state root is from.
+pub fn verify<T: Config, Addr: Address>(
+    address: &Addr,
+    state_root: T::Hash,
+    proof: &[impl AsRef<[u8]>],
+    metadata: &Metadata,
+) -> Result<Option<Addr::Target>, Error> {
+    let key = super::get_address_bytes(address, metadata)?;
+    let Some(value_bytes) = verify_raw::<T>(state_root, proof, &key)? else {
+        return Ok(None);
+    };
+    let value = super::decode_value(&mut &*value_bytes, address, metadata)?;
+    Ok(Some(value))
+}
+
+/// Verify a storage proof for the given storage `key` against the `state_root` of some block, returning
+/// the bytes of the value that it proves to exist, or `Ok(None)` if the proof shows that no value exists
+/// at the key.
+pub fn verify_raw<T: Config>(
+    state_root: T::Hash,
+    proof: &[impl AsRef<[u8]>],
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, StorageProofError> {
+    // Every node (and hashed value) in the proof is referenced by its hash.
+    let hashes: Vec<T::Hash> = proof.iter().map(|n| T::Hasher::hash(n.as_ref())).collect();
+    let nodes: BTreeMap<&[u8], &[u8]> = hashes
+        .iter()
+        .zip(proof)
+        .map(|(hash, node)| (hash.as_ref(), node.as_ref()))
+        .collect();
+    let get_node = |hash: &[u8]| {
+        nodes
+            .get(hash)
+            .copied()
+            .ok_or(StorageProofError::IncompleteProof)
+    };
+
+    let hash_len = state_root.as_ref().len();
+    let key_nibbles: Vec<u8> = key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect();
+    let mut key_nibbles = &key_nibbles[..];
+    let mut node_bytes = get_node(state_root.as_ref())?;
+
+    loop {
+        let node = Node::decode(node_bytes, hash_len)?;
+
+        // The partial key of the node must match the next nibbles in our key,
+        // else the key is not in the trie.
+        let Some(remaining_nibbles) = key_nibbles.strip_prefix(&node.partial_key[..]) else {
+            return Ok(None);
+        };
+
+        // We've found the node for our key; return its value if it has one.
+        let Some((&next_nibble, remaining_nibbles)) = remaining_nibbles.split_first() else {
+            let value = match node.value {
+                None => None,
+                Some(Value::Inline(value)) => Some(value.to_vec()),
+                Some(Value::Hashed(hash)) => Some(get_node(hash)?.to_vec()),
+            };
+            return Ok(value);
+        };
+
+        // Else, descend into the child that the next nibble of the key points to.
+        node_bytes = match node.children[next_nibble as usize] {
+            None => return Ok(None),
+            Some(Child::Inline(child)) => child,
+            Some(Child::Hashed(hash)) => get_node(hash)?,
+        };
+        key_nibbles = remaining_nibbles;
+    }
+}
+
+/// A decoded trie node. Leaves are nodes without any children.
+struct Node<'a> {
+    partial_key: Vec<u8>,
+    value: Option<Value<'a>>,
+    children: [Option<Child<'a>>; 16],
+}
+
+enum Value<'a> {
+    Inline(&'a [u8]),
+    Hashed(&'a [u8]),
+}
+
+enum Child<'a> {
+    Inline(&'a [u8]),
+    Hashed(&'a [u8]),
+}
+
+impl<'a> Node<'a> {
+    /// Decode a node encoded in the format used by Substrate, given the length of
+    /// the hashes used to reference other nodes and values.
+    fn decode(bytes: &'a [u8], hash_len: usize) -> Result<Self, StorageProofError> {
+        let input = &mut &*bytes;
+        let first_byte = take(input, 1)?[0];
+
+        // The first byte tells us the type of node, as well as the number of nibbles
+        // in the partial key (which may overflow into the following bytes).
+        let (is_branch, value_kind, nibble_count) = match first_byte >> 6 {
+            0b01 => (false, ValueKind::Inline, decode_size(first_byte, input, 2)?),
+            0b10 => (true, ValueKind::None, decode_size(first_byte, input, 2)?),
+            0b11 => (true, ValueKind::Inline, decode_size(first_byte, input, 2)?),
+            _ if first_byte >> 5 == 0b001 => {
+                (false, ValueKind::Hashed, decode_size(first_byte, input, 3)?)
+            }
+            _ if first_byte >> 4 == 0b0001 => {
+                (true, ValueKind::Hashed, decode_size(first_byte, input, 4)?)
+            }
+            // The empty trie; no key exists in it.
+            _ if first_byte == 0 => {
+                return Ok(Node {
+                    partial_key: Vec::new(),
+                    value: None,
+                    children: Default::default(),
+                })
+            }
+            _ => return Err(StorageProofError::InvalidNode),
+        };
+
+        // The partial key is stored two nibbles per byte. If there are an odd number
+        // of nibbles then the first byte is padded, and its high nibble must be 0.
+        let partial_key_bytes = take(input, nibble_count.div_ceil(2))?;
+        let mut partial_key: Vec<u8> = partial_key_bytes
+            .iter()
+            .flat_map(|b| [b >> 4, b & 0x0f])
+            .collect();
+        if nibble_count % 2 == 1 {
+            if partial_key[0] != 0 {
+                return Err(StorageProofError::InvalidNode);
+            }
+            partial_key.remove(0);
+        }
+
+        // Branches have a bitmap denoting which of their children are present.
+        let children_bitmap = if is_branch {
+            u16::decode(input).map_err(|_| StorageProofError::InvalidNode)?
+        } else {
+            0
+        };
+
+        let value = match value_kind {
+            ValueKind::None => None,
+            ValueKind::Inline => {
+                let len = decode_len(input)?;
+                Some(Value::Inline(take(input, len)?))
+            }
+            ValueKind::Hashed => Some(Value::Hashed(take(input, hash_len)?)),
+        };
+
+        // Children that encode to fewer bytes than a hash are inlined.
+        let mut children: [Option<Child>; 16] = Default::default();
+        for (idx, child) in children.iter_mut().enumerate() {
+            if children_bitmap & (1 << idx) == 0 {
+                continue;
+            }
+            let len = decode_len(input)?;
+            let child_bytes = take(input, len)?;
+            *child = Some(if len == hash_len {
+                Child::Hashed(child_bytes)
+            } else {
+                Child::Inline(child_bytes)
+            });
+        }
+
+        if !input.is_empty() {
+            return Err(StorageProofError::InvalidNode);
+        }
+
+        Ok(Node {
+            partial_key,
+            value,
+            children,
+        })
+    }
+}
+
+enum ValueKind {
+    None,
+    Inline,
+    Hashed,
+}
+
+/// Decode the number of nibbles in a partial key. This is stored in the bits of the first
+/// byte that aren't used to denote the node type, overflowing into following bytes if needed.
+fn decode_size(
+    first_byte: u8,
+    input: &mut &[u8],
+    prefix_bits: usize,
+) -> Result<usize, StorageProofError> {
+    let max_value = 255u8 >> prefix_bits;
+    let mut size = (first_byte & max_value) as usize;
+    if size < max_value as usize {
+        return Ok(size);
+    }
+    size -= 1;
+    loop {
+        let next = take(input, 1)?[0] as usize;
+        if next < 255 {
+            return Ok(size + next + 1);
+        }
+        size += 255;
+    }
+}
+
+/// Decode a compact encoded length.
+fn decode_len(input: &mut &[u8]) -> Result<usize, StorageProofError> {
+    let len = Compact::<u32>::decode(input).map_err(|_| StorageProofError::InvalidNode)?;
+    Ok(len.0 as usize)
+}
+
+/// Take the given number of bytes from the input.
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], StorageProofError> {
+    if input.len() < len {
+        return Err(StorageProofError::InvalidNode);
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SubstrateConfig;
+    use crate::utils::H256;
+    use alloc::vec;
+
+    // A trie containing just the key 0x12 with the value 0x2a, which
+    // is a single leaf node whose partial key is the whole key.
+    fn leaf_proof() -> (H256, Vec<Vec<u8>>) {
+        let leaf = vec![0b01_000010, 0x12, 0x04, 0x2a];
+        let root = sp_crypto_hashing::blake2_256(&leaf).into();
+        (root, vec![leaf])
+    }
+
+    #[test]
+    fn verifies_present_value() {
+        let (root, proof) = leaf_proof();
+        let value = verify_raw::<SubstrateConfig>(root, &proof, &[0x12]).unwrap();
+        assert_eq!(value, Some(vec![0x2a]));
+    }
+
+    #[test]
+    fn verifies_absent_value() {
+        let (root, proof) = leaf_proof();
+        let value = verify_raw::<SubstrateConfig>(root, &proof, &[0x13]).unwrap();
+        assert_eq!(value, None);
+        let value = verify_raw::<SubstrateConfig>(root, &proof, &[0x12, 0x00]).unwrap();
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn rejects_proof_for_other_root() {
+        let (_, proof) = leaf_proof();
+        let err = verify_raw::<SubstrateConfig>(H256::zero(), &proof, &[0x12]).unwrap_err();
+        assert_eq!(err, StorageProofError::IncompleteProof);
+    }
+
+    #[test]
+    fn rejects_invalid_node() {
+        // The leaf claims a one byte value but none follows.
+        let leaf = vec![0b01_000010, 0x12, 0x04];
+        let root = sp_crypto_hashing::blake2_256(&leaf).into();
+        let err = verify_raw::<SubstrateConfig>(root, &[leaf], &[0x12]).unwrap_err();
+        assert_eq!(err, StorageProofError::InvalidNode);
+    }
+}
@@ -67,6 +67,7 @@ use codec::{Compact, Encode};
 use payload::Payload;
 use signer::Signer as SignerT;
 use sp_crypto_hashing::blake2_256;
+use subxt_metadata::{ExtrinsicProof, MerkleizeError, MerkleizedMetadata, MetadataDigestInfo};
 
 // Expose these here since we expect them in some calls below.
 pub use crate::client::{ClientState, RuntimeVersion};
@@ -89,6 +90,39 @@ pub fn validate<Call: Payload>(call: &Call, metadata: &Metadata) -> Result<(), E
     Ok(())
 }
 
+/// The extrinsic version that transactions are built with. This is v5 if the runtime
+/// supports it and the latest transaction extensions include `VerifySignature`, which
+/// carries the signature of v5 transactions, and v4 otherwise.
+pub(crate) fn extrinsic_version(metadata: &Metadata) -> u8 {
+    let extrinsic = metadata.extrinsic();
+    let verifies_signature = extrinsic
+        .transaction_extensions_by_version(latest_transaction_extension_version(metadata))
+        .is_some_and(|mut exts| exts.any(|ext| ext.identifier() == "VerifySignature"));
+
+    if extrinsic.highest_version() >= 5 && verifies_signature {
+        5
+    } else {
+        4
+    }
+}
+
+/// The transaction extension version that transactions are built with. v4 transactions
+/// always use version 0, and v5 transactions use the latest version that the runtime supports.
+pub(crate) fn transaction_extension_version(metadata: &Metadata) -> u8 {
+    if extrinsic_version(metadata) == 4 {
+        return 0;
+    }
+    latest_transaction_extension_version(metadata)
+}
+
+fn latest_transaction_extension_version(metadata: &Metadata) -> u8 {
+    metadata
+        .extrinsic()
+        .transaction_extension_versions()
+        .max()
+        .unwrap_or(0)
+}
+
 /// Return the SCALE encoded bytes representing the call data of the transaction.
 pub fn call_data<Call: Payload>(call: &Call, metadata: &Metadata) -> Result<Vec<u8>, Error> {
     let mut bytes = Vec::new();
@@ -96,7 +130,8 @@ pub fn call_data<Call: Payload>(call: &Call, metadata: &Metadata) -> Result<Vec<
     Ok(bytes)
 }
 
-/// Creates an unsigned extrinsic without submitting it.
+/// Creates an unsigned extrinsic without submitting it. If the runtime supports v5 extrinsics,
+/// this is a v5 "bare" extrinsic, and otherwise it is a v4 unsigned one.
 pub fn create_unsigned<T: Config, Call: Payload>(
     call: &Call,
     metadata: &Metadata,
@@ -108,18 +143,18 @@ pub fn create_unsigned<T: Config, Call: Payload>(
     // 2. Encode extrinsic
     let extrinsic = {
         let mut encoded_inner = Vec::new();
-        // transaction protocol version (4) (is not signed, so no 1 bit at the front).
-        4u8.encode_to(&mut encoded_inner);
+        // transaction protocol version (4 or 5) (is not signed, so no bits set at the front).
+        // Unlike signed transactions, bare ones don't need the `VerifySignature` extension.
+        let version: u8 = if metadata.extrinsic().highest_version() >= 5 {
+            5
+        } else {
+            4
+        };
+        version.encode_to(&mut encoded_inner);
         // encode call data after this byte.
         call.encode_call_data_to(metadata, &mut encoded_inner)?;
         // now, prefix byte length:
-        let len = Compact(
-            u32::try_from(encoded_inner.len()).expect("extrinsic size expected to be <4GB"),
-        );
-        let mut encoded = Vec::new();
-        len.encode_to(&mut encoded);
-        encoded.extend(encoded_inner);
-        encoded
+        prefix_with_len(encoded_inner)
     };
 
     // Wrap in Encoded to ensure that any more "encode" calls leave it in the right state.
@@ -150,10 +185,14 @@ pub fn create_partial_signed<T: Config, Call: Payload>(
     Ok(PartialTransaction {
         call_data,
         additional_and_extra_params,
+        version: extrinsic_version(&client_state.metadata),
+        transaction_extension_version: transaction_extension_version(&client_state.metadata),
     })
 }
 
-/// Creates a signed extrinsic without submitting it.
+/// Creates a signed extrinsic without submitting it. If the runtime supports v5 extrinsics
+/// and the `VerifySignature` transaction extension, this is a v5 "general" extrinsic whose
+/// signature is carried by that extension, and otherwise it is a v4 signed one.
 ///
 /// Note: if not provided, the default account nonce will be set to 0 and the default mortality will be _immortal_.
 /// This is because this method runs offline, and so is unable to fetch the data needed for more appropriate values.
@@ -183,11 +222,15 @@ where
 /// This represents a partially constructed transaction that needs signing before it is ready
 /// to submit. Use [`PartialTransaction::signer_payload()`] to return the payload that needs signing,
 /// [`PartialTransaction::sign()`] to sign the transaction using a [`SignerT`] impl, or
-/// [`PartialTransaction::sign_with_address_and_signature()`] to apply an existing signature and address
+/// [`PartialTransaction::sign_with_account_and_signature()`] to apply an existing signature and account
 /// to the transaction.
 pub struct PartialTransaction<T: Config> {
     call_data: Vec<u8>,
     additional_and_extra_params: T::ExtrinsicParams,
+    /// The extrinsic version (4 or 5) to build.
+    version: u8,
+    /// The version of the transaction extensions used, if building a v5 extrinsic.
+    transaction_extension_version: u8,
 }
 
 impl<T: Config> PartialTransaction<T> {
@@ -198,14 +241,28 @@ impl<T: Config> PartialTransaction<T> {
     where
         F: for<'a> FnOnce(Cow<'a, [u8]>) -> R,
     {
-        let mut bytes = self.call_data.clone();
-        self.additional_and_extra_params.encode_extra_to(&mut bytes);
-        self.additional_and_extra_params
-            .encode_additional_to(&mut bytes);
-        if bytes.len() > 256 {
-            f(Cow::Borrowed(blake2_256(&bytes).as_ref()))
+        if self.version == 4 {
+            let mut bytes = self.call_data.clone();
+            self.additional_and_extra_params.encode_extra_to(&mut bytes);
+            self.additional_and_extra_params
+                .encode_additional_to(&mut bytes);
+            if bytes.len() > 256 {
+                f(Cow::Borrowed(blake2_256(&bytes).as_ref()))
+            } else {
+                f(Cow::Owned(bytes))
+            }
         } else {
-            f(Cow::Owned(bytes))
+            // v5 transactions always sign the hash of the transaction extension version,
+            // the call and then the extensions after `VerifySignature`, which carries the
+            // signature.
+            let mut bytes = Vec::with_capacity(1 + self.call_data.len());
+            bytes.push(self.transaction_extension_version);
+            bytes.extend_from_slice(&self.call_data);
+            self.additional_and_extra_params
+                .encode_signer_payload_extra_to(&mut bytes);
+            self.additional_and_extra_params
+                .encode_signer_payload_additional_to(&mut bytes);
+            f(Cow::Borrowed(blake2_256(&bytes).as_ref()))
         }
     }
 
@@ -215,12 +272,39 @@ impl<T: Config> PartialTransaction<T> {
         self.with_signer_payload(|bytes| bytes.to_vec())
     }
 
+    /// The extrinsic version (4 or 5) that this transaction will be built as.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     /// Return the bytes representing the call data for this partially constructed
     /// extrinsic.
     pub fn call_data(&self) -> &[u8] {
         &self.call_data
     }
 
+    /// Return a proof containing the type information needed to decode this extrinsic, as
+    /// described in RFC-0078. Offline and hardware signers can use this, alongside the
+    /// [`PartialTransaction::signer_payload()`], to display the extrinsic being signed and
+    /// to check it against the metadata hash.
+    ///
+    /// The [`MerkleizedMetadata`] can be obtained via [`subxt_metadata::Metadata::merkleize()`],
+    /// and should be reused across transactions where possible. The `info` should match the
+    /// details used to compute the metadata hash, and is included in the proof.
+    pub fn metadata_proof(
+        &self,
+        metadata: &MerkleizedMetadata,
+        info: MetadataDigestInfo,
+    ) -> Result<ExtrinsicProof, MerkleizeError> {
+        let mut extra = Vec::new();
+        self.additional_and_extra_params.encode_extra_to(&mut extra);
+        let mut additional = Vec::new();
+        self.additional_and_extra_params
+            .encode_additional_to(&mut additional);
+
+        metadata.extrinsic_parts_proof(&self.call_data, Some((&extra, &additional)), info)
+    }
+
     /// Convert this [`PartialTransaction`] into a [`Transaction`], ready to submit.
     /// The provided `signer` is responsible for providing the "from" address for the transaction,
     /// as well as providing a signature to attach to it.
@@ -230,19 +314,84 @@ impl<T: Config> PartialTransaction<T> {
     {
         // Given our signer, we can sign the payload representing this extrinsic.
         let signature = self.with_signer_payload(|bytes| signer.sign(&bytes));
-        // Now, use the signature and "from" address to build the extrinsic.
-        self.sign_with_address_and_signature(&signer.address(), &signature)
+        // Now, use the signature and "from" address or account to build the extrinsic.
+        if self.version == 4 {
+            self.sign_v4(&signer.address(), &signature.encode())
+        } else {
+            self.sign_with_account_and_signature(&signer.account_id(), &signature)
+        }
+    }
+
+    /// Convert this [`PartialTransaction`] into a [`Transaction`], ready to submit.
+    /// An account ID, and something representing a signature that can be SCALE encoded, are both
+    /// needed in order to construct it. If you have a `Signer` to hand, you can use
+    /// [`PartialTransaction::sign()`] instead.
+    pub fn sign_with_account_and_signature(
+        &self,
+        account_id: &T::AccountId,
+        signature: &T::Signature,
+    ) -> Transaction<T> {
+        self.sign_with_account_and_encoded_signature(account_id, &signature.encode())
+    }
+
+    /// Convert this [`PartialTransaction`] into a [`Transaction`], ready to submit. This is the
+    /// same as [`PartialTransaction::sign_with_account_and_signature()`], except that the
+    /// signature is given as SCALE encoded bytes.
+    pub fn sign_with_account_and_encoded_signature(
+        &self,
+        account_id: &T::AccountId,
+        signature: &[u8],
+    ) -> Transaction<T> {
+        if self.version == 4 {
+            return self.sign_v4(&account_id.clone().into(), signature);
+        }
+
+        // Encode the extrinsic (into the format expected by protocol version 5)
+        let extrinsic = {
+            let mut encoded_inner = Vec::new();
+            // "general" + transaction protocol version (5)
+            (0b01000000 + 5u8).encode_to(&mut encoded_inner);
+            // the version of the transaction extensions that follow
+            self.transaction_extension_version
+                .encode_to(&mut encoded_inner);
+            // attach custom extra params, which now include the signature and account
+            self.additional_and_extra_params.encode_signed_extra_to(
+                &account_id.encode(),
+                signature,
+                &mut encoded_inner,
+            );
+            // and now, call data (remembering that it's been encoded already and just needs appending)
+            encoded_inner.extend(&self.call_data);
+            // now, prefix byte length:
+            prefix_with_len(encoded_inner)
+        };
+
+        // Return an extrinsic ready to be submitted.
+        Transaction::from_bytes(extrinsic)
     }
 
     /// Convert this [`PartialTransaction`] into a [`Transaction`], ready to submit.
     /// An address, and something representing a signature that can be SCALE encoded, are both
     /// needed in order to construct it. If you have a `Signer` to hand, you can use
     /// [`PartialTransaction::sign()`] instead.
+    ///
+    /// Only v4 extrinsics can be signed with an address, and so this returns
+    /// [`Error::SignedWithAddress`] if [`PartialTransaction::version()`] is 5. Prefer
+    /// [`PartialTransaction::sign_with_account_and_signature()`], which builds whichever
+    /// version the runtime supports.
     pub fn sign_with_address_and_signature(
         &self,
         address: &T::Address,
         signature: &T::Signature,
-    ) -> Transaction<T> {
+    ) -> Result<Transaction<T>, Error> {
+        if self.version != 4 {
+            return Err(Error::SignedWithAddress);
+        }
+        Ok(self.sign_v4(address, &signature.encode()))
+    }
+
+    // Build a v4 signed transaction, given the address and encoded signature.
+    fn sign_v4(&self, address: &T::Address, signature: &[u8]) -> Transaction<T> {
         // Encode the extrinsic (into the format expected by protocol version 4)
         let extrinsic = {
             let mut encoded_inner = Vec::new();
@@ -251,20 +400,14 @@ impl<T: Config> PartialTransaction<T> {
             // from address for signature
             address.encode_to(&mut encoded_inner);
             // the signature
-            signature.encode_to(&mut encoded_inner);
+            encoded_inner.extend_from_slice(signature);
             // attach custom extra params
             self.additional_and_extra_params
                 .encode_extra_to(&mut encoded_inner);
             // and now, call data (remembering that it's been encoded already and just needs appending)
             encoded_inner.extend(&self.call_data);
             // now, prefix byte length:
-            let len = Compact(
-                u32::try_from(encoded_inner.len()).expect("extrinsic size expected to be <4GB"),
-            );
-            let mut encoded = Vec::new();
-            len.encode_to(&mut encoded);
-            encoded.extend(encoded_inner);
-            encoded
+            prefix_with_len(encoded_inner)
         };
 
         // Return an extrinsic ready to be submitted.
@@ -272,6 +415,16 @@ impl<T: Config> PartialTransaction<T> {
     }
 }
 
+// Prefix some encoded extrinsic bytes with their compact encoded length.
+fn prefix_with_len(encoded_inner: Vec<u8>) -> Vec<u8> {
+    let len =
+        Compact(u32::try_from(encoded_inner.len()).expect("extrinsic size expected to be <4GB"));
+    let mut encoded = Vec::new();
+    len.encode_to(&mut encoded);
+    encoded.extend(encoded_inner);
+    encoded
+}
+
 /// This represents a signed transaction that's ready to be submitted.
 /// Use [`Transaction::encoded()`] or [`Transaction::into_encoded()`] to
 /// get the bytes for it, or [`Transaction::hash()`] to get the hash.
@@ -306,3 +459,170 @@ impl<T: Config> Transaction<T> {
         self.encoded.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DefaultExtrinsicParamsBuilder, SubstrateConfig};
+    use crate::utils::{AccountId32, MultiAddress, MultiSignature, H256};
+    use codec::Decode;
+    use core::cell::RefCell;
+    use frame_metadata::v16;
+
+    const SPEC_VERSION: u32 = 1_000_001;
+
+    /// The small Polkadot metadata, changed to support v5 extrinsics whose latest transaction
+    /// extensions (version 1) are the given ones, in order.
+    fn v5_metadata(extensions: &[&str]) -> Metadata {
+        let bytes = include_bytes!("../../../artifacts/polkadot_metadata_small.scale");
+        let metadata = subxt_metadata::Metadata::decode(&mut &bytes[..]).unwrap();
+        let mut v16: v16::RuntimeMetadataV16 = metadata.into();
+        let extrinsic = &mut v16.extrinsic;
+
+        // `CheckSpecVersion` has nothing in the extrinsic itself.
+        let unit = extrinsic
+            .transaction_extensions
+            .iter()
+            .find(|ext| ext.identifier == "CheckSpecVersion")
+            .unwrap()
+            .ty
+            .clone();
+        extrinsic
+            .transaction_extensions
+            .push(v16::TransactionExtensionMetadata {
+                identifier: "VerifySignature".into(),
+                ty: unit.clone(),
+                implicit: unit,
+            });
+
+        let extensions = extensions
+            .iter()
+            .map(|identifier| {
+                extrinsic
+                    .transaction_extensions
+                    .iter()
+                    .position(|ext| ext.identifier == *identifier)
+                    .unwrap() as u32
+            })
+            .collect();
+        extrinsic
+            .transaction_extensions_by_version
+            .insert(1, extensions);
+        extrinsic.versions = vec![4, 5];
+
+        subxt_metadata::Metadata::try_from(v16).unwrap().into()
+    }
+
+    fn client_state(metadata: Metadata) -> ClientState<SubstrateConfig> {
+        ClientState {
+            genesis_hash: H256::zero(),
+            runtime_version: RuntimeVersion {
+                spec_version: SPEC_VERSION,
+                transaction_version: 1,
+            },
+            metadata,
+        }
+    }
+
+    // A signer which records the payload that it's given to sign.
+    struct RecordingSigner {
+        payload: RefCell<Vec<u8>>,
+    }
+
+    impl SignerT<SubstrateConfig> for RecordingSigner {
+        fn account_id(&self) -> AccountId32 {
+            AccountId32([1; 32])
+        }
+        fn address(&self) -> MultiAddress<AccountId32, u32> {
+            self.account_id().into()
+        }
+        fn sign(&self, signer_payload: &[u8]) -> MultiSignature {
+            *self.payload.borrow_mut() = signer_payload.to_vec();
+            MultiSignature::Sr25519([2; 64])
+        }
+    }
+
+    #[test]
+    fn v5_signer_payload_starts_with_the_transaction_extension_version() {
+        let state = client_state(v5_metadata(&[
+            "VerifySignature",
+            "CheckSpecVersion",
+            "CheckNonce",
+        ]));
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let params = DefaultExtrinsicParamsBuilder::new().nonce(5).build();
+
+        let partial = create_partial_signed(&call, &state, params).unwrap();
+        assert_eq!(partial.version(), 5);
+
+        // The transaction extension version, then the call (System.remark(vec![1, 2, 3])),
+        // then the explicit data of the extensions after `VerifySignature` (the nonce, as
+        // compact 5) and then their implicit data (the spec version, 1_000_001).
+        let payload: [u8; 12] = [1, 0, 0, 12, 1, 2, 3, 20, 0x41, 0x42, 0x0f, 0x00];
+        let expected = blake2_256(&payload);
+        assert_eq!(partial.signer_payload(), expected);
+
+        let signer = RecordingSigner {
+            payload: RefCell::new(Vec::new()),
+        };
+        let tx = partial.sign(&signer);
+        assert_eq!(*signer.payload.borrow(), expected);
+
+        // A "general" v5 extrinsic using transaction extension version 1, after the two
+        // byte compact length.
+        assert_eq!(tx.encoded()[2..4], [0b01000101, 1]);
+    }
+
+    #[test]
+    fn v4_is_used_if_the_signature_cannot_be_verified_by_an_extension() {
+        let metadata = v5_metadata(&["CheckSpecVersion", "CheckNonce"]);
+        assert_eq!(extrinsic_version(&metadata), 4);
+        assert_eq!(transaction_extension_version(&metadata), 0);
+
+        let metadata = v5_metadata(&["VerifySignature", "CheckSpecVersion", "CheckNonce"]);
+        assert_eq!(extrinsic_version(&metadata), 5);
+        assert_eq!(transaction_extension_version(&metadata), 1);
+    }
+
+    #[test]
+    fn v5_signer_payload_leaves_out_extensions_before_verify_signature() {
+        let state = client_state(v5_metadata(&[
+            "CheckNonce",
+            "VerifySignature",
+            "CheckSpecVersion",
+        ]));
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let params = DefaultExtrinsicParamsBuilder::new().nonce(5).build();
+
+        let partial = create_partial_signed(&call, &state, params).unwrap();
+        assert_eq!(partial.version(), 5);
+
+        // As above, but without the nonce, since `CheckNonce` comes before `VerifySignature`.
+        let payload: [u8; 11] = [1, 0, 0, 12, 1, 2, 3, 0x41, 0x42, 0x0f, 0x00];
+        assert_eq!(partial.signer_payload(), blake2_256(&payload));
+
+        // The nonce is still in the extrinsic, before the signature.
+        let tx = partial.sign(&RecordingSigner {
+            payload: RefCell::new(Vec::new()),
+        });
+        assert_eq!(tx.encoded()[2..6], [0b01000101, 1, 20, 0]);
+    }
+
+    #[test]
+    fn v5_transactions_cannot_be_signed_with_an_address() {
+        let state = client_state(v5_metadata(&[
+            "VerifySignature",
+            "CheckSpecVersion",
+            "CheckNonce",
+        ]));
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let partial = create_partial_signed(&call, &state, Default::default()).unwrap();
+
+        let address = MultiAddress::Id(AccountId32([1; 32]));
+        let signature = MultiSignature::Sr25519([2; 64]);
+        assert!(matches!(
+            partial.sign_with_address_and_signature(&address, &signature),
+            Err(Error::SignedWithAddress)
+        ));
+    }
+}
@@ -70,6 +70,25 @@ impl Era {
             phase: quantized_phase,
         }
     }
+
+    /// Get the block number of the start of the era whose properties this object
+    /// describes that `current` belongs to.
+    pub fn birth(self, current: u64) -> u64 {
+        match self {
+            Self::Immortal => 0,
+            Self::Mortal { period, phase } => {
+                (current.max(phase) - phase) / period * period + phase
+            }
+        }
+    }
+
+    /// Get the block number of the first block at which the era has ended.
+    pub fn death(self, current: u64) -> u64 {
+        match self {
+            Self::Immortal => u64::MAX,
+            Self::Mortal { period, .. } => self.birth(current) + period,
+        }
+    }
 }
 
 // Both copied from `sp_runtime::generic::Era`; this is the wire interface and so
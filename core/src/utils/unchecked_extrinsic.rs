@@ -8,6 +8,9 @@
 //! The inner bytes represent the encoded extrinsic expected by the
 //! runtime APIs. Deriving `EncodeAsType` would lead to the inner
 //! bytes to be re-encoded (length prefixed).
+//!
+//! The bytes are not interpreted here, and so can represent any extrinsic
+//! version (ie v4 signed/unsigned or v5 bare/general extrinsics).
 
 use core::marker::PhantomData;
 
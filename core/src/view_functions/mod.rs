@@ -0,0 +1,189 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Encode pallet view function payloads, decode the associated values returned from them, and
+//! validate static view function payloads.
+//!
+//! View functions are only available in V16 metadata and later. They are all executed via a
+//! single runtime API call (see [`call_name()`]), which is handed the unique ID of the view
+//! function along with its encoded arguments (see [`call_args()`]).
+
+pub mod payload;
+
+use crate::error::{Error, MetadataError, ViewFunctionError};
+use crate::metadata::{DecodeWithMetadata, Metadata};
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use payload::Payload;
+
+/// The name of the runtime API call used to execute all pallet view functions.
+const CALL_NAME: &str = "RuntimeViewFunction_execute_view_function";
+
+/// Run the validation logic against some view function payload you'd like to use. Returns `Ok(())`
+/// if the payload is valid (or if it's not possible to check since the payload has no validation hash).
+/// Return an error if the payload was not valid or something went wrong trying to validate it (ie
+/// the view function in question does not exist at all)
+pub fn validate<P: Payload>(payload: &P, metadata: &Metadata) -> Result<(), Error> {
+    let Some(static_hash) = payload.validation_hash() else {
+        return Ok(());
+    };
+
+    let pallet = metadata.pallet_by_name_err(payload.pallet_name())?;
+
+    let Some(runtime_hash) = pallet.view_function_hash(payload.function_name()) else {
+        return Err(MetadataError::IncompatibleCodegen.into());
+    };
+    if static_hash != runtime_hash {
+        return Err(MetadataError::IncompatibleCodegen.into());
+    }
+    Ok(())
+}
+
+/// Return the name of the runtime API call used to execute view functions.
+pub fn call_name() -> &'static str {
+    CALL_NAME
+}
+
+/// Return the encoded call args given a view function payload. This is the unique ID of
+/// the view function followed by the SCALE encoded bytes of its arguments.
+pub fn call_args<P: Payload>(payload: &P, metadata: &Metadata) -> Result<Vec<u8>, Error> {
+    let view_function = metadata
+        .pallet_by_name_err(payload.pallet_name())?
+        .view_function_by_name(payload.function_name())
+        .ok_or_else(|| MetadataError::ViewFunctionNotFound(payload.function_name().to_owned()))?;
+
+    let input = payload.encode_args(metadata)?;
+
+    let mut out = view_function.id().to_vec();
+    input.encode_to(&mut out);
+    Ok(out)
+}
+
+/// Decode the value bytes returned from executing the view function given by the provided payload.
+pub fn decode_value<P: Payload>(
+    bytes: &mut &[u8],
+    payload: &P,
+    metadata: &Metadata,
+) -> Result<P::ReturnType, Error> {
+    let view_function = metadata
+        .pallet_by_name_err(payload.pallet_name())?
+        .view_function_by_name(payload.function_name())
+        .ok_or_else(|| MetadataError::ViewFunctionNotFound(payload.function_name().to_owned()))?;
+
+    // The runtime API wraps the output bytes in a result, so unwrap that first.
+    let value_bytes = Result::<Vec<u8>, ViewFunctionError>::decode(bytes)??;
+
+    let val = <P::ReturnType as DecodeWithMetadata>::decode_with_metadata(
+        &mut &value_bytes[..],
+        view_function.output_ty(),
+        metadata,
+    )?;
+
+    Ok(val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ViewFunctionError;
+    use alloc::vec;
+    use frame_metadata::v16;
+    use scale_info::{TypeDef, TypeDefPrimitive};
+    use scale_value::{Composite, Value};
+
+    const ID: [u8; 32] = [7; 32];
+
+    /// The small Polkadot metadata, with a `System.nonce_plus(n: u32) -> u64` view function.
+    fn metadata() -> Metadata {
+        let bytes = include_bytes!("../../../artifacts/polkadot_metadata_small.scale");
+        let metadata = subxt_metadata::Metadata::decode(&mut &bytes[..]).unwrap();
+
+        let primitive_id = |primitive: TypeDefPrimitive| {
+            metadata
+                .types()
+                .types
+                .iter()
+                .find(|ty| ty.ty.type_def == TypeDef::Primitive(primitive.clone()))
+                .unwrap()
+                .id
+        };
+        let view_function = v16::PalletViewFunctionMetadata {
+            name: "nonce_plus".into(),
+            id: ID,
+            inputs: vec![v16::PalletViewFunctionParamMetadata {
+                name: "n".into(),
+                ty: primitive_id(TypeDefPrimitive::U32).into(),
+            }],
+            output: primitive_id(TypeDefPrimitive::U64).into(),
+            docs: vec![],
+            deprecation_info: v16::DeprecationStatus::NotDeprecated,
+        };
+
+        let mut v16: v16::RuntimeMetadataV16 = metadata.into();
+        v16.pallets
+            .iter_mut()
+            .find(|p| p.name == "System")
+            .unwrap()
+            .view_functions
+            .push(view_function);
+        subxt_metadata::Metadata::try_from(v16).unwrap().into()
+    }
+
+    #[test]
+    fn call_args_are_prefixed_with_the_view_function_id() {
+        let metadata = metadata();
+        let payload = payload::dynamic("System", "nonce_plus", vec![Value::u128(5)]);
+
+        let args = call_args(&payload, &metadata).unwrap();
+        assert_eq!(args[..32], ID);
+        assert_eq!(args[32..], 5u32.encode().encode());
+
+        let missing = payload::dynamic("System", "nonce_minus", vec![Value::u128(5)]);
+        assert!(matches!(
+            call_args(&missing, &metadata),
+            Err(Error::Metadata(MetadataError::ViewFunctionNotFound(name))) if name == "nonce_minus"
+        ));
+    }
+
+    #[test]
+    fn values_are_decoded_from_the_runtime_result() {
+        let metadata = metadata();
+        let args = Composite::unnamed([Value::u128(5)]);
+        let payload = payload::DefaultPayload::<_, u64>::new("System", "nonce_plus", args);
+
+        let ok = Ok::<_, ViewFunctionError>(10u64.encode()).encode();
+        let value = decode_value(&mut &*ok, &payload, &metadata).unwrap();
+        assert_eq!(value, 10);
+
+        let err = Err::<Vec<u8>, _>(ViewFunctionError::NotFound(ID)).encode();
+        assert!(matches!(
+            decode_value(&mut &*err, &payload, &metadata),
+            Err(Error::ViewFunction(ViewFunctionError::NotFound(ID)))
+        ));
+    }
+
+    #[test]
+    fn static_payloads_are_validated_against_the_metadata_hash() {
+        let metadata = metadata();
+        let hash = metadata
+            .pallet_by_name("System")
+            .unwrap()
+            .view_function_hash("nonce_plus")
+            .unwrap();
+        let args = || Composite::unnamed([Value::u128(5)]);
+
+        let valid =
+            payload::StaticPayload::<_, u64>::new_static("System", "nonce_plus", args(), hash);
+        assert!(validate(&valid, &metadata).is_ok());
+
+        let invalid =
+            payload::StaticPayload::<_, u64>::new_static("System", "nonce_plus", args(), [0; 32]);
+        assert!(matches!(
+            validate(&invalid, &metadata),
+            Err(Error::Metadata(MetadataError::IncompatibleCodegen))
+        ));
+        assert!(validate(&invalid.unvalidated(), &metadata).is_ok());
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module contains the trait and types used to represent
+//! pallet view function calls that can be made.
+
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use derive_where::derive_where;
+use scale_encode::EncodeAsFields;
+use scale_value::Composite;
+
+use crate::dynamic::DecodedValueThunk;
+use crate::error::MetadataError;
+use crate::Error;
+
+use crate::metadata::{DecodeWithMetadata, Metadata};
+
+/// This represents a pallet view function payload that can be executed
+/// against the runtime of a node.
+///
+/// # Components
+///
+/// - associated return type
+///
+/// Resulting bytes of the call are interpreted into this type.
+///
+/// - pallet and function name
+///
+/// Used to look up the view function in the metadata, in order to find its
+/// unique ID, argument types and return type.
+///
+/// - encoded arguments
+///
+/// Each argument of the view function must be scale-encoded.
+pub trait Payload {
+    /// The return type of the view function.
+    // Note: `DecodeWithMetadata` is needed to decode the function call result
+    // with the `subxt::Metadata.
+    type ReturnType: DecodeWithMetadata;
+
+    /// The name of the pallet that the view function belongs to.
+    fn pallet_name(&self) -> &str;
+
+    /// The view function name.
+    fn function_name(&self) -> &str;
+
+    /// Scale encode the arguments data.
+    fn encode_args_to(&self, metadata: &Metadata, out: &mut Vec<u8>) -> Result<(), Error>;
+
+    /// Encode arguments data and return the output. This is a convenience
+    /// wrapper around [`Payload::encode_args_to`].
+    fn encode_args(&self, metadata: &Metadata) -> Result<Vec<u8>, Error> {
+        let mut v = Vec::new();
+        self.encode_args_to(metadata, &mut v)?;
+        Ok(v)
+    }
+
+    /// Returns the statically generated validation hash.
+    fn validation_hash(&self) -> Option<[u8; 32]> {
+        None
+    }
+}
+
+/// A view function payload containing the generic argument data
+/// and interpreting the result of the call as `ReturnTy`.
+///
+/// This can be created from static values (ie those generated
+/// via the `subxt` macro) or dynamic values via [`dynamic`].
+#[derive_where(Clone, Debug, Eq, Ord, PartialEq, PartialOrd; ArgsData)]
+pub struct DefaultPayload<ArgsData, ReturnTy> {
+    pallet_name: Cow<'static, str>,
+    function_name: Cow<'static, str>,
+    args_data: ArgsData,
+    validation_hash: Option<[u8; 32]>,
+    _marker: PhantomData<ReturnTy>,
+}
+
+/// A statically generated view function payload.
+pub type StaticPayload<ArgsData, ReturnTy> = DefaultPayload<ArgsData, ReturnTy>;
+/// A dynamic view function payload.
+pub type DynamicPayload = DefaultPayload<Composite<()>, DecodedValueThunk>;
+
+impl<ArgsData: EncodeAsFields, ReturnTy: DecodeWithMetadata> Payload
+    for DefaultPayload<ArgsData, ReturnTy>
+{
+    type ReturnType = ReturnTy;
+
+    fn pallet_name(&self) -> &str {
+        &self.pallet_name
+    }
+
+    fn function_name(&self) -> &str {
+        &self.function_name
+    }
+
+    fn encode_args_to(&self, metadata: &Metadata, out: &mut Vec<u8>) -> Result<(), Error> {
+        let view_function = metadata
+            .pallet_by_name_err(&self.pallet_name)?
+            .view_function_by_name(&self.function_name)
+            .ok_or_else(|| MetadataError::ViewFunctionNotFound((*self.function_name).to_owned()))?;
+        let mut fields = view_function
+            .inputs()
+            .map(|input| scale_encode::Field::named(input.ty, &input.name));
+
+        self.args_data
+            .encode_as_fields_to(&mut fields, metadata.types(), out)?;
+        Ok(())
+    }
+
+    fn validation_hash(&self) -> Option<[u8; 32]> {
+        self.validation_hash
+    }
+}
+
+impl<ReturnTy, ArgsData> DefaultPayload<ArgsData, ReturnTy> {
+    /// Create a new [`DefaultPayload`].
+    pub fn new(
+        pallet_name: impl Into<String>,
+        function_name: impl Into<String>,
+        args_data: ArgsData,
+    ) -> Self {
+        DefaultPayload {
+            pallet_name: Cow::Owned(pallet_name.into()),
+            function_name: Cow::Owned(function_name.into()),
+            args_data,
+            validation_hash: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a new static [`DefaultPayload`] using static pallet and function
+    /// names and scale-encoded argument data.
+    ///
+    /// This is only expected to be used from codegen.
+    #[doc(hidden)]
+    pub fn new_static(
+        pallet_name: &'static str,
+        function_name: &'static str,
+        args_data: ArgsData,
+        hash: [u8; 32],
+    ) -> DefaultPayload<ArgsData, ReturnTy> {
+        DefaultPayload {
+            pallet_name: Cow::Borrowed(pallet_name),
+            function_name: Cow::Borrowed(function_name),
+            args_data,
+            validation_hash: Some(hash),
+            _marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Do not validate this call prior to submitting it.
+    pub fn unvalidated(self) -> Self {
+        Self {
+            validation_hash: None,
+            ..self
+        }
+    }
+
+    /// Returns the pallet name.
+    pub fn pallet_name(&self) -> &str {
+        &self.pallet_name
+    }
+
+    /// Returns the view function name.
+    pub fn function_name(&self) -> &str {
+        &self.function_name
+    }
+
+    /// Returns the arguments data.
+    pub fn args_data(&self) -> &ArgsData {
+        &self.args_data
+    }
+}
+
+/// Create a new [`DynamicPayload`].
+pub fn dynamic(
+    pallet_name: impl Into<String>,
+    function_name: impl Into<String>,
+    args_data: impl Into<Composite<()>>,
+) -> DynamicPayload {
+    DefaultPayload::new(pallet_name, function_name, args_data.into())
+}
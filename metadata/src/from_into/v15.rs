@@ -6,12 +6,15 @@ use super::TryFromError;
 
 use crate::utils::variant_index::VariantIndex;
 use crate::{
-    utils::ordered_map::OrderedMap, ArcStr, ConstantMetadata, ExtrinsicMetadata, Metadata,
-    OuterEnumsMetadata, PalletMetadataInner, RuntimeApiMetadataInner, RuntimeApiMethodMetadata,
-    RuntimeApiMethodParamMetadata, SignedExtensionMetadata, StorageEntryMetadata,
-    StorageEntryModifier, StorageEntryType, StorageHasher, StorageMetadata,
+    utils::ordered_map::OrderedMap, ArcStr, ConstantMetadata, DeprecationInfo, DeprecationStatus,
+    ExtrinsicMetadata, Metadata, OuterEnumsMetadata, PalletMetadataInner, RuntimeApiMetadataInner,
+    RuntimeApiMethodMetadata, RuntimeApiMethodParamMetadata, SignedExtensionMetadata,
+    StorageEntryMetadata, StorageEntryModifier, StorageEntryType, StorageHasher, StorageMetadata,
 };
 use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
 use frame_metadata::v15;
 use hashbrown::HashMap;
 use scale_info::form::PortableForm;
@@ -60,12 +63,18 @@ mod from_v15 {
                         storage,
                         call_ty: p.calls.map(|c| c.ty.id),
                         call_variant_index,
+                        call_deprecation_info: DeprecationInfo::NotDeprecated,
                         event_ty: p.event.map(|e| e.ty.id),
                         event_variant_index,
+                        event_deprecation_info: DeprecationInfo::NotDeprecated,
                         error_ty: p.error.map(|e| e.ty.id),
                         error_variant_index,
+                        error_deprecation_info: DeprecationInfo::NotDeprecated,
                         constants: constants.collect(),
+                        associated_types: OrderedMap::new(),
+                        view_functions: OrderedMap::new(),
                         docs: p.docs,
+                        deprecation_info: DeprecationStatus::NotDeprecated,
                     },
                 );
             }
@@ -87,7 +96,7 @@ mod from_v15 {
                 pallets,
                 pallets_by_index,
                 extrinsic: from_extrinsic_metadata(m.extrinsic),
-                runtime_ty: m.ty.id,
+                runtime_ty: Some(m.ty.id),
                 dispatch_error_ty,
                 apis: apis.collect(),
                 outer_enums: OuterEnumsMetadata {
@@ -111,17 +120,26 @@ mod from_v15 {
     }
 
     fn from_extrinsic_metadata(value: v15::ExtrinsicMetadata<PortableForm>) -> ExtrinsicMetadata {
+        // Prior to V16, every signed extension is used, in order, by the
+        // one and only transaction extension version (0).
+        let num_signed_extensions = value.signed_extensions.len() as u32;
+        let signed_extensions: Vec<_> = value
+            .signed_extensions
+            .into_iter()
+            .map(from_signed_extension_metadata)
+            .collect();
         ExtrinsicMetadata {
-            version: value.version,
-            signed_extensions: value
-                .signed_extensions
-                .into_iter()
-                .map(from_signed_extension_metadata)
-                .collect(),
+            supported_versions: vec![value.version],
+            transaction_extensions: signed_extensions.clone(),
+            signed_extensions,
+            transaction_extensions_by_version: BTreeMap::from([(
+                0,
+                (0..num_signed_extensions).collect(),
+            )]),
             address_ty: value.address_ty.id,
             call_ty: value.call_ty.id,
             signature_ty: value.signature_ty.id,
-            extra_ty: value.extra_ty.id,
+            extra_ty: Some(value.extra_ty.id),
         }
     }
 
@@ -169,6 +187,7 @@ mod from_v15 {
             entry_type: from_storage_entry_type(s.ty),
             default: s.default,
             docs: s.docs,
+            deprecation_info: DeprecationStatus::NotDeprecated,
         }
     }
 
@@ -181,6 +200,7 @@ mod from_v15 {
             ty: s.ty.id,
             value: s.value,
             docs: s.docs,
+            deprecation_info: DeprecationStatus::NotDeprecated,
         }
     }
 
@@ -199,6 +219,8 @@ mod from_v15 {
                     (name.clone(), from_runtime_api_method_metadata(name, m))
                 })
                 .collect(),
+            version: None,
+            deprecation_info: DeprecationStatus::NotDeprecated,
         }
     }
 
@@ -215,6 +237,7 @@ mod from_v15 {
                 .collect(),
             output_ty: s.output.id,
             docs: s.docs,
+            deprecation_info: DeprecationStatus::NotDeprecated,
         }
     }
 
@@ -233,7 +256,18 @@ mod into_v15 {
     use super::*;
 
     impl From<Metadata> for v15::RuntimeMetadataV15 {
-        fn from(m: Metadata) -> Self {
+        fn from(mut m: Metadata) -> Self {
+            // Metadata from V16 onwards may not know about the `Runtime` and `Extra` types,
+            // which V15 requires, so we add them to the type registry here if needed.
+            let runtime_ty = match m.runtime_ty {
+                Some(ty) => ty,
+                None => generate_runtime_type(&mut m),
+            };
+            let extra_ty = match m.extrinsic.extra_ty {
+                Some(ty) => ty,
+                None => generate_extra_type(&mut m),
+            };
+
             let pallets = m.pallets.into_values().into_iter().map(|p| {
                 let storage = p.storage.map(|s| v15::PalletStorageMetadata {
                     prefix: s.prefix,
@@ -271,8 +305,8 @@ mod into_v15 {
             v15::RuntimeMetadataV15 {
                 types: m.types,
                 pallets: pallets.collect(),
-                ty: m.runtime_ty.into(),
-                extrinsic: from_extrinsic_metadata(m.extrinsic),
+                ty: runtime_ty.into(),
+                extrinsic: from_extrinsic_metadata(m.extrinsic, extra_ty),
                 apis: m
                     .apis
                     .into_values()
@@ -330,9 +364,65 @@ mod into_v15 {
         }
     }
 
-    fn from_extrinsic_metadata(e: ExtrinsicMetadata) -> v15::ExtrinsicMetadata<PortableForm> {
+    /// The `Runtime` type lives alongside the outer `RuntimeCall` enum, so add an
+    /// empty composite type with the expected path.
+    fn generate_runtime_type(m: &mut Metadata) -> u32 {
+        let mut segments = m
+            .types
+            .resolve(m.outer_enums.call_enum_ty)
+            .map(|call_enum| call_enum.path.segments.clone())
+            .unwrap_or_default();
+        segments.pop();
+        segments.push("Runtime".to_owned());
+
+        let runtime_type = scale_info::Type {
+            path: scale_info::Path { segments },
+            type_params: vec![],
+            type_def: scale_info::TypeDef::Composite(scale_info::TypeDefComposite {
+                fields: vec![],
+            }),
+            docs: vec![],
+        };
+
+        push_type(&mut m.types, runtime_type)
+    }
+
+    /// The `Extra` type is the tuple of every signed extension type, so add that.
+    fn generate_extra_type(m: &mut Metadata) -> u32 {
+        let extension_tys = m
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|ext| ext.extra_ty.into())
+            .collect();
+
+        let extra_type = scale_info::Type {
+            path: scale_info::Path::default(),
+            type_params: vec![],
+            type_def: scale_info::TypeDef::Tuple(scale_info::TypeDefTuple {
+                fields: extension_tys,
+            }),
+            docs: vec![],
+        };
+
+        push_type(&mut m.types, extra_type)
+    }
+
+    fn push_type(
+        types: &mut scale_info::PortableRegistry,
+        ty: scale_info::Type<PortableForm>,
+    ) -> u32 {
+        let id = types.types.len() as u32;
+        types.types.push(scale_info::PortableType { id, ty });
+        id
+    }
+
+    fn from_extrinsic_metadata(
+        e: ExtrinsicMetadata,
+        extra_ty: u32,
+    ) -> v15::ExtrinsicMetadata<PortableForm> {
         v15::ExtrinsicMetadata {
-            version: e.version,
+            version: e.version(),
             signed_extensions: e
                 .signed_extensions
                 .into_iter()
@@ -341,7 +431,7 @@ mod into_v15 {
             address_ty: e.address_ty.into(),
             call_ty: e.call_ty.into(),
             signature_ty: e.signature_ty.into(),
-            extra_ty: e.extra_ty.into(),
+            extra_ty: extra_ty.into(),
         }
     }
 
@@ -0,0 +1,901 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::TryFromError;
+
+use crate::utils::variant_index::VariantIndex;
+use crate::{
+    utils::ordered_map::OrderedMap, ArcStr, AssociatedTypeMetadata, ConstantMetadata,
+    DeprecationInfo, DeprecationStatus, ExtrinsicMetadata, Metadata, OuterEnumsMetadata,
+    PalletMetadataInner, RuntimeApiMetadataInner, RuntimeApiMethodMetadata,
+    RuntimeApiMethodParamMetadata, SignedExtensionMetadata, StorageEntryMetadata,
+    StorageEntryModifier, StorageEntryType, StorageHasher, StorageMetadata, ViewFunctionMetadata,
+    ViewFunctionParamMetadata,
+};
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+use frame_metadata::{v15, v16};
+use hashbrown::HashMap;
+use scale_info::form::PortableForm;
+
+// Converting from V16 metadata into our Subxt repr.
+mod from_v16 {
+    use super::*;
+
+    impl TryFrom<v16::RuntimeMetadataV16> for Metadata {
+        type Error = TryFromError;
+        fn try_from(m: v16::RuntimeMetadataV16) -> Result<Self, TryFromError> {
+            // V16 no longer hands us the `Runtime` and `Extra` types. We keep hold of them
+            // if they exist anyway, but never add them to the type registry, since doing so
+            // would change the types (and thus hashes) of the metadata.
+            let runtime_ty = find_runtime_type(&m);
+            let extra_ty = find_extra_type(&m);
+
+            let mut pallets = OrderedMap::new();
+            let mut pallets_by_index = HashMap::new();
+            for (pos, p) in m.pallets.into_iter().enumerate() {
+                let name: ArcStr = p.name.into();
+
+                let storage = p.storage.map(|s| StorageMetadata {
+                    prefix: s.prefix,
+                    entries: s
+                        .entries
+                        .into_iter()
+                        .map(|s| {
+                            let name: ArcStr = s.name.clone().into();
+                            (name.clone(), from_storage_entry_metadata(name, s))
+                        })
+                        .collect(),
+                });
+                let constants = p.constants.into_iter().map(|c| {
+                    let name: ArcStr = c.name.clone().into();
+                    (name.clone(), from_constant_metadata(name, c))
+                });
+                let associated_types = p.associated_types.into_iter().map(|a| {
+                    let name: ArcStr = a.name.clone().into();
+                    (name.clone(), from_associated_type_metadata(name, a))
+                });
+                let view_functions = p.view_functions.into_iter().map(|v| {
+                    let name: ArcStr = v.name.clone().into();
+                    (name.clone(), from_view_function_metadata(name, v))
+                });
+
+                let call_variant_index =
+                    VariantIndex::build(p.calls.as_ref().map(|c| c.ty.id), &m.types);
+                let error_variant_index =
+                    VariantIndex::build(p.error.as_ref().map(|e| e.ty.id), &m.types);
+                let event_variant_index =
+                    VariantIndex::build(p.event.as_ref().map(|e| e.ty.id), &m.types);
+
+                let (call_ty, call_deprecation_info) = match p.calls {
+                    Some(c) => (Some(c.ty.id), from_deprecation_info(c.deprecation_info)),
+                    None => (None, DeprecationInfo::NotDeprecated),
+                };
+                let (event_ty, event_deprecation_info) = match p.event {
+                    Some(e) => (Some(e.ty.id), from_deprecation_info(e.deprecation_info)),
+                    None => (None, DeprecationInfo::NotDeprecated),
+                };
+                let (error_ty, error_deprecation_info) = match p.error {
+                    Some(e) => (Some(e.ty.id), from_deprecation_info(e.deprecation_info)),
+                    None => (None, DeprecationInfo::NotDeprecated),
+                };
+
+                pallets_by_index.insert(p.index, pos);
+                pallets.push_insert(
+                    name.clone(),
+                    PalletMetadataInner {
+                        name,
+                        index: p.index,
+                        storage,
+                        call_ty,
+                        call_variant_index,
+                        call_deprecation_info,
+                        event_ty,
+                        event_variant_index,
+                        event_deprecation_info,
+                        error_ty,
+                        error_variant_index,
+                        error_deprecation_info,
+                        constants: constants.collect(),
+                        associated_types: associated_types.collect(),
+                        view_functions: view_functions.collect(),
+                        docs: p.docs,
+                        deprecation_info: from_deprecation_status(p.deprecation_info),
+                    },
+                );
+            }
+
+            let apis = m.apis.into_iter().map(|api| {
+                let name: ArcStr = api.name.clone().into();
+                (name.clone(), from_runtime_api_metadata(name, api))
+            });
+
+            let dispatch_error_ty = m
+                .types
+                .types
+                .iter()
+                .find(|ty| ty.ty.path.segments == ["sp_runtime", "DispatchError"])
+                .map(|ty| ty.id);
+
+            let custom = v15::CustomMetadata {
+                map: m
+                    .custom
+                    .map
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = v15::CustomValueMetadata {
+                            ty: value.ty,
+                            value: value.value,
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+            };
+
+            Ok(Metadata {
+                types: m.types,
+                pallets,
+                pallets_by_index,
+                extrinsic: from_extrinsic_metadata(
+                    m.extrinsic,
+                    m.outer_enums.call_enum_ty.id,
+                    extra_ty,
+                ),
+                runtime_ty,
+                dispatch_error_ty,
+                apis: apis.collect(),
+                outer_enums: OuterEnumsMetadata {
+                    call_enum_ty: m.outer_enums.call_enum_ty.id,
+                    event_enum_ty: m.outer_enums.event_enum_ty.id,
+                    error_enum_ty: m.outer_enums.error_enum_ty.id,
+                },
+                custom,
+            })
+        }
+    }
+
+    /// The `Runtime` type lives alongside the outer `RuntimeCall` enum. Find it, if it's
+    /// in the type registry.
+    fn find_runtime_type(m: &v16::RuntimeMetadataV16) -> Option<u32> {
+        let call_enum = m.types.resolve(m.outer_enums.call_enum_ty.id)?;
+
+        let mut segments = call_enum.path.segments.clone();
+        "Runtime".clone_into(segments.last_mut()?);
+
+        m.types
+            .types
+            .iter()
+            .find(|ty| ty.ty.path.segments == segments)
+            .map(|ty| ty.id)
+    }
+
+    /// The `Extra` type is the tuple of the types of every transaction extension used by
+    /// transaction extension version 0. Find it, if it's in the type registry.
+    fn find_extra_type(m: &v16::RuntimeMetadataV16) -> Option<u32> {
+        let extension_tys: Vec<_> = m
+            .extrinsic
+            .transaction_extensions_by_version
+            .get(&0)?
+            .iter()
+            .map(|&idx| {
+                m.extrinsic
+                    .transaction_extensions
+                    .get(idx as usize)
+                    .map(|ext| ext.ty)
+            })
+            .collect::<Option<_>>()?;
+
+        m.types
+            .types
+            .iter()
+            .find(|ty| {
+                matches!(
+                    &ty.ty.type_def,
+                    scale_info::TypeDef::Tuple(tuple) if tuple.fields == extension_tys
+                )
+            })
+            .map(|ty| ty.id)
+    }
+
+    fn from_deprecation_status(value: v16::DeprecationStatus<PortableForm>) -> DeprecationStatus {
+        match value {
+            v16::DeprecationStatus::NotDeprecated => DeprecationStatus::NotDeprecated,
+            v16::DeprecationStatus::DeprecatedWithoutNote => {
+                DeprecationStatus::DeprecatedWithoutNote
+            }
+            v16::DeprecationStatus::Deprecated { note, since } => {
+                DeprecationStatus::Deprecated { note, since }
+            }
+        }
+    }
+
+    fn from_deprecation_info(value: v16::DeprecationInfo<PortableForm>) -> DeprecationInfo {
+        match value {
+            v16::DeprecationInfo::NotDeprecated => DeprecationInfo::NotDeprecated,
+            v16::DeprecationInfo::ItemDeprecated(status) => {
+                DeprecationInfo::ItemDeprecated(from_deprecation_status(status))
+            }
+            v16::DeprecationInfo::VariantsDeprecated(variants) => {
+                DeprecationInfo::VariantsDeprecated(
+                    variants
+                        .into_iter()
+                        .map(|(idx, status)| (idx, from_deprecation_status(status)))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn from_transaction_extension_metadata(
+        value: v16::TransactionExtensionMetadata<PortableForm>,
+    ) -> SignedExtensionMetadata {
+        SignedExtensionMetadata {
+            identifier: value.identifier,
+            extra_ty: value.ty.id,
+            additional_ty: value.implicit.id,
+        }
+    }
+
+    fn from_extrinsic_metadata(
+        value: v16::ExtrinsicMetadata<PortableForm>,
+        call_ty: u32,
+        extra_ty: Option<u32>,
+    ) -> ExtrinsicMetadata {
+        let transaction_extensions: Vec<_> = value
+            .transaction_extensions
+            .into_iter()
+            .map(from_transaction_extension_metadata)
+            .collect();
+        let signed_extensions = value
+            .transaction_extensions_by_version
+            .get(&0)
+            .into_iter()
+            .flatten()
+            .filter_map(|&idx| transaction_extensions.get(idx as usize).cloned())
+            .collect();
+        ExtrinsicMetadata {
+            supported_versions: value.versions,
+            signed_extensions,
+            transaction_extensions,
+            transaction_extensions_by_version: value.transaction_extensions_by_version,
+            address_ty: value.address_ty.id,
+            call_ty,
+            signature_ty: value.signature_ty.id,
+            extra_ty,
+        }
+    }
+
+    fn from_storage_hasher(value: v16::StorageHasher) -> StorageHasher {
+        match value {
+            v16::StorageHasher::Blake2_128 => StorageHasher::Blake2_128,
+            v16::StorageHasher::Blake2_256 => StorageHasher::Blake2_256,
+            v16::StorageHasher::Blake2_128Concat => StorageHasher::Blake2_128Concat,
+            v16::StorageHasher::Twox128 => StorageHasher::Twox128,
+            v16::StorageHasher::Twox256 => StorageHasher::Twox256,
+            v16::StorageHasher::Twox64Concat => StorageHasher::Twox64Concat,
+            v16::StorageHasher::Identity => StorageHasher::Identity,
+        }
+    }
+
+    fn from_storage_entry_type(value: v16::StorageEntryType<PortableForm>) -> StorageEntryType {
+        match value {
+            v16::StorageEntryType::Plain(ty) => StorageEntryType::Plain(ty.id),
+            v16::StorageEntryType::Map {
+                hashers,
+                key,
+                value,
+            } => StorageEntryType::Map {
+                hashers: hashers.into_iter().map(from_storage_hasher).collect(),
+                key_ty: key.id,
+                value_ty: value.id,
+            },
+        }
+    }
+
+    fn from_storage_entry_modifier(value: v16::StorageEntryModifier) -> StorageEntryModifier {
+        match value {
+            v16::StorageEntryModifier::Optional => StorageEntryModifier::Optional,
+            v16::StorageEntryModifier::Default => StorageEntryModifier::Default,
+        }
+    }
+
+    fn from_storage_entry_metadata(
+        name: ArcStr,
+        s: v16::StorageEntryMetadata<PortableForm>,
+    ) -> StorageEntryMetadata {
+        StorageEntryMetadata {
+            name,
+            modifier: from_storage_entry_modifier(s.modifier),
+            entry_type: from_storage_entry_type(s.ty),
+            default: s.default,
+            docs: s.docs,
+            deprecation_info: from_deprecation_status(s.deprecation_info),
+        }
+    }
+
+    fn from_constant_metadata(
+        name: ArcStr,
+        s: v16::PalletConstantMetadata<PortableForm>,
+    ) -> ConstantMetadata {
+        ConstantMetadata {
+            name,
+            ty: s.ty.id,
+            value: s.value,
+            docs: s.docs,
+            deprecation_info: from_deprecation_status(s.deprecation_info),
+        }
+    }
+
+    fn from_associated_type_metadata(
+        name: ArcStr,
+        s: v16::PalletAssociatedTypeMetadata<PortableForm>,
+    ) -> AssociatedTypeMetadata {
+        AssociatedTypeMetadata {
+            name,
+            ty: s.ty.id,
+            docs: s.docs,
+        }
+    }
+
+    fn from_view_function_metadata(
+        name: ArcStr,
+        s: v16::PalletViewFunctionMetadata<PortableForm>,
+    ) -> ViewFunctionMetadata {
+        ViewFunctionMetadata {
+            name,
+            id: s.id,
+            inputs: s
+                .inputs
+                .into_iter()
+                .map(|p| ViewFunctionParamMetadata {
+                    name: p.name,
+                    ty: p.ty.id,
+                })
+                .collect(),
+            output_ty: s.output.id,
+            docs: s.docs,
+            deprecation_info: from_deprecation_status(s.deprecation_info),
+        }
+    }
+
+    fn from_runtime_api_metadata(
+        name: ArcStr,
+        s: v16::RuntimeApiMetadata<PortableForm>,
+    ) -> RuntimeApiMetadataInner {
+        RuntimeApiMetadataInner {
+            name,
+            docs: s.docs,
+            methods: s
+                .methods
+                .into_iter()
+                .map(|m| {
+                    let name: ArcStr = m.name.clone().into();
+                    (name.clone(), from_runtime_api_method_metadata(name, m))
+                })
+                .collect(),
+            version: Some(s.version),
+            deprecation_info: from_deprecation_status(s.deprecation_info),
+        }
+    }
+
+    fn from_runtime_api_method_metadata(
+        name: ArcStr,
+        s: v16::RuntimeApiMethodMetadata<PortableForm>,
+    ) -> RuntimeApiMethodMetadata {
+        RuntimeApiMethodMetadata {
+            name,
+            inputs: s
+                .inputs
+                .into_iter()
+                .map(|p| RuntimeApiMethodParamMetadata {
+                    name: p.name,
+                    ty: p.ty.id,
+                })
+                .collect(),
+            output_ty: s.output.id,
+            docs: s.docs,
+            deprecation_info: from_deprecation_status(s.deprecation_info),
+        }
+    }
+}
+
+// Converting from our metadata repr to V16 metadata.
+mod into_v16 {
+    use super::*;
+
+    impl From<Metadata> for v16::RuntimeMetadataV16 {
+        fn from(m: Metadata) -> Self {
+            let pallets = m.pallets.into_values().into_iter().map(|p| {
+                let storage = p.storage.map(|s| v16::PalletStorageMetadata {
+                    prefix: s.prefix,
+                    entries: s
+                        .entries
+                        .into_values()
+                        .into_iter()
+                        .map(from_storage_entry_metadata)
+                        .collect(),
+                });
+
+                v16::PalletMetadata {
+                    name: (*p.name).to_owned(),
+                    calls: p.call_ty.map(|id| v16::PalletCallMetadata {
+                        ty: id.into(),
+                        deprecation_info: from_deprecation_info(p.call_deprecation_info),
+                    }),
+                    event: p.event_ty.map(|id| v16::PalletEventMetadata {
+                        ty: id.into(),
+                        deprecation_info: from_deprecation_info(p.event_deprecation_info),
+                    }),
+                    error: p.error_ty.map(|id| v16::PalletErrorMetadata {
+                        ty: id.into(),
+                        deprecation_info: from_deprecation_info(p.error_deprecation_info),
+                    }),
+                    storage,
+                    constants: p
+                        .constants
+                        .into_values()
+                        .into_iter()
+                        .map(from_constant_metadata)
+                        .collect(),
+                    associated_types: p
+                        .associated_types
+                        .into_values()
+                        .into_iter()
+                        .map(from_associated_type_metadata)
+                        .collect(),
+                    view_functions: p
+                        .view_functions
+                        .into_values()
+                        .into_iter()
+                        .map(from_view_function_metadata)
+                        .collect(),
+                    index: p.index,
+                    docs: p.docs,
+                    deprecation_info: from_deprecation_status(p.deprecation_info),
+                }
+            });
+
+            v16::RuntimeMetadataV16 {
+                types: m.types,
+                pallets: pallets.collect(),
+                extrinsic: from_extrinsic_metadata(m.extrinsic),
+                apis: m
+                    .apis
+                    .into_values()
+                    .into_iter()
+                    .map(from_runtime_api_metadata)
+                    .collect(),
+                outer_enums: v16::OuterEnums {
+                    call_enum_ty: m.outer_enums.call_enum_ty.into(),
+                    event_enum_ty: m.outer_enums.event_enum_ty.into(),
+                    error_enum_ty: m.outer_enums.error_enum_ty.into(),
+                },
+                custom: v16::CustomMetadata {
+                    map: m
+                        .custom
+                        .map
+                        .into_iter()
+                        .map(|(name, value)| {
+                            let value = v16::CustomValueMetadata {
+                                ty: value.ty,
+                                value: value.value,
+                            };
+                            (name, value)
+                        })
+                        .collect(),
+                },
+            }
+        }
+    }
+
+    fn from_deprecation_status(s: DeprecationStatus) -> v16::DeprecationStatus<PortableForm> {
+        match s {
+            DeprecationStatus::NotDeprecated => v16::DeprecationStatus::NotDeprecated,
+            DeprecationStatus::DeprecatedWithoutNote => {
+                v16::DeprecationStatus::DeprecatedWithoutNote
+            }
+            DeprecationStatus::Deprecated { note, since } => {
+                v16::DeprecationStatus::Deprecated { note, since }
+            }
+        }
+    }
+
+    fn from_deprecation_info(d: DeprecationInfo) -> v16::DeprecationInfo<PortableForm> {
+        match d {
+            DeprecationInfo::NotDeprecated => v16::DeprecationInfo::NotDeprecated,
+            DeprecationInfo::ItemDeprecated(status) => {
+                v16::DeprecationInfo::ItemDeprecated(from_deprecation_status(status))
+            }
+            DeprecationInfo::VariantsDeprecated(variants) => {
+                v16::DeprecationInfo::VariantsDeprecated(
+                    variants
+                        .into_iter()
+                        .map(|(idx, status)| (idx, from_deprecation_status(status)))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn from_runtime_api_metadata(
+        r: RuntimeApiMetadataInner,
+    ) -> v16::RuntimeApiMetadata<PortableForm> {
+        v16::RuntimeApiMetadata {
+            name: (*r.name).to_owned(),
+            methods: r
+                .methods
+                .into_values()
+                .into_iter()
+                .map(from_runtime_api_method_metadata)
+                .collect(),
+            docs: r.docs,
+            deprecation_info: from_deprecation_status(r.deprecation_info),
+            version: r.version.unwrap_or_default(),
+        }
+    }
+
+    fn from_runtime_api_method_metadata(
+        m: RuntimeApiMethodMetadata,
+    ) -> v16::RuntimeApiMethodMetadata<PortableForm> {
+        v16::RuntimeApiMethodMetadata {
+            name: (*m.name).to_owned(),
+            inputs: m
+                .inputs
+                .into_iter()
+                .map(|p| v16::RuntimeApiMethodParamMetadata {
+                    name: p.name,
+                    ty: p.ty.into(),
+                })
+                .collect(),
+            output: m.output_ty.into(),
+            docs: m.docs,
+            deprecation_info: from_deprecation_status(m.deprecation_info),
+        }
+    }
+
+    fn from_extrinsic_metadata(e: ExtrinsicMetadata) -> v16::ExtrinsicMetadata<PortableForm> {
+        v16::ExtrinsicMetadata {
+            versions: e.supported_versions,
+            address_ty: e.address_ty.into(),
+            signature_ty: e.signature_ty.into(),
+            transaction_extensions_by_version: e.transaction_extensions_by_version,
+            transaction_extensions: e
+                .transaction_extensions
+                .into_iter()
+                .map(from_signed_extension_metadata)
+                .collect(),
+        }
+    }
+
+    fn from_signed_extension_metadata(
+        s: SignedExtensionMetadata,
+    ) -> v16::TransactionExtensionMetadata<PortableForm> {
+        v16::TransactionExtensionMetadata {
+            identifier: s.identifier,
+            ty: s.extra_ty.into(),
+            implicit: s.additional_ty.into(),
+        }
+    }
+
+    fn from_constant_metadata(c: ConstantMetadata) -> v16::PalletConstantMetadata<PortableForm> {
+        v16::PalletConstantMetadata {
+            name: (*c.name).to_owned(),
+            ty: c.ty.into(),
+            value: c.value,
+            docs: c.docs,
+            deprecation_info: from_deprecation_status(c.deprecation_info),
+        }
+    }
+
+    fn from_associated_type_metadata(
+        a: AssociatedTypeMetadata,
+    ) -> v16::PalletAssociatedTypeMetadata<PortableForm> {
+        v16::PalletAssociatedTypeMetadata {
+            name: (*a.name).to_owned(),
+            ty: a.ty.into(),
+            docs: a.docs,
+        }
+    }
+
+    fn from_view_function_metadata(
+        v: ViewFunctionMetadata,
+    ) -> v16::PalletViewFunctionMetadata<PortableForm> {
+        v16::PalletViewFunctionMetadata {
+            name: (*v.name).to_owned(),
+            id: v.id,
+            inputs: v
+                .inputs
+                .into_iter()
+                .map(|p| v16::PalletViewFunctionParamMetadata {
+                    name: p.name,
+                    ty: p.ty.into(),
+                })
+                .collect(),
+            output: v.output_ty.into(),
+            docs: v.docs,
+            deprecation_info: from_deprecation_status(v.deprecation_info),
+        }
+    }
+
+    fn from_storage_entry_metadata(
+        s: StorageEntryMetadata,
+    ) -> v16::StorageEntryMetadata<PortableForm> {
+        v16::StorageEntryMetadata {
+            docs: s.docs,
+            default: s.default,
+            name: (*s.name).to_owned(),
+            ty: from_storage_entry_type(s.entry_type),
+            modifier: from_storage_entry_modifier(s.modifier),
+            deprecation_info: from_deprecation_status(s.deprecation_info),
+        }
+    }
+
+    fn from_storage_entry_modifier(s: StorageEntryModifier) -> v16::StorageEntryModifier {
+        match s {
+            StorageEntryModifier::Default => v16::StorageEntryModifier::Default,
+            StorageEntryModifier::Optional => v16::StorageEntryModifier::Optional,
+        }
+    }
+
+    fn from_storage_entry_type(s: StorageEntryType) -> v16::StorageEntryType<PortableForm> {
+        match s {
+            StorageEntryType::Plain(ty) => v16::StorageEntryType::Plain(ty.into()),
+            StorageEntryType::Map {
+                hashers,
+                key_ty,
+                value_ty,
+            } => v16::StorageEntryType::Map {
+                hashers: hashers.into_iter().map(from_storage_hasher).collect(),
+                key: key_ty.into(),
+                value: value_ty.into(),
+            },
+        }
+    }
+
+    fn from_storage_hasher(s: StorageHasher) -> v16::StorageHasher {
+        match s {
+            StorageHasher::Blake2_128 => v16::StorageHasher::Blake2_128,
+            StorageHasher::Blake2_256 => v16::StorageHasher::Blake2_256,
+            StorageHasher::Blake2_128Concat => v16::StorageHasher::Blake2_128Concat,
+            StorageHasher::Twox128 => v16::StorageHasher::Twox128,
+            StorageHasher::Twox256 => v16::StorageHasher::Twox256,
+            StorageHasher::Twox64Concat => v16::StorageHasher::Twox64Concat,
+            StorageHasher::Identity => v16::StorageHasher::Identity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Decode;
+    use scale_info::{meta_type, TypeInfo};
+
+    fn load_metadata() -> Metadata {
+        let bytes = std::fs::read("../artifacts/polkadot_metadata_full.scale").unwrap();
+        Metadata::decode(&mut &*bytes).unwrap()
+    }
+
+    #[test]
+    fn v16_round_trips() {
+        let metadata = load_metadata();
+
+        let v16: v16::RuntimeMetadataV16 = metadata.clone().into();
+        let from_v16 = Metadata::try_from(v16.clone()).unwrap();
+
+        // The `Runtime` and `Extra` types already exist, so we should find them again:
+        assert_eq!(from_v16.try_runtime_ty(), metadata.try_runtime_ty());
+        assert_eq!(
+            from_v16.extrinsic().try_extra_ty(),
+            metadata.extrinsic().try_extra_ty()
+        );
+        assert_eq!(from_v16.types().types.len(), metadata.types().types.len());
+
+        let v16_again: v16::RuntimeMetadataV16 = from_v16.into();
+        assert_eq!(v16, v16_again);
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Call {
+        #[codec(index = 0)]
+        Foo,
+        #[codec(index = 1)]
+        Bar,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum RuntimeCall {
+        Test(Call),
+    }
+
+    fn test_metadata() -> v16::RuntimeMetadataV16 {
+        let pallet = v16::PalletMetadata {
+            name: "Test",
+            storage: None,
+            calls: Some(v16::PalletCallMetadata {
+                ty: meta_type::<Call>(),
+                deprecation_info: v16::DeprecationInfo::VariantsDeprecated(
+                    [(
+                        1,
+                        v16::DeprecationStatus::Deprecated {
+                            note: "use foo instead",
+                            since: None,
+                        },
+                    )]
+                    .into(),
+                ),
+            }),
+            event: None,
+            constants: vec![],
+            error: None,
+            associated_types: vec![v16::PalletAssociatedTypeMetadata {
+                name: "Balance",
+                ty: meta_type::<u128>(),
+                docs: vec![],
+            }],
+            view_functions: vec![v16::PalletViewFunctionMetadata {
+                name: "get_value",
+                id: [1; 32],
+                inputs: vec![v16::PalletViewFunctionParamMetadata {
+                    name: "key",
+                    ty: meta_type::<u32>(),
+                }],
+                output: meta_type::<Option<u64>>(),
+                docs: vec![],
+                deprecation_info: v16::DeprecationStatus::DeprecatedWithoutNote,
+            }],
+            index: 0,
+            docs: vec![],
+            deprecation_info: v16::DeprecationStatus::NotDeprecated,
+        };
+
+        v16::RuntimeMetadataV16::new(
+            vec![pallet],
+            v16::ExtrinsicMetadata {
+                versions: vec![4, 5],
+                address_ty: meta_type::<()>(),
+                signature_ty: meta_type::<()>(),
+                transaction_extensions_by_version: [(0, vec![1, 0]), (1, vec![0])].into(),
+                transaction_extensions: vec![
+                    v16::TransactionExtensionMetadata {
+                        identifier: "First",
+                        ty: meta_type::<u8>(),
+                        implicit: meta_type::<()>(),
+                    },
+                    v16::TransactionExtensionMetadata {
+                        identifier: "Second",
+                        ty: meta_type::<u16>(),
+                        implicit: meta_type::<()>(),
+                    },
+                ],
+            },
+            vec![],
+            v16::OuterEnums {
+                call_enum_ty: meta_type::<RuntimeCall>(),
+                event_enum_ty: meta_type::<()>(),
+                error_enum_ty: meta_type::<()>(),
+            },
+            v16::CustomMetadata {
+                map: Default::default(),
+            },
+        )
+    }
+
+    #[test]
+    fn v16_specific_items_are_exposed() {
+        let metadata: Metadata = test_metadata().try_into().unwrap();
+        let pallet = metadata.pallet_by_name("Test").unwrap();
+
+        let balance = pallet.associated_type_by_name("Balance").unwrap();
+        assert_eq!(
+            metadata.types().resolve(balance.ty()).unwrap().type_def,
+            scale_info::TypeDef::Primitive(scale_info::TypeDefPrimitive::U128)
+        );
+
+        let view_function = pallet.view_function_by_name("get_value").unwrap();
+        assert_eq!(view_function.id(), [1; 32]);
+        assert_eq!(view_function.inputs().len(), 1);
+        assert!(view_function.deprecation_info().is_deprecated());
+
+        let calls = pallet.call_deprecation_info();
+        assert!(!calls.variant(0).is_deprecated());
+        assert_eq!(calls.variant(1).note(), Some("use foo instead"));
+
+        let extrinsic = metadata.extrinsic();
+        assert_eq!(extrinsic.supported_versions(), &[4, 5]);
+        assert_eq!(extrinsic.version(), 4);
+        assert_eq!(extrinsic.highest_version(), 5);
+
+        let ext_names = |version| -> Vec<_> {
+            extrinsic
+                .transaction_extensions_by_version(version)
+                .unwrap()
+                .map(|ext| ext.identifier())
+                .collect()
+        };
+        assert_eq!(ext_names(0), ["Second", "First"]);
+        assert_eq!(ext_names(1), ["First"]);
+        assert!(extrinsic.transaction_extensions_by_version(2).is_none());
+
+        // The signed extensions are those used by v4 extrinsics, ie version 0.
+        let signed_ext_names: Vec<_> = extrinsic
+            .signed_extensions()
+            .iter()
+            .map(|ext| ext.identifier())
+            .collect();
+        assert_eq!(signed_ext_names, ["Second", "First"]);
+    }
+
+    #[test]
+    fn missing_runtime_and_extra_types_are_only_generated_for_v15() {
+        let v16 = test_metadata();
+        let num_types = v16.types.types.len();
+        let metadata: Metadata = v16.clone().try_into().unwrap();
+
+        // The type registry is left untouched, so type IDs and hashes are unaffected.
+        assert_eq!(metadata.types().types.len(), num_types);
+        assert!(metadata.try_runtime_ty().is_none());
+        assert!(metadata.extrinsic().try_extra_ty().is_none());
+        let v16_again: v16::RuntimeMetadataV16 = metadata.clone().into();
+        assert_eq!(v16, v16_again);
+
+        // V15 metadata needs these types, so they are added when converting to it.
+        let v15: v15::RuntimeMetadataV15 = metadata.into();
+        assert_eq!(v15.types.types.len(), num_types + 2);
+
+        let runtime_ty = v15.types.resolve(v15.ty.id).unwrap();
+        assert!(runtime_ty.path.segments.ends_with(&["Runtime".to_owned()]));
+
+        let extra_ty = v15.types.resolve(v15.extrinsic.extra_ty.id).unwrap();
+        let scale_info::TypeDef::Tuple(tuple) = &extra_ty.type_def else {
+            panic!("Extra type should be a tuple");
+        };
+        let extension_tys: Vec<_> = v15
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|ext| ext.ty)
+            .collect();
+        assert_eq!(tuple.fields, extension_tys);
+        assert_eq!(extension_tys.len(), 2);
+    }
+
+    #[test]
+    fn view_function_hash_depends_on_its_id_inputs_and_output() {
+        let view_function_hash = |v16: v16::RuntimeMetadataV16| {
+            let metadata: Metadata = v16.try_into().unwrap();
+            let pallet = metadata.pallet_by_name("Test").unwrap();
+            pallet.view_function_hash("get_value").unwrap()
+        };
+        let with_view_function = |f: fn(&mut v16::PalletViewFunctionMetadata<PortableForm>)| {
+            let mut v16 = test_metadata();
+            f(&mut v16.pallets[0].view_functions[0]);
+            view_function_hash(v16)
+        };
+
+        let hash = view_function_hash(test_metadata());
+        assert_eq!(hash, view_function_hash(test_metadata()));
+
+        // Docs and deprecation info don't change how the view function is called.
+        assert_eq!(
+            hash,
+            with_view_function(|v| v.docs.push("Some docs".into()))
+        );
+        assert_eq!(
+            hash,
+            with_view_function(|v| v.deprecation_info = v16::DeprecationStatus::NotDeprecated)
+        );
+
+        // The ID, inputs and output do.
+        assert_ne!(hash, with_view_function(|v| v.id = [2; 32]));
+        assert_ne!(
+            hash,
+            with_view_function(|v| v.inputs[0].name = "other".into())
+        );
+        assert_ne!(hash, with_view_function(|v| v.inputs.clear()));
+        assert_ne!(hash, with_view_function(|v| v.output = v.inputs[0].ty));
+    }
+}
@@ -22,6 +22,7 @@ extern crate alloc;
 mod from_into;
 mod utils;
 
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -34,6 +35,9 @@ type ArcStr = Arc<str>;
 
 use crate::utils::validation::{get_custom_value_hash, HASH_LEN};
 pub use from_into::TryFromError;
+pub use utils::merkleize::{
+    ExtrinsicProof, MerkleizeError, MerkleizedMetadata, MetadataDigest, MetadataDigestInfo,
+};
 pub use utils::validation::MetadataHasher;
 
 /// Node metadata. This can be constructed by providing some compatible [`frame_metadata`]
@@ -49,8 +53,8 @@ pub struct Metadata {
     pallets_by_index: HashMap<u8, usize>,
     /// Metadata of the extrinsic.
     extrinsic: ExtrinsicMetadata,
-    /// The type ID of the `Runtime` type.
-    runtime_ty: u32,
+    /// The type ID of the `Runtime` type, if known.
+    runtime_ty: Option<u32>,
     /// The types of the outer enums.
     outer_enums: OuterEnumsMetadata,
     /// The type Id of the `DispatchError` type, which Subxt makes use of.
@@ -73,7 +77,19 @@ impl Metadata {
     }
 
     /// The type ID of the `Runtime` type.
+    ///
+    /// # Panics
+    ///
+    /// V16 metadata no longer provides this type, and so this panics if it could not be
+    /// found in the type registry. Use [`Metadata::try_runtime_ty()`] to handle this case.
     pub fn runtime_ty(&self) -> u32 {
+        self.runtime_ty
+            .expect("the Runtime type is not known for this metadata")
+    }
+
+    /// The type ID of the `Runtime` type, if known. V16 metadata no longer provides this,
+    /// so it is only known if the type happens to be in the type registry.
+    pub fn try_runtime_ty(&self) -> Option<u32> {
         self.runtime_ty
     }
 
@@ -153,6 +169,14 @@ impl Metadata {
         MetadataHasher::new(self)
     }
 
+    /// Merkleize the type information needed to decode extrinsics, as described in
+    /// [RFC-0078](https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html).
+    /// This can then be used to calculate the metadata hash expected by the
+    /// `CheckMetadataHash` signed extension.
+    pub fn merkleize(&self) -> Result<MerkleizedMetadata, MerkleizeError> {
+        MerkleizedMetadata::new(self)
+    }
+
     /// Filter out any pallets that we don't want to keep, retaining only those that we do.
     pub fn retain<F, G>(&mut self, pallet_filter: F, api_filter: G)
     where
@@ -196,6 +220,11 @@ impl<'a> PalletMetadata<'a> {
         &self.inner.docs
     }
 
+    /// Whether the pallet is deprecated.
+    pub fn deprecation_info(&self) -> &'a DeprecationStatus {
+        &self.inner.deprecation_info
+    }
+
     /// Type ID for the pallet's Call type, if it exists.
     pub fn call_ty_id(&self) -> Option<u32> {
         self.inner.call_ty
@@ -211,6 +240,21 @@ impl<'a> PalletMetadata<'a> {
         self.inner.error_ty
     }
 
+    /// Whether the pallet's calls, or individual call variants, are deprecated.
+    pub fn call_deprecation_info(&self) -> &'a DeprecationInfo {
+        &self.inner.call_deprecation_info
+    }
+
+    /// Whether the pallet's events, or individual event variants, are deprecated.
+    pub fn event_deprecation_info(&self) -> &'a DeprecationInfo {
+        &self.inner.event_deprecation_info
+    }
+
+    /// Whether the pallet's errors, or individual error variants, are deprecated.
+    pub fn error_deprecation_info(&self) -> &'a DeprecationInfo {
+        &self.inner.error_deprecation_info
+    }
+
     /// Return metadata about the pallet's storage entries.
     pub fn storage(&self) -> Option<&'a StorageMetadata> {
         self.inner.storage.as_ref()
@@ -273,6 +317,26 @@ impl<'a> PalletMetadata<'a> {
         self.inner.constants.values().iter()
     }
 
+    /// Return associated type details given the associated type name.
+    pub fn associated_type_by_name(&self, name: &str) -> Option<&'a AssociatedTypeMetadata> {
+        self.inner.associated_types.get_by_key(name)
+    }
+
+    /// An iterator over the associated types of this pallet's config.
+    pub fn associated_types(&self) -> impl ExactSizeIterator<Item = &'a AssociatedTypeMetadata> {
+        self.inner.associated_types.values().iter()
+    }
+
+    /// Return view function details given the view function name.
+    pub fn view_function_by_name(&self, name: &str) -> Option<&'a ViewFunctionMetadata> {
+        self.inner.view_functions.get_by_key(name)
+    }
+
+    /// An iterator over the view functions in this pallet.
+    pub fn view_functions(&self) -> impl ExactSizeIterator<Item = &'a ViewFunctionMetadata> {
+        self.inner.view_functions.values().iter()
+    }
+
     /// Return a hash for the storage entry, or None if it was not found.
     pub fn storage_hash(&self, entry_name: &str) -> Option<[u8; HASH_LEN]> {
         crate::utils::validation::get_storage_hash(self, entry_name)
@@ -288,6 +352,11 @@ impl<'a> PalletMetadata<'a> {
         crate::utils::validation::get_call_hash(self, call_name)
     }
 
+    /// Return a hash for the view function, or None if it was not found.
+    pub fn view_function_hash(&self, function_name: &str) -> Option<[u8; HASH_LEN]> {
+        crate::utils::validation::get_view_function_hash(self, function_name)
+    }
+
     /// Return a hash for the entire pallet.
     pub fn hash(&self) -> [u8; HASH_LEN] {
         crate::utils::validation::get_pallet_hash(*self, &OuterEnumHashes::empty())
@@ -306,18 +375,30 @@ struct PalletMetadataInner {
     call_ty: Option<u32>,
     /// Call variants by name/u8.
     call_variant_index: VariantIndex,
+    /// Deprecation info for the pallet calls.
+    call_deprecation_info: DeprecationInfo,
     /// Type ID for the pallet Event enum.
     event_ty: Option<u32>,
     /// Event variants by name/u8.
     event_variant_index: VariantIndex,
+    /// Deprecation info for the pallet events.
+    event_deprecation_info: DeprecationInfo,
     /// Type ID for the pallet Error enum.
     error_ty: Option<u32>,
     /// Error variants by name/u8.
     error_variant_index: VariantIndex,
+    /// Deprecation info for the pallet errors.
+    error_deprecation_info: DeprecationInfo,
     /// Map from constant name to constant details.
     constants: OrderedMap<ArcStr, ConstantMetadata>,
+    /// Map from associated type name to associated type details.
+    associated_types: OrderedMap<ArcStr, AssociatedTypeMetadata>,
+    /// Map from view function name to view function details.
+    view_functions: OrderedMap<ArcStr, ViewFunctionMetadata>,
     /// Pallet documentation.
     docs: Vec<String>,
+    /// Deprecation info for the pallet.
+    deprecation_info: DeprecationStatus,
 }
 
 /// Metadata for the storage entries in a pallet.
@@ -359,6 +440,8 @@ pub struct StorageEntryMetadata {
     default: Vec<u8>,
     /// Storage entry documentation.
     docs: Vec<String>,
+    /// Deprecation info for the storage entry.
+    deprecation_info: DeprecationStatus,
 }
 
 impl StorageEntryMetadata {
@@ -382,6 +465,10 @@ impl StorageEntryMetadata {
     pub fn docs(&self) -> &[String] {
         &self.docs
     }
+    /// Whether the storage entry is deprecated.
+    pub fn deprecation_info(&self) -> &DeprecationStatus {
+        &self.deprecation_info
+    }
 }
 
 /// The type of a storage entry.
@@ -485,6 +572,8 @@ pub struct ConstantMetadata {
     value: Vec<u8>,
     /// Constant documentation.
     docs: Vec<String>,
+    /// Deprecation info for the constant.
+    deprecation_info: DeprecationStatus,
 }
 
 impl ConstantMetadata {
@@ -504,6 +593,147 @@ impl ConstantMetadata {
     pub fn docs(&self) -> &[String] {
         &self.docs
     }
+    /// Whether the constant is deprecated.
+    pub fn deprecation_info(&self) -> &DeprecationStatus {
+        &self.deprecation_info
+    }
+}
+
+/// Metadata for a single associated type of a pallet's config.
+#[derive(Debug, Clone)]
+pub struct AssociatedTypeMetadata {
+    /// Name of the associated type.
+    name: ArcStr,
+    /// Type of the associated type.
+    ty: u32,
+    /// Associated type documentation.
+    docs: Vec<String>,
+}
+
+impl AssociatedTypeMetadata {
+    /// Name of the associated type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Type of the associated type.
+    pub fn ty(&self) -> u32 {
+        self.ty
+    }
+    /// Associated type documentation.
+    pub fn docs(&self) -> &[String] {
+        &self.docs
+    }
+}
+
+/// Metadata for a single pallet view function.
+#[derive(Debug, Clone)]
+pub struct ViewFunctionMetadata {
+    /// View function name.
+    name: ArcStr,
+    /// The unique ID used to call the view function.
+    id: [u8; 32],
+    /// View function parameters.
+    inputs: Vec<ViewFunctionParamMetadata>,
+    /// View function output type.
+    output_ty: u32,
+    /// View function documentation.
+    docs: Vec<String>,
+    /// Deprecation info for the view function.
+    deprecation_info: DeprecationStatus,
+}
+
+impl ViewFunctionMetadata {
+    /// View function name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The unique ID used to call the view function.
+    pub fn id(&self) -> [u8; 32] {
+        self.id
+    }
+    /// View function documentation.
+    pub fn docs(&self) -> &[String] {
+        &self.docs
+    }
+    /// View function inputs.
+    pub fn inputs(&self) -> impl ExactSizeIterator<Item = &ViewFunctionParamMetadata> {
+        self.inputs.iter()
+    }
+    /// View function return type.
+    pub fn output_ty(&self) -> u32 {
+        self.output_ty
+    }
+    /// Whether the view function is deprecated.
+    pub fn deprecation_info(&self) -> &DeprecationStatus {
+        &self.deprecation_info
+    }
+}
+
+/// Metadata for a single input parameter to a pallet view function.
+#[derive(Debug, Clone)]
+pub struct ViewFunctionParamMetadata {
+    /// Parameter name.
+    pub name: String,
+    /// Parameter type.
+    pub ty: u32,
+}
+
+/// The deprecation status of a single item in the metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeprecationStatus {
+    /// The item is not deprecated.
+    NotDeprecated,
+    /// The item is deprecated, but no note was given.
+    DeprecatedWithoutNote,
+    /// The item is deprecated.
+    Deprecated {
+        /// A note explaining the deprecation.
+        note: String,
+        /// The version from which the item is deprecated, if given.
+        since: Option<String>,
+    },
+}
+
+impl DeprecationStatus {
+    /// Is the item deprecated.
+    pub fn is_deprecated(&self) -> bool {
+        !matches!(self, DeprecationStatus::NotDeprecated)
+    }
+
+    /// The note explaining the deprecation, if one was given.
+    pub fn note(&self) -> Option<&str> {
+        match self {
+            DeprecationStatus::Deprecated { note, .. } => Some(note),
+            _ => None,
+        }
+    }
+}
+
+/// The deprecation status of an enum item (ie pallet calls, events or errors),
+/// which can be deprecated as a whole or variant by variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeprecationInfo {
+    /// Neither the item nor any of its variants are deprecated.
+    NotDeprecated,
+    /// The entire item is deprecated.
+    ItemDeprecated(DeprecationStatus),
+    /// Some variants of the item are deprecated, keyed by variant index.
+    VariantsDeprecated(BTreeMap<u8, DeprecationStatus>),
+}
+
+impl DeprecationInfo {
+    /// The deprecation status of a single variant, given its index. This takes
+    /// into account whether the item as a whole is deprecated.
+    pub fn variant(&self, variant_index: u8) -> &DeprecationStatus {
+        const NOT_DEPRECATED: &DeprecationStatus = &DeprecationStatus::NotDeprecated;
+        match self {
+            DeprecationInfo::NotDeprecated => NOT_DEPRECATED,
+            DeprecationInfo::ItemDeprecated(status) => status,
+            DeprecationInfo::VariantsDeprecated(variants) => {
+                variants.get(&variant_index).unwrap_or(NOT_DEPRECATED)
+            }
+        }
+    }
 }
 
 /// Metadata for the extrinsic type.
@@ -515,12 +745,18 @@ pub struct ExtrinsicMetadata {
     call_ty: u32,
     /// The type of the extrinsic's signature.
     signature_ty: u32,
-    /// The type of the outermost Extra enum.
-    extra_ty: u32,
-    /// Extrinsic version.
-    version: u8,
-    /// The signed extensions in the order they appear in the extrinsic.
+    /// The type of the outermost Extra enum, if known.
+    extra_ty: Option<u32>,
+    /// The extrinsic versions supported by the runtime.
+    supported_versions: Vec<u8>,
+    /// The signed extensions used by transaction extension version 0, in the order
+    /// they appear in the extrinsic.
     signed_extensions: Vec<SignedExtensionMetadata>,
+    /// Every transaction extension that the runtime knows about, across all versions.
+    transaction_extensions: Vec<SignedExtensionMetadata>,
+    /// For each transaction extension version, the indexes of the transaction
+    /// extensions used, in the order they appear in the extrinsic.
+    transaction_extensions_by_version: BTreeMap<u8, Vec<u32>>,
 }
 
 impl ExtrinsicMetadata {
@@ -538,19 +774,73 @@ impl ExtrinsicMetadata {
         self.signature_ty
     }
     /// The type of the outermost Extra enum.
+    ///
+    /// # Panics
+    ///
+    /// V16 metadata no longer provides this type, and so this panics if it could not be
+    /// found in the type registry. Use [`ExtrinsicMetadata::try_extra_ty()`] to handle this case.
     pub fn extra_ty(&self) -> u32 {
         self.extra_ty
+            .expect("the Extra type is not known for this metadata")
     }
 
-    /// Extrinsic version.
+    /// The type of the outermost Extra enum, if known. V16 metadata no longer provides
+    /// this, so it is only known if the type happens to be in the type registry.
+    pub fn try_extra_ty(&self) -> Option<u32> {
+        self.extra_ty
+    }
+
+    /// Extrinsic version. If the runtime supports more than one version, this is the
+    /// oldest of them, which is the version that metadata prior to V16 would describe.
+    /// See [`ExtrinsicMetadata::highest_version()`] for the newest supported version.
     pub fn version(&self) -> u8 {
-        self.version
+        self.supported_versions
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// The newest extrinsic version supported by the runtime.
+    pub fn highest_version(&self) -> u8 {
+        self.supported_versions
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_default()
     }
 
-    /// The extra/additional information associated with the extrinsic.
+    /// All of the extrinsic versions supported by the runtime.
+    pub fn supported_versions(&self) -> &[u8] {
+        &self.supported_versions
+    }
+
+    /// The extra/additional information associated with the extrinsic. These are the
+    /// signed extensions used by transaction extension version 0, which is the version
+    /// used by v4 extrinsics. See [`ExtrinsicMetadata::transaction_extensions_by_version()`]
+    /// for the extensions used by other versions.
     pub fn signed_extensions(&self) -> &[SignedExtensionMetadata] {
         &self.signed_extensions
     }
+
+    /// The transaction extension versions supported by the runtime.
+    pub fn transaction_extension_versions(&self) -> impl Iterator<Item = u8> + '_ {
+        self.transaction_extensions_by_version.keys().copied()
+    }
+
+    /// The signed extensions used by the given transaction extension version, in the
+    /// order they appear in the extrinsic, or `None` if the version is not supported.
+    pub fn transaction_extensions_by_version(
+        &self,
+        version: u8,
+    ) -> Option<impl Iterator<Item = &SignedExtensionMetadata>> {
+        let indexes = self.transaction_extensions_by_version.get(&version)?;
+        Some(
+            indexes
+                .iter()
+                .filter_map(|idx| self.transaction_extensions.get(*idx as usize)),
+        )
+    }
 }
 
 /// Metadata for the signed extensions used by extrinsics.
@@ -623,6 +913,14 @@ impl<'a> RuntimeApiMetadata<'a> {
     pub fn docs(&self) -> &[String] {
         &self.inner.docs
     }
+    /// Trait version, if known. This is only provided by V16 metadata and later.
+    pub fn version(&self) -> Option<u32> {
+        self.inner.version
+    }
+    /// Whether the trait is deprecated.
+    pub fn deprecation_info(&self) -> &'a DeprecationStatus {
+        &self.inner.deprecation_info
+    }
     /// An iterator over the trait methods.
     pub fn methods(&self) -> impl ExactSizeIterator<Item = &'a RuntimeApiMethodMetadata> {
         self.inner.methods.values().iter()
@@ -650,6 +948,10 @@ struct RuntimeApiMetadataInner {
     methods: OrderedMap<ArcStr, RuntimeApiMethodMetadata>,
     /// Trait documentation.
     docs: Vec<String>,
+    /// Trait version, if known.
+    version: Option<u32>,
+    /// Deprecation info for the trait.
+    deprecation_info: DeprecationStatus,
 }
 
 /// Metadata for a single runtime API method.
@@ -663,6 +965,8 @@ pub struct RuntimeApiMethodMetadata {
     output_ty: u32,
     /// Method documentation.
     docs: Vec<String>,
+    /// Deprecation info for the method.
+    deprecation_info: DeprecationStatus,
 }
 
 impl RuntimeApiMethodMetadata {
@@ -682,6 +986,10 @@ impl RuntimeApiMethodMetadata {
     pub fn output_ty(&self) -> u32 {
         self.output_ty
     }
+    /// Whether the method is deprecated.
+    pub fn deprecation_info(&self) -> &DeprecationStatus {
+        &self.deprecation_info
+    }
 }
 
 /// Metadata for a single input parameter to a runtime API method.
@@ -773,6 +1081,7 @@ impl codec::Decode for Metadata {
         let metadata = match metadata.1 {
             frame_metadata::RuntimeMetadata::V14(md) => md.try_into(),
             frame_metadata::RuntimeMetadata::V15(md) => md.try_into(),
+            frame_metadata::RuntimeMetadata::V16(md) => md.try_into(),
             _ => return Err("Cannot try_into() to Metadata: unsupported metadata version".into()),
         };
 
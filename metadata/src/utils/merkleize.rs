@@ -0,0 +1,882 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Merkleization of the metadata, as described in
+//! [RFC-0078](https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html).
+//!
+//! Only the types needed to decode extrinsics are kept. These are converted into a
+//! simpler form, split so that each enum variant is its own leaf, and then used to
+//! build a merkle tree. The root of this tree, along with a hash of the extrinsic
+//! details and some chain specific information, makes up the [`MetadataDigest`], whose
+//! hash is what the `CheckMetadataHash` signed extension expects.
+//!
+//! Offline signers can be handed an [`ExtrinsicProof`] alongside the signer payload. This
+//! contains just the leaves needed to decode the extrinsic being signed, plus the nodes
+//! needed to check them against the metadata hash.
+
+mod extrinsic_decoder;
+mod merkle_tree;
+mod types;
+
+use crate::Metadata;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use codec::Encode;
+use core::fmt::Display;
+use extrinsic_decoder::LeafCollector;
+use merkle_tree::{LeafId, MerkleTree};
+use scale_info::{form::PortableForm, PortableRegistry, TypeDef, TypeDefPrimitive};
+
+use types::Hash;
+
+/// An error emitted if the metadata cannot be merkleized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MerkleizeError {
+    /// Type missing from type registry.
+    TypeNotFound(u32),
+    /// A compact type does not wrap a single unsigned integer.
+    InvalidCompactType(u32),
+    /// A bit sequence has an unsupported store or order type.
+    InvalidBitSequence(u32),
+    /// A leaf was not found in the merkle tree.
+    LeafNotFound(usize),
+    /// Only version 4 and version 5 extrinsics can be decoded.
+    UnsupportedExtrinsicVersion(u8),
+    /// The extrinsic could not be decoded using the type information.
+    Decode(String),
+    /// There were bytes left over after decoding the extrinsic.
+    LeftoverBytes,
+}
+
+impl Display for MerkleizeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MerkleizeError::TypeNotFound(e) => write!(
+                f,
+                "Type id {e} is expected but not found in the type registry"
+            ),
+            MerkleizeError::InvalidCompactType(e) => write!(
+                f,
+                "Type {e} is compact encoded but does not wrap a single unsigned integer"
+            ),
+            MerkleizeError::InvalidBitSequence(e) => write!(
+                f,
+                "Type {e} is a bit sequence with an unsupported store or order type"
+            ),
+            MerkleizeError::LeafNotFound(e) => {
+                write!(f, "Leaf {e} was not found in the merkle tree")
+            }
+            MerkleizeError::UnsupportedExtrinsicVersion(e) => {
+                write!(
+                    f,
+                    "Cannot decode v{e} extrinsics; only v4 and v5 are supported"
+                )
+            }
+            MerkleizeError::Decode(e) => write!(f, "Cannot decode extrinsic: {e}"),
+            MerkleizeError::LeftoverBytes => {
+                write!(f, "Bytes were left over after decoding the extrinsic")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleizeError {}
+
+/// Chain specific details which, alongside the metadata, make up the [`MetadataDigest`].
+/// This is the `ExtraInfo` described in RFC-0078.
+#[derive(Debug, Clone, PartialEq, Eq, Encode)]
+pub struct MetadataDigestInfo {
+    /// The spec version of the runtime.
+    pub spec_version: u32,
+    /// The spec name of the runtime.
+    pub spec_name: String,
+    /// The SS58 prefix used for addresses.
+    pub base58_prefix: u16,
+    /// The number of decimals of the native token.
+    pub decimals: u8,
+    /// The symbol of the native token.
+    pub token_symbol: String,
+}
+
+/// The digest whose hash is the "metadata hash" described in RFC-0078.
+#[derive(Debug, Clone, PartialEq, Eq, Encode)]
+#[non_exhaustive]
+pub enum MetadataDigest {
+    /// The first version of the digest.
+    #[codec(index = 1)]
+    V1 {
+        /// The root of the merkle tree built from the type information.
+        types_tree_root: Hash,
+        /// The hash of the extrinsic details.
+        extrinsic_metadata_hash: Hash,
+        /// The spec version of the runtime.
+        spec_version: u32,
+        /// The spec name of the runtime.
+        spec_name: String,
+        /// The SS58 prefix used for addresses.
+        base58_prefix: u16,
+        /// The number of decimals of the native token.
+        decimals: u8,
+        /// The symbol of the native token.
+        token_symbol: String,
+    },
+}
+
+impl MetadataDigest {
+    /// The hash of this digest. This is the value that is added to the signer
+    /// payload when the `CheckMetadataHash` signed extension is enabled.
+    pub fn hash(&self) -> Hash {
+        types::hash(&self.encode())
+    }
+}
+
+/// A proof containing the type information needed to decode a single extrinsic, as
+/// described in RFC-0078. This also contains the extrinsic details and chain specific
+/// information, so that a signer can recalculate the metadata hash from it alone. Use
+/// [`codec::Encode`] to obtain the bytes to hand to a signer.
+#[derive(Debug, Clone, PartialEq, Eq, Encode)]
+pub struct ExtrinsicProof {
+    /// The leaves needed to decode the extrinsic.
+    leaves: Vec<types::Type>,
+    /// The index of each leaf in the tree, in the same order as the leaves.
+    leaf_indices: Vec<u32>,
+    /// The hashes of any other nodes needed to recalculate the root.
+    nodes: Vec<Hash>,
+    /// The extrinsic details.
+    extrinsic: types::ExtrinsicMetadata,
+    /// The chain specific details.
+    extra_info: MetadataDigestInfo,
+}
+
+impl ExtrinsicProof {
+    /// The number of leaves in the proof.
+    pub fn num_leaves(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// The index of each leaf in the tree.
+    pub fn leaf_indices(&self) -> &[u32] {
+        &self.leaf_indices
+    }
+
+    /// The hashes of the additional nodes needed to recalculate the root.
+    pub fn nodes(&self) -> &[Hash] {
+        &self.nodes
+    }
+
+    /// The chain specific details that the proof was generated with.
+    pub fn extra_info(&self) -> &MetadataDigestInfo {
+        &self.extra_info
+    }
+
+    /// The SCALE encoded extrinsic details.
+    pub fn encoded_extrinsic_metadata(&self) -> Vec<u8> {
+        self.extrinsic.encode()
+    }
+}
+
+/// The type information required to decode extrinsics, arranged into a merkle tree.
+/// This is obtained by calling [`crate::Metadata::merkleize()`].
+pub struct MerkleizedMetadata {
+    extrinsic: types::ExtrinsicMetadata,
+    types: BTreeMap<LeafId, types::Type>,
+    tree: MerkleTree,
+}
+
+impl MerkleizedMetadata {
+    /// Merkleize the given metadata.
+    pub(crate) fn new(metadata: &Metadata) -> Result<Self, MerkleizeError> {
+        let registry = metadata.types();
+        let extrinsic = metadata.extrinsic();
+
+        // Find every type that can be reached from the extrinsic.
+        let mut accessible_types = BTreeSet::new();
+        collect_accessible_types(extrinsic.call_ty(), &mut accessible_types, registry)?;
+        collect_accessible_types(extrinsic.address_ty(), &mut accessible_types, registry)?;
+        collect_accessible_types(extrinsic.signature_ty(), &mut accessible_types, registry)?;
+        for ext in extrinsic.signed_extensions() {
+            collect_accessible_types(ext.extra_ty(), &mut accessible_types, registry)?;
+            collect_accessible_types(ext.additional_ty(), &mut accessible_types, registry)?;
+        }
+
+        // Primitive, compact and empty types are described inline rather than being
+        // given leaves, so only the remaining types are assigned new IDs, in order.
+        let mut new_ids = BTreeMap::new();
+        for id in accessible_types {
+            let ty = resolve(registry, id)?;
+            if is_leaf_type(&ty.type_def) {
+                let new_id = new_ids.len() as u32;
+                new_ids.insert(id, new_id);
+            }
+        }
+
+        let converter = Converter { registry, new_ids };
+
+        let extrinsic_metadata = types::ExtrinsicMetadata {
+            version: extrinsic.version(),
+            address_ty: converter.type_ref(extrinsic.address_ty())?,
+            call_ty: converter.type_ref(extrinsic.call_ty())?,
+            signature_ty: converter.type_ref(extrinsic.signature_ty())?,
+            signed_extensions: extrinsic
+                .signed_extensions()
+                .iter()
+                .map(|ext| {
+                    Ok(types::SignedExtensionMetadata {
+                        identifier: ext.identifier().to_string(),
+                        included_in_extrinsic: converter.type_ref(ext.extra_ty())?,
+                        included_in_signed_data: converter.type_ref(ext.additional_ty())?,
+                    })
+                })
+                .collect::<Result<_, MerkleizeError>>()?,
+        };
+
+        let mut leaves = BTreeMap::new();
+        for (&id, &new_id) in &converter.new_ids {
+            for mut leaf in converter.leaves(id)? {
+                leaf.type_id = new_id.into();
+                let leaf_id = match leaf.type_def.as_enumeration() {
+                    Some(variant) => LeafId::Enumeration {
+                        type_id: new_id,
+                        variant: variant.index.0,
+                    },
+                    None => LeafId::Other(new_id),
+                };
+                leaves.insert(leaf_id, leaf);
+            }
+        }
+
+        let tree = MerkleTree::new(leaves.values());
+
+        Ok(MerkleizedMetadata {
+            extrinsic: extrinsic_metadata,
+            types: leaves,
+            tree,
+        })
+    }
+
+    /// The root of the merkle tree built from the type information.
+    pub fn types_tree_root(&self) -> Hash {
+        self.tree.root()
+    }
+
+    /// The hash of the extrinsic details.
+    pub fn extrinsic_metadata_hash(&self) -> Hash {
+        self.extrinsic.hash()
+    }
+
+    /// The number of leaves in the merkle tree.
+    pub fn num_leaves(&self) -> usize {
+        self.types.len()
+    }
+
+    /// The SCALE encoded extrinsic details. A signer needs these, along with the
+    /// [`MetadataDigestInfo`], in order to recalculate the metadata hash.
+    pub fn encoded_extrinsic_metadata(&self) -> Vec<u8> {
+        self.extrinsic.encode()
+    }
+
+    /// Generate a proof containing the type information needed to decode the given
+    /// extrinsic. If `additional_signed` is provided, the types needed to decode it are
+    /// included too. The `info` is included in the proof as is.
+    pub fn extrinsic_proof(
+        &self,
+        mut extrinsic: &[u8],
+        additional_signed: Option<&[u8]>,
+        info: MetadataDigestInfo,
+    ) -> Result<ExtrinsicProof, MerkleizeError> {
+        let mut collector = LeafCollector::new(self.types.values());
+        collector.decode_extrinsic(&mut extrinsic, additional_signed, &self.extrinsic)?;
+        if !extrinsic.is_empty() {
+            return Err(MerkleizeError::LeftoverBytes);
+        }
+        self.build_proof(collector.into_accessed(), info)
+    }
+
+    /// Generate a proof containing the type information needed to decode an extrinsic
+    /// with the given call data. This is useful when the extrinsic has yet to be signed.
+    ///
+    /// If the extrinsic will be signed, `signed_ext_data` should contain the bytes from each
+    /// signed extension that are included in the extrinsic and in the signer payload
+    /// respectively. The address and signature types are then included in their entirety,
+    /// since we don't yet know which variants of them will be used. The `info` is included
+    /// in the proof as is.
+    pub fn extrinsic_parts_proof(
+        &self,
+        mut call: &[u8],
+        signed_ext_data: Option<(&[u8], &[u8])>,
+        info: MetadataDigestInfo,
+    ) -> Result<ExtrinsicProof, MerkleizeError> {
+        let mut collector = LeafCollector::new(self.types.values());
+        collector.decode_extrinsic_parts(&mut call, signed_ext_data, &self.extrinsic)?;
+        if !call.is_empty() {
+            return Err(MerkleizeError::LeftoverBytes);
+        }
+        self.build_proof(collector.into_accessed(), info)
+    }
+
+    fn build_proof(
+        &self,
+        accessed: BTreeSet<LeafId>,
+        extra_info: MetadataDigestInfo,
+    ) -> Result<ExtrinsicProof, MerkleizeError> {
+        // Leaves are stored in order, so their position is their index in the tree.
+        let leaf_indices = self
+            .types
+            .keys()
+            .enumerate()
+            .filter(|(_, id)| accessed.contains(id))
+            .map(|(idx, _)| idx);
+
+        let (node_indices, nodes) = self.tree.build_proof(leaf_indices)?;
+
+        let num_leaves = self.types.len();
+        let all_leaves: Vec<&types::Type> = self.types.values().collect();
+        let leaves = node_indices
+            .iter()
+            .map(|node_idx| {
+                let leaf_idx = node_idx + 1 - num_leaves;
+                all_leaves
+                    .get(leaf_idx)
+                    .map(|&ty| ty.clone())
+                    .ok_or(MerkleizeError::LeafNotFound(leaf_idx))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ExtrinsicProof {
+            leaves,
+            leaf_indices: node_indices.into_iter().map(|idx| idx as u32).collect(),
+            nodes,
+            extrinsic: self.extrinsic.clone(),
+            extra_info,
+        })
+    }
+
+    /// Combine this with some chain specific details to build the [`MetadataDigest`].
+    pub fn digest(&self, info: MetadataDigestInfo) -> MetadataDigest {
+        MetadataDigest::V1 {
+            types_tree_root: self.types_tree_root(),
+            extrinsic_metadata_hash: self.extrinsic_metadata_hash(),
+            spec_version: info.spec_version,
+            spec_name: info.spec_name,
+            base58_prefix: info.base58_prefix,
+            decimals: info.decimals,
+            token_symbol: info.token_symbol,
+        }
+    }
+}
+
+fn resolve(
+    registry: &PortableRegistry,
+    id: u32,
+) -> Result<&scale_info::Type<PortableForm>, MerkleizeError> {
+    registry.resolve(id).ok_or(MerkleizeError::TypeNotFound(id))
+}
+
+/// Types which are given their own leaves in the tree. Everything else is described
+/// inline by a [`types::TypeRef`].
+fn is_leaf_type(type_def: &TypeDef<PortableForm>) -> bool {
+    match type_def {
+        TypeDef::Compact(_) | TypeDef::Primitive(_) => false,
+        TypeDef::Variant(v) => !v.variants.is_empty(),
+        TypeDef::Composite(c) => !c.fields.is_empty(),
+        TypeDef::Tuple(t) => !t.fields.is_empty(),
+        TypeDef::Sequence(_) | TypeDef::Array(_) | TypeDef::BitSequence(_) => true,
+    }
+}
+
+fn collect_accessible_types(
+    id: u32,
+    accessible_types: &mut BTreeSet<u32>,
+    registry: &PortableRegistry,
+) -> Result<(), MerkleizeError> {
+    if !accessible_types.insert(id) {
+        return Ok(());
+    }
+
+    match &resolve(registry, id)?.type_def {
+        TypeDef::Composite(c) => {
+            for f in &c.fields {
+                collect_accessible_types(f.ty.id, accessible_types, registry)?;
+            }
+        }
+        TypeDef::Variant(v) => {
+            for f in v.variants.iter().flat_map(|v| &v.fields) {
+                collect_accessible_types(f.ty.id, accessible_types, registry)?;
+            }
+        }
+        TypeDef::Sequence(s) => {
+            collect_accessible_types(s.type_param.id, accessible_types, registry)?
+        }
+        TypeDef::Array(a) => collect_accessible_types(a.type_param.id, accessible_types, registry)?,
+        TypeDef::Tuple(t) => {
+            for f in &t.fields {
+                collect_accessible_types(f.id, accessible_types, registry)?;
+            }
+        }
+        // Compact types are described inline, and bit sequences only need to know
+        // about the shape of their store and order types, so we don't need to dig
+        // into any of these.
+        TypeDef::Primitive(_) | TypeDef::Compact(_) | TypeDef::BitSequence(_) => {}
+    }
+
+    Ok(())
+}
+
+/// Find all of the primitive types reachable from the given type.
+fn collect_primitives(
+    registry: &PortableRegistry,
+    id: u32,
+    found: &mut Vec<TypeDefPrimitive>,
+    visited: &mut BTreeSet<u32>,
+) -> Result<(), MerkleizeError> {
+    let mut visit = |id: u32, found: &mut Vec<TypeDefPrimitive>| {
+        if visited.insert(id) {
+            collect_primitives(registry, id, found, visited)
+        } else {
+            Ok(())
+        }
+    };
+
+    match &resolve(registry, id)?.type_def {
+        TypeDef::Composite(c) => c.fields.iter().try_for_each(|f| visit(f.ty.id, found))?,
+        TypeDef::Variant(v) => v
+            .variants
+            .iter()
+            .flat_map(|v| &v.fields)
+            .try_for_each(|f| visit(f.ty.id, found))?,
+        TypeDef::Sequence(s) => visit(s.type_param.id, found)?,
+        TypeDef::Array(a) => visit(a.type_param.id, found)?,
+        TypeDef::Tuple(t) => t.fields.iter().try_for_each(|f| visit(f.id, found))?,
+        TypeDef::Compact(c) => visit(c.type_param.id, found)?,
+        TypeDef::BitSequence(b) => {
+            visit(b.bit_order_type.id, found)?;
+            visit(b.bit_store_type.id, found)?;
+        }
+        TypeDef::Primitive(p) => found.push(p.clone()),
+    }
+
+    Ok(())
+}
+
+/// Converts types from the registry into the format that is merkleized.
+struct Converter<'a> {
+    registry: &'a PortableRegistry,
+    /// Map from the ID of a type in the registry to its new ID.
+    new_ids: BTreeMap<u32, u32>,
+}
+
+impl Converter<'_> {
+    fn type_ref(&self, id: u32) -> Result<types::TypeRef, MerkleizeError> {
+        use types::TypeRef;
+
+        let type_ref = match &resolve(self.registry, id)?.type_def {
+            TypeDef::Primitive(p) => match p {
+                TypeDefPrimitive::Bool => TypeRef::Bool,
+                TypeDefPrimitive::Char => TypeRef::Char,
+                TypeDefPrimitive::Str => TypeRef::Str,
+                TypeDefPrimitive::U8 => TypeRef::U8,
+                TypeDefPrimitive::U16 => TypeRef::U16,
+                TypeDefPrimitive::U32 => TypeRef::U32,
+                TypeDefPrimitive::U64 => TypeRef::U64,
+                TypeDefPrimitive::U128 => TypeRef::U128,
+                TypeDefPrimitive::U256 => TypeRef::U256,
+                TypeDefPrimitive::I8 => TypeRef::I8,
+                TypeDefPrimitive::I16 => TypeRef::I16,
+                TypeDefPrimitive::I32 => TypeRef::I32,
+                TypeDefPrimitive::I64 => TypeRef::I64,
+                TypeDefPrimitive::I128 => TypeRef::I128,
+                TypeDefPrimitive::I256 => TypeRef::I256,
+            },
+            TypeDef::Compact(_) => {
+                let mut found = Vec::new();
+                collect_primitives(self.registry, id, &mut found, &mut BTreeSet::new())?;
+                match found.as_slice() {
+                    [] => TypeRef::Void,
+                    [TypeDefPrimitive::U8] => TypeRef::CompactU8,
+                    [TypeDefPrimitive::U16] => TypeRef::CompactU16,
+                    [TypeDefPrimitive::U32] => TypeRef::CompactU32,
+                    [TypeDefPrimitive::U64] => TypeRef::CompactU64,
+                    [TypeDefPrimitive::U128] => TypeRef::CompactU128,
+                    [TypeDefPrimitive::U256] => TypeRef::CompactU256,
+                    _ => return Err(MerkleizeError::InvalidCompactType(id)),
+                }
+            }
+            TypeDef::Variant(v) if v.variants.is_empty() => TypeRef::Void,
+            TypeDef::Composite(c) if c.fields.is_empty() => TypeRef::Void,
+            TypeDef::Tuple(t) if t.fields.is_empty() => TypeRef::Void,
+            _ => {
+                let new_id = self
+                    .new_ids
+                    .get(&id)
+                    .ok_or(MerkleizeError::TypeNotFound(id))?;
+                TypeRef::ById((*new_id).into())
+            }
+        };
+
+        Ok(type_ref)
+    }
+
+    fn field(
+        &self,
+        field: &scale_info::Field<PortableForm>,
+    ) -> Result<types::Field, MerkleizeError> {
+        Ok(types::Field {
+            name: field.name.clone(),
+            ty: self.type_ref(field.ty.id)?,
+            type_name: field.type_name.clone(),
+        })
+    }
+
+    /// Convert the given type into one or more leaves. The type IDs of
+    /// the leaves are left for the caller to fill in.
+    fn leaves(&self, id: u32) -> Result<Vec<types::Type>, MerkleizeError> {
+        let ty = resolve(self.registry, id)?;
+        let path: Vec<String> = ty.path.segments.clone();
+
+        let type_def = match &ty.type_def {
+            TypeDef::Variant(v) => {
+                let mut variants: Vec<_> = v.variants.iter().collect();
+                variants.sort_by_key(|v| v.index);
+
+                return variants
+                    .into_iter()
+                    .map(|v| {
+                        Ok(types::Type {
+                            path: path.clone(),
+                            type_def: types::TypeDef::Enumeration(types::EnumerationVariant {
+                                name: v.name.clone(),
+                                fields: v
+                                    .fields
+                                    .iter()
+                                    .map(|f| self.field(f))
+                                    .collect::<Result<_, _>>()?,
+                                index: (v.index as u32).into(),
+                            }),
+                            type_id: 0u32.into(),
+                        })
+                    })
+                    .collect();
+            }
+            TypeDef::Composite(c) => types::TypeDef::Composite(
+                c.fields
+                    .iter()
+                    .map(|f| self.field(f))
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypeDef::Sequence(s) => types::TypeDef::Sequence(self.type_ref(s.type_param.id)?),
+            TypeDef::Array(a) => types::TypeDef::Array(types::TypeDefArray {
+                len: a.len,
+                type_param: self.type_ref(a.type_param.id)?,
+            }),
+            TypeDef::Tuple(t) => types::TypeDef::Tuple(
+                t.fields
+                    .iter()
+                    .map(|f| self.type_ref(f.id))
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypeDef::BitSequence(b) => types::TypeDef::BitSequence(self.bit_sequence(
+                id,
+                b.bit_store_type.id,
+                b.bit_order_type.id,
+            )?),
+            TypeDef::Compact(_) | TypeDef::Primitive(_) => return Ok(Vec::new()),
+        };
+
+        Ok(alloc::vec![types::Type {
+            path,
+            type_def,
+            type_id: 0u32.into(),
+        }])
+    }
+
+    fn bit_sequence(
+        &self,
+        id: u32,
+        store_ty: u32,
+        order_ty: u32,
+    ) -> Result<types::TypeDefBitSequence, MerkleizeError> {
+        let mut found = Vec::new();
+        collect_primitives(self.registry, store_ty, &mut found, &mut BTreeSet::new())?;
+        let num_bytes = match found.as_slice() {
+            [TypeDefPrimitive::U8] => 1,
+            [TypeDefPrimitive::U16] => 2,
+            [TypeDefPrimitive::U32] => 4,
+            [TypeDefPrimitive::U64] => 8,
+            _ => return Err(MerkleizeError::InvalidBitSequence(id)),
+        };
+
+        let least_significant_bit_first = resolve(self.registry, order_ty)?
+            .path
+            .segments
+            .iter()
+            .find(|s| *s == "Lsb0" || *s == "Msb0")
+            .map(|s| s == "Lsb0")
+            .ok_or(MerkleizeError::InvalidBitSequence(id))?;
+
+        Ok(types::TypeDefBitSequence {
+            num_bytes,
+            least_significant_bit_first,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Decode;
+
+    fn load_metadata() -> Metadata {
+        let bytes = std::fs::read("../artifacts/polkadot_metadata_full.scale").unwrap();
+        Metadata::decode(&mut &*bytes).unwrap()
+    }
+
+    fn digest_info() -> MetadataDigestInfo {
+        MetadataDigestInfo {
+            spec_version: 1,
+            spec_name: "polkadot".into(),
+            base58_prefix: 0,
+            decimals: 10,
+            token_symbol: "DOT".into(),
+        }
+    }
+
+    fn leaf(n: u32) -> types::Type {
+        types::Type {
+            path: Vec::new(),
+            type_def: types::TypeDef::Array(types::TypeDefArray {
+                len: n,
+                type_param: types::TypeRef::U8,
+            }),
+            type_id: n.into(),
+        }
+    }
+
+    #[test]
+    fn merkle_tree_root_is_built_from_the_back() {
+        let leaves: Vec<_> = (0..3).map(leaf).collect();
+        let tree = MerkleTree::new(&leaves);
+
+        // With 3 leaves, the last two are paired first and the result is paired with the first.
+        let right = merkle_tree::hash_node(&leaves[1].hash(), &leaves[2].hash());
+        let expected = merkle_tree::hash_node(&right, &leaves[0].hash());
+        assert_eq!(tree.root(), expected);
+
+        // A single leaf is its own root, and an empty tree has a zero root.
+        assert_eq!(MerkleTree::new(&leaves[..1]).root(), leaves[0].hash());
+        assert_eq!(MerkleTree::new(&[]).root(), [0u8; 32]);
+    }
+
+    #[test]
+    fn merkle_tree_proof_contains_missing_nodes() {
+        let leaves: Vec<_> = (0..3).map(leaf).collect();
+        let tree = MerkleTree::new(&leaves);
+
+        // Proving the first leaf requires the hash of the node above the other two.
+        let right = merkle_tree::hash_node(&leaves[1].hash(), &leaves[2].hash());
+        let (node_indices, nodes) = tree.build_proof([0]).unwrap();
+        assert_eq!(node_indices, vec![2]);
+        assert_eq!(nodes, vec![right]);
+
+        // Proving every leaf requires no additional nodes.
+        let (node_indices, nodes) = tree.build_proof([0, 1, 2]).unwrap();
+        assert_eq!(node_indices, vec![3, 4, 2]);
+        assert!(nodes.is_empty());
+    }
+
+    #[test]
+    fn extrinsic_parts_proof_works() {
+        let metadata = load_metadata();
+        let merkleized = metadata.merkleize().unwrap();
+
+        let system = metadata.pallet_by_name("System").unwrap();
+        let remark = system.call_variant_by_name("remark").unwrap();
+        let mut call = vec![system.index(), remark.index];
+        codec::Encode::encode_to(&b"hello".to_vec(), &mut call);
+
+        let proof = merkleized
+            .extrinsic_parts_proof(&call, None, digest_info())
+            .unwrap();
+
+        // The outer call variant, the System call variant and the remark bytes.
+        assert_eq!(proof.num_leaves(), 3);
+        assert_eq!(proof.leaf_indices().len(), 3);
+
+        // The extrinsic details and chain info are included, so that the metadata
+        // hash can be recalculated from the proof.
+        assert_eq!(proof.extra_info(), &digest_info());
+        assert_eq!(
+            proof.encoded_extrinsic_metadata(),
+            merkleized.encoded_extrinsic_metadata()
+        );
+        let encoded = proof.encode();
+        let info_and_extrinsic = [
+            merkleized.encoded_extrinsic_metadata(),
+            digest_info().encode(),
+        ]
+        .concat();
+        assert!(encoded.ends_with(&info_and_extrinsic));
+
+        // Any leftover bytes are an error.
+        call.push(0);
+        assert_eq!(
+            merkleized.extrinsic_parts_proof(&call, None, digest_info()),
+            Err(MerkleizeError::LeftoverBytes)
+        );
+    }
+
+    // Extrinsic details where the call is a `u8`, and the single signed extension
+    // includes a compact encoded `U256` in the extrinsic.
+    fn simple_extrinsic_metadata(version: u8) -> types::ExtrinsicMetadata {
+        types::ExtrinsicMetadata {
+            version,
+            address_ty: types::TypeRef::U32,
+            call_ty: types::TypeRef::U8,
+            signature_ty: types::TypeRef::U64,
+            signed_extensions: vec![types::SignedExtensionMetadata {
+                identifier: "Tip".into(),
+                included_in_extrinsic: types::TypeRef::CompactU256,
+                included_in_signed_data: types::TypeRef::Void,
+            }],
+        }
+    }
+
+    fn decode_extrinsic(
+        ext: &[u8],
+        metadata: &types::ExtrinsicMetadata,
+    ) -> Result<(), MerkleizeError> {
+        let mut bytes = ext;
+        LeafCollector::new([]).decode_extrinsic(&mut bytes, None, metadata)?;
+        assert!(bytes.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn v4_and_v5_extrinsics_can_be_decoded() {
+        let metadata = simple_extrinsic_metadata(4);
+        let tip = codec::Compact(1u128 << 100).encode();
+
+        // v4 bare and signed extrinsics.
+        let bare = [4u8, 1];
+        let signed = [&[0b1000_0100][..], &[0; 4], &[0; 8], &tip, &[1]].concat();
+        for ext in [&bare[..], &signed] {
+            decode_extrinsic(&ext.to_vec().encode(), &metadata).unwrap();
+        }
+
+        // v5 bare and general extrinsics; the latter have an extension version byte
+        // and then the extension data, but no address or signature.
+        let metadata = simple_extrinsic_metadata(5);
+        let bare = [5u8, 1];
+        let general = [&[0b0100_0101, 0][..], &tip, &[1]].concat();
+        for ext in [&bare[..], &general] {
+            decode_extrinsic(&ext.to_vec().encode(), &metadata).unwrap();
+        }
+
+        // v4 extrinsics can't be general and v5 ones can't be signed.
+        let bad = [
+            [&[0b0100_0100, 0][..], &tip, &[1]].concat(),
+            [&[0b1000_0101][..], &[0; 4], &[0; 8], &tip, &[1]].concat(),
+        ];
+        for ext in bad {
+            assert!(matches!(
+                decode_extrinsic(&ext.encode(), &metadata),
+                Err(MerkleizeError::Decode(_))
+            ));
+        }
+
+        // Other versions are unsupported.
+        assert_eq!(
+            decode_extrinsic(&vec![3u8, 1].encode(), &metadata),
+            Err(MerkleizeError::UnsupportedExtrinsicVersion(3))
+        );
+    }
+
+    #[test]
+    fn compact_u256_values_can_be_decoded() {
+        let metadata = simple_extrinsic_metadata(5);
+        let general = |tip: &[u8]| [&[0b0100_0101, 0][..], tip, &[1]].concat().encode();
+
+        // Each compact mode, including the largest 32 byte value.
+        let largest = [&[0b0111_0011][..], &[0xff; 32]].concat();
+        for tip in [
+            codec::Compact(1u8).encode(),
+            codec::Compact(1u16 << 8).encode(),
+            codec::Compact(1u32 << 16).encode(),
+            codec::Compact(u128::MAX).encode(),
+            largest,
+        ] {
+            decode_extrinsic(&general(&tip), &metadata).unwrap();
+        }
+
+        // Anything larger doesn't fit in a U256.
+        let too_large = [&[0b0111_0111][..], &[0xff; 33]].concat();
+        assert!(matches!(
+            decode_extrinsic(&general(&too_large), &metadata),
+            Err(MerkleizeError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn leaves_are_ordered_by_type_id_and_variant() {
+        let merkleized = load_metadata().merkleize().unwrap();
+
+        let mut prev: Option<&types::Type> = None;
+        for ty in merkleized.types.values() {
+            if let Some(prev) = prev {
+                let same_enum_variant_increases =
+                    match (prev.type_def.as_enumeration(), ty.type_def.as_enumeration()) {
+                        (Some(a), Some(b)) => a.index.0 < b.index.0,
+                        _ => false,
+                    };
+                assert!(
+                    prev.type_id.0 < ty.type_id.0
+                        || (prev.type_id.0 == ty.type_id.0 && same_enum_variant_increases)
+                );
+            }
+            prev = Some(ty);
+        }
+    }
+
+    #[test]
+    fn digest_depends_on_chain_info() {
+        let merkleized = load_metadata().merkleize().unwrap();
+        let info = digest_info();
+
+        let hash1 = merkleized.digest(info.clone()).hash();
+        let hash2 = merkleized
+            .digest(MetadataDigestInfo {
+                spec_version: 2,
+                ..info
+            })
+            .hash();
+
+        assert_ne!(hash1, hash2);
+    }
+
+    #[test]
+    fn digest_matches_reference_implementation() {
+        // Computed from the same artifact and chain info using v0.1.0 of the
+        // `merkleized-metadata` crate, which is the reference implementation of RFC-0078.
+        let merkleized = load_metadata().merkleize().unwrap();
+        let digest = merkleized.digest(MetadataDigestInfo {
+            spec_version: 1_002_000,
+            ..digest_info()
+        });
+
+        assert_eq!(
+            hex::encode(merkleized.types_tree_root()),
+            "3174f26c152eb83df929aa47767b8d9c4c7dfe36892305f5d8237a7d0af21332"
+        );
+        assert_eq!(
+            hex::encode(merkleized.extrinsic_metadata_hash()),
+            "462defe7948db92878d8e0ced806a72f3d015187d79a41cd64cf5b857355287f"
+        );
+        assert_eq!(
+            hex::encode(digest.hash()),
+            "5f833643becd79683536eb4d37ba2f086a5cedfd0248dd2796839692473dfa43"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Decode extrinsics using the merkleized type information, recording
+//! which leaves are needed in order to do so.
+
+use super::merkle_tree::LeafId;
+use super::types::{ExtrinsicMetadata, Type, TypeDef, TypeRef};
+use super::MerkleizeError;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use codec::{Compact, Decode};
+
+/// Decoding is recursive, so bail if types are nested deeper than this.
+const MAX_DEPTH: usize = 1000;
+
+/// Decodes values using the merkleized type information, and keeps track
+/// of every leaf that was needed to do so.
+pub struct LeafCollector<'a> {
+    types_by_id: BTreeMap<u32, Vec<&'a Type>>,
+    accessed: BTreeSet<LeafId>,
+    depth: usize,
+}
+
+impl<'a> LeafCollector<'a> {
+    /// Create a new collector given all of the leaves in the tree.
+    pub fn new(types: impl IntoIterator<Item = &'a Type>) -> Self {
+        let mut types_by_id: BTreeMap<u32, Vec<&'a Type>> = BTreeMap::new();
+        for ty in types {
+            types_by_id.entry(ty.type_id.0).or_default().push(ty);
+        }
+        LeafCollector {
+            types_by_id,
+            accessed: BTreeSet::new(),
+            depth: 0,
+        }
+    }
+
+    /// The leaves that have been accessed so far.
+    pub fn into_accessed(self) -> BTreeSet<LeafId> {
+        self.accessed
+    }
+
+    /// Decode a full extrinsic (including the length prefix), and optionally the
+    /// "additional signed" bytes which are included in the signer payload.
+    pub fn decode_extrinsic(
+        &mut self,
+        extrinsic: &mut &[u8],
+        additional_signed: Option<&[u8]>,
+        metadata: &ExtrinsicMetadata,
+    ) -> Result<(), MerkleizeError> {
+        let _len = decode::<Compact<u32>>(extrinsic, "extrinsic length")?;
+        let version = decode::<u8>(extrinsic, "extrinsic version")?;
+
+        // The top two bits give the type of extrinsic; bare (0b00), signed (0b10,
+        // v4 only) or general (0b01, v5 only).
+        let kind = version & 0b1100_0000;
+        let version = version & 0b0011_1111;
+        match (version, kind) {
+            (4, 0b0000_0000) | (5, 0b0000_0000) => {}
+            (4, 0b1000_0000) => {
+                self.decode(metadata.address_ty, extrinsic)?;
+                self.decode(metadata.signature_ty, extrinsic)?;
+                for ext in &metadata.signed_extensions {
+                    self.decode(ext.included_in_extrinsic, extrinsic)?;
+                }
+            }
+            (5, 0b0100_0000) => {
+                let _extension_version = decode::<u8>(extrinsic, "extension version")?;
+                for ext in &metadata.signed_extensions {
+                    self.decode(ext.included_in_extrinsic, extrinsic)?;
+                }
+            }
+            (4 | 5, _) => {
+                return Err(MerkleizeError::Decode(format!(
+                    "invalid extrinsic type bits {kind:#010b} for a v{version} extrinsic"
+                )))
+            }
+            _ => return Err(MerkleizeError::UnsupportedExtrinsicVersion(version)),
+        }
+
+        self.decode(metadata.call_ty, extrinsic)?;
+
+        if let Some(mut additional_signed) = additional_signed {
+            for ext in &metadata.signed_extensions {
+                self.decode(ext.included_in_signed_data, &mut additional_signed)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decode the parts of an extrinsic that are available before it has been signed. If
+    /// signed extension data is provided, the address and signature types are included in
+    /// their entirety, since we don't know which variants of them will be used.
+    pub fn decode_extrinsic_parts(
+        &mut self,
+        call: &mut &[u8],
+        signed_ext_data: Option<(&[u8], &[u8])>,
+        metadata: &ExtrinsicMetadata,
+    ) -> Result<(), MerkleizeError> {
+        self.decode(metadata.call_ty, call)?;
+
+        if let Some((mut included_in_extrinsic, mut included_in_signed_data)) = signed_ext_data {
+            self.collect_all(metadata.address_ty);
+            self.collect_all(metadata.signature_ty);
+
+            for ext in &metadata.signed_extensions {
+                self.decode(ext.included_in_extrinsic, &mut included_in_extrinsic)?;
+                self.decode(ext.included_in_signed_data, &mut included_in_signed_data)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record every leaf reachable from the given type.
+    fn collect_all(&mut self, ty: TypeRef) {
+        let Some(id) = ty.id() else {
+            return;
+        };
+        let Some(leaves) = self.types_by_id.get(&id).cloned() else {
+            return;
+        };
+
+        for leaf in leaves {
+            if !self.accessed.insert(leaf_id(leaf)) {
+                continue;
+            }
+            match &leaf.type_def {
+                TypeDef::Composite(fields) => fields.iter().for_each(|f| self.collect_all(f.ty)),
+                TypeDef::Enumeration(v) => v.fields.iter().for_each(|f| self.collect_all(f.ty)),
+                TypeDef::Sequence(ty) => self.collect_all(*ty),
+                TypeDef::Array(a) => self.collect_all(a.type_param),
+                TypeDef::Tuple(tys) => tys.iter().for_each(|ty| self.collect_all(*ty)),
+                TypeDef::BitSequence(_) => {}
+            }
+        }
+    }
+
+    /// Decode a single value of the given type from the bytes.
+    fn decode(&mut self, ty: TypeRef, bytes: &mut &[u8]) -> Result<(), MerkleizeError> {
+        let id = match ty {
+            TypeRef::Void => return Ok(()),
+            TypeRef::Bool | TypeRef::U8 | TypeRef::I8 => return skip(bytes, 1),
+            TypeRef::U16 | TypeRef::I16 => return skip(bytes, 2),
+            TypeRef::Char | TypeRef::U32 | TypeRef::I32 => return skip(bytes, 4),
+            TypeRef::U64 | TypeRef::I64 => return skip(bytes, 8),
+            TypeRef::U128 | TypeRef::I128 => return skip(bytes, 16),
+            TypeRef::U256 | TypeRef::I256 => return skip(bytes, 32),
+            TypeRef::Str => {
+                let len = decode::<Compact<u32>>(bytes, "string length")?.0;
+                return skip(bytes, len as usize);
+            }
+            TypeRef::CompactU8
+            | TypeRef::CompactU16
+            | TypeRef::CompactU32
+            | TypeRef::CompactU64
+            | TypeRef::CompactU128 => {
+                decode::<Compact<u128>>(bytes, "compact value")?;
+                return Ok(());
+            }
+            TypeRef::CompactU256 => return skip_compact_u256(bytes),
+            TypeRef::ById(id) => id.0,
+        };
+
+        if self.depth >= MAX_DEPTH {
+            return Err(MerkleizeError::Decode(
+                "types are nested too deeply".to_string(),
+            ));
+        }
+        self.depth += 1;
+        let res = self.decode_by_id(id, bytes);
+        self.depth -= 1;
+        res
+    }
+
+    fn decode_by_id(&mut self, id: u32, bytes: &mut &[u8]) -> Result<(), MerkleizeError> {
+        let leaves = self
+            .types_by_id
+            .get(&id)
+            .cloned()
+            .ok_or(MerkleizeError::TypeNotFound(id))?;
+        let first = leaves.first().ok_or(MerkleizeError::TypeNotFound(id))?;
+
+        match &first.type_def {
+            TypeDef::Enumeration(_) => {
+                let index = decode::<u8>(bytes, "variant index")? as u32;
+                let variant = leaves
+                    .iter()
+                    .filter_map(|leaf| leaf.type_def.as_enumeration())
+                    .find(|v| v.index.0 == index)
+                    .ok_or_else(|| {
+                        MerkleizeError::Decode(format!(
+                            "variant index {index} not found in type {id}"
+                        ))
+                    })?;
+                self.accessed.insert(LeafId::Enumeration {
+                    type_id: id,
+                    variant: index,
+                });
+                for field in &variant.fields {
+                    self.decode(field.ty, bytes)?;
+                }
+            }
+            TypeDef::Composite(fields) => {
+                self.accessed.insert(LeafId::Other(id));
+                for field in fields {
+                    self.decode(field.ty, bytes)?;
+                }
+            }
+            TypeDef::Sequence(ty) => {
+                self.accessed.insert(LeafId::Other(id));
+                let len = decode::<Compact<u32>>(bytes, "sequence length")?.0;
+                for _ in 0..len {
+                    self.decode(*ty, bytes)?;
+                }
+            }
+            TypeDef::Array(a) => {
+                self.accessed.insert(LeafId::Other(id));
+                for _ in 0..a.len {
+                    self.decode(a.type_param, bytes)?;
+                }
+            }
+            TypeDef::Tuple(tys) => {
+                self.accessed.insert(LeafId::Other(id));
+                for ty in tys {
+                    self.decode(*ty, bytes)?;
+                }
+            }
+            TypeDef::BitSequence(b) => {
+                self.accessed.insert(LeafId::Other(id));
+                let num_bits = decode::<Compact<u32>>(bytes, "bit sequence length")?.0 as usize;
+                let bits_per_item = b.num_bytes as usize * 8;
+                let num_items = num_bits.div_ceil(bits_per_item);
+                skip(bytes, num_items * b.num_bytes as usize)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn leaf_id(leaf: &Type) -> LeafId {
+    match leaf.type_def.as_enumeration() {
+        Some(v) => LeafId::Enumeration {
+            type_id: leaf.type_id.0,
+            variant: v.index.0,
+        },
+        None => LeafId::Other(leaf.type_id.0),
+    }
+}
+
+fn decode<T: Decode>(bytes: &mut &[u8], what: &str) -> Result<T, MerkleizeError> {
+    T::decode(bytes).map_err(|e| MerkleizeError::Decode(format!("cannot decode {what}: {e}")))
+}
+
+fn skip(bytes: &mut &[u8], n: usize) -> Result<(), MerkleizeError> {
+    if bytes.len() < n {
+        return Err(MerkleizeError::Decode(
+            "not enough bytes left to decode".to_string(),
+        ));
+    }
+    *bytes = &bytes[n..];
+    Ok(())
+}
+
+/// There's no `Compact<U256>` to decode into, so skip over the bytes by hand. Values
+/// which don't fit in 4 bytes are encoded as a length byte followed by up to 32 bytes.
+fn skip_compact_u256(bytes: &mut &[u8]) -> Result<(), MerkleizeError> {
+    let prefix = *bytes
+        .first()
+        .ok_or_else(|| MerkleizeError::Decode("not enough bytes left to decode".to_string()))?;
+    let len = match prefix & 0b11 {
+        0b00 => 1,
+        0b01 => 2,
+        0b10 => 4,
+        _ => {
+            let num_bytes = (prefix >> 2) as usize + 4;
+            if num_bytes > 32 {
+                return Err(MerkleizeError::Decode(
+                    "cannot decode compact value: out of range for U256".to_string(),
+                ));
+            }
+            num_bytes + 1
+        }
+    };
+    skip(bytes, len)
+}
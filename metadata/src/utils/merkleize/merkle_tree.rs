@@ -0,0 +1,232 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! The merkle tree built over the type information leaves.
+
+use super::types::{hash, Hash, Type};
+use super::MerkleizeError;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::iter::Peekable;
+
+/// Identifies a single leaf. Enums are split so that each variant is its own leaf.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeafId {
+    /// A single variant of some enum type.
+    Enumeration {
+        /// The ID of the enum type.
+        type_id: u32,
+        /// The index of the variant.
+        variant: u32,
+    },
+    /// Any other type.
+    Other(u32),
+}
+
+impl LeafId {
+    /// The type ID that this leaf belongs to.
+    pub fn type_id(&self) -> u32 {
+        match self {
+            LeafId::Enumeration { type_id, .. } => *type_id,
+            LeafId::Other(id) => *id,
+        }
+    }
+}
+
+impl PartialOrd for LeafId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LeafId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (
+                LeafId::Enumeration { type_id, variant },
+                LeafId::Enumeration {
+                    type_id: other_type_id,
+                    variant: other_variant,
+                },
+            ) => type_id
+                .cmp(other_type_id)
+                .then_with(|| variant.cmp(other_variant)),
+            (a, b) => a.type_id().cmp(&b.type_id()),
+        }
+    }
+}
+
+/// A complete binary tree stored as a flat list of node hashes. The root lives at
+/// index 0, the children of node `i` live at `2i + 1` and `2i + 2`, and the `n` leaves
+/// occupy the last `n` positions in order.
+pub struct MerkleTree {
+    nodes: Vec<Hash>,
+}
+
+impl MerkleTree {
+    /// Build a tree from the given leaves, which are expected to be in order.
+    pub fn new<'a>(leaves: impl IntoIterator<Item = &'a Type>) -> Self {
+        let leaf_hashes: Vec<Hash> = leaves.into_iter().map(|ty| ty.hash()).collect();
+        let num_leaves = leaf_hashes.len();
+        if num_leaves == 0 {
+            return MerkleTree { nodes: Vec::new() };
+        }
+
+        let mut nodes = Vec::with_capacity(2 * num_leaves - 1);
+        nodes.resize(num_leaves - 1, [0u8; 32]);
+        nodes.extend(leaf_hashes);
+
+        for idx in (0..num_leaves - 1).rev() {
+            nodes[idx] = hash_node(&nodes[2 * idx + 1], &nodes[2 * idx + 2]);
+        }
+
+        MerkleTree { nodes }
+    }
+
+    /// The root hash of the tree. An empty tree has a root of all zeroes.
+    pub fn root(&self) -> Hash {
+        self.nodes.first().copied().unwrap_or_default()
+    }
+
+    /// Build a proof for the given leaves. This returns the node indices of the leaves,
+    /// in the order that the leaves should appear in the proof, along with the hashes
+    /// of the additional nodes needed to recalculate the root.
+    pub fn build_proof(
+        &self,
+        leaf_indices: impl IntoIterator<Item = usize>,
+    ) -> Result<(Vec<usize>, Vec<Hash>), MerkleizeError> {
+        let num_leaves = self.nodes.len().div_ceil(2);
+
+        let mut leaf_node_indices = Vec::new();
+        for leaf_index in leaf_indices {
+            if leaf_index >= num_leaves {
+                return Err(MerkleizeError::LeafNotFound(leaf_index));
+            }
+            leaf_node_indices.push(NodeIndex(num_leaves - 1 + leaf_index));
+        }
+
+        // Deepest leaves first, and then left to right.
+        leaf_node_indices.sort_by(|a, b| b.level().cmp(&a.level()).then_with(|| a.0.cmp(&b.0)));
+        leaf_node_indices.dedup();
+
+        let mut node_hashes = Vec::new();
+        let mut iter = leaf_node_indices.iter().peekable();
+        if let Some(leaf) = iter.next() {
+            // A tree with a single leaf needs no other nodes to recalculate the root.
+            if !leaf.is_root() {
+                self.collect_node_hashes(NodeIndex(0), *leaf, &mut iter, &mut node_hashes)?;
+            }
+        }
+
+        Ok((
+            leaf_node_indices.into_iter().map(|i| i.0).collect(),
+            node_hashes,
+        ))
+    }
+
+    /// Walk from the given leaf up to `stop_at_parent`, recording the hashes of any
+    /// sibling nodes that can't be calculated from the other leaves being proven.
+    fn collect_node_hashes<'a, I: Iterator<Item = &'a NodeIndex>>(
+        &self,
+        stop_at_parent: NodeIndex,
+        leaf: NodeIndex,
+        leaves: &mut Peekable<I>,
+        node_hashes: &mut Vec<Hash>,
+    ) -> Result<(), MerkleizeError> {
+        let mut node_index = leaf;
+        let left_most_hash_pos = node_hashes.len();
+
+        loop {
+            let parent = node_index.parent();
+
+            if node_index.is_left_child() {
+                let right_child = parent.right_child();
+
+                if leaves.peek().is_some_and(|l| **l == right_child) {
+                    // The sibling is itself a leaf in the proof.
+                    leaves.next();
+                } else if let Some(next_leaf) = leaves
+                    .peek()
+                    .copied()
+                    .filter(|l| right_child.is_ancestor_of(**l))
+                {
+                    // The sibling can be calculated from other leaves in the proof.
+                    leaves.next();
+                    self.collect_node_hashes(right_child, *next_leaf, leaves, node_hashes)?;
+                } else {
+                    node_hashes.push(self.hash_at(right_child)?);
+                }
+            } else {
+                let left_child = parent.left_child();
+                node_hashes.insert(left_most_hash_pos, self.hash_at(left_child)?);
+            }
+
+            if parent == stop_at_parent {
+                return Ok(());
+            }
+
+            node_index = parent;
+        }
+    }
+
+    fn hash_at(&self, node_index: NodeIndex) -> Result<Hash, MerkleizeError> {
+        self.nodes
+            .get(node_index.0)
+            .copied()
+            .ok_or(MerkleizeError::LeafNotFound(node_index.0))
+    }
+}
+
+/// The index of some node in a [`MerkleTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct NodeIndex(usize);
+
+impl NodeIndex {
+    fn is_root(self) -> bool {
+        self.0 == 0
+    }
+
+    fn parent(self) -> Self {
+        if self.is_root() {
+            self
+        } else {
+            NodeIndex((self.0 - 1) / 2)
+        }
+    }
+
+    fn is_left_child(self) -> bool {
+        self.0 % 2 == 1
+    }
+
+    fn left_child(self) -> Self {
+        NodeIndex(self.0 * 2 + 1)
+    }
+
+    fn right_child(self) -> Self {
+        NodeIndex(self.0 * 2 + 2)
+    }
+
+    fn level(self) -> u32 {
+        (self.0 + 1).ilog2()
+    }
+
+    fn is_ancestor_of(self, other: Self) -> bool {
+        if self.is_root() {
+            return true;
+        }
+        if self.0 > other.0 {
+            return false;
+        }
+        let level_diff = other.level() - self.level();
+        self.0 + 1 == (other.0 + 1) >> level_diff
+    }
+}
+
+/// Hash two child nodes together to produce the parent node hash.
+pub fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(left);
+    bytes[32..].copy_from_slice(right);
+    hash(&bytes)
+}
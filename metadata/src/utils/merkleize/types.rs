@@ -0,0 +1,204 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! The type information format described in RFC-0078. Each of these types is SCALE encoded
+//! and then hashed, so the shape, order and codec indexes of everything here must not change.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use codec::{Compact, Encode};
+
+/// The hash type used throughout the merkleized metadata.
+pub type Hash = [u8; 32];
+
+/// Hash some bytes using the hashing algorithm mandated by RFC-0078.
+pub fn hash(data: &[u8]) -> Hash {
+    blake3::hash(data).into()
+}
+
+/// A reference to some type. Primitive and empty types are described inline,
+/// and everything else refers to a [`Type`] by its (new) ID.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Encode)]
+pub enum TypeRef {
+    #[codec(index = 0)]
+    Bool,
+    #[codec(index = 1)]
+    Char,
+    #[codec(index = 2)]
+    Str,
+    #[codec(index = 3)]
+    U8,
+    #[codec(index = 4)]
+    U16,
+    #[codec(index = 5)]
+    U32,
+    #[codec(index = 6)]
+    U64,
+    #[codec(index = 7)]
+    U128,
+    #[codec(index = 8)]
+    U256,
+    #[codec(index = 9)]
+    I8,
+    #[codec(index = 10)]
+    I16,
+    #[codec(index = 11)]
+    I32,
+    #[codec(index = 12)]
+    I64,
+    #[codec(index = 13)]
+    I128,
+    #[codec(index = 14)]
+    I256,
+    #[codec(index = 15)]
+    CompactU8,
+    #[codec(index = 16)]
+    CompactU16,
+    #[codec(index = 17)]
+    CompactU32,
+    #[codec(index = 18)]
+    CompactU64,
+    #[codec(index = 19)]
+    CompactU128,
+    #[codec(index = 20)]
+    CompactU256,
+    #[codec(index = 21)]
+    Void,
+    #[codec(index = 22)]
+    ById(Compact<u32>),
+}
+
+impl TypeRef {
+    /// The ID of the referenced type, if this refers to one.
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            TypeRef::ById(id) => Some(id.0),
+            _ => None,
+        }
+    }
+}
+
+/// The shape of some [`Type`].
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub enum TypeDef {
+    /// A struct-like type.
+    #[codec(index = 0)]
+    Composite(Vec<Field>),
+    /// A single variant of an enum. Each variant is a separate leaf.
+    #[codec(index = 1)]
+    Enumeration(EnumerationVariant),
+    /// A sequence of values whose length is known at runtime.
+    #[codec(index = 2)]
+    Sequence(TypeRef),
+    /// A sequence of values whose length is known up front.
+    #[codec(index = 3)]
+    Array(TypeDefArray),
+    /// A tuple of values.
+    #[codec(index = 4)]
+    Tuple(Vec<TypeRef>),
+    /// A sequence of bits.
+    #[codec(index = 5)]
+    BitSequence(TypeDefBitSequence),
+}
+
+impl TypeDef {
+    /// Return the enum variant if this is one.
+    pub fn as_enumeration(&self) -> Option<&EnumerationVariant> {
+        match self {
+            TypeDef::Enumeration(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// A field in a composite type or enum variant.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct Field {
+    /// The field name, if it has one.
+    pub name: Option<String>,
+    /// The field type.
+    pub ty: TypeRef,
+    /// The name of the type as it appeared in the source.
+    pub type_name: Option<String>,
+}
+
+/// A single enum variant.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct EnumerationVariant {
+    /// The variant name.
+    pub name: String,
+    /// The fields of the variant.
+    pub fields: Vec<Field>,
+    /// The variant index.
+    pub index: Compact<u32>,
+}
+
+/// An array type.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct TypeDefArray {
+    /// The number of items in the array.
+    pub len: u32,
+    /// The type of each item.
+    pub type_param: TypeRef,
+}
+
+/// A bit sequence type.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct TypeDefBitSequence {
+    /// How many bytes are used to store each group of bits.
+    pub num_bytes: u8,
+    /// Bit order; `Lsb0` if true, `Msb0` if false.
+    pub least_significant_bit_first: bool,
+}
+
+/// A single leaf of the merkle tree.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct Type {
+    /// The path to the type. Empty for built-in types.
+    pub path: Vec<String>,
+    /// The type definition.
+    pub type_def: TypeDef,
+    /// The ID of the type. This is shared between all variants of an enum.
+    pub type_id: Compact<u32>,
+}
+
+impl Type {
+    /// The hash of this type, which is the leaf hash in the merkle tree.
+    pub fn hash(&self) -> Hash {
+        hash(&self.encode())
+    }
+}
+
+/// The extrinsic details that are hashed into the [`crate::MetadataDigest`].
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct ExtrinsicMetadata {
+    /// Extrinsic version.
+    pub version: u8,
+    /// The type of the address.
+    pub address_ty: TypeRef,
+    /// The type of the call.
+    pub call_ty: TypeRef,
+    /// The type of the signature.
+    pub signature_ty: TypeRef,
+    /// The signed extensions in the order they appear in the extrinsic.
+    pub signed_extensions: Vec<SignedExtensionMetadata>,
+}
+
+impl ExtrinsicMetadata {
+    /// The hash of the extrinsic details.
+    pub fn hash(&self) -> Hash {
+        hash(&self.encode())
+    }
+}
+
+/// Details of a single signed extension.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Encode)]
+pub struct SignedExtensionMetadata {
+    /// The signed extension identifier.
+    pub identifier: String,
+    /// The type of the data included in the extrinsic.
+    pub included_in_extrinsic: TypeRef,
+    /// The type of the data included in the signer payload.
+    pub included_in_signed_data: TypeRef,
+}
@@ -2,6 +2,7 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
+pub mod merkleize;
 pub mod ordered_map;
 pub mod retain;
 pub mod validation;
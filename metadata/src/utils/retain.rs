@@ -42,6 +42,17 @@ fn collect_pallet_types(pallet: &PalletMetadataInner, type_ids: &mut HashSet<u32
         type_ids.insert(constant.ty);
     }
 
+    for associated_type in pallet.associated_types.values() {
+        type_ids.insert(associated_type.ty);
+    }
+
+    for view_function in pallet.view_functions.values() {
+        for input in &view_function.inputs {
+            type_ids.insert(input.ty);
+        }
+        type_ids.insert(view_function.output_ty);
+    }
+
     if let Some(ty) = pallet.error_ty {
         type_ids.insert(ty);
     }
@@ -80,6 +91,17 @@ fn update_pallet_types(pallet: &mut PalletMetadataInner, map_ids: &BTreeMap<u32,
     for constant in pallet.constants.values_mut() {
         update_type(&mut constant.ty, map_ids);
     }
+
+    for associated_type in pallet.associated_types.values_mut() {
+        update_type(&mut associated_type.ty, map_ids);
+    }
+
+    for view_function in pallet.view_functions.values_mut() {
+        for input in &mut view_function.inputs {
+            update_type(&mut input.ty, map_ids);
+        }
+        update_type(&mut view_function.output_ty, map_ids);
+    }
 }
 
 /// Collect all type IDs needed to represent the extrinsic metadata.
@@ -87,9 +109,13 @@ fn collect_extrinsic_types(extrinsic: &ExtrinsicMetadata, type_ids: &mut HashSet
     type_ids.insert(extrinsic.address_ty);
     type_ids.insert(extrinsic.call_ty);
     type_ids.insert(extrinsic.signature_ty);
-    type_ids.insert(extrinsic.extra_ty);
+    type_ids.extend(extrinsic.extra_ty);
 
-    for signed in &extrinsic.signed_extensions {
+    for signed in extrinsic
+        .signed_extensions
+        .iter()
+        .chain(&extrinsic.transaction_extensions)
+    {
         type_ids.insert(signed.extra_ty);
         type_ids.insert(signed.additional_ty);
     }
@@ -100,9 +126,15 @@ fn update_extrinsic_types(extrinsic: &mut ExtrinsicMetadata, map_ids: &BTreeMap<
     update_type(&mut extrinsic.address_ty, map_ids);
     update_type(&mut extrinsic.call_ty, map_ids);
     update_type(&mut extrinsic.signature_ty, map_ids);
-    update_type(&mut extrinsic.extra_ty, map_ids);
+    if let Some(extra_ty) = &mut extrinsic.extra_ty {
+        update_type(extra_ty, map_ids);
+    }
 
-    for signed in &mut extrinsic.signed_extensions {
+    for signed in extrinsic
+        .signed_extensions
+        .iter_mut()
+        .chain(&mut extrinsic.transaction_extensions)
+    {
         update_type(&mut signed.extra_ty, map_ids);
         update_type(&mut signed.additional_ty, map_ids);
     }
@@ -241,7 +273,7 @@ pub fn retain_metadata<F, G>(
     collect_extrinsic_types(&metadata.extrinsic, &mut type_ids);
 
     // Keep the "runtime" type ID, since it's referenced in our metadata.
-    type_ids.insert(metadata.runtime_ty);
+    type_ids.extend(metadata.runtime_ty);
 
     // Keep only the runtime API types that the filter allows for. Keep hold of all
     // type IDs in the runtime apis we're keeping. Retain all, if no filter specified.
@@ -273,7 +305,9 @@ pub fn retain_metadata<F, G>(
         update_pallet_types(pallets, &map_ids);
     }
     update_extrinsic_types(&mut metadata.extrinsic, &map_ids);
-    update_type(&mut metadata.runtime_ty, &map_ids);
+    if let Some(runtime_ty) = &mut metadata.runtime_ty {
+        update_type(runtime_ty, &map_ids);
+    }
     update_runtime_api_types(metadata.apis.values_mut(), &map_ids);
 }
 
@@ -7,6 +7,7 @@
 use crate::{
     CustomMetadata, CustomValueMetadata, ExtrinsicMetadata, Metadata, PalletMetadata,
     RuntimeApiMetadata, RuntimeApiMethodMetadata, StorageEntryMetadata, StorageEntryType,
+    ViewFunctionMetadata,
 };
 use alloc::vec::Vec;
 use hashbrown::HashMap;
@@ -71,7 +72,6 @@ macro_rules! concat_and_hash_n {
 concat_and_hash_n!(concat_and_hash2(a b));
 concat_and_hash_n!(concat_and_hash3(a b c));
 concat_and_hash_n!(concat_and_hash4(a b c d));
-concat_and_hash_n!(concat_and_hash5(a b c d e));
 concat_and_hash_n!(concat_and_hash6(a b c d e f));
 
 /// Obtain the hash representation of a `scale_info::Field`.
@@ -290,22 +290,37 @@ fn get_extrinsic_hash(
     let address_hash = get_type_hash(registry, extrinsic.address_ty, outer_enum_hashes);
     // The `RuntimeCall` type is intentionally omitted and hashed by the outer enums instead.
     let signature_hash = get_type_hash(registry, extrinsic.signature_ty, outer_enum_hashes);
-    let extra_hash = get_type_hash(registry, extrinsic.extra_ty, outer_enum_hashes);
+    // V16 metadata doesn't provide the `Extra` type, but the signed extension types
+    // which make it up are hashed below anyway.
+    let extra_hash = extrinsic
+        .extra_ty
+        .map(|ty| get_type_hash(registry, ty, outer_enum_hashes))
+        .unwrap_or_default();
 
     let mut bytes = concat_and_hash4(
         &address_hash,
         &signature_hash,
         &extra_hash,
-        &[extrinsic.version; 32],
+        &[extrinsic.version(); 32],
     );
 
-    for signed_extension in extrinsic.signed_extensions.iter() {
-        bytes = concat_and_hash4(
-            &bytes,
-            &hash(signed_extension.identifier.as_bytes()),
-            &get_type_hash(registry, signed_extension.extra_ty, outer_enum_hashes),
-            &get_type_hash(registry, signed_extension.additional_ty, outer_enum_hashes),
-        )
+    // Hash the transaction extensions used by every version, so that a change to any of
+    // them (and not just to the version 0 extensions) is noticed.
+    for version in extrinsic.transaction_extension_versions() {
+        bytes = concat_and_hash2(&bytes, &[version; HASH_LEN]);
+
+        let signed_extensions = extrinsic
+            .transaction_extensions_by_version(version)
+            .into_iter()
+            .flatten();
+        for signed_extension in signed_extensions {
+            bytes = concat_and_hash4(
+                &bytes,
+                &hash(signed_extension.identifier.as_bytes()),
+                &get_type_hash(registry, signed_extension.extra_ty, outer_enum_hashes),
+                &get_type_hash(registry, signed_extension.additional_ty, outer_enum_hashes),
+            )
+        }
     }
 
     bytes
@@ -378,6 +393,32 @@ fn get_runtime_method_hash(
     bytes
 }
 
+/// Get the hash corresponding to a single pallet view function.
+fn get_view_function_hash_inner(
+    registry: &PortableRegistry,
+    view_function: &ViewFunctionMetadata,
+    outer_enum_hashes: &OuterEnumHashes,
+) -> Hash {
+    // The ID is what is used to actually call the view function, so it
+    // is hashed alongside the name.
+    let mut bytes = concat_and_hash2(&view_function.id, &hash(view_function.name.as_bytes()));
+
+    for input in &view_function.inputs {
+        bytes = concat_and_hash3(
+            &bytes,
+            &hash(input.name.as_bytes()),
+            &get_type_hash(registry, input.ty, outer_enum_hashes),
+        );
+    }
+
+    bytes = concat_and_hash2(
+        &bytes,
+        &get_type_hash(registry, view_function.output_ty, outer_enum_hashes),
+    );
+
+    bytes
+}
+
 /// Obtain the hash of all of a runtime API trait, including all of its methods.
 pub fn get_runtime_trait_hash(
     trait_metadata: RuntimeApiMetadata,
@@ -442,6 +483,13 @@ pub fn get_custom_value_hash(
     }
 }
 
+/// Obtain the hash for a specific pallet view function, or an error if it's not found.
+pub fn get_view_function_hash(pallet: &PalletMetadata, function_name: &str) -> Option<Hash> {
+    let view_function = pallet.view_function_by_name(function_name)?;
+    let hash = get_view_function_hash_inner(pallet.types, view_function, &OuterEnumHashes::empty());
+    Some(hash)
+}
+
 /// Obtain the hash for a specific storage item, or an error if it's not found.
 pub fn get_storage_hash(pallet: &PalletMetadata, entry_name: &str) -> Option<Hash> {
     let storage = pallet.storage()?;
@@ -529,14 +577,26 @@ pub fn get_pallet_hash(pallet: PalletMetadata, outer_enum_hashes: &OuterEnumHash
         }
         None => [0u8; HASH_LEN],
     };
+    let view_function_bytes =
+        pallet
+            .view_functions()
+            .fold([0u8; HASH_LEN], |bytes, view_function| {
+                // We don't care what order the view functions occur in, so XOR them together
+                // to make the order irrelevant.
+                xor(
+                    bytes,
+                    get_view_function_hash_inner(registry, view_function, outer_enum_hashes),
+                )
+            });
 
     // Hash all of the above together:
-    concat_and_hash5(
+    concat_and_hash6(
         &call_bytes,
         &event_bytes,
         &error_bytes,
         &constant_bytes,
         &storage_bytes,
+        &view_function_bytes,
     )
 }
 
@@ -626,8 +686,10 @@ impl<'a> MetadataHasher<'a> {
 
         let extrinsic_hash =
             get_extrinsic_hash(&metadata.types, &metadata.extrinsic, &outer_enum_hashes);
-        let runtime_hash =
-            get_type_hash(&metadata.types, metadata.runtime_ty(), &outer_enum_hashes);
+        let runtime_hash = metadata
+            .try_runtime_ty()
+            .map(|ty| get_type_hash(&metadata.types, ty, &outer_enum_hashes))
+            .unwrap_or_default();
         let custom_values_hash = self
             .include_custom_values
             .then(|| get_custom_metadata_hash(&metadata.custom(), &outer_enum_hashes))
@@ -957,6 +1019,103 @@ mod tests {
         compare_pallets_hash(&pallet_lhs, &pallet);
     }
 
+    #[test]
+    fn view_function_changes_change_pallet_hash() {
+        // Register some types for the view functions to use via constants.
+        let pallet = v15::PalletMetadata {
+            constants: vec![
+                v15::PalletConstantMetadata {
+                    name: "U32",
+                    ty: meta_type::<u32>(),
+                    value: vec![],
+                    docs: vec![],
+                },
+                v15::PalletConstantMetadata {
+                    name: "U64",
+                    ty: meta_type::<u64>(),
+                    value: vec![],
+                    docs: vec![],
+                },
+            ],
+            ..default_pallet()
+        };
+        let metadata = pallets_to_metadata(vec![pallet]);
+        let type_of = |name| {
+            let pallet = metadata.pallet_by_name("Test").unwrap();
+            pallet.constant_by_name(name).unwrap().ty()
+        };
+        let (u32_ty, u64_ty) = (type_of("U32"), type_of("U64"));
+
+        let default_view_function = ViewFunctionMetadata {
+            name: "value".into(),
+            id: [1u8; 32],
+            inputs: vec![crate::ViewFunctionParamMetadata {
+                name: "key".into(),
+                ty: u32_ty,
+            }],
+            output_ty: u32_ty,
+            docs: vec![],
+            deprecation_info: crate::DeprecationStatus::NotDeprecated,
+        };
+        let pallet_hash_with = |view_function: Option<ViewFunctionMetadata>| {
+            let mut metadata = metadata.clone();
+            if let Some(view_function) = view_function {
+                metadata.pallets.values_mut()[0]
+                    .view_functions
+                    .push_insert(view_function.name.clone(), view_function);
+            }
+            metadata.pallet_by_name("Test").unwrap().hash()
+        };
+
+        let hash = pallet_hash_with(Some(default_view_function.clone()));
+        assert_ne!(hash, pallet_hash_with(None));
+
+        let mut view_function = default_view_function.clone();
+        view_function.name = "other_value".into();
+        assert_ne!(hash, pallet_hash_with(Some(view_function)));
+
+        let mut view_function = default_view_function.clone();
+        view_function.id = [2u8; 32];
+        assert_ne!(hash, pallet_hash_with(Some(view_function)));
+
+        let mut view_function = default_view_function.clone();
+        view_function.inputs[0].ty = u64_ty;
+        assert_ne!(hash, pallet_hash_with(Some(view_function)));
+
+        let mut view_function = default_view_function;
+        view_function.output_ty = u64_ty;
+        assert_ne!(hash, pallet_hash_with(Some(view_function)));
+    }
+
+    #[test]
+    fn transaction_extension_changes_in_any_version_change_hash() {
+        let mut metadata = pallets_to_metadata(vec![default_pallet()]);
+        let unit_ty = metadata.extrinsic.address_ty;
+        metadata.extrinsic.transaction_extensions = ["First", "Second"]
+            .into_iter()
+            .map(|identifier| crate::SignedExtensionMetadata {
+                identifier: identifier.into(),
+                extra_ty: unit_ty,
+                additional_ty: unit_ty,
+            })
+            .collect();
+
+        let hash_with_versions = |versions: &[(u8, Vec<u32>)]| {
+            let mut metadata = metadata.clone();
+            metadata.extrinsic.transaction_extensions_by_version =
+                versions.iter().cloned().collect();
+            MetadataHasher::new(&metadata).hash()
+        };
+
+        let hash = hash_with_versions(&[(0, vec![0, 1]), (1, vec![0])]);
+        // Removing a version changes the hash.
+        assert_ne!(hash, hash_with_versions(&[(0, vec![0, 1])]));
+        // Changing the extensions used by a version other than 0 changes the hash.
+        assert_ne!(hash, hash_with_versions(&[(0, vec![0, 1]), (1, vec![1])]));
+        // Changing the version number changes the hash.
+        assert_ne!(hash, hash_with_versions(&[(0, vec![0, 1]), (2, vec![0])]));
+    }
+
     #[test]
     fn metadata_per_pallet_hash_correctness() {
         let pallets = build_default_pallets();
@@ -0,0 +1,561 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module exposes a backend implementation based on the `archive_unstable_*` methods
+//! described at <https://github.com/paritytech/json-rpc-interface-spec/>. See
+//! [`rpc_methods`] for the raw API calls.
+//!
+//! Unlike the `chainHead` methods used by the [`crate::backend::unstable::UnstableBackend`],
+//! these can be used to query blocks of any age, but offer no way to follow new blocks or
+//! submit transactions. The [`ArchiveBackend`] is thus best suited to looking at historic blocks.
+//!
+//! # Warning
+//!
+//! Everything in this module is **unstable**, meaning that it could change without
+//! warning at any time.
+
+pub mod rpc_methods;
+
+use self::rpc_methods::{
+    ArchiveCallResult, ArchiveStorageMethodOk, ArchiveStorageQuery, ArchiveStorageResponse,
+    ArchiveStorageResult, StorageQueryType,
+};
+use crate::backend::{
+    rpc::RpcClient, storage_changes::storage_changes_between, utils::retry, Backend, BlockRef,
+    ChainEvent, RuntimeVersion, StorageChanges, StorageResponse, StreamOf, StreamOfResults,
+    TransactionStatus,
+};
+use crate::error::Error;
+use crate::Config;
+use async_trait::async_trait;
+use codec::Decode;
+use derive_where::derive_where;
+use futures::future::Either;
+use futures::{stream, StreamExt};
+
+// Expose the RPC methods.
+pub use rpc_methods::ArchiveRpcMethods;
+
+/// The archive backend.
+#[derive_where(Debug, Clone)]
+pub struct ArchiveBackend<T> {
+    methods: ArchiveRpcMethods<T>,
+}
+
+impl<T: Config> ArchiveBackend<T> {
+    /// Given an [`RpcClient`] to use to make requests, this returns an [`ArchiveBackend`],
+    /// which implements the [`Backend`] trait.
+    pub fn new(client: impl Into<RpcClient>) -> Self {
+        ArchiveBackend {
+            methods: ArchiveRpcMethods::new(client.into()),
+        }
+    }
+
+    // Fetch values from either the main trie or the child trie with the given (unprefixed)
+    // storage key, re-querying any keys that the server discards.
+    async fn fetch_values(
+        &self,
+        child_key: Option<&[u8]>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        let mut remaining_keys = keys.as_slice();
+        let mut responses = Vec::new();
+
+        while !remaining_keys.is_empty() {
+            let queries: Vec<_> = remaining_keys
+                .iter()
+                .map(|key| ArchiveStorageQuery {
+                    key: key.as_slice(),
+                    query_type: StorageQueryType::Value,
+                    pagination_start_key: None,
+                })
+                .collect();
+            let res = retry(|| {
+                self.methods
+                    .archive_unstable_storage(at, queries.iter().cloned(), child_key)
+            })
+            .await?;
+            let res = storage_method_ok(res)?;
+
+            // Items are discarded from the back, so anything before those has been queried.
+            let num_queried = remaining_keys.len().saturating_sub(res.discarded_items);
+            if num_queried == 0 {
+                return Err(Error::Other(
+                    "The server discarded every archive_unstable_storage query".into(),
+                ));
+            }
+            remaining_keys = &remaining_keys[num_queried..];
+
+            responses.extend(res.result.into_iter().filter_map(|res| {
+                Some(Ok(StorageResponse {
+                    key: res.key.0,
+                    value: res.value?.0,
+                }))
+            }));
+        }
+
+        Ok(StreamOf(Box::pin(stream::iter(responses))))
+    }
+
+    // Stream the results of a query for the descendants of some key, in either the main trie or
+    // the child trie with the given (unprefixed) storage key. The server may return a limited
+    // number of results for each query, and so we keep querying from the last key returned until
+    // no more results come back.
+    fn fetch_descendants(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        query_type: StorageQueryType,
+        at: T::Hash,
+    ) -> StreamOfResults<ArchiveStorageResult> {
+        let methods = self.methods.clone();
+
+        // The state is the key to continue from, or `None` once we're done.
+        let pages = stream::unfold(Some(None::<Vec<u8>>), move |start_key| {
+            let methods = methods.clone();
+            let child_key = child_key.clone();
+            let key = key.clone();
+            let query_type = query_type.clone();
+            async move {
+                let start_key = start_key?;
+                let query = ArchiveStorageQuery {
+                    key: key.as_slice(),
+                    query_type,
+                    pagination_start_key: start_key.as_deref(),
+                };
+                let res = retry(|| {
+                    methods.archive_unstable_storage(at, [query.clone()], child_key.as_deref())
+                })
+                .await
+                .and_then(storage_method_ok);
+
+                let res = match res {
+                    Ok(res) if res.discarded_items > 0 => Err(Error::Other(
+                        "The server discarded the archive_unstable_storage query".into(),
+                    )),
+                    res => res,
+                };
+                let results: Vec<_> = match res {
+                    Ok(res) => res.result,
+                    // Hand back the error and then stop.
+                    Err(e) => return Some((Err(e), None)),
+                };
+
+                // Only keep results after the start key, in case the server includes it.
+                let results: Vec<_> = results
+                    .into_iter()
+                    .filter(|res| start_key.as_ref().map_or(true, |start| res.key.0 > *start))
+                    .collect();
+                let next_start_key = results.last()?.key.0.clone();
+                Some((Ok(results), Some(Some(next_start_key))))
+            }
+        });
+
+        let results = pages.flat_map(|page| match page {
+            Ok(results) => Either::Left(stream::iter(results.into_iter().map(Ok))),
+            Err(e) => Either::Right(stream::iter(std::iter::once(Err(e)))),
+        });
+
+        StreamOf(Box::pin(results))
+    }
+
+    fn fetch_descendant_keys(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> StreamOfResults<Vec<u8>> {
+        // Hashes are smaller than values, and give us every key.
+        let keys = self
+            .fetch_descendants(child_key, key, StorageQueryType::DescendantsHashes, at)
+            .map(|res| res.map(|res| res.key.0));
+        StreamOf(Box::pin(keys))
+    }
+
+    fn fetch_descendant_values(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> StreamOfResults<StorageResponse> {
+        let values = self
+            .fetch_descendants(child_key, key, StorageQueryType::DescendantsValues, at)
+            .filter_map(|res| {
+                let res = res.map(|res| {
+                    Some(StorageResponse {
+                        key: res.key.0,
+                        value: res.value?.0,
+                    })
+                });
+                std::future::ready(res.transpose())
+            });
+        StreamOf(Box::pin(values))
+    }
+}
+
+fn storage_method_ok(res: ArchiveStorageResponse) -> Result<ArchiveStorageMethodOk, Error> {
+    match res {
+        ArchiveStorageResponse::Ok(res) => Ok(res),
+        ArchiveStorageResponse::Err(e) => Err(Error::Other(e.error)),
+    }
+}
+
+impl<T: Config> super::sealed::Sealed for ArchiveBackend<T> {}
+
+#[async_trait]
+impl<T: Config + Send + Sync + 'static> Backend<T> for ArchiveBackend<T> {
+    async fn storage_fetch_values(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_values(None, keys, at).await
+    }
+
+    async fn storage_fetch_descendant_keys(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        Ok(self.fetch_descendant_keys(None, key, at))
+    }
+
+    async fn storage_fetch_descendant_values(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_descendant_values(None, key, at))
+    }
+
+    async fn storage_fetch_read_proof(
+        &self,
+        _keys: Vec<Vec<u8>>,
+        _at: T::Hash,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        Err(Error::ReadProofsUnsupported)
+    }
+
+    async fn storage_fetch_changes(
+        &self,
+        keys: Vec<Vec<u8>>,
+        from: T::Hash,
+        to: T::Hash,
+    ) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error> {
+        // Walk back from the `to` block to the `from` block via parent hashes,
+        // and then compare the values at each block.
+        storage_changes_between(self.clone(), keys, from, to).await
+    }
+
+    async fn storage_fetch_child_values(
+        &self,
+        child_key: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_values(Some(&child_key), keys, at).await
+    }
+
+    async fn storage_fetch_child_descendant_keys(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        Ok(self.fetch_descendant_keys(Some(child_key), key, at))
+    }
+
+    async fn storage_fetch_child_descendant_values(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_descendant_values(Some(child_key), key, at))
+    }
+
+    async fn genesis_hash(&self) -> Result<T::Hash, Error> {
+        retry(|| self.methods.archive_unstable_genesis_hash()).await
+    }
+
+    async fn block_hash_at_height(&self, height: u64) -> Result<Option<T::Hash>, Error> {
+        let hashes = retry(|| self.methods.archive_unstable_hash_by_height(height)).await?;
+        // Several blocks may exist at heights which aren't finalized yet, in
+        // which case we don't know which of them is on the canonical chain.
+        match hashes.as_slice() {
+            [hash] => Ok(Some(*hash)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn block_header(&self, at: T::Hash) -> Result<Option<T::Header>, Error> {
+        retry(|| self.methods.archive_unstable_header(at)).await
+    }
+
+    async fn block_body(&self, at: T::Hash) -> Result<Option<Vec<Vec<u8>>>, Error> {
+        let body = retry(|| self.methods.archive_unstable_body(at)).await?;
+        Ok(body.map(|extrinsics| extrinsics.into_iter().map(|ext| ext.0).collect()))
+    }
+
+    async fn latest_finalized_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        let height = retry(|| self.methods.archive_unstable_finalized_height()).await?;
+        let hashes = retry(|| self.methods.archive_unstable_hash_by_height(height)).await?;
+        // Only the finalized block is returned for finalized heights.
+        let hash = hashes.into_iter().next().ok_or_else(|| {
+            Error::Other(format!("No block hash found at finalized height {height}"))
+        })?;
+        Ok(BlockRef::from_hash(hash))
+    }
+
+    async fn latest_best_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        // The archive methods only know about finalized blocks.
+        self.latest_finalized_block_ref().await
+    }
+
+    async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        let at = self.latest_finalized_block_ref().await?.hash();
+        let version = self.call("Core_version", None, at).await?;
+
+        // The spec and transaction versions are found at these positions in
+        // the encoded `sp_version::RuntimeVersion`.
+        #[allow(clippy::type_complexity)]
+        let (
+            _spec_name,
+            _impl_name,
+            _authoring_version,
+            spec_version,
+            _impl_version,
+            _apis,
+            transaction_version,
+        ): (String, String, u32, u32, u32, Vec<([u8; 8], u32)>, u32) =
+            Decode::decode(&mut &*version)?;
+
+        Ok(RuntimeVersion {
+            spec_version,
+            transaction_version,
+        })
+    }
+
+    async fn stream_runtime_version(&self) -> Result<StreamOfResults<RuntimeVersion>, Error> {
+        Err(not_supported("Following runtime versions"))
+    }
+
+    async fn stream_all_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        Err(not_supported("Following new blocks"))
+    }
+
+    async fn stream_best_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        Err(not_supported("Following new blocks"))
+    }
+
+    async fn stream_finalized_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        Err(not_supported("Following new blocks"))
+    }
+
+    async fn stream_chain_events(&self) -> Result<StreamOfResults<ChainEvent<T::Hash>>, Error> {
+        Err(not_supported("Following the chain"))
+    }
+
+    async fn submit_transaction(
+        &self,
+        _bytes: &[u8],
+    ) -> Result<StreamOfResults<TransactionStatus<T::Hash>>, Error> {
+        Err(not_supported("Submitting transactions"))
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        call_parameters: Option<&[u8]>,
+        at: T::Hash,
+    ) -> Result<Vec<u8>, Error> {
+        let call_parameters = call_parameters.unwrap_or(&[]);
+        let res = retry(|| {
+            self.methods
+                .archive_unstable_call(at, method, call_parameters)
+        })
+        .await?;
+
+        match res {
+            ArchiveCallResult::Success(res) => Ok(res.value.0),
+            ArchiveCallResult::Error(e) => Err(Error::Other(e.error)),
+        }
+    }
+}
+
+fn not_supported(what: &str) -> Error {
+    Error::Other(format!(
+        "{what} is not supported by the archive RPC methods"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::test_utils::header;
+    use crate::backend::rpc::mock_rpc_client::MockRpcClient;
+    use crate::config::Header;
+    use crate::SubstrateConfig;
+    use codec::Encode;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    type Hash = <SubstrateConfig as Config>::Hash;
+
+    fn hex(bytes: impl AsRef<[u8]>) -> Value {
+        json!(format!("0x{}", hex::encode(bytes.as_ref())))
+    }
+
+    // Respond to `archive_unstable_storage` value queries with the given values in each block.
+    fn storage_handler(
+        storage: Vec<(Hash, Vec<u8>, Vec<u8>)>,
+    ) -> impl Fn(Vec<Value>) -> Result<Value, crate::error::RpcError> + Send + Sync + 'static {
+        move |params| {
+            let result: Vec<_> = params[1]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|item| {
+                    let (_, _, value) = storage
+                        .iter()
+                        .find(|(at, key, _)| hex(at.0) == params[0] && hex(key) == item["key"])?;
+                    Some(json!({ "key": item["key"], "value": hex(value) }))
+                })
+                .collect();
+            Ok(json!({ "result": result, "discardedItems": 0 }))
+        }
+    }
+
+    #[tokio::test]
+    async fn block_hash_at_height_is_only_given_for_a_single_block() {
+        let client = MockRpcClient::builder()
+            .method("archive_unstable_hashByHeight", |params| {
+                match params[0].as_u64().unwrap() {
+                    1 => Ok(json!([hex([1u8; 32])])),
+                    _ => Ok(json!([hex([2u8; 32]), hex([3u8; 32])])),
+                }
+            })
+            .build();
+        let backend = ArchiveBackend::<SubstrateConfig>::new(client);
+
+        let hash = backend.block_hash_at_height(1).await.unwrap();
+        assert_eq!(hash, Some(Hash::from([1u8; 32])));
+        // More than one block exists at an unfinalized height.
+        let hash = backend.block_hash_at_height(2).await.unwrap();
+        assert_eq!(hash, None);
+    }
+
+    #[tokio::test]
+    async fn discarded_storage_queries_are_retried() {
+        let at = Hash::from([1u8; 32]);
+        let storage = storage_handler(vec![
+            (at, vec![1], vec![10]),
+            (at, vec![2], vec![20]),
+            (at, vec![3], vec![30]),
+        ]);
+
+        // The first call only answers the first query.
+        let calls = AtomicUsize::new(0);
+        let client = MockRpcClient::builder()
+            .method("archive_unstable_storage", move |mut params| {
+                if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                    params[1].as_array_mut().unwrap().truncate(1);
+                    let mut res = storage(params)?;
+                    res["discardedItems"] = json!(3);
+                    Ok(res)
+                } else {
+                    storage(params)
+                }
+            })
+            .build();
+        let backend = ArchiveBackend::<SubstrateConfig>::new(client.clone());
+
+        let values: Vec<_> = backend
+            .storage_fetch_values(vec![vec![1], vec![2], vec![3], vec![4]], at)
+            .await
+            .unwrap()
+            .map(|res| {
+                let res = res.unwrap();
+                (res.key, res.value)
+            })
+            .collect()
+            .await;
+        assert_eq!(
+            values,
+            vec![
+                (vec![1], vec![10]),
+                (vec![2], vec![20]),
+                (vec![3], vec![30])
+            ]
+        );
+
+        let queried_keys: Vec<_> = client
+            .requests()
+            .into_iter()
+            .map(|(_, params)| params[1].as_array().unwrap().len())
+            .collect();
+        assert_eq!(queried_keys, vec![4, 3]);
+    }
+
+    #[tokio::test]
+    async fn storage_changes_follow_parent_hashes() {
+        let block1 = header(1, Default::default());
+        let block2 = header(2, block1.hash());
+        let block3 = header(3, block2.hash());
+        let (hash1, hash2, hash3) = (block1.hash(), block2.hash(), block3.hash());
+        let headers: Arc<Vec<_>> = Arc::new(vec![block1, block2, block3]);
+
+        let key = vec![1u8];
+        let client = MockRpcClient::builder()
+            .method("archive_unstable_header", move |params| {
+                let header = headers.iter().find(|h| hex(h.hash().0) == params[0]);
+                Ok(header.map_or(Value::Null, |h| hex(h.encode())))
+            })
+            .method(
+                "archive_unstable_storage",
+                storage_handler(vec![
+                    (hash1, key.clone(), vec![1]),
+                    (hash2, key.clone(), vec![1]),
+                    (hash3, key.clone(), vec![3]),
+                ]),
+            )
+            .build();
+        let backend = ArchiveBackend::<SubstrateConfig>::new(client);
+
+        let changes: Vec<_> = backend
+            .storage_fetch_changes(vec![key.clone()], hash1, hash3)
+            .await
+            .unwrap()
+            .map(|changes| changes.unwrap())
+            .collect()
+            .await;
+        assert_eq!(
+            changes,
+            vec![
+                StorageChanges {
+                    block_hash: hash1,
+                    changes: vec![(key.clone(), Some(vec![1]))],
+                },
+                StorageChanges {
+                    block_hash: hash3,
+                    changes: vec![(key.clone(), Some(vec![3]))],
+                },
+            ]
+        );
+
+        // Block 3 is not an ancestor of block 1.
+        assert!(backend
+            .storage_fetch_changes(vec![key], hash3, hash1)
+            .await
+            .is_err());
+    }
+}
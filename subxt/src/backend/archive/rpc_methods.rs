@@ -0,0 +1,258 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An interface to call the `archive_unstable_*` RPC methods. See
+//! <https://github.com/paritytech/json-rpc-interface-spec/> for details of the API
+//! methods exposed here.
+
+use crate::backend::rpc::{rpc_params, RpcClient};
+use crate::{Config, Error};
+use derive_where::derive_where;
+use serde::{Deserialize, Serialize};
+
+// These types are shared with the chainHead methods.
+pub use crate::backend::unstable::rpc_methods::{Bytes, StorageQueryType};
+
+/// An interface to call the archive RPC methods. This interface is instantiated with
+/// some `T: Config` trait which determines some of the types that the RPC methods will
+/// take or hand back.
+#[derive_where(Clone, Debug)]
+pub struct ArchiveRpcMethods<T> {
+    client: RpcClient,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Config> ArchiveRpcMethods<T> {
+    /// Instantiate the archive RPC method interface.
+    pub fn new(client: RpcClient) -> Self {
+        ArchiveRpcMethods {
+            client,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Return the body of the block with the given hash, or `None` if the block is not known.
+    /// Each extrinsic is SCALE encoded.
+    pub async fn archive_unstable_body(&self, hash: T::Hash) -> Result<Option<Vec<Bytes>>, Error> {
+        self.client
+            .request("archive_unstable_body", rpc_params![hash])
+            .await
+    }
+
+    /// Call a runtime API function at the block with the given hash.
+    pub async fn archive_unstable_call(
+        &self,
+        hash: T::Hash,
+        function: &str,
+        call_parameters: &[u8],
+    ) -> Result<ArchiveCallResult, Error> {
+        self.client
+            .request(
+                "archive_unstable_call",
+                rpc_params![hash, function, to_hex(call_parameters)],
+            )
+            .await
+    }
+
+    /// Return the height of the current finalized block.
+    pub async fn archive_unstable_finalized_height(&self) -> Result<u64, Error> {
+        self.client
+            .request("archive_unstable_finalizedHeight", rpc_params![])
+            .await
+    }
+
+    /// Return the genesis hash.
+    pub async fn archive_unstable_genesis_hash(&self) -> Result<T::Hash, Error> {
+        self.client
+            .request("archive_unstable_genesisHash", rpc_params![])
+            .await
+    }
+
+    /// Return the hashes of the blocks at the given height. If the height is finalized, then
+    /// only the hash of the finalized block at that height is returned. Otherwise, any number
+    /// of hashes may be returned.
+    pub async fn archive_unstable_hash_by_height(
+        &self,
+        height: u64,
+    ) -> Result<Vec<T::Hash>, Error> {
+        self.client
+            .request("archive_unstable_hashByHeight", rpc_params![height])
+            .await
+    }
+
+    /// Return the header of the block with the given hash, or `None` if the block is not known.
+    pub async fn archive_unstable_header(&self, hash: T::Hash) -> Result<Option<T::Header>, Error> {
+        // header returned as hex encoded SCALE encoded bytes.
+        let header: Option<Bytes> = self
+            .client
+            .request("archive_unstable_header", rpc_params![hash])
+            .await?;
+
+        let header = header
+            .map(|h| codec::Decode::decode(&mut &*h.0))
+            .transpose()?;
+        Ok(header)
+    }
+
+    /// Query the storage of the block with the given hash, either in the main trie or in the
+    /// default child trie with the given (unprefixed) key. The server may not respond to every
+    /// item queried (see [`ArchiveStorageMethodOk::discarded_items`]), and may limit the number
+    /// of results returned for queries of descendant keys, which can be continued by setting
+    /// the [`ArchiveStorageQuery::pagination_start_key`].
+    pub async fn archive_unstable_storage(
+        &self,
+        hash: T::Hash,
+        items: impl IntoIterator<Item = ArchiveStorageQuery<&[u8]>>,
+        child_key: Option<&[u8]>,
+    ) -> Result<ArchiveStorageResponse, Error> {
+        let items: Vec<ArchiveStorageQuery<String>> = items
+            .into_iter()
+            .map(|item| ArchiveStorageQuery {
+                key: to_hex(item.key),
+                query_type: item.query_type,
+                pagination_start_key: item.pagination_start_key.map(to_hex),
+            })
+            .collect();
+
+        self.client
+            .request(
+                "archive_unstable_storage",
+                rpc_params![hash, items, child_key.map(to_hex)],
+            )
+            .await
+    }
+}
+
+/// The result of calling a runtime API function via `archive_unstable_call`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ArchiveCallResult {
+    /// The call succeeded.
+    Success(ArchiveCallSuccess),
+    /// The call failed.
+    Error(ArchiveCallError),
+}
+
+/// The output of a successful `archive_unstable_call`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ArchiveCallSuccess {
+    /// Hexadecimal-encoded output of the runtime function call.
+    pub value: Bytes,
+}
+
+/// The reason that an `archive_unstable_call` failed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ArchiveCallError {
+    /// Human readable message; why the call failed.
+    pub error: String,
+}
+
+/// An item to query via `archive_unstable_storage`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageQuery<Key> {
+    /// The provided key.
+    pub key: Key,
+    /// The type of the storage query.
+    #[serde(rename = "type")]
+    pub query_type: StorageQueryType,
+    /// For queries of descendant keys, only return results for keys which come after this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination_start_key: Option<Key>,
+}
+
+/// The response from `archive_unstable_storage`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum ArchiveStorageResponse {
+    /// The storage was queried.
+    Ok(ArchiveStorageMethodOk),
+    /// The storage could not be queried.
+    Err(ArchiveStorageMethodErr),
+}
+
+/// The results of a successful `archive_unstable_storage` call.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageMethodOk {
+    /// The results of the queries.
+    pub result: Vec<ArchiveStorageResult>,
+    /// The number of items from the back of the queries that have been discarded,
+    /// and should be queried again.
+    pub discarded_items: usize,
+}
+
+/// The reason that an `archive_unstable_storage` call failed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ArchiveStorageMethodErr {
+    /// Human readable message; why the storage could not be queried.
+    pub error: String,
+}
+
+/// A single result from `archive_unstable_storage`. Which of the fields are present
+/// depends on the type of the query that led to it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageResult {
+    /// The hex-encoded key of the result.
+    pub key: Bytes,
+    /// The value at the key.
+    pub value: Option<Bytes>,
+    /// The hash of the value at the key.
+    pub hash: Option<Bytes>,
+    /// The closest descendant merkle value of the key.
+    pub closest_descendant_merkle_value: Option<Bytes>,
+    /// The key of the child trie that this result is from, if any.
+    pub child_trie_key: Option<Bytes>,
+}
+
+fn to_hex(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes.as_ref()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_deserialize_storage_responses() {
+        let ok = r#"{
+            "result": [{ "key": "0x01", "value": "0x02" }, { "key": "0x03", "hash": "0x04" }],
+            "discardedItems": 1
+        }"#;
+        let ArchiveStorageResponse::Ok(ok) = serde_json::from_str(ok).unwrap() else {
+            panic!("expected ok response");
+        };
+        assert_eq!(ok.discarded_items, 1);
+        assert_eq!(ok.result[0].value, Some(Bytes(vec![2])));
+        assert_eq!(ok.result[1].hash, Some(Bytes(vec![4])));
+
+        let err = r#"{ "error": "bad block" }"#;
+        assert_eq!(
+            serde_json::from_str::<ArchiveStorageResponse>(err).unwrap(),
+            ArchiveStorageResponse::Err(ArchiveStorageMethodErr {
+                error: "bad block".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn can_deserialize_call_results() {
+        let ok = r#"{ "success": true, "value": "0x0102" }"#;
+        assert_eq!(
+            serde_json::from_str::<ArchiveCallResult>(ok).unwrap(),
+            ArchiveCallResult::Success(ArchiveCallSuccess {
+                value: Bytes(vec![1, 2])
+            })
+        );
+
+        let err = r#"{ "success": false, "error": "wasm trap" }"#;
+        assert_eq!(
+            serde_json::from_str::<ArchiveCallResult>(err).unwrap(),
+            ArchiveCallResult::Error(ArchiveCallError {
+                error: "wasm trap".to_owned()
+            })
+        );
+    }
+}
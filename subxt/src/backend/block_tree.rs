@@ -0,0 +1,387 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Track the tree of unfinalized blocks, so that we can describe how the chain changes
+//! as blocks are imported and finalized via [`ChainEvent`]s.
+
+use super::{BlockRef, ChainEvent, StreamOf, StreamOfResults};
+use crate::config::{Config, Header};
+use crate::error::Error;
+use futures::{stream, StreamExt};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A tree of the unfinalized blocks that we know about, rooted at the latest finalized block.
+#[derive(Debug)]
+pub(crate) struct BlockTree<H> {
+    // The parent of each unfinalized block.
+    parents: HashMap<H, H>,
+    finalized: H,
+    best: H,
+}
+
+impl<H: Copy + Eq + Hash> BlockTree<H> {
+    /// Create a new tree, rooted at the given finalized block, which is also the best block.
+    pub fn new(finalized: H) -> Self {
+        BlockTree {
+            parents: HashMap::new(),
+            finalized,
+            best: finalized,
+        }
+    }
+
+    /// Is the given block the finalized block or an unfinalized block that we know about?
+    pub fn contains(&self, hash: H) -> bool {
+        hash == self.finalized || self.parents.contains_key(&hash)
+    }
+
+    /// The latest finalized block.
+    pub fn finalized(&self) -> H {
+        self.finalized
+    }
+
+    /// Add a new unfinalized block to the tree.
+    pub fn add_block(&mut self, hash: H, parent_hash: H) {
+        self.parents.insert(hash, parent_hash);
+    }
+
+    /// Make the given block the best block, returning a [`ChainEvent::BestBlockChanged`]
+    /// if this changed anything.
+    pub fn set_best(&mut self, hash: H) -> Option<ChainEvent<H>> {
+        if hash == self.best {
+            return None;
+        }
+
+        let old_ancestry = self.unfinalized_ancestry(self.best);
+
+        // Walk back from the new best block until we hit the old best chain. If we never do
+        // (because we've missed some blocks), then every unfinalized block on the old best
+        // chain is swapped out. Blocks that we've never been told about aren't reported.
+        let mut retracted = old_ancestry.clone();
+        let mut enacted = Vec::new();
+        for ancestor in self.unfinalized_ancestry(hash) {
+            if let Some(pos) = old_ancestry.iter().position(|h| *h == ancestor) {
+                retracted.truncate(pos);
+                break;
+            }
+            enacted.push(ancestor);
+        }
+        enacted.reverse();
+
+        self.best = hash;
+        Some(ChainEvent::BestBlockChanged { retracted, enacted })
+    }
+
+    /// Finalize the given blocks (the last of which is the new finalized block), and forget
+    /// about them and the given pruned blocks. Anything else which does not descend from the
+    /// new finalized block is forgotten about too.
+    pub fn finalize(&mut self, finalized: &[H], pruned: &[H]) {
+        let Some(&new_finalized) = finalized.last() else {
+            return;
+        };
+
+        for hash in finalized.iter().chain(pruned) {
+            self.parents.remove(hash);
+        }
+        self.finalized = new_finalized;
+
+        let stale: Vec<H> = self
+            .parents
+            .keys()
+            .copied()
+            .filter(|&hash| !self.is_descendant_of(hash, new_finalized))
+            .collect();
+        for hash in stale {
+            self.parents.remove(&hash);
+        }
+
+        if !self.is_descendant_of(self.best, new_finalized) {
+            self.best = new_finalized;
+        }
+    }
+
+    /// Finalize the given known block and any unfinalized ancestors of it, working out which
+    /// blocks are pruned as a result. This returns the [`ChainEvent::Finalized`] event, preceded
+    /// by a [`ChainEvent::BestBlockChanged`] event if the best block needed to move to remain a
+    /// descendant of the finalized block.
+    pub fn finalize_to(&mut self, hash: H) -> Vec<ChainEvent<H>> {
+        let ancestry = self.ancestry(hash);
+        let mut finalized: Vec<H> = ancestry
+            .iter()
+            .copied()
+            .take_while(|&h| h != self.finalized)
+            .collect();
+        finalized.reverse();
+
+        if finalized.is_empty() {
+            return Vec::new();
+        }
+
+        // Blocks which are neither ancestors nor descendants of the new finalized block.
+        let pruned: Vec<H> = self
+            .parents
+            .keys()
+            .copied()
+            .filter(|&h| !ancestry.contains(&h) && !self.is_descendant_of(h, hash))
+            .collect();
+
+        let mut events = Vec::new();
+        if !self.is_descendant_of(self.best, hash) {
+            events.extend(self.set_best(hash));
+        }
+
+        self.finalize(&finalized, &pruned);
+        events.push(ChainEvent::Finalized {
+            hashes: finalized.into_iter().map(BlockRef::from_hash).collect(),
+            pruned,
+        });
+        events
+    }
+
+    // The given block followed by its known ancestors, nearest first.
+    fn ancestry(&self, hash: H) -> Vec<H> {
+        let mut ancestry = vec![hash];
+        let mut hash = hash;
+        while let Some(&parent) = self.parents.get(&hash) {
+            ancestry.push(parent);
+            hash = parent;
+        }
+        ancestry
+    }
+
+    // The given block followed by its ancestors, nearest first, stopping at the finalized
+    // block or the first block that we don't know about (neither of which are included).
+    fn unfinalized_ancestry(&self, hash: H) -> Vec<H> {
+        self.ancestry(hash)
+            .into_iter()
+            .take_while(|h| self.parents.contains_key(h))
+            .collect()
+    }
+
+    // Is the first block the same as, or a descendant of, the second?
+    fn is_descendant_of(&self, hash: H, ancestor: H) -> bool {
+        self.ancestry(hash).contains(&ancestor)
+    }
+}
+
+/// Which kind of subscription a block header was reported by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HeadKind {
+    /// A newly imported block.
+    New,
+    /// A new best block.
+    Best,
+    /// A newly finalized block.
+    Finalized,
+}
+
+/// Build [`ChainEvent`]s from the new, best and finalized block headers that backends like
+/// the legacy one are able to report. Headers can arrive in any order across the different
+/// subscriptions, and finalized blocks are expected to be reported one by one.
+#[derive(Debug)]
+pub(crate) struct HeaderChainEvents<H> {
+    tree: Option<BlockTree<H>>,
+    finalized_number: u64,
+    // Heads seen before the first finalized block, which we'll handle once we have it.
+    pending: Vec<(HeadKind, H, H, u64)>,
+}
+
+impl<H: Copy + Eq + Hash> HeaderChainEvents<H> {
+    /// Create a new [`HeaderChainEvents`]. Nothing is reported until a finalized block is seen.
+    pub fn new() -> Self {
+        HeaderChainEvents {
+            tree: None,
+            finalized_number: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Handle a block header reported by some subscription, returning any resulting events.
+    pub fn handle(
+        &mut self,
+        kind: HeadKind,
+        hash: H,
+        parent_hash: H,
+        number: u64,
+    ) -> Vec<ChainEvent<H>> {
+        let Some(tree) = &mut self.tree else {
+            if kind != HeadKind::Finalized {
+                self.pending.push((kind, hash, parent_hash, number));
+                return Vec::new();
+            }
+
+            // The first finalized block is the root that every later event builds on.
+            self.tree = Some(BlockTree::new(hash));
+            self.finalized_number = number;
+            let mut events = vec![ChainEvent::Finalized {
+                hashes: vec![BlockRef::from_hash(hash)],
+                pruned: Vec::new(),
+            }];
+            for (kind, hash, parent_hash, number) in std::mem::take(&mut self.pending) {
+                events.extend(self.handle(kind, hash, parent_hash, number));
+            }
+            return events;
+        };
+
+        // Ignore anything that's already been finalized or pruned.
+        if number <= self.finalized_number {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        if !tree.contains(hash) {
+            tree.add_block(hash, parent_hash);
+            events.push(ChainEvent::NewBlock {
+                block: BlockRef::from_hash(hash),
+                parent_hash,
+            });
+        }
+
+        match kind {
+            HeadKind::New => {}
+            HeadKind::Best => events.extend(tree.set_best(hash)),
+            HeadKind::Finalized => {
+                events.extend(tree.finalize_to(hash));
+                self.finalized_number = number;
+            }
+        }
+        events
+    }
+}
+
+/// Build a stream of [`ChainEvent`]s from streams of new, best and finalized block headers.
+pub(crate) fn chain_events_from_headers<T: Config>(
+    new_heads: StreamOfResults<(T::Header, BlockRef<T::Hash>)>,
+    best_heads: StreamOfResults<(T::Header, BlockRef<T::Hash>)>,
+    finalized_heads: StreamOfResults<(T::Header, BlockRef<T::Hash>)>,
+) -> StreamOfResults<ChainEvent<T::Hash>> {
+    let heads = stream::select_all([
+        tag_heads::<T>(HeadKind::New, new_heads),
+        tag_heads::<T>(HeadKind::Best, best_heads),
+        tag_heads::<T>(HeadKind::Finalized, finalized_heads),
+    ]);
+
+    let mut chain_events = HeaderChainEvents::new();
+    let events = heads.flat_map(move |head| {
+        let events = head.map(|(kind, header)| {
+            let number = header.number().into();
+            chain_events.handle(kind, header.hash(), header.parent_hash(), number)
+        });
+
+        let events: Vec<Result<ChainEvent<T::Hash>, Error>> = match events {
+            Ok(events) => events.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(events)
+    });
+
+    StreamOf(Box::pin(events))
+}
+
+fn tag_heads<T: Config>(
+    kind: HeadKind,
+    heads: StreamOfResults<(T::Header, BlockRef<T::Hash>)>,
+) -> StreamOfResults<(HeadKind, T::Header)> {
+    StreamOf(Box::pin(
+        heads.map(move |head| head.map(|(header, _)| (kind, header))),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(events: &[ChainEvent<u64>]) -> Vec<(&'static str, Vec<u64>, Vec<u64>)> {
+        events
+            .iter()
+            .map(|ev| match ev {
+                ChainEvent::NewBlock { block, parent_hash } => {
+                    ("new", vec![block.hash()], vec![*parent_hash])
+                }
+                ChainEvent::BestBlockChanged { retracted, enacted } => {
+                    ("best", retracted.clone(), enacted.clone())
+                }
+                ChainEvent::Finalized { hashes, pruned } => (
+                    "finalized",
+                    hashes.iter().map(|h| h.hash()).collect(),
+                    pruned.clone(),
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_reorgs_and_pruned_forks() {
+        // Block hashes are the block number * 10, plus the fork index.
+        let mut events = HeaderChainEvents::new();
+        let mut handle = |kind, hash: u64, parent_hash: u64| {
+            hashes(&events.handle(kind, hash, parent_hash, hash / 10))
+        };
+
+        // Heads before the first finalized block are handled once we see it.
+        assert_eq!(handle(HeadKind::New, 10, 0), vec![]);
+        assert_eq!(
+            handle(HeadKind::Finalized, 0, 0),
+            vec![("finalized", vec![0], vec![]), ("new", vec![10], vec![0])]
+        );
+
+        assert_eq!(
+            handle(HeadKind::Best, 20, 10),
+            vec![("new", vec![20], vec![10]), ("best", vec![], vec![10, 20])]
+        );
+
+        // A fork from block 1 becomes the best chain.
+        assert_eq!(
+            handle(HeadKind::New, 21, 10),
+            vec![("new", vec![21], vec![10])]
+        );
+        assert_eq!(
+            handle(HeadKind::Best, 31, 21),
+            vec![
+                ("new", vec![31], vec![21]),
+                ("best", vec![20], vec![21, 31])
+            ]
+        );
+
+        // Finalizing the original fork moves the best block back and prunes the other fork.
+        let mut finalized = handle(HeadKind::Finalized, 20, 10);
+        finalized[1].2.sort();
+        assert_eq!(
+            finalized,
+            vec![
+                ("best", vec![31, 21], vec![20]),
+                ("finalized", vec![10, 20], vec![21, 31])
+            ]
+        );
+
+        // Heads that are already finalized are ignored.
+        assert_eq!(handle(HeadKind::New, 21, 10), vec![]);
+        assert_eq!(handle(HeadKind::Finalized, 20, 10), vec![]);
+    }
+
+    #[test]
+    fn only_reports_known_unfinalized_blocks_when_the_best_block_changes() {
+        let mut events = HeaderChainEvents::new();
+        let mut handle = |kind, hash: u64, parent_hash: u64| {
+            hashes(&events.handle(kind, hash, parent_hash, hash / 10))
+        };
+        assert_eq!(
+            handle(HeadKind::Finalized, 0, 0),
+            vec![("finalized", vec![0], vec![])]
+        );
+
+        // We never heard about block 10, so it's not enacted.
+        assert_eq!(
+            handle(HeadKind::Best, 20, 10),
+            vec![("new", vec![20], vec![10]), ("best", vec![], vec![20])]
+        );
+
+        // Nor do we know about block 21. The old best chain is retracted, but not the
+        // finalized block that it descends from.
+        assert_eq!(
+            handle(HeadKind::Best, 31, 21),
+            vec![("new", vec![31], vec![21]), ("best", vec![20], vec![31])]
+        );
+    }
+}
@@ -0,0 +1,882 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module exposes a [`CachingBackend`], which wraps some other [`Backend`] and caches
+//! the responses to queries whose results can never change, such as fetching the header,
+//! body or storage of a block with a given hash, or making a runtime API call at one.
+//!
+//! ```rust,no_run
+//! # async fn foo() -> Result<(), subxt::Error> {
+//! use subxt::backend::{caching::CachingBackend, legacy::LegacyBackend, rpc::RpcClient};
+//! use subxt::{OnlineClient, PolkadotConfig};
+//!
+//! let rpc_client = RpcClient::from_url("ws://127.0.0.1:9944").await?;
+//! let backend = CachingBackend::builder()
+//!     .capacity(100_000)
+//!     .build(LegacyBackend::<PolkadotConfig>::builder().build(rpc_client));
+//!
+//! // Optionally, load responses cached by a previous run:
+//! backend.load_from_file("subxt-cache.bin").await?;
+//!
+//! let api = OnlineClient::<PolkadotConfig>::from_backend(std::sync::Arc::new(backend)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::backend::{
+    Backend, BlockRef, ChainEvent, RuntimeVersion, StorageChanges, StorageResponse, StreamOf,
+    StreamOfResults, TransactionStatus,
+};
+use crate::{Config, Error};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::{stream, StreamExt};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// The default maximum number of responses that a [`CachingBackend`] will hold.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Configure and build a [`CachingBackend`].
+#[derive(Debug, Clone)]
+pub struct CachingBackendBuilder {
+    capacity: usize,
+}
+
+impl Default for CachingBackendBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CachingBackendBuilder {
+    /// Create a new [`CachingBackendBuilder`].
+    pub fn new() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+
+    /// The maximum number of responses to cache (default: 10,000). Once this is
+    /// reached, the least recently used responses are evicted to make room for new ones.
+    ///
+    /// This bounds the number of responses and not their size in bytes. Each storage value is
+    /// cached separately, whereas each iteration over the entries under some storage key
+    /// counts as a single response regardless of its length, as does each block body or
+    /// runtime API response. Keep this in mind when caching large responses.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Given some [`Backend`] to wrap, this returns a [`CachingBackend`].
+    pub fn build<B>(self, backend: B) -> CachingBackend<B> {
+        CachingBackend {
+            backend,
+            cache: Arc::new(Mutex::new(LruCache::new(self.capacity))),
+        }
+    }
+}
+
+/// A [`Backend`] which wraps some other backend, and caches the responses to the
+/// queries made at specific block hashes, whose results will never change. This makes
+/// it cheap to repeatedly look at the same blocks, for instance to fetch their events.
+///
+/// The following are cached:
+///
+/// - [`Backend::block_header`] and [`Backend::block_body`], if the block is found.
+/// - [`Backend::call`].
+/// - [`Backend::storage_fetch_values`], per storage key, including the absence of a value.
+/// - [`Backend::storage_fetch_descendant_keys`] and [`Backend::storage_fetch_descendant_values`],
+///   once the whole stream has been successfully consumed.
+/// - [`Backend::storage_fetch_read_proof`].
+/// - The child trie equivalents of the above storage methods.
+///
+/// Everything else is passed straight through to the wrapped backend.
+///
+/// Cloning this shares the cache between the clones.
+#[derive(Debug, Clone)]
+pub struct CachingBackend<B> {
+    backend: B,
+    cache: Arc<Mutex<LruCache>>,
+}
+
+impl CachingBackend<()> {
+    /// Configure and construct a [`CachingBackend`].
+    pub fn builder() -> CachingBackendBuilder {
+        CachingBackendBuilder::new()
+    }
+}
+
+impl<B> CachingBackend<B> {
+    /// Wrap the given backend, caching up to 10,000 responses.
+    pub fn new(backend: B) -> Self {
+        CachingBackendBuilder::new().build(backend)
+    }
+
+    /// Return the wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+
+    /// The number of responses that are currently cached.
+    pub fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Return true if no responses are currently cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Discard every cached response.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+crate::macros::cfg_native! {
+    impl<B> CachingBackend<B> {
+        /// Write every cached response to the file at the given path, so that they can be
+        /// loaded again via [`CachingBackend::load_from_file`], for instance in a later run.
+        /// Any existing file is overwritten. The genesis hash of the chain is saved alongside
+        /// the responses.
+        pub async fn save_to_file<T: Config>(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error>
+        where
+            B: Backend<T>,
+        {
+            let genesis_hash = Backend::<T>::genesis_hash(self).await?;
+            let mut bytes = genesis_hash.encode();
+            bytes.extend(self.cache.lock().unwrap().to_bytes());
+            std::fs::write(path, bytes)?;
+            Ok(())
+        }
+
+        /// Load the responses saved via [`CachingBackend::save_to_file`] into the cache. If the
+        /// file does not exist then nothing is loaded. An error is returned if the file was saved
+        /// by a backend connected to a chain with a different genesis hash.
+        pub async fn load_from_file<T: Config>(&self, path: impl AsRef<std::path::Path>) -> Result<(), Error>
+        where
+            B: Backend<T>,
+        {
+            let bytes = match std::fs::read(path) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+
+            let cursor = &mut &*bytes;
+            let saved_genesis_hash = T::Hash::decode(cursor)?;
+            let genesis_hash = Backend::<T>::genesis_hash(self).await?;
+            if saved_genesis_hash != genesis_hash {
+                return Err(Error::Other(format!(
+                    "The cached responses were saved from a chain with genesis hash {saved_genesis_hash:?}, \
+                     but this chain has genesis hash {genesis_hash:?}"
+                )));
+            }
+
+            self.cache.lock().unwrap().extend_from_bytes(cursor)?;
+            Ok(())
+        }
+    }
+}
+
+impl<B> CachingBackend<B> {
+    fn cache_get<V: Decode>(&self, key: &[u8]) -> Option<V> {
+        let bytes = self.cache.lock().unwrap().get(key)?;
+        // If the entry can't be decoded (perhaps it was loaded from a bad file),
+        // treat it as missing; it'll be overwritten once fetched again.
+        V::decode(&mut &*bytes).ok()
+    }
+
+    fn cache_insert<V: Encode>(&self, key: Vec<u8>, value: &V) {
+        self.cache.lock().unwrap().insert(key, value.encode());
+    }
+
+    // Fetch values from either the main trie or the child trie with the given
+    // storage key, using the cached value (or absence of one) for each key if we can.
+    async fn fetch_values<T: Config>(
+        &self,
+        child_key: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error>
+    where
+        B: Backend<T>,
+    {
+        let cache_key = |key: &[u8]| {
+            CacheKey::StorageValue {
+                at,
+                child_key: child_key.as_deref(),
+                key,
+            }
+            .encode()
+        };
+
+        let mut responses = Vec::new();
+        let mut missing_keys = Vec::new();
+        for key in keys {
+            match self.cache_get::<Option<Vec<u8>>>(&cache_key(&key)) {
+                Some(Some(value)) => responses.push(StorageResponse { key, value }),
+                Some(None) => {}
+                None => missing_keys.push(key),
+            }
+        }
+
+        if !missing_keys.is_empty() {
+            let mut fetched = match &child_key {
+                Some(child_key) => {
+                    self.backend
+                        .storage_fetch_child_values(child_key.clone(), missing_keys.clone(), at)
+                        .await?
+                }
+                None => {
+                    self.backend
+                        .storage_fetch_values(missing_keys.clone(), at)
+                        .await?
+                }
+            };
+
+            // Only keys with values are returned, so we need to see all of the
+            // responses before we know which keys have no value.
+            let mut values = HashMap::new();
+            while let Some(res) = fetched.next().await {
+                let res = res?;
+                values.insert(res.key, res.value);
+            }
+            for key in missing_keys {
+                let value = values.remove(&key);
+                self.cache_insert(cache_key(&key), &value);
+                if let Some(value) = value {
+                    responses.push(StorageResponse { key, value });
+                }
+            }
+        }
+
+        Ok(StreamOf::new(Box::pin(stream::iter(
+            responses.into_iter().map(Ok),
+        ))))
+    }
+
+    // Return the cached items if they exist, else pass through the items from the given
+    // stream, caching them all if the stream completes without error.
+    async fn cached_stream<Item, Fut>(
+        &self,
+        cache_key: Vec<u8>,
+        fetch: Fut,
+    ) -> Result<StreamOfResults<Item>, Error>
+    where
+        Item: Encode + Decode + Clone + Send + 'static,
+        Fut: std::future::Future<Output = Result<StreamOfResults<Item>, Error>>,
+    {
+        if let Some(items) = self.cache_get::<Vec<Item>>(&cache_key) {
+            return Ok(StreamOf::new(Box::pin(stream::iter(
+                items.into_iter().map(Ok),
+            ))));
+        }
+
+        let items_stream = fetch.await?;
+        let cache = self.cache.clone();
+        let items_stream = stream::unfold(
+            (items_stream, Some(Vec::new())),
+            move |(mut items_stream, mut items)| {
+                let cache = cache.clone();
+                let cache_key = cache_key.clone();
+                async move {
+                    match items_stream.next().await {
+                        Some(Ok(item)) => {
+                            if let Some(items) = &mut items {
+                                items.push(item.clone());
+                            }
+                            Some((Ok(item), (items_stream, items)))
+                        }
+                        // Don't cache anything if we hit an error.
+                        Some(Err(e)) => Some((Err(e), (items_stream, None))),
+                        None => {
+                            if let Some(items) = items {
+                                cache.lock().unwrap().insert(cache_key, items.encode());
+                            }
+                            None
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(StreamOf::new(Box::pin(items_stream)))
+    }
+}
+
+impl<B> super::sealed::Sealed for CachingBackend<B> {}
+
+#[async_trait]
+impl<T: Config, B: Backend<T>> Backend<T> for CachingBackend<B> {
+    async fn storage_fetch_values(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_values(None, keys, at).await
+    }
+
+    async fn storage_fetch_descendant_keys(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        let cache_key = CacheKey::DescendantKeys {
+            at,
+            child_key: None,
+            key: &key,
+        }
+        .encode();
+        self.cached_stream(
+            cache_key,
+            self.backend.storage_fetch_descendant_keys(key.clone(), at),
+        )
+        .await
+    }
+
+    async fn storage_fetch_descendant_values(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        let cache_key = CacheKey::DescendantValues {
+            at,
+            child_key: None,
+            key: &key,
+        }
+        .encode();
+        let fetch = async {
+            let values = self
+                .backend
+                .storage_fetch_descendant_values(key.clone(), at)
+                .await?;
+            Ok(response_to_pair_stream(values))
+        };
+        let values = self.cached_stream(cache_key, fetch).await?;
+        Ok(pair_to_response_stream(values))
+    }
+
+    async fn storage_fetch_read_proof(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let cache_key = CacheKey::ReadProof { at, keys: &keys }.encode();
+        if let Some(proof) = self.cache_get(&cache_key) {
+            return Ok(proof);
+        }
+        let proof = self
+            .backend
+            .storage_fetch_read_proof(keys.clone(), at)
+            .await?;
+        self.cache_insert(cache_key, &proof);
+        Ok(proof)
+    }
+
+    async fn storage_fetch_changes(
+        &self,
+        keys: Vec<Vec<u8>>,
+        from: T::Hash,
+        to: T::Hash,
+    ) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error> {
+        self.backend.storage_fetch_changes(keys, from, to).await
+    }
+
+    async fn storage_fetch_child_values(
+        &self,
+        child_key: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_values(Some(child_key), keys, at).await
+    }
+
+    async fn storage_fetch_child_descendant_keys(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        let cache_key = CacheKey::DescendantKeys {
+            at,
+            child_key: Some(&child_key),
+            key: &key,
+        }
+        .encode();
+        self.cached_stream(
+            cache_key,
+            self.backend
+                .storage_fetch_child_descendant_keys(child_key.clone(), key.clone(), at),
+        )
+        .await
+    }
+
+    async fn storage_fetch_child_descendant_values(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        let cache_key = CacheKey::DescendantValues {
+            at,
+            child_key: Some(&child_key),
+            key: &key,
+        }
+        .encode();
+        let fetch = async {
+            let values = self
+                .backend
+                .storage_fetch_child_descendant_values(child_key.clone(), key.clone(), at)
+                .await?;
+            Ok(response_to_pair_stream(values))
+        };
+        let values = self.cached_stream(cache_key, fetch).await?;
+        Ok(pair_to_response_stream(values))
+    }
+
+    async fn genesis_hash(&self) -> Result<T::Hash, Error> {
+        let cache_key = CacheKey::<T::Hash>::GenesisHash.encode();
+        if let Some(hash) = self.cache_get(&cache_key) {
+            return Ok(hash);
+        }
+        let hash = self.backend.genesis_hash().await?;
+        self.cache_insert(cache_key, &hash);
+        Ok(hash)
+    }
+
+    async fn block_hash_at_height(&self, height: u64) -> Result<Option<T::Hash>, Error> {
+        // Which block is at some height can change until it's finalized, so we don't cache this.
+        self.backend.block_hash_at_height(height).await
+    }
+
+    async fn block_header(&self, at: T::Hash) -> Result<Option<T::Header>, Error> {
+        let cache_key = CacheKey::BlockHeader { at }.encode();
+        if let Some(header) = self.cache_get(&cache_key) {
+            return Ok(Some(header));
+        }
+        // Blocks which aren't found may be found later, so only cache those that are.
+        let header = self.backend.block_header(at).await?;
+        if let Some(header) = &header {
+            self.cache_insert(cache_key, header);
+        }
+        Ok(header)
+    }
+
+    async fn block_body(&self, at: T::Hash) -> Result<Option<Vec<Vec<u8>>>, Error> {
+        let cache_key = CacheKey::BlockBody { at }.encode();
+        if let Some(body) = self.cache_get(&cache_key) {
+            return Ok(Some(body));
+        }
+        let body = self.backend.block_body(at).await?;
+        if let Some(body) = &body {
+            self.cache_insert(cache_key, body);
+        }
+        Ok(body)
+    }
+
+    async fn latest_finalized_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        self.backend.latest_finalized_block_ref().await
+    }
+
+    async fn latest_best_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        self.backend.latest_best_block_ref().await
+    }
+
+    async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        self.backend.current_runtime_version().await
+    }
+
+    async fn stream_runtime_version(&self) -> Result<StreamOfResults<RuntimeVersion>, Error> {
+        self.backend.stream_runtime_version().await
+    }
+
+    async fn stream_all_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        self.backend.stream_all_block_headers().await
+    }
+
+    async fn stream_best_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        self.backend.stream_best_block_headers().await
+    }
+
+    async fn stream_finalized_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        self.backend.stream_finalized_block_headers().await
+    }
+
+    async fn stream_chain_events(&self) -> Result<StreamOfResults<ChainEvent<T::Hash>>, Error> {
+        self.backend.stream_chain_events().await
+    }
+
+    async fn submit_transaction(
+        &self,
+        bytes: &[u8],
+    ) -> Result<StreamOfResults<TransactionStatus<T::Hash>>, Error> {
+        self.backend.submit_transaction(bytes).await
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        call_parameters: Option<&[u8]>,
+        at: T::Hash,
+    ) -> Result<Vec<u8>, Error> {
+        let cache_key = CacheKey::Call {
+            at,
+            method,
+            call_parameters,
+        }
+        .encode();
+        if let Some(res) = self.cache_get(&cache_key) {
+            return Ok(res);
+        }
+        let res = self.backend.call(method, call_parameters, at).await?;
+        self.cache_insert(cache_key, &res);
+        Ok(res)
+    }
+}
+
+/// Identifies a cached response. This is SCALE encoded to obtain the key
+/// that the response is stored under (and persisted to disk with).
+#[derive(Encode)]
+enum CacheKey<'a, Hash> {
+    GenesisHash,
+    BlockHeader {
+        at: Hash,
+    },
+    BlockBody {
+        at: Hash,
+    },
+    Call {
+        at: Hash,
+        method: &'a str,
+        call_parameters: Option<&'a [u8]>,
+    },
+    StorageValue {
+        at: Hash,
+        child_key: Option<&'a [u8]>,
+        key: &'a [u8],
+    },
+    DescendantKeys {
+        at: Hash,
+        child_key: Option<&'a [u8]>,
+        key: &'a [u8],
+    },
+    DescendantValues {
+        at: Hash,
+        child_key: Option<&'a [u8]>,
+        key: &'a [u8],
+    },
+    ReadProof {
+        at: Hash,
+        keys: &'a [Vec<u8>],
+    },
+}
+
+fn response_to_pair_stream(
+    values: StreamOfResults<StorageResponse>,
+) -> StreamOfResults<(Vec<u8>, Vec<u8>)> {
+    StreamOf::new(Box::pin(
+        values.map(|res| res.map(|res| (res.key, res.value))),
+    ))
+}
+
+fn pair_to_response_stream(
+    values: StreamOfResults<(Vec<u8>, Vec<u8>)>,
+) -> StreamOfResults<StorageResponse> {
+    StreamOf::new(Box::pin(
+        values.map(|res| res.map(|(key, value)| StorageResponse { key, value })),
+    ))
+}
+
+/// A simple bounded cache of encoded keys to encoded values, which evicts
+/// the least recently used entry once it's full.
+#[derive(Debug)]
+struct LruCache {
+    capacity: usize,
+    // Incremented on each access, so that entries with a lower
+    // "last used" value were used less recently.
+    counter: u64,
+    entries: HashMap<Vec<u8>, (Vec<u8>, u64)>,
+    by_last_used: BTreeMap<u64, Vec<u8>>,
+}
+
+impl LruCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            counter: 0,
+            entries: HashMap::new(),
+            by_last_used: BTreeMap::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.by_last_used.clear();
+    }
+
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.counter += 1;
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self
+            .by_last_used
+            .remove(last_used)
+            .expect("every entry has a last used counter; qed");
+        *last_used = self.counter;
+        self.by_last_used.insert(self.counter, key);
+        Some(value.clone())
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.counter += 1;
+        if let Some((_, last_used)) = self.entries.remove(&key) {
+            self.by_last_used.remove(&last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest_key)) = self.by_last_used.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest_key);
+        }
+        self.by_last_used.insert(self.counter, key.clone());
+        self.entries.insert(key, (value, self.counter));
+    }
+
+    // Encode the entries from least to most recently used, so that
+    // this order is preserved when they are loaded again.
+    fn to_bytes(&self) -> Vec<u8> {
+        let entries: Vec<(&Vec<u8>, &Vec<u8>)> = self
+            .by_last_used
+            .values()
+            .map(|key| (key, &self.entries[key].0))
+            .collect();
+        entries.encode()
+    }
+
+    fn extend_from_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let entries = <Vec<(Vec<u8>, Vec<u8>)>>::decode(&mut &*bytes)?;
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::archive::ArchiveBackend;
+    use crate::backend::rpc::mock_rpc_client::MockRpcClient;
+    use crate::SubstrateConfig;
+    use serde_json::{json, Value};
+
+    type Hash = <SubstrateConfig as Config>::Hash;
+
+    fn hex(bytes: impl AsRef<[u8]>) -> Value {
+        json!(format!("0x{}", hex::encode(bytes.as_ref())))
+    }
+
+    // A caching backend on top of an archive backend, whose storage has a value at key 1
+    // and nothing at key 2, and whose descendant keys of anything are 1 and 2. The mock
+    // client is handed back too so that we can see which requests were made.
+    fn caching_backend(
+        genesis_hash: Hash,
+    ) -> (
+        CachingBackend<ArchiveBackend<SubstrateConfig>>,
+        MockRpcClient,
+    ) {
+        let client = MockRpcClient::builder()
+            .method("archive_unstable_genesisHash", move |_| {
+                Ok(hex(genesis_hash.0))
+            })
+            .method("archive_unstable_call", |_| {
+                Ok(json!({ "success": true, "value": "0x01" }))
+            })
+            .method("archive_unstable_storage", |params| {
+                let query = &params[1][0];
+                let result = match query["type"].as_str().unwrap() {
+                    "value" => params[1]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .filter(|item| item["key"] == hex([1]))
+                        .map(|item| json!({ "key": item["key"], "value": "0x0a" }))
+                        .collect(),
+                    // The archive backend ignores any keys before the pagination start key.
+                    _ => vec![
+                        json!({ "key": hex([1]), "hash": "0x00" }),
+                        json!({ "key": hex([2]), "hash": "0x00" }),
+                    ],
+                };
+                Ok(json!({ "result": result, "discardedItems": 0 }))
+            })
+            .build();
+        let backend = CachingBackend::new(ArchiveBackend::new(client.clone()));
+        (backend, client)
+    }
+
+    fn num_requests(client: &MockRpcClient, method: &str) -> usize {
+        client
+            .requests()
+            .iter()
+            .filter(|(name, _)| name == method)
+            .count()
+    }
+
+    #[tokio::test]
+    async fn runtime_calls_are_cached() {
+        let (backend, client) = caching_backend(Hash::zero());
+        let at = Hash::repeat_byte(1);
+
+        for _ in 0..2 {
+            let res = Backend::<SubstrateConfig>::call(&backend, "Foo_bar", None, at).await;
+            assert_eq!(res.unwrap(), vec![1]);
+        }
+        assert_eq!(num_requests(&client, "archive_unstable_call"), 1);
+
+        // A call at a different block isn't cached.
+        Backend::<SubstrateConfig>::call(&backend, "Foo_bar", None, Hash::zero())
+            .await
+            .unwrap();
+        assert_eq!(num_requests(&client, "archive_unstable_call"), 2);
+    }
+
+    #[tokio::test]
+    async fn absent_storage_values_are_cached() {
+        let (backend, client) = caching_backend(Hash::zero());
+        let at = Hash::repeat_byte(1);
+
+        for _ in 0..2 {
+            let values: Vec<_> = backend
+                .storage_fetch_values(vec![vec![1], vec![2]], at)
+                .await
+                .unwrap()
+                .map(|res| {
+                    let res = res.unwrap();
+                    (res.key, res.value)
+                })
+                .collect()
+                .await;
+            assert_eq!(values, vec![(vec![1], vec![10])]);
+        }
+
+        // Neither the value nor the absence of one needed fetching again.
+        assert_eq!(num_requests(&client, "archive_unstable_storage"), 1);
+    }
+
+    #[tokio::test]
+    async fn streams_are_only_cached_once_complete() {
+        let (backend, client) = caching_backend(Hash::zero());
+        let at = Hash::repeat_byte(1);
+        let fetch_keys = || backend.storage_fetch_descendant_keys(vec![], at);
+
+        // Stop after the first key; nothing is cached.
+        let keys: Vec<_> = fetch_keys().await.unwrap().take(1).collect().await;
+        assert_eq!(keys.len(), 1);
+        assert!(backend.is_empty());
+
+        // Get every key; they are now cached.
+        let keys: Vec<_> = fetch_keys()
+            .await
+            .unwrap()
+            .map(|key| key.unwrap())
+            .collect()
+            .await;
+        assert_eq!(keys, vec![vec![1], vec![2]]);
+        assert_eq!(backend.len(), 1);
+
+        let num_fetched = num_requests(&client, "archive_unstable_storage");
+        let keys: Vec<_> = fetch_keys()
+            .await
+            .unwrap()
+            .map(|key| key.unwrap())
+            .collect()
+            .await;
+        assert_eq!(keys, vec![vec![1], vec![2]]);
+        assert_eq!(
+            num_requests(&client, "archive_unstable_storage"),
+            num_fetched
+        );
+    }
+
+    #[tokio::test]
+    async fn saved_responses_are_only_loaded_for_the_same_chain() {
+        let path = std::env::temp_dir().join(format!(
+            "subxt-caching-backend-test-{}.bin",
+            std::process::id()
+        ));
+        let at = Hash::repeat_byte(1);
+
+        let (backend, _) = caching_backend(Hash::zero());
+        Backend::<SubstrateConfig>::call(&backend, "Foo_bar", None, at)
+            .await
+            .unwrap();
+        backend.save_to_file(&path).await.unwrap();
+
+        // The same chain; the call response is loaded and not requested again.
+        let (backend, client) = caching_backend(Hash::zero());
+        backend.load_from_file(&path).await.unwrap();
+        Backend::<SubstrateConfig>::call(&backend, "Foo_bar", None, at)
+            .await
+            .unwrap();
+        assert_eq!(num_requests(&client, "archive_unstable_call"), 0);
+
+        // A different chain; nothing is loaded.
+        let (backend, _) = caching_backend(Hash::repeat_byte(2));
+        let res = backend.load_from_file(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(res.is_err());
+        // Only the genesis hash that was fetched to compare with is cached.
+        assert_eq!(backend.len(), 1);
+    }
+
+    #[test]
+    fn lru_cache_evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.insert(vec![1], vec![10]);
+        cache.insert(vec![2], vec![20]);
+
+        // Using 1 means that 2 is now the least recently used, and is evicted.
+        assert_eq!(cache.get(&[1]), Some(vec![10]));
+        cache.insert(vec![3], vec![30]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&[1]), Some(vec![10]));
+        assert_eq!(cache.get(&[2]), None);
+        assert_eq!(cache.get(&[3]), Some(vec![30]));
+    }
+
+    #[test]
+    fn lru_cache_roundtrips_through_bytes() {
+        let mut cache = LruCache::new(3);
+        cache.insert(vec![1], vec![10]);
+        cache.insert(vec![2], vec![20]);
+        cache.insert(vec![3], vec![30]);
+        cache.get(&[1]);
+
+        // Loading into a smaller cache keeps the most recently used entries.
+        let mut loaded = LruCache::new(2);
+        loaded.extend_from_bytes(&cache.to_bytes()).unwrap();
+
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(&[1]), Some(vec![10]));
+        assert_eq!(loaded.get(&[2]), None);
+        assert_eq!(loaded.get(&[3]), Some(vec![30]));
+    }
+}
@@ -8,10 +8,11 @@
 pub mod rpc_methods;
 
 use self::rpc_methods::TransactionStatus as RpcTransactionStatus;
+use crate::backend::block_tree::chain_events_from_headers;
 use crate::backend::utils::{retry, retry_stream};
 use crate::backend::{
-    rpc::RpcClient, Backend, BlockRef, RuntimeVersion, StorageResponse, StreamOf, StreamOfResults,
-    TransactionStatus,
+    rpc::RpcClient, Backend, BlockRef, ChainEvent, RuntimeVersion, StorageChanges, StorageResponse,
+    StreamOf, StreamOfResults, TransactionStatus,
 };
 use crate::error::RpcError;
 use crate::{config::Header, Config, Error};
@@ -86,6 +87,69 @@ impl<T: Config> LegacyBackend<T> {
     }
 }
 
+impl<T: Config> LegacyBackend<T> {
+    // Stream the keys underneath the given key, either in the main trie or
+    // in the child trie with the given prefixed storage key.
+    fn fetch_descendant_keys(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> StreamOfResults<Vec<u8>> {
+        let keys = self.descendant_keys_stream(child_key, key, at);
+
+        let keys = keys.flat_map(|keys| {
+            match keys {
+                Err(e) => {
+                    // If there's an error, return that next:
+                    Either::Left(stream::iter(std::iter::once(Err(e))))
+                }
+                Ok(keys) => {
+                    // Or, stream each "ok" value:
+                    Either::Right(stream::iter(keys.into_iter().map(Ok)))
+                }
+            }
+        });
+
+        StreamOf(Box::pin(keys))
+    }
+
+    // Stream the values underneath the given key, either in the main trie or
+    // in the child trie with the given prefixed storage key.
+    fn fetch_descendant_values(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> StreamOfResults<StorageResponse> {
+        let keys_stream = self.descendant_keys_stream(child_key, key, at);
+
+        StreamOf(Box::pin(StorageFetchDescendantValuesStream {
+            keys: keys_stream,
+            results_fut: None,
+            results: Default::default(),
+        }))
+    }
+
+    fn descendant_keys_stream(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> StorageFetchDescendantKeysStream<T> {
+        StorageFetchDescendantKeysStream {
+            at,
+            key,
+            child_key,
+            storage_page_size: self.storage_page_size,
+            methods: self.methods.clone(),
+            done: Default::default(),
+            keys_fut: Default::default(),
+            pagination_start_key: None,
+        }
+    }
+}
+
 impl<T: Config> super::sealed::Sealed for LegacyBackend<T> {}
 
 #[async_trait]
@@ -96,86 +160,158 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for LegacyBackend<T> {
         at: T::Hash,
     ) -> Result<StreamOfResults<StorageResponse>, Error> {
         retry(|| async {
-            let keys = keys.clone();
             let methods = self.methods.clone();
 
-            // For each key, return it + a future to get the result.
-            let iter = keys.into_iter().map(move |key| {
+            // Split the keys into pages, and for each page return a future to fetch
+            // the values at each key in the page using a single batch request.
+            let pages: Vec<Vec<Vec<u8>>> = keys
+                .chunks(self.storage_page_size.max(1) as usize)
+                .map(|page| page.to_vec())
+                .collect();
+            let iter = pages.into_iter().map(move |keys| {
                 let methods = methods.clone();
                 async move {
-                    let res = methods.state_get_storage(&key, Some(at)).await?;
-                    Ok(res.map(|value| StorageResponse { key, value }))
+                    let values = methods
+                        .state_get_storage_batch(keys.iter().map(Vec::as_slice), Some(at))
+                        .await?;
+                    // Filter any Options out (ie if we didn't find a value at some key we return nothing for it).
+                    let responses: Vec<_> = keys
+                        .into_iter()
+                        .zip(values)
+                        .filter_map(|(key, value)| Some(StorageResponse { key, value: value? }))
+                        .collect();
+                    Ok(responses)
                 }
             });
 
             let s = stream::iter(iter)
                 // Resolve the future
                 .then(|fut| fut)
-                // Filter any Options out (ie if we didn't find a value at some key we return nothing for it).
-                .filter_map(|r| future::ready(r.transpose()));
+                // Return each of the responses (or the error) in turn.
+                .flat_map(|res| match res {
+                    Ok(responses) => Either::Left(stream::iter(responses.into_iter().map(Ok))),
+                    Err(e) => Either::Right(stream::once(future::ready(Err(e)))),
+                });
 
             Ok(StreamOf(Box::pin(s)))
         })
         .await
     }
 
+    async fn storage_fetch_read_proof(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        retry(|| async {
+            let keys = keys.iter().map(|key| &**key);
+            let read_proof = self.methods.state_get_read_proof(keys, Some(at)).await?;
+            Ok(read_proof.proof.into_iter().map(|node| node.0).collect())
+        })
+        .await
+    }
+
     async fn storage_fetch_descendant_keys(
         &self,
         key: Vec<u8>,
         at: T::Hash,
     ) -> Result<StreamOfResults<Vec<u8>>, Error> {
-        let keys = StorageFetchDescendantKeysStream {
-            at,
-            key,
-            storage_page_size: self.storage_page_size,
-            methods: self.methods.clone(),
-            done: Default::default(),
-            keys_fut: Default::default(),
-            pagination_start_key: None,
-        };
+        Ok(self.fetch_descendant_keys(None, key, at))
+    }
 
-        let keys = keys.flat_map(|keys| {
-            match keys {
-                Err(e) => {
-                    // If there's an error, return that next:
-                    Either::Left(stream::iter(std::iter::once(Err(e))))
-                }
-                Ok(keys) => {
-                    // Or, stream each "ok" value:
-                    Either::Right(stream::iter(keys.into_iter().map(Ok)))
-                }
-            }
+    async fn storage_fetch_descendant_values(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_descendant_values(None, key, at))
+    }
+
+    async fn storage_fetch_changes(
+        &self,
+        keys: Vec<Vec<u8>>,
+        from: T::Hash,
+        to: T::Hash,
+    ) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error> {
+        let change_sets = retry(|| {
+            let keys = keys.iter().map(|key| &**key);
+            self.methods.state_query_storage(keys, from, Some(to))
+        })
+        .await?;
+
+        let changes = change_sets.into_iter().map(|change_set| {
+            Ok(StorageChanges {
+                block_hash: change_set.block,
+                changes: change_set
+                    .changes
+                    .into_iter()
+                    .map(|(key, value)| (key.0, value.map(|v| v.0)))
+                    .collect(),
+            })
         });
 
-        Ok(StreamOf(Box::pin(keys)))
+        Ok(StreamOf(Box::pin(stream::iter(changes))))
     }
 
-    async fn storage_fetch_descendant_values(
+    async fn storage_fetch_child_values(
         &self,
-        key: Vec<u8>,
+        child_key: Vec<u8>,
+        keys: Vec<Vec<u8>>,
         at: T::Hash,
     ) -> Result<StreamOfResults<StorageResponse>, Error> {
-        let keys_stream = StorageFetchDescendantKeysStream {
-            at,
-            key,
-            storage_page_size: self.storage_page_size,
-            methods: self.methods.clone(),
-            done: Default::default(),
-            keys_fut: Default::default(),
-            pagination_start_key: None,
-        };
+        let child_key = crate::storage::ChildInfo::new_default(child_key).prefixed_storage_key();
+        retry(|| async {
+            let values = self
+                .methods
+                .childstate_get_storage_entries(&child_key, keys.iter().map(|key| &**key), Some(at))
+                .await?;
+
+            // Return nothing for any keys that we didn't find a value at.
+            let responses: Vec<_> = keys
+                .iter()
+                .zip(values)
+                .filter_map(|(key, value)| {
+                    let value = value?;
+                    Some(Ok(StorageResponse {
+                        key: key.clone(),
+                        value,
+                    }))
+                })
+                .collect();
 
-        Ok(StreamOf(Box::pin(StorageFetchDescendantValuesStream {
-            keys: keys_stream,
-            results_fut: None,
-            results: Default::default(),
-        })))
+            Ok(StreamOf(Box::pin(stream::iter(responses))))
+        })
+        .await
+    }
+
+    async fn storage_fetch_child_descendant_keys(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        let child_key = crate::storage::ChildInfo::new_default(child_key).prefixed_storage_key();
+        Ok(self.fetch_descendant_keys(Some(child_key), key, at))
+    }
+
+    async fn storage_fetch_child_descendant_values(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        let child_key = crate::storage::ChildInfo::new_default(child_key).prefixed_storage_key();
+        Ok(self.fetch_descendant_values(Some(child_key), key, at))
     }
 
     async fn genesis_hash(&self) -> Result<T::Hash, Error> {
         retry(|| self.methods.genesis_hash()).await
     }
 
+    async fn block_hash_at_height(&self, height: u64) -> Result<Option<T::Hash>, Error> {
+        retry(|| self.methods.chain_get_block_hash(Some(height.into()))).await
+    }
+
     async fn block_header(&self, at: T::Hash) -> Result<Option<T::Header>, Error> {
         retry(|| self.methods.chain_get_header(Some(at))).await
     }
@@ -200,6 +336,19 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for LegacyBackend<T> {
         .await
     }
 
+    async fn latest_best_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        retry(|| async {
+            // Asking for a block hash without a block number gives back the best block hash.
+            let hash = self
+                .methods
+                .chain_get_block_hash(None)
+                .await?
+                .ok_or_else(|| Error::Other("The node did not return a best block".into()))?;
+            Ok(BlockRef::from_hash(hash))
+        })
+        .await
+    }
+
     async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error> {
         retry(|| async {
             let details = self.methods.state_get_runtime_version(None).await?;
@@ -328,6 +477,19 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for LegacyBackend<T> {
         Ok(retry_sub)
     }
 
+    async fn stream_chain_events(&self) -> Result<StreamOfResults<ChainEvent<T::Hash>>, Error> {
+        // The legacy methods don't tell us about forks, so we emulate this by tracking the
+        // parents of the new, best and finalized blocks that we're told about.
+        let new_heads = self.stream_all_block_headers().await?;
+        let best_heads = self.stream_best_block_headers().await?;
+        let finalized_heads = self.stream_finalized_block_headers().await?;
+        Ok(chain_events_from_headers::<T>(
+            new_heads,
+            best_heads,
+            finalized_heads,
+        ))
+    }
+
     async fn submit_transaction(
         &self,
         extrinsic: &[u8],
@@ -453,6 +615,8 @@ where
 pub struct StorageFetchDescendantKeysStream<T: Config> {
     methods: LegacyRpcMethods<T>,
     key: Vec<u8>,
+    // The prefixed storage key of the child trie to look in, if any.
+    child_key: Option<Vec<u8>>,
     at: T::Hash,
     // How many entries to ask for each time.
     storage_page_size: u32,
@@ -520,18 +684,34 @@ impl<T: Config> Stream for StorageFetchDescendantKeysStream<T> {
             // Else, we don't have a fut to get keys yet so start one going.
             let methods = this.methods.clone();
             let key = this.key.clone();
+            let child_key = this.child_key.clone();
             let at = this.at;
             let storage_page_size = this.storage_page_size;
             let pagination_start_key = this.pagination_start_key.clone();
             let keys_fut = async move {
-                methods
-                    .state_get_keys_paged(
-                        &key,
-                        storage_page_size,
-                        pagination_start_key.as_deref(),
-                        Some(at),
-                    )
-                    .await
+                match child_key {
+                    Some(child_key) => {
+                        methods
+                            .childstate_get_keys_paged(
+                                &child_key,
+                                &key,
+                                storage_page_size,
+                                pagination_start_key.as_deref(),
+                                Some(at),
+                            )
+                            .await
+                    }
+                    None => {
+                        methods
+                            .state_get_keys_paged(
+                                &key,
+                                storage_page_size,
+                                pagination_start_key.as_deref(),
+                                Some(at),
+                            )
+                            .await
+                    }
+                }
             };
             this.keys_fut = Some(Box::pin(keys_fut));
         }
@@ -590,8 +770,23 @@ impl<T: Config> Stream for StorageFetchDescendantValuesStream<T> {
             match this.keys.poll_next_unpin(cx) {
                 Poll::Ready(Some(Ok(keys))) => {
                     let methods = this.keys.methods.clone();
+                    let child_key = this.keys.child_key.clone();
                     let at = this.keys.at;
                     let results_fut = async move {
+                        if let Some(child_key) = child_key {
+                            let values = retry(|| {
+                                let keys = keys.iter().map(|k| &**k);
+                                methods.childstate_get_storage_entries(&child_key, keys, Some(at))
+                            })
+                            .await?;
+                            let values: VecDeque<_> = keys
+                                .into_iter()
+                                .zip(values)
+                                .filter_map(|(k, v)| Some((k, v?)))
+                                .collect();
+                            return Ok(Some(values));
+                        }
+
                         let keys = keys.iter().map(|k| &**k);
                         let values =
                             retry(|| methods.state_query_storage_at(keys.clone(), Some(at)))
@@ -618,3 +813,168 @@ impl<T: Config> Stream for StorageFetchDescendantValuesStream<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::rpc::mock_rpc_client::MockRpcClient;
+    use crate::SubstrateConfig;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn storage_values_are_fetched_in_pages() {
+        // Every key has a value except for the key 3.
+        let client = MockRpcClient::builder()
+            .method("state_getStorage", |params| match params[0].as_str() {
+                Some("0x03") => Ok(Value::Null),
+                Some(key) => Ok(json!(format!("{key}00"))),
+                None => panic!("expected a hex encoded key"),
+            })
+            .build();
+        let backend = LegacyBackend::<SubstrateConfig>::builder()
+            .storage_page_size(2)
+            .build(client.clone());
+
+        let keys: Vec<Vec<u8>> = (1..=5).map(|n| vec![n]).collect();
+        let values: Vec<_> = backend
+            .storage_fetch_values(keys, Default::default())
+            .await
+            .unwrap()
+            .map(|res| {
+                let res = res.unwrap();
+                (res.key, res.value)
+            })
+            .collect()
+            .await;
+        assert_eq!(
+            values,
+            vec![
+                (vec![1], vec![1, 0]),
+                (vec![2], vec![2, 0]),
+                (vec![4], vec![4, 0]),
+                (vec![5], vec![5, 0]),
+            ]
+        );
+        assert_eq!(client.batch_sizes(), vec![2, 2, 1]);
+    }
+
+    const CHILD_KEY: &[u8] = b"crowdloan";
+    // The keys in the child trie. The value of each is the key followed by 0x00.
+    const CHILD_KEYS: [&str; 3] = ["0x0101", "0x0102", "0x0201"];
+
+    // A client which answers `childstate` requests against the `CHILD_KEY` child trie.
+    fn child_storage_client() -> MockRpcClient {
+        fn check_child_key(child_key: &Value) {
+            let prefixed =
+                crate::storage::ChildInfo::new_default(CHILD_KEY.to_vec()).prefixed_storage_key();
+            assert_eq!(child_key, &json!(format!("0x{}", hex::encode(prefixed))));
+        }
+
+        MockRpcClient::builder()
+            .method("childstate_getStorageEntries", |params| {
+                check_child_key(&params[0]);
+                let values = params[1]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|key| {
+                        let key = key.as_str().unwrap();
+                        match CHILD_KEYS.contains(&key) {
+                            true => json!(format!("{key}00")),
+                            false => Value::Null,
+                        }
+                    })
+                    .collect();
+                Ok(Value::Array(values))
+            })
+            .method("childstate_getKeysPaged", |params| {
+                check_child_key(&params[0]);
+                let prefix = params[1].as_str().unwrap();
+                let count = params[2].as_u64().unwrap() as usize;
+                let start_key = params[3].as_str().unwrap_or_default();
+                let keys: Vec<_> = CHILD_KEYS
+                    .into_iter()
+                    .filter(|key| key.starts_with(prefix) && *key > start_key)
+                    .take(count)
+                    .collect();
+                Ok(json!(keys))
+            })
+            .build()
+    }
+
+    async fn collect_values(values: StreamOfResults<StorageResponse>) -> Vec<(Vec<u8>, Vec<u8>)> {
+        values
+            .map(|res| {
+                let res = res.unwrap();
+                (res.key, res.value)
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn child_storage_values_are_fetched() {
+        let backend = LegacyBackend::<SubstrateConfig>::builder().build(child_storage_client());
+
+        let values = backend
+            .storage_fetch_child_values(
+                CHILD_KEY.to_vec(),
+                vec![vec![1, 2], vec![3, 3]],
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            collect_values(values).await,
+            vec![(vec![1, 2], vec![1, 2, 0])]
+        );
+    }
+
+    #[tokio::test]
+    async fn child_storage_can_be_iterated() {
+        let client = child_storage_client();
+        let backend = LegacyBackend::<SubstrateConfig>::builder()
+            .storage_page_size(1)
+            .build(client.clone());
+
+        let keys: Vec<Vec<u8>> = backend
+            .storage_fetch_child_descendant_keys(CHILD_KEY.to_vec(), vec![1], Default::default())
+            .await
+            .unwrap()
+            .map(|key| key.unwrap())
+            .collect()
+            .await;
+        assert_eq!(keys, vec![vec![1, 1], vec![1, 2]]);
+
+        let values = backend
+            .storage_fetch_child_descendant_values(CHILD_KEY.to_vec(), vec![1], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            collect_values(values).await,
+            vec![(vec![1, 1], vec![1, 1, 0]), (vec![1, 2], vec![1, 2, 0])]
+        );
+
+        // Keys are fetched a page at a time, until an empty page comes back.
+        let pages = client
+            .requests()
+            .iter()
+            .filter(|(method, _)| method == "childstate_getKeysPaged")
+            .count();
+        assert_eq!(pages, 6);
+    }
+
+    #[tokio::test]
+    async fn latest_best_block_is_fetched_without_a_block_number() {
+        let client = MockRpcClient::builder()
+            .method("chain_getBlockHash", |params| {
+                assert_eq!(params, vec![Value::Null]);
+                Ok(json!(format!("0x{}", "01".repeat(32))))
+            })
+            .build();
+        let backend = LegacyBackend::<SubstrateConfig>::builder().build(client);
+
+        let block_ref = backend.latest_best_block_ref().await.unwrap();
+        assert_eq!(block_ref.hash(), crate::utils::H256::repeat_byte(1));
+    }
+}
@@ -41,6 +41,24 @@ impl<T: Config> LegacyRpcMethods<T> {
         Ok(data.map(|b| b.0))
     }
 
+    /// Fetch the raw bytes for each of the given storage keys using a single batch request.
+    /// One entry is returned for each key, in the same order.
+    pub async fn state_get_storage_batch(
+        &self,
+        keys: impl IntoIterator<Item = &[u8]>,
+        hash: Option<T::Hash>,
+    ) -> Result<Vec<Option<StorageData>>, Error> {
+        let requests = keys
+            .into_iter()
+            .map(|key| ("state_getStorage", rpc_params![to_hex(key), hash]));
+        let results: Vec<Result<Option<Bytes>, Error>> =
+            self.client.batch_request(requests).await?;
+        results
+            .into_iter()
+            .map(|res| res.map(|data| data.map(|b| b.0)))
+            .collect()
+    }
+
     /// Returns the keys with prefix with pagination support.
     /// Up to `count` keys will be returned.
     /// If `start_key` is passed, return next keys in storage in lexicographic order.
@@ -91,6 +109,55 @@ impl<T: Config> LegacyRpcMethods<T> {
             .map_err(Into::into)
     }
 
+    /// Fetch the raw bytes for a given key in the child trie with the given prefixed storage key.
+    pub async fn childstate_get_storage(
+        &self,
+        child_storage_key: &[u8],
+        key: &[u8],
+        hash: Option<T::Hash>,
+    ) -> Result<Option<StorageData>, Error> {
+        let params = rpc_params![to_hex(child_storage_key), to_hex(key), hash];
+        let data: Option<Bytes> = self.client.request("childstate_getStorage", params).await?;
+        Ok(data.map(|b| b.0))
+    }
+
+    /// Fetch the raw bytes for each of the given keys in the child trie with the given
+    /// prefixed storage key. One entry is returned for each key, in the same order.
+    pub async fn childstate_get_storage_entries(
+        &self,
+        child_storage_key: &[u8],
+        keys: impl IntoIterator<Item = &[u8]>,
+        hash: Option<T::Hash>,
+    ) -> Result<Vec<Option<StorageData>>, Error> {
+        let keys: Vec<String> = keys.into_iter().map(to_hex).collect();
+        let params = rpc_params![to_hex(child_storage_key), keys, hash];
+        let data: Vec<Option<Bytes>> = self
+            .client
+            .request("childstate_getStorageEntries", params)
+            .await?;
+        Ok(data.into_iter().map(|b| b.map(|b| b.0)).collect())
+    }
+
+    /// Returns the keys with prefix in the child trie with the given prefixed storage key,
+    /// with pagination support. Up to `count` keys will be returned.
+    /// If `start_key` is passed, return next keys in storage in lexicographic order.
+    pub async fn childstate_get_keys_paged(
+        &self,
+        child_storage_key: &[u8],
+        key: &[u8],
+        count: u32,
+        start_key: Option<&[u8]>,
+        at: Option<T::Hash>,
+    ) -> Result<Vec<StorageKey>, Error> {
+        let start_key = start_key.map(to_hex);
+        let params = rpc_params![to_hex(child_storage_key), to_hex(key), count, start_key, at];
+        let data: Vec<Bytes> = self
+            .client
+            .request("childstate_getKeysPaged", params)
+            .await?;
+        Ok(data.into_iter().map(|b| b.0).collect())
+    }
+
     /// Fetch the genesis hash
     pub async fn genesis_hash(&self) -> Result<T::Hash, Error> {
         let block_zero = 0u32;
@@ -169,6 +236,20 @@ impl<T: Config> LegacyRpcMethods<T> {
         Ok(block_hash)
     }
 
+    /// Get the block hash for each of the given block numbers using a single batch request.
+    /// One entry is returned for each block number, in the same order.
+    pub async fn chain_get_block_hash_batch(
+        &self,
+        block_numbers: impl IntoIterator<Item = BlockNumber>,
+    ) -> Result<Vec<Option<T::Hash>>, Error> {
+        let requests = block_numbers
+            .into_iter()
+            .map(|block_number| ("chain_getBlockHash", rpc_params![block_number]));
+        let results: Vec<Result<Option<T::Hash>, Error>> =
+            self.client.batch_request(requests).await?;
+        results.into_iter().collect()
+    }
+
     /// Get a block hash of the latest finalized block
     pub async fn chain_get_finalized_head(&self) -> Result<T::Hash, Error> {
         let hash = self
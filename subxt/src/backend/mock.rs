@@ -0,0 +1,782 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module exposes a [`MockBackend`], which is an in-memory [`Backend`] that hands back
+//! whatever it's been given. It can be used to test code built on top of an [`crate::OnlineClient`]
+//! without needing to run a node.
+//!
+//! ```rust,no_run
+//! # async fn foo() -> Result<(), subxt::Error> {
+//! use subxt::backend::mock::MockBackend;
+//! use subxt::config::substrate::{Digest, SubstrateHeader};
+//! use subxt::backend::TransactionStatus;
+//! use subxt::{Metadata, OnlineClient, SubstrateConfig};
+//!
+//! # let metadata_bytes: Vec<u8> = vec![];
+//! let metadata: Metadata = subxt::ext::codec::Decode::decode(&mut &*metadata_bytes)?;
+//! let header = SubstrateHeader {
+//!     parent_hash: Default::default(),
+//!     number: 1,
+//!     state_root: Default::default(),
+//!     extrinsics_root: Default::default(),
+//!     digest: Digest::default(),
+//! };
+//! let block_hash = subxt::config::Header::hash(&header);
+//!
+//! let backend = MockBackend::<SubstrateConfig>::builder()
+//!     .metadata(metadata)
+//!     .block(header, vec![])
+//!     .storage(block_hash, b"some storage key".to_vec(), b"some value".to_vec())
+//!     .transaction_statuses(vec![
+//!         TransactionStatus::Validated,
+//!         TransactionStatus::InFinalizedBlock { hash: block_hash.into() },
+//!     ])
+//!     .build();
+//!
+//! let api = OnlineClient::<SubstrateConfig>::from_backend(std::sync::Arc::new(backend)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::backend::{
+    block_tree::chain_events_from_headers, storage_changes::storage_changes_between, Backend,
+    BlockRef, ChainEvent, RuntimeVersion, StorageChanges, StorageResponse, StreamOf,
+    StreamOfResults, TransactionStatus,
+};
+use crate::config::Header;
+use crate::metadata::Metadata;
+use crate::{Config, Error};
+use async_trait::async_trait;
+use codec::{Decode, Encode};
+use futures::{stream, StreamExt};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Configure and build a [`MockBackend`].
+pub struct MockBackendBuilder<T: Config> {
+    genesis_hash: Option<T::Hash>,
+    runtime_version: RuntimeVersion,
+    metadata: Option<Metadata>,
+    blocks: Vec<MockBlock<T::Hash>>,
+    stream_blocks_from: u64,
+    storage: HashMap<T::Hash, StorageEntries>,
+    child_storage: HashMap<(T::Hash, Vec<u8>), StorageEntries>,
+    read_proofs: HashMap<T::Hash, Vec<Vec<u8>>>,
+    calls: HashMap<String, Vec<u8>>,
+    transaction_statuses: VecDeque<TransactionStatuses<T>>,
+}
+
+impl<T: Config> Default for MockBackendBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Config> MockBackendBuilder<T> {
+    /// Create a new [`MockBackendBuilder`].
+    pub fn new() -> Self {
+        Self {
+            genesis_hash: None,
+            runtime_version: RuntimeVersion {
+                spec_version: 0,
+                transaction_version: 0,
+            },
+            metadata: None,
+            blocks: Vec::new(),
+            stream_blocks_from: 0,
+            storage: HashMap::new(),
+            child_storage: HashMap::new(),
+            read_proofs: HashMap::new(),
+            calls: HashMap::new(),
+            transaction_statuses: VecDeque::new(),
+        }
+    }
+
+    /// The genesis hash to hand back. If this isn't provided, the hash of the
+    /// first block given to [`MockBackendBuilder::block`] is used.
+    pub fn genesis_hash(mut self, genesis_hash: T::Hash) -> Self {
+        self.genesis_hash = Some(genesis_hash);
+        self
+    }
+
+    /// The runtime version to hand back (default: a spec and transaction version of 0).
+    pub fn runtime_version(mut self, runtime_version: RuntimeVersion) -> Self {
+        self.runtime_version = runtime_version;
+        self
+    }
+
+    /// The metadata to hand back when it's requested via the `Metadata_*` runtime APIs. It is
+    /// encoded as V14, V15 or (unstable) V16 metadata, depending on the version requested.
+    pub fn metadata(mut self, metadata: impl Into<Metadata>) -> Self {
+        self.metadata = Some(metadata.into());
+        self
+    }
+
+    /// Add a block, given its header and the encoded extrinsics in its body. Blocks are
+    /// considered to be finalized in the order that they are added, and so the last block
+    /// added is the latest finalized block. Storage changes between blocks are found by
+    /// following the parent hashes in their headers.
+    pub fn block(mut self, header: T::Header, body: Vec<Vec<u8>>) -> Self {
+        self.blocks.push(MockBlock {
+            hash: header.hash(),
+            header: header.encode(),
+            body,
+        });
+        self
+    }
+
+    /// Only hand back blocks from the given height onwards when subscribing to blocks, as if
+    /// the earlier blocks had been finalized before subscribing (default: 0).
+    pub fn stream_blocks_from(mut self, number: u64) -> Self {
+        self.stream_blocks_from = number;
+        self
+    }
+
+    /// Set the value at some storage key in the block with the given hash.
+    pub fn storage(mut self, at: T::Hash, key: Vec<u8>, value: Vec<u8>) -> Self {
+        self.storage.entry(at).or_default().insert(key, value);
+        self
+    }
+
+    /// Set the value at some storage key in the default child trie with the given
+    /// (unprefixed) storage key, in the block with the given hash.
+    pub fn child_storage(
+        mut self,
+        at: T::Hash,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Self {
+        self.child_storage
+            .entry((at, child_key))
+            .or_default()
+            .insert(key, value);
+        self
+    }
+
+    /// Set the trie nodes to hand back as a storage read proof for any keys in the block
+    /// with the given hash. These aren't checked against the storage given to
+    /// [`MockBackendBuilder::storage`], and so can be used to hand back bad proofs.
+    pub fn read_proof(mut self, at: T::Hash, proof: Vec<Vec<u8>>) -> Self {
+        self.read_proofs.insert(at, proof);
+        self
+    }
+
+    /// Set the encoded response to hand back when the given runtime API method is called,
+    /// regardless of the call parameters or block. This takes precedence over the responses
+    /// to the `Metadata_*` runtime APIs that are generated from [`MockBackendBuilder::metadata`].
+    pub fn call(mut self, method: impl Into<String>, response: Vec<u8>) -> Self {
+        self.calls.insert(method.into(), response);
+        self
+    }
+
+    /// Add a sequence of statuses to hand back for a submitted transaction. Each submitted
+    /// transaction is given the next sequence, in the order that they were added, and
+    /// submitting a transaction when none remain leads to an error.
+    pub fn transaction_statuses(self, statuses: Vec<TransactionStatus<T::Hash>>) -> Self {
+        self.transaction_status_results(statuses.into_iter().map(Ok).collect())
+    }
+
+    /// Like [`MockBackendBuilder::transaction_statuses`], but the sequence can also contain
+    /// errors, to simulate the transaction status subscription failing.
+    pub fn transaction_status_results(
+        mut self,
+        statuses: Vec<Result<TransactionStatus<T::Hash>, Error>>,
+    ) -> Self {
+        self.transaction_statuses.push_back(statuses);
+        self
+    }
+
+    /// Build a [`MockBackend`], which implements the [`Backend`] trait.
+    pub fn build(self) -> MockBackend<T> {
+        MockBackend {
+            inner: Arc::new(MockBackendInner {
+                genesis_hash: self.genesis_hash,
+                runtime_version: self.runtime_version,
+                metadata: self.metadata,
+                blocks: self.blocks,
+                stream_blocks_from: self.stream_blocks_from,
+                storage: self.storage,
+                child_storage: self.child_storage,
+                read_proofs: self.read_proofs,
+                calls: self.calls,
+                transaction_statuses: Mutex::new(self.transaction_statuses),
+                submitted_transactions: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+/// An in-memory [`Backend`] which hands back the blocks, storage, runtime API responses
+/// and transaction statuses that it was configured with via [`MockBackendBuilder`].
+///
+/// Cloning this is cheap, and the clones share the transactions that have been submitted.
+pub struct MockBackend<T: Config> {
+    inner: Arc<MockBackendInner<T>>,
+}
+
+impl<T: Config> Clone for MockBackend<T> {
+    fn clone(&self) -> Self {
+        MockBackend {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Config> std::fmt::Debug for MockBackend<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockBackend")
+            .field("blocks", &self.inner.blocks.len())
+            .finish()
+    }
+}
+
+struct MockBackendInner<T: Config> {
+    genesis_hash: Option<T::Hash>,
+    runtime_version: RuntimeVersion,
+    metadata: Option<Metadata>,
+    blocks: Vec<MockBlock<T::Hash>>,
+    stream_blocks_from: u64,
+    storage: HashMap<T::Hash, StorageEntries>,
+    child_storage: HashMap<(T::Hash, Vec<u8>), StorageEntries>,
+    read_proofs: HashMap<T::Hash, Vec<Vec<u8>>>,
+    calls: HashMap<String, Vec<u8>>,
+    transaction_statuses: Mutex<VecDeque<TransactionStatuses<T>>>,
+    submitted_transactions: Mutex<Vec<Vec<u8>>>,
+}
+
+// The storage keys and values in some trie.
+type StorageEntries = BTreeMap<Vec<u8>, Vec<u8>>;
+
+// The statuses (or errors) to hand back for a single submitted transaction.
+type TransactionStatuses<T> = Vec<Result<TransactionStatus<<T as Config>::Hash>, Error>>;
+
+struct MockBlock<Hash> {
+    hash: Hash,
+    // Headers aren't necessarily `Clone`, so we store them encoded.
+    header: Vec<u8>,
+    body: Vec<Vec<u8>>,
+}
+
+impl<T: Config> MockBackend<T> {
+    /// Configure and construct a [`MockBackend`].
+    pub fn builder() -> MockBackendBuilder<T> {
+        MockBackendBuilder::new()
+    }
+
+    /// The encoded transactions that have been submitted, in the order that they were submitted.
+    pub fn submitted_transactions(&self) -> Vec<Vec<u8>> {
+        self.inner.submitted_transactions.lock().unwrap().clone()
+    }
+
+    fn block(&self, at: T::Hash) -> Option<&MockBlock<T::Hash>> {
+        self.inner.blocks.iter().find(|b| b.hash == at)
+    }
+
+    fn storage_at(&self, child_key: Option<Vec<u8>>, at: T::Hash) -> StorageEntries {
+        let storage = match child_key {
+            Some(child_key) => self.inner.child_storage.get(&(at, child_key)),
+            None => self.inner.storage.get(&at),
+        };
+        storage.cloned().unwrap_or_default()
+    }
+
+    fn fetch_values(
+        &self,
+        child_key: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> StreamOfResults<StorageResponse> {
+        let storage = self.storage_at(child_key, at);
+        let responses: Vec<_> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let value = storage.get(&key)?.clone();
+                Some(Ok(StorageResponse { key, value }))
+            })
+            .collect();
+        StreamOf::new(Box::pin(stream::iter(responses)))
+    }
+
+    fn fetch_descendant_values(
+        &self,
+        child_key: Option<Vec<u8>>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> StreamOfResults<StorageResponse> {
+        let responses: Vec<_> = self
+            .storage_at(child_key, at)
+            .into_iter()
+            .filter(|(k, _)| k.starts_with(&key))
+            .map(|(key, value)| Ok(StorageResponse { key, value }))
+            .collect();
+        StreamOf::new(Box::pin(stream::iter(responses)))
+    }
+
+    fn block_headers(&self) -> StreamOfResults<(T::Header, BlockRef<T::Hash>)> {
+        let headers = self
+            .inner
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                let header = match T::Header::decode(&mut &*block.header) {
+                    Ok(header) => header,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                (header.number().into() >= self.inner.stream_blocks_from)
+                    .then(|| Ok((header, BlockRef::from_hash(block.hash))))
+            })
+            .collect::<Vec<_>>();
+        StreamOf::new(Box::pin(stream::iter(headers)))
+    }
+
+    fn metadata_call(
+        &self,
+        method: &str,
+        call_parameters: Option<&[u8]>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let Some(metadata) = &self.inner.metadata else {
+            return Ok(None);
+        };
+        // V16 metadata is still unstable, and so it's requested as version `u32::MAX`.
+        const UNSTABLE_METADATA_VERSION: u32 = u32::MAX;
+        const METADATA_VERSIONS: [u32; 3] = [14, 15, UNSTABLE_METADATA_VERSION];
+        let opaque_metadata = |version: u32| {
+            use frame_metadata::{v14, v15, RuntimeMetadata, RuntimeMetadataPrefixed};
+            let metadata = (**metadata).clone();
+            let metadata: RuntimeMetadataPrefixed = match version {
+                14 => v14::RuntimeMetadataV14::from(metadata).into(),
+                15 => v15::RuntimeMetadataV15::from(metadata).into(),
+                UNSTABLE_METADATA_VERSION => RuntimeMetadataPrefixed(
+                    frame_metadata::META_RESERVED,
+                    RuntimeMetadata::V16(metadata.into()),
+                ),
+                _ => return None,
+            };
+            Some(frame_metadata::OpaqueMetadata(metadata.encode()))
+        };
+
+        let res = match method {
+            // The legacy metadata call always hands back V14 metadata.
+            "Metadata_metadata" => opaque_metadata(14).map(|m| m.encode()),
+            "Metadata_metadata_versions" => Some(METADATA_VERSIONS.to_vec().encode()),
+            "Metadata_metadata_at_version" => {
+                let version = u32::decode(&mut call_parameters.unwrap_or_default())?;
+                Some(opaque_metadata(version).encode())
+            }
+            _ => None,
+        };
+        Ok(res)
+    }
+}
+
+impl<T: Config> super::sealed::Sealed for MockBackend<T> {}
+
+#[async_trait]
+impl<T: Config + Send + Sync + 'static> Backend<T> for MockBackend<T> {
+    async fn storage_fetch_values(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_values(None, keys, at))
+    }
+
+    async fn storage_fetch_descendant_keys(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        let keys = self
+            .fetch_descendant_values(None, key, at)
+            .map(|res| res.map(|res| res.key));
+        Ok(StreamOf::new(Box::pin(keys)))
+    }
+
+    async fn storage_fetch_descendant_values(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_descendant_values(None, key, at))
+    }
+
+    async fn storage_fetch_read_proof(
+        &self,
+        _keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        self.inner.read_proofs.get(&at).cloned().ok_or_else(|| {
+            Error::Other("No storage read proof was given to the MockBackend for this block".into())
+        })
+    }
+
+    async fn storage_fetch_changes(
+        &self,
+        keys: Vec<Vec<u8>>,
+        from: T::Hash,
+        to: T::Hash,
+    ) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error> {
+        // Blocks are linked via the parent hashes in their headers, like they would be on a node.
+        storage_changes_between(self.clone(), keys, from, to).await
+    }
+
+    async fn storage_fetch_child_values(
+        &self,
+        child_key: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_values(Some(child_key), keys, at))
+    }
+
+    async fn storage_fetch_child_descendant_keys(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        let keys = self
+            .fetch_descendant_values(Some(child_key), key, at)
+            .map(|res| res.map(|res| res.key));
+        Ok(StreamOf::new(Box::pin(keys)))
+    }
+
+    async fn storage_fetch_child_descendant_values(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        Ok(self.fetch_descendant_values(Some(child_key), key, at))
+    }
+
+    async fn genesis_hash(&self) -> Result<T::Hash, Error> {
+        self.inner
+            .genesis_hash
+            .or_else(|| self.inner.blocks.first().map(|b| b.hash))
+            .ok_or_else(|| Error::Other("No genesis hash or blocks given to MockBackend".into()))
+    }
+
+    async fn block_hash_at_height(&self, height: u64) -> Result<Option<T::Hash>, Error> {
+        // The most recently added block at the height is considered to be canonical.
+        for block in self.inner.blocks.iter().rev() {
+            let header = T::Header::decode(&mut &*block.header)?;
+            if header.number().into() == height {
+                return Ok(Some(block.hash));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn block_header(&self, at: T::Hash) -> Result<Option<T::Header>, Error> {
+        let Some(block) = self.block(at) else {
+            return Ok(None);
+        };
+        let header = T::Header::decode(&mut &*block.header)?;
+        Ok(Some(header))
+    }
+
+    async fn block_body(&self, at: T::Hash) -> Result<Option<Vec<Vec<u8>>>, Error> {
+        Ok(self.block(at).map(|b| b.body.clone()))
+    }
+
+    async fn latest_finalized_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        self.inner
+            .blocks
+            .last()
+            .map(|b| BlockRef::from_hash(b.hash))
+            .ok_or_else(|| Error::Other("No blocks given to MockBackend".into()))
+    }
+
+    async fn latest_best_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        self.latest_finalized_block_ref().await
+    }
+
+    async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        Ok(self.inner.runtime_version)
+    }
+
+    async fn stream_runtime_version(&self) -> Result<StreamOfResults<RuntimeVersion>, Error> {
+        let runtime_version = self.inner.runtime_version;
+        Ok(StreamOf::new(Box::pin(stream::iter([Ok(runtime_version)]))))
+    }
+
+    async fn stream_all_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        Ok(self.block_headers())
+    }
+
+    async fn stream_best_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        Ok(self.block_headers())
+    }
+
+    async fn stream_finalized_block_headers(
+        &self,
+    ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error> {
+        Ok(self.block_headers())
+    }
+
+    async fn stream_chain_events(&self) -> Result<StreamOfResults<ChainEvent<T::Hash>>, Error> {
+        // Each block is treated as being finalized in turn.
+        let no_heads = || StreamOf::new(Box::pin(stream::empty()));
+        Ok(chain_events_from_headers::<T>(
+            no_heads(),
+            no_heads(),
+            self.block_headers(),
+        ))
+    }
+
+    async fn submit_transaction(
+        &self,
+        bytes: &[u8],
+    ) -> Result<StreamOfResults<TransactionStatus<T::Hash>>, Error> {
+        let statuses = self
+            .inner
+            .transaction_statuses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| {
+                Error::Other("No more transaction statuses given to MockBackend".into())
+            })?;
+        self.inner
+            .submitted_transactions
+            .lock()
+            .unwrap()
+            .push(bytes.to_vec());
+        Ok(StreamOf::new(Box::pin(stream::iter(statuses))))
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        call_parameters: Option<&[u8]>,
+        _at: T::Hash,
+    ) -> Result<Vec<u8>, Error> {
+        if let Some(res) = self.inner.calls.get(method) {
+            return Ok(res.clone());
+        }
+        if let Some(res) = self.metadata_call(method, call_parameters)? {
+            return Ok(res);
+        }
+        Err(Error::Other(format!(
+            "No response to the runtime API call {method} given to MockBackend"
+        )))
+    }
+}
+
+/// Helpers for setting up a [`MockBackend`] in tests across the crate.
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::{MockBackend, MockBackendBuilder};
+    use crate::config::substrate::{Digest, SubstrateHeader};
+    use crate::{Config, Metadata, OnlineClient, SubstrateConfig};
+    use codec::Decode;
+    use std::sync::Arc;
+
+    type Hash = <SubstrateConfig as Config>::Hash;
+
+    /// Load one of the checked in metadata artifacts, for instance `"polkadot_metadata_small"`.
+    pub fn metadata(name: &str) -> Metadata {
+        let metadata_bytes = std::fs::read(format!("../artifacts/{name}.scale")).unwrap();
+        Decode::decode(&mut &*metadata_bytes).unwrap()
+    }
+
+    /// A header for the block with the given number and parent, with everything else defaulted.
+    pub fn header(number: u32, parent_hash: Hash) -> <SubstrateConfig as Config>::Header {
+        SubstrateHeader {
+            parent_hash,
+            number,
+            state_root: Default::default(),
+            extrinsics_root: Default::default(),
+            digest: Digest::default(),
+        }
+    }
+
+    /// Build an [`OnlineClient`] on top of the given [`MockBackend`].
+    pub async fn client(backend: MockBackend<SubstrateConfig>) -> OnlineClient<SubstrateConfig> {
+        OnlineClient::from_backend(Arc::new(backend)).await.unwrap()
+    }
+
+    impl MockBackend<SubstrateConfig> {
+        /// A builder which is given the small Polkadot metadata and a genesis block
+        /// (see [`header`]), which is enough to build an [`OnlineClient`] from.
+        pub(crate) fn test_builder() -> MockBackendBuilder<SubstrateConfig> {
+            MockBackend::builder()
+                .metadata(metadata("polkadot_metadata_small"))
+                .block(header(0, Default::default()), vec![])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::{client, header};
+    use super::*;
+    use crate::backend::BackendExt;
+    use crate::SubstrateConfig;
+
+    #[tokio::test]
+    async fn online_client_works_with_mock_backend() {
+        let genesis = header(0, Default::default());
+        let block = header(1, genesis.hash());
+        let block_hash = block.hash();
+
+        let backend = MockBackend::test_builder()
+            .block(block, vec![vec![1, 2, 3]])
+            .storage(block_hash, b"key".to_vec(), b"value".to_vec())
+            .transaction_statuses(vec![
+                TransactionStatus::Validated,
+                TransactionStatus::InFinalizedBlock {
+                    hash: block_hash.into(),
+                },
+            ])
+            .build();
+
+        let api = client(backend.clone()).await;
+        assert_eq!(api.genesis_hash(), genesis.hash());
+
+        let storage = api.storage().at_latest().await.unwrap();
+        assert_eq!(
+            storage.fetch_raw(b"key".to_vec()).await.unwrap(),
+            Some(b"value".to_vec())
+        );
+        assert_eq!(storage.fetch_raw(b"other".to_vec()).await.unwrap(), None);
+
+        let body = backend.block_body(block_hash).await.unwrap();
+        assert_eq!(body, Some(vec![vec![1, 2, 3]]));
+
+        let mut statuses = backend.submit_transaction(&[4, 5, 6]).await.unwrap();
+        assert_eq!(
+            statuses.next().await.unwrap().unwrap(),
+            TransactionStatus::Validated
+        );
+        assert!(matches!(
+            statuses.next().await.unwrap().unwrap(),
+            TransactionStatus::InFinalizedBlock { .. }
+        ));
+        assert!(statuses.next().await.is_none());
+        assert_eq!(backend.submitted_transactions(), vec![vec![4, 5, 6]]);
+
+        // Only one sequence of statuses was given:
+        assert!(backend.submit_transaction(&[7]).await.is_err());
+        // Metadata calls are answered from the given metadata:
+        assert!(backend.metadata_at_version(14, block_hash).await.is_ok());
+        assert!(backend.metadata_at_version(13, block_hash).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn metadata_is_served_at_the_requested_version() {
+        use frame_metadata::{OpaqueMetadata, RuntimeMetadataPrefixed};
+
+        let backend = MockBackend::test_builder().build();
+        let block_hash = header(0, Default::default()).hash();
+
+        let versions: Vec<u32> = backend
+            .call_decoding("Metadata_metadata_versions", None, block_hash)
+            .await
+            .unwrap();
+        assert_eq!(versions, vec![14, 15, u32::MAX]);
+
+        for version in versions {
+            let metadata: Option<OpaqueMetadata> = backend
+                .call_decoding(
+                    "Metadata_metadata_at_version",
+                    Some(&version.encode()),
+                    block_hash,
+                )
+                .await
+                .unwrap();
+            let metadata = RuntimeMetadataPrefixed::decode(&mut &*metadata.unwrap().0).unwrap();
+            assert_eq!(metadata.1.version(), version);
+        }
+
+        let metadata: OpaqueMetadata = backend
+            .call_decoding("Metadata_metadata", None, block_hash)
+            .await
+            .unwrap();
+        let metadata = RuntimeMetadataPrefixed::decode(&mut &*metadata.0).unwrap();
+        assert_eq!(metadata.1.version(), 14);
+    }
+
+    #[tokio::test]
+    async fn blocks_can_be_fetched_by_number() {
+        let mut builder = MockBackend::test_builder();
+        let mut hashes = vec![header(0, Default::default()).hash()];
+        for number in 1..5 {
+            let block = header(number, hashes[number as usize - 1]);
+            hashes.push(block.hash());
+            builder = builder.block(block, vec![]);
+        }
+
+        let api = client(builder.build()).await;
+
+        let block = api.blocks().at_number(3).await.unwrap();
+        assert_eq!(block.hash(), hashes[3]);
+        assert!(api.blocks().at_number(5).await.is_err());
+
+        let blocks: Vec<_> = api
+            .blocks()
+            .range_with_concurrency(1..4, 2)
+            .map(|block| block.unwrap().hash())
+            .collect()
+            .await;
+        assert_eq!(blocks, hashes[1..4]);
+
+        let blocks: Vec<_> = api
+            .blocks()
+            .subscribe_finalized_from(2)
+            .await
+            .unwrap()
+            .map(|block| block.unwrap().hash())
+            .collect()
+            .await;
+        assert_eq!(blocks, hashes[2..]);
+    }
+
+    #[tokio::test]
+    async fn chain_events_treat_each_block_as_finalized() {
+        let genesis = header(0, Default::default());
+        let block = header(1, genesis.hash());
+
+        let backend = MockBackend::<SubstrateConfig>::builder()
+            .block(genesis.clone(), vec![])
+            .block(block.clone(), vec![])
+            .build();
+
+        let events: Vec<_> = backend
+            .stream_chain_events()
+            .await
+            .unwrap()
+            .map(|ev| ev.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(
+            events,
+            vec![
+                ChainEvent::Finalized {
+                    hashes: vec![BlockRef::from_hash(genesis.hash())],
+                    pruned: vec![]
+                },
+                ChainEvent::NewBlock {
+                    block: BlockRef::from_hash(block.hash()),
+                    parent_hash: genesis.hash()
+                },
+                ChainEvent::BestBlockChanged {
+                    retracted: vec![],
+                    enacted: vec![block.hash()]
+                },
+                ChainEvent::Finalized {
+                    hashes: vec![BlockRef::from_hash(block.hash())],
+                    pruned: vec![]
+                },
+            ]
+        );
+    }
+}
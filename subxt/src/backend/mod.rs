@@ -6,8 +6,13 @@
 //! the necessary information (probably from a JSON-RPC API, but that's up to the
 //! implementation).
 
+pub mod archive;
+mod block_tree;
+pub mod caching;
 pub mod legacy;
+pub mod mock;
 pub mod rpc;
+mod storage_changes;
 pub mod unstable;
 pub mod utils;
 
@@ -53,9 +58,58 @@ pub trait Backend<T: Config>: sealed::Sealed + Send + Sync + 'static {
         at: T::Hash,
     ) -> Result<StreamOfResults<StorageResponse>, Error>;
 
+    /// Fetch a proof that can be used to verify the values (or absence of values)
+    /// at the given keys against the state root of the block. The proof is a list of
+    /// encoded trie nodes.
+    async fn storage_fetch_read_proof(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<Vec<Vec<u8>>, Error>;
+
+    /// Fetch the values at the given keys in each block from `from` to `to` (inclusive) in which
+    /// any of them changed. The first [`StorageChanges`] returned contains the value of every key
+    /// at the `from` block, and subsequent ones only contain the keys whose values changed.
+    async fn storage_fetch_changes(
+        &self,
+        keys: Vec<Vec<u8>>,
+        from: T::Hash,
+        to: T::Hash,
+    ) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error>;
+
+    /// Fetch values from the default child trie with the given (unprefixed) storage key.
+    async fn storage_fetch_child_values(
+        &self,
+        child_key: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error>;
+
+    /// Fetch keys underneath the given key from the default child trie with the
+    /// given (unprefixed) storage key.
+    async fn storage_fetch_child_descendant_keys(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error>;
+
+    /// Fetch values underneath the given key from the default child trie with the
+    /// given (unprefixed) storage key.
+    async fn storage_fetch_child_descendant_values(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error>;
+
     /// Fetch the genesis hash
     async fn genesis_hash(&self) -> Result<T::Hash, Error>;
 
+    /// Get the hash of the block at the given height on the canonical chain, or `None`
+    /// if no such block is known.
+    async fn block_hash_at_height(&self, height: u64) -> Result<Option<T::Hash>, Error>;
+
     /// Get a block header
     async fn block_header(&self, at: T::Hash) -> Result<Option<T::Header>, Error>;
 
@@ -68,6 +122,10 @@ pub trait Backend<T: Config>: sealed::Sealed + Send + Sync + 'static {
     /// Note: needed only in blocks client for finalized block stream; can prolly be removed.
     async fn latest_finalized_block_ref(&self) -> Result<BlockRef<T::Hash>, Error>;
 
+    /// Get the most recent best block hash. Unlike the latest finalized block, the state
+    /// at this block includes the effects of transactions in blocks which are not yet finalized.
+    async fn latest_best_block_ref(&self) -> Result<BlockRef<T::Hash>, Error>;
+
     /// Get information about the current runtime.
     async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error>;
 
@@ -89,6 +147,12 @@ pub trait Backend<T: Config>: sealed::Sealed + Send + Sync + 'static {
         &self,
     ) -> Result<StreamOfResults<(T::Header, BlockRef<T::Hash>)>, Error>;
 
+    /// A stream of [`ChainEvent`]s, describing the blocks that are imported and finalized,
+    /// and how the best chain changes (for example when it switches forks) as this happens.
+    /// The first event is a [`ChainEvent::Finalized`] event containing the current finalized
+    /// block, which every later event builds on.
+    async fn stream_chain_events(&self) -> Result<StreamOfResults<ChainEvent<T::Hash>>, Error>;
+
     /// Submit a transaction. This will return a stream of events about it.
     async fn submit_transaction(
         &self,
@@ -280,6 +344,15 @@ impl<T> StreamOf<T> {
 /// A stream of [`Result<Item, Error>`].
 pub type StreamOfResults<T> = StreamOf<Result<T, Error>>;
 
+/// The values at some storage keys which changed in a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageChanges<Hash> {
+    /// The hash of the block that the changes are from.
+    pub block_hash: Hash,
+    /// The keys which changed, and their new values (`None` if no value exists at the key).
+    pub changes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
 /// The status of the transaction.
 ///
 /// If the status is [`TransactionStatus::InFinalizedBlock`], [`TransactionStatus::Error`],
@@ -323,6 +396,36 @@ pub enum TransactionStatus<Hash> {
     },
 }
 
+/// An event describing how the chain has changed. See [`Backend::stream_chain_events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent<Hash> {
+    /// A new, unfinalized block was imported.
+    NewBlock {
+        /// The new block.
+        block: BlockRef<Hash>,
+        /// The hash of the parent of the new block.
+        parent_hash: Hash,
+    },
+    /// The best block changed. Any state built on the retracted blocks should be rolled
+    /// back, and then the enacted blocks applied.
+    BestBlockChanged {
+        /// The blocks which are no longer on the best chain, starting from the old best block
+        /// and working back towards the common ancestor of the old and new best blocks.
+        retracted: Vec<Hash>,
+        /// The blocks which are now on the best chain, working forwards from the common
+        /// ancestor of the old and new best blocks and ending with the new best block.
+        enacted: Vec<Hash>,
+    },
+    /// Some blocks were finalized.
+    Finalized {
+        /// The newly finalized blocks, in order. The last is the latest finalized block.
+        hashes: Vec<BlockRef<Hash>>,
+        /// Blocks which are not descendants of the latest finalized block, and so have been
+        /// discarded.
+        pruned: Vec<Hash>,
+    },
+}
+
 /// A response from calls like [`Backend::storage_fetch_values`] or
 /// [`Backend::storage_fetch_descendant_values`].
 pub struct StorageResponse {
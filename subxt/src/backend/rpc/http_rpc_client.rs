@@ -0,0 +1,879 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An [`RpcClientT`] implementation which talks to a node using JSON-RPC over HTTP.
+//!
+//! HTTP has no support for subscriptions, and so the subscriptions used by the
+//! [`crate::backend::legacy::LegacyBackend`] are emulated by polling the node. This
+//! allows the legacy backend to run entirely over HTTP. JSON-RPC batch requests made via
+//! [`RpcClientT::batch_request_raw`] are sent in a single HTTP request. The unstable backend relies
+//! on `chainHead_v1_follow` subscriptions and cannot be used with this client.
+//!
+//! If polling the node fails, the emulated subscriptions back off and try again a few times
+//! before giving up and ending with the error. Since the node can't tell us when a transaction
+//! is dropped from its pool, a submitted transaction which doesn't make it into a block within
+//! [`HttpRpcClientBuilder::transaction_timeout()`] is reported as dropped.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() {
+//! use std::time::Duration;
+//! use subxt::backend::{legacy::LegacyBackend, rpc::http_rpc_client::HttpRpcClient};
+//! use subxt::{OnlineClient, PolkadotConfig};
+//!
+//! let rpc_client = HttpRpcClient::builder()
+//!     .poll_interval(Duration::from_secs(2))
+//!     .build("https://rpc.polkadot.io:443")
+//!     .unwrap();
+//!
+//! let backend = LegacyBackend::builder().build(rpc_client);
+//! let api = OnlineClient::<PolkadotConfig>::from_backend(std::sync::Arc::new(backend))
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use super::{rpc_params, RawRpcFuture, RawRpcSubscription, RawValue, RpcClientT, RpcParams};
+use crate::error::RpcError;
+use futures::{FutureExt, Stream};
+use instant::Instant;
+use super::jsonrpsee_impl::Params;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use serde::de::DeserializeOwned;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Builder for an [`HttpRpcClient`].
+#[derive(Debug, Clone)]
+pub struct HttpRpcClientBuilder {
+    max_request_size: u32,
+    max_response_size: u32,
+    request_timeout: Duration,
+    poll_interval: Duration,
+    poll_retries: u32,
+    transaction_timeout: Duration,
+}
+
+impl Default for HttpRpcClientBuilder {
+    fn default() -> Self {
+        Self {
+            max_request_size: 10 * 1024 * 1024,
+            max_response_size: 10 * 1024 * 1024,
+            request_timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(3),
+            poll_retries: 5,
+            transaction_timeout: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl HttpRpcClientBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure the max request size.
+    ///
+    /// Default: 10MB
+    pub fn max_request_size(mut self, max: u32) -> Self {
+        self.max_request_size = max;
+        self
+    }
+
+    /// Configure the max response size.
+    ///
+    /// Default: 10MB
+    pub fn max_response_size(mut self, max: u32) -> Self {
+        self.max_response_size = max;
+        self
+    }
+
+    /// Configure how long until a method call is regarded as failed.
+    ///
+    /// Default: 1 minute
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Configure how often the node is polled for updates in order to emulate subscriptions.
+    ///
+    /// Default: 3 seconds
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Configure how many times in a row polling the node can fail before an emulated
+    /// subscription gives up and ends with the error. We wait twice as long before each retry.
+    ///
+    /// Default: 5
+    pub fn poll_retries(mut self, retries: u32) -> Self {
+        self.poll_retries = retries;
+        self
+    }
+
+    /// Configure how long to wait for a submitted transaction to make it into a block before
+    /// reporting it as dropped. For mortal transactions, this should be longer than the
+    /// mortality period, after which they can no longer be included.
+    ///
+    /// Default: 5 minutes
+    pub fn transaction_timeout(mut self, timeout: Duration) -> Self {
+        self.transaction_timeout = timeout;
+        self
+    }
+
+    /// Build a client which sends requests to the given URL.
+    pub fn build(self, url: impl AsRef<str>) -> Result<HttpRpcClient, RpcError> {
+        let client = HttpClientBuilder::default()
+            .max_request_size(self.max_request_size)
+            .max_response_size(self.max_response_size)
+            .request_timeout(self.request_timeout)
+            .build(url)
+            .map_err(|e| RpcError::ClientError(Box::new(e)))?;
+
+        Ok(HttpRpcClient {
+            inner: Arc::new(Inner {
+                client,
+                poll_interval: self.poll_interval,
+                poll_retries: self.poll_retries,
+                transaction_timeout: self.transaction_timeout,
+            }),
+        })
+    }
+}
+
+/// An RPC client which talks to a node over HTTP. See the [module docs](self) for more.
+#[derive(Debug, Clone)]
+pub struct HttpRpcClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    client: HttpClient,
+    poll_interval: Duration,
+    poll_retries: u32,
+    transaction_timeout: Duration,
+}
+
+impl HttpRpcClient {
+    /// Create a builder.
+    pub fn builder() -> HttpRpcClientBuilder {
+        HttpRpcClientBuilder::new()
+    }
+
+    /// Create a client with the default configuration which sends requests to the given URL.
+    pub fn new(url: impl AsRef<str>) -> Result<Self, RpcError> {
+        HttpRpcClientBuilder::new().build(url)
+    }
+}
+
+impl RpcClientT for HttpRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        self.inner.request_raw(method, params).boxed()
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        let inner = self.inner.clone();
+        async move {
+            let stream = match sub {
+                "chain_subscribeNewHeads" | "chain_subscribeAllHeads" => {
+                    poll_for_changes(inner, PollTarget::BestHeader)
+                }
+                "chain_subscribeFinalizedHeads" => {
+                    poll_for_changes(inner, PollTarget::FinalizedHeader)
+                }
+                "state_subscribeRuntimeVersion" => {
+                    poll_for_changes(inner, PollTarget::RuntimeVersion)
+                }
+                "author_submitAndWatchExtrinsic" => watch_extrinsic(inner, params).await?,
+                _ => {
+                    return Err(RpcError::request_rejected(format!(
+                        "the subscription '{sub}' is not supported over HTTP"
+                    )))
+                }
+            };
+
+            Ok(RawRpcSubscription { stream, id: None })
+        }
+        .boxed()
+    }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        super::jsonrpsee_impl::batch_request(&self.inner.client, requests).boxed()
+    }
+}
+
+impl Inner {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Box<RawValue>, RpcError> {
+        ClientT::request(&self.client, method, Params(params))
+            .await
+            .map_err(|e| RpcError::ClientError(Box::new(e)))
+    }
+
+    async fn request<Res: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: RpcParams,
+    ) -> Result<Res, RpcError> {
+        let res = self.request_raw(method, params.build()).await?;
+        serde_json::from_str(res.get()).map_err(|e| RpcError::ClientError(Box::new(e)))
+    }
+
+    /// Fetch the number of the given block, or of the best block if no hash is given.
+    async fn block_number(&self, hash: Option<&str>) -> Result<u64, RpcError> {
+        #[derive(serde::Deserialize)]
+        struct Header {
+            number: String,
+        }
+
+        let header: Header = self.request("chain_getHeader", rpc_params![hash]).await?;
+        u64::from_str_radix(header.number.trim_start_matches("0x"), 16)
+            .map_err(|e| RpcError::ClientError(Box::new(e)))
+    }
+
+    async fn block_hash(&self, number: u64) -> Result<Option<String>, RpcError> {
+        self.request("chain_getBlockHash", rpc_params![number])
+            .await
+    }
+
+    /// Called when polling the node fails, given the number of failures in a row so far.
+    /// Unless we've already retried `poll_retries` times, this waits (for longer each time)
+    /// and returns `true` to say that we should try again.
+    async fn retry_after_failure(&self, failures: &mut u32) -> bool {
+        if *failures >= self.poll_retries {
+            return false;
+        }
+        let backoff = 1 << (*failures).min(16);
+        *failures += 1;
+        futures_timer::Delay::new(self.poll_interval.saturating_mul(backoff)).await;
+        true
+    }
+}
+
+type RawSubscriptionStream =
+    Pin<Box<dyn Stream<Item = Result<Box<RawValue>, RpcError>> + Send + 'static>>;
+
+/// The things that we can poll for in order to emulate subscriptions.
+#[derive(Clone, Copy)]
+enum PollTarget {
+    BestHeader,
+    FinalizedHeader,
+    RuntimeVersion,
+}
+
+impl PollTarget {
+    async fn fetch(self, inner: &Inner) -> Result<Box<RawValue>, RpcError> {
+        match self {
+            PollTarget::BestHeader => inner.request("chain_getHeader", rpc_params![]).await,
+            PollTarget::FinalizedHeader => {
+                let hash: Box<RawValue> =
+                    inner.request("chain_getFinalizedHead", rpc_params![]).await?;
+                inner.request("chain_getHeader", rpc_params![hash]).await
+            }
+            PollTarget::RuntimeVersion => {
+                inner
+                    .request("state_getRuntimeVersion", rpc_params![])
+                    .await
+            }
+        }
+    }
+}
+
+/// Poll the node for some value, returning it each time that it changes. The stream
+/// ends after returning an error, which happens once polling has failed too many times
+/// in a row.
+fn poll_for_changes(inner: Arc<Inner>, target: PollTarget) -> RawSubscriptionStream {
+    struct State {
+        inner: Arc<Inner>,
+        last: Option<String>,
+        failures: u32,
+        done: bool,
+    }
+
+    let state = State {
+        inner,
+        last: None,
+        failures: 0,
+        done: false,
+    };
+
+    let stream = futures::stream::unfold(state, move |mut state| async move {
+        if state.done {
+            return None;
+        }
+        loop {
+            // Don't wait before fetching the first value.
+            if state.last.is_some() {
+                futures_timer::Delay::new(state.inner.poll_interval).await;
+            }
+
+            let value = match target.fetch(&state.inner).await {
+                Ok(value) => value,
+                Err(e) => {
+                    if state.inner.retry_after_failure(&mut state.failures).await {
+                        continue;
+                    }
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+            };
+            state.failures = 0;
+
+            if state.last.as_deref() != Some(value.get()) {
+                state.last = Some(value.get().to_owned());
+                return Some((Ok(value), state));
+            }
+        }
+    });
+
+    Box::pin(stream)
+}
+
+/// Submit an extrinsic and then poll the node to emulate the status updates that
+/// `author_submitAndWatchExtrinsic` would provide. Blocks are checked for the extrinsic
+/// until it's included in one, and then we wait for that block to be finalized. If it's
+/// not included in a block within the `transaction_timeout`, it's reported as dropped.
+async fn watch_extrinsic(
+    inner: Arc<Inner>,
+    params: Option<Box<RawValue>>,
+) -> Result<RawSubscriptionStream, RpcError> {
+    let (extrinsic,): (String,) = params
+        .as_deref()
+        .and_then(|params| serde_json::from_str(params.get()).ok())
+        .ok_or_else(|| RpcError::request_rejected("expected an extrinsic to submit"))?;
+
+    // The extrinsic can't be included in any block up to the current best one.
+    let next_block_number = inner.block_number(None).await? + 1;
+    let _hash: Box<RawValue> = inner
+        .request("author_submitExtrinsic", rpc_params![&extrinsic])
+        .await?;
+
+    let watch = ExtrinsicWatch {
+        inner,
+        extrinsic: extrinsic.to_lowercase(),
+        submitted_at: Instant::now(),
+        next_block_number,
+        in_block: None,
+        sent_ready: false,
+        done: false,
+    };
+
+    let stream = futures::stream::unfold(watch, |mut watch| async move {
+        let item = watch.next().await?;
+        Some((item, watch))
+    });
+
+    Ok(Box::pin(stream))
+}
+
+struct ExtrinsicWatch {
+    inner: Arc<Inner>,
+    // The hex encoded extrinsic.
+    extrinsic: String,
+    // When the extrinsic was submitted, so that we know when to give up on it.
+    submitted_at: Instant,
+    // The next block to look for the extrinsic in.
+    next_block_number: u64,
+    // The hash and number of the block that the extrinsic is in, if any.
+    in_block: Option<(String, u64)>,
+    sent_ready: bool,
+    done: bool,
+}
+
+impl ExtrinsicWatch {
+    async fn next(&mut self) -> Option<Result<Box<RawValue>, RpcError>> {
+        if self.done {
+            return None;
+        }
+        if !self.sent_ready {
+            self.sent_ready = true;
+            return Some(Ok(to_raw_value(&"ready")));
+        }
+        let mut failures = 0;
+        loop {
+            match self.next_status().await {
+                Ok(status) => return Some(Ok(status)),
+                Err(e) => {
+                    if self.inner.retry_after_failure(&mut failures).await {
+                        continue;
+                    }
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    async fn next_status(&mut self) -> Result<Box<RawValue>, RpcError> {
+        loop {
+            futures_timer::Delay::new(self.inner.poll_interval).await;
+
+            match self.in_block.clone() {
+                // Look for the extrinsic in any new blocks.
+                None => {
+                    let best_number = self.inner.block_number(None).await?;
+                    while self.next_block_number <= best_number {
+                        let number = self.next_block_number;
+                        let Some(hash) = self.inner.block_hash(number).await? else {
+                            break;
+                        };
+                        // Only move on once the block has been checked, so that no block is
+                        // skipped if checking it fails and we try again.
+                        let contains_extrinsic = self.block_contains_extrinsic(&hash).await?;
+                        self.next_block_number += 1;
+
+                        if contains_extrinsic {
+                            let status = to_raw_value(&serde_json::json!({ "inBlock": hash }));
+                            self.in_block = Some((hash, number));
+                            return Ok(status);
+                        }
+                    }
+
+                    // The node doesn't tell us if the extrinsic is dropped from its pool
+                    // (for instance because its mortality ran out), so give up eventually.
+                    if self.submitted_at.elapsed() >= self.inner.transaction_timeout {
+                        self.done = true;
+                        return Ok(to_raw_value(&"dropped"));
+                    }
+                }
+                // Wait for the block containing the extrinsic to be finalized.
+                Some((hash, number)) => {
+                    let finalized_hash: String = self
+                        .inner
+                        .request("chain_getFinalizedHead", rpc_params![])
+                        .await?;
+                    let finalized_number = self.inner.block_number(Some(&finalized_hash)).await?;
+                    if finalized_number < number {
+                        continue;
+                    }
+
+                    let canonical_hash = self.inner.block_hash(number).await?;
+                    if canonical_hash.as_deref() == Some(&*hash) {
+                        self.done = true;
+                        return Ok(to_raw_value(&serde_json::json!({ "finalized": hash })));
+                    }
+
+                    // The block we saw the extrinsic in was not finalized, so look again.
+                    self.in_block = None;
+                    self.next_block_number = number;
+                    return Ok(to_raw_value(&serde_json::json!({ "retracted": hash })));
+                }
+            }
+        }
+    }
+
+    async fn block_contains_extrinsic(&self, hash: &str) -> Result<bool, RpcError> {
+        #[derive(serde::Deserialize)]
+        struct SignedBlock {
+            block: Block,
+        }
+        #[derive(serde::Deserialize)]
+        struct Block {
+            extrinsics: Vec<String>,
+        }
+
+        let block: Option<SignedBlock> = self
+            .inner
+            .request("chain_getBlock", rpc_params![hash])
+            .await?;
+        Ok(block.is_some_and(|b| {
+            b.block
+                .extrinsics
+                .iter()
+                .any(|ext| ext.to_lowercase() == self.extrinsic)
+        }))
+    }
+}
+
+fn to_raw_value<T: serde::Serialize>(value: &T) -> Box<RawValue> {
+    serde_json::value::to_raw_value(value).expect("values are valid JSON; qed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    type Handler = Arc<dyn Fn(&str, &Value) -> Result<Value, String> + Send + Sync>;
+
+    /// A minimal HTTP server which answers JSON-RPC requests (and batches of them)
+    /// using the given handler, and records the body of each HTTP request it receives.
+    struct MockServer {
+        url: String,
+        bodies: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl MockServer {
+        async fn start(
+            handler: impl Fn(&str, &Value) -> Result<Value, String> + Send + Sync + 'static,
+        ) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let bodies = Arc::new(Mutex::new(Vec::new()));
+            let handler: Handler = Arc::new(handler);
+
+            let server_bodies = bodies.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    tokio::spawn(serve(socket, handler.clone(), server_bodies.clone()));
+                }
+            });
+
+            MockServer { url, bodies }
+        }
+
+        fn bodies(&self) -> Vec<Value> {
+            self.bodies.lock().unwrap().clone()
+        }
+    }
+
+    async fn serve(
+        socket: tokio::net::TcpStream,
+        handler: Handler,
+        bodies: Arc<Mutex<Vec<Value>>>,
+    ) {
+        let (reader, mut writer) = socket.into_split();
+        let mut reader = BufReader::new(reader);
+        loop {
+            // Read the headers (until an empty line) to find the length of the body.
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await.unwrap();
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            bodies.lock().unwrap().push(body.clone());
+
+            let respond = |request: &Value| {
+                let method = request["method"].as_str().unwrap();
+                match handler(method, &request["params"]) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                    Err(message) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request["id"],
+                        "error": { "code": -32000, "message": message }
+                    }),
+                }
+            };
+            let response = match &body {
+                Value::Array(requests) => Value::Array(requests.iter().map(respond).collect()),
+                request => respond(request),
+            }
+            .to_string();
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{response}",
+                response.len()
+            );
+            if writer.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn params(value: Value) -> Option<Box<RawValue>> {
+        Some(to_raw_value(&value))
+    }
+
+    #[tokio::test]
+    async fn batch_requests_are_sent_in_one_http_request() {
+        let server = MockServer::start(|method, params| match method {
+            "fail" => Err("failed".to_owned()),
+            _ => Ok(params.clone()),
+        })
+        .await;
+        let client = HttpRpcClient::new(&server.url).unwrap();
+
+        let results = client
+            .batch_request_raw(vec![
+                ("first", params(json!([1]))),
+                ("fail", params(json!([2]))),
+                ("third", params(json!([3]))),
+            ])
+            .await
+            .unwrap();
+
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|res| res.map(|value| value.get().to_owned()))
+            .collect();
+        assert_eq!(results[0].as_ref().unwrap(), "[1]");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), "[3]");
+
+        let bodies = server.bodies();
+        assert_eq!(bodies.len(), 1);
+        assert_eq!(bodies[0].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn requests_are_answered() {
+        let server = MockServer::start(|method, params| match method {
+            "echo" => Ok(params.clone()),
+            _ => Err(format!("unknown method {method}")),
+        })
+        .await;
+        let client = HttpRpcClient::new(&server.url).unwrap();
+
+        let res = client
+            .request_raw("echo", params(json!(["hello", 1])))
+            .await
+            .unwrap();
+        assert_eq!(res.get(), r#"["hello",1]"#);
+
+        let err = client.request_raw("nope", None).await.unwrap_err();
+        assert!(err.to_string().contains("unknown method nope"), "{err}");
+
+        let bodies = server.bodies();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0]["method"], "echo");
+        assert_eq!(bodies[0]["params"], json!(["hello", 1]));
+    }
+
+    // A client which polls often, so that the tests don't take long.
+    fn polling_client() -> HttpRpcClientBuilder {
+        HttpRpcClient::builder().poll_interval(Duration::from_millis(10))
+    }
+
+    async fn subscribe(
+        client: &HttpRpcClient,
+        sub: &str,
+        params: Option<Box<RawValue>>,
+    ) -> impl Stream<Item = Result<Value, RpcError>> {
+        use futures::StreamExt;
+        client
+            .subscribe_raw(sub, params, "unsubscribe")
+            .await
+            .unwrap()
+            .stream
+            .map(|item| item.map(|value| serde_json::from_str(value.get()).unwrap()))
+    }
+
+    fn header(number: u64) -> Value {
+        json!({ "number": format!("0x{number:x}") })
+    }
+
+    #[tokio::test]
+    async fn subscriptions_are_emulated_by_polling_for_changes() {
+        use futures::StreamExt;
+
+        // Each header is returned twice, and a couple of requests fail along the way.
+        let calls = Arc::new(Mutex::new(0u64));
+        let server_calls = calls.clone();
+        let server = MockServer::start(move |method, _params| {
+            assert_eq!(method, "chain_getHeader");
+            let mut calls = server_calls.lock().unwrap();
+            *calls += 1;
+            match *calls {
+                3 | 4 => Err("temporarily unavailable".to_owned()),
+                n => Ok(header(n / 2)),
+            }
+        })
+        .await;
+        let client = polling_client().build(&server.url).unwrap();
+
+        let headers: Vec<Value> = subscribe(&client, "chain_subscribeNewHeads", None)
+            .await
+            .take(4)
+            .map(|header| header.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(headers, vec![header(0), header(1), header(2), header(3)]);
+    }
+
+    #[tokio::test]
+    async fn subscriptions_end_after_failing_too_many_times_in_a_row() {
+        use futures::StreamExt;
+
+        let calls = Arc::new(Mutex::new(0u64));
+        let server_calls = calls.clone();
+        let server = MockServer::start(move |_method, _params| {
+            let mut calls = server_calls.lock().unwrap();
+            *calls += 1;
+            match *calls {
+                1 => Ok(json!({ "specVersion": 1 })),
+                _ => Err("gone".to_owned()),
+            }
+        })
+        .await;
+        let client = polling_client()
+            .poll_retries(2)
+            .build(&server.url)
+            .unwrap();
+
+        let mut versions = subscribe(&client, "state_subscribeRuntimeVersion", None).await;
+        assert_eq!(
+            versions.next().await.unwrap().unwrap(),
+            json!({ "specVersion": 1 })
+        );
+        assert!(versions.next().await.unwrap().is_err());
+        assert!(versions.next().await.is_none());
+
+        // The first request, and then the first failure and 2 retries.
+        assert_eq!(*calls.lock().unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn unknown_subscriptions_are_rejected() {
+        let server = MockServer::start(|_method, _params| Ok(Value::Null)).await;
+        let client = HttpRpcClient::new(&server.url).unwrap();
+
+        let res = client
+            .subscribe_raw("chain_subscribeSomething", None, "unsubscribe")
+            .await;
+        assert!(res.is_err());
+    }
+
+    const EXTRINSIC: &str = "0xAB01";
+
+    // A chain which is at block 1 when the extrinsic is submitted, includes it in block
+    // `included_in` (if any), and finalizes the block containing it once asked for the
+    // finalized head a few times. Block hashes are just the block numbers.
+    fn chain(included_in: Option<u64>) -> impl Fn(&str, &Value) -> Result<Value, String> {
+        #[derive(Default)]
+        struct Chain {
+            best: u64,
+            finalized_head_calls: u64,
+            failed_to_get_block: bool,
+        }
+        let chain = Mutex::new(Chain {
+            best: 1,
+            ..Default::default()
+        });
+
+        let hash = |number: u64| format!("0x{number:02x}");
+        let number = |hash: &Value| u64::from_str_radix(&hash.as_str().unwrap()[2..], 16).unwrap();
+
+        move |method, params| {
+            let mut chain = chain.lock().unwrap();
+            match method {
+                "author_submitExtrinsic" => {
+                    assert_eq!(params, &json!([EXTRINSIC]));
+                    Ok(json!("0x1234"))
+                }
+                "chain_getHeader" if params[0].is_null() => {
+                    // A new block is produced each time we're asked for the best block.
+                    chain.best += 1;
+                    Ok(header(chain.best - 1))
+                }
+                "chain_getHeader" => Ok(header(number(&params[0]))),
+                "chain_getBlockHash" => {
+                    let n = params[0].as_u64().unwrap();
+                    Ok(if n <= chain.best { json!(hash(n)) } else { Value::Null })
+                }
+                "chain_getBlock" => {
+                    // Fail once, to check that no blocks are skipped when we retry.
+                    if !chain.failed_to_get_block {
+                        chain.failed_to_get_block = true;
+                        return Err("temporarily unavailable".to_owned());
+                    }
+                    let extrinsics = if included_in == Some(number(&params[0])) {
+                        vec![json!("0x00"), json!(EXTRINSIC.to_lowercase())]
+                    } else {
+                        vec![json!("0x00")]
+                    };
+                    Ok(json!({ "block": { "extrinsics": extrinsics }, "justifications": null }))
+                }
+                "chain_getFinalizedHead" => {
+                    chain.finalized_head_calls += 1;
+                    let finalized = if chain.finalized_head_calls < 3 { 1 } else { chain.best };
+                    Ok(json!(hash(finalized)))
+                }
+                _ => Err(format!("unexpected method {method}")),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn submitted_extrinsics_are_watched_until_finalized() {
+        use futures::StreamExt;
+
+        let server = MockServer::start(chain(Some(3))).await;
+        let client = polling_client().build(&server.url).unwrap();
+
+        let statuses: Vec<Value> = subscribe(
+            &client,
+            "author_submitAndWatchExtrinsic",
+            params(json!([EXTRINSIC])),
+        )
+        .await
+        .map(|status| status.unwrap())
+        .collect()
+        .await;
+
+        assert_eq!(
+            statuses,
+            vec![
+                json!("ready"),
+                json!({ "inBlock": "0x03" }),
+                json!({ "finalized": "0x03" }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn extrinsics_which_are_never_included_are_dropped() {
+        use futures::StreamExt;
+
+        let server = MockServer::start(chain(None)).await;
+        let client = polling_client()
+            .transaction_timeout(Duration::from_millis(100))
+            .build(&server.url)
+            .unwrap();
+
+        let statuses: Vec<Value> = subscribe(
+            &client,
+            "author_submitAndWatchExtrinsic",
+            params(json!([EXTRINSIC])),
+        )
+        .await
+        .map(|status| status.unwrap())
+        .collect()
+        .await;
+
+        assert_eq!(statuses, vec![json!("ready"), json!("dropped")]);
+    }
+}
@@ -8,13 +8,15 @@ use futures::stream::{StreamExt, TryStreamExt};
 use jsonrpsee::{
     core::{
         client::{Client, ClientT, SubscriptionClientT, SubscriptionKind},
+        params::BatchRequestBuilder,
         traits::ToRpcParams,
+        ClientError,
     },
     types::SubscriptionId,
 };
 use serde_json::value::RawValue;
 
-struct Params(Option<Box<RawValue>>);
+pub(crate) struct Params(pub(crate) Option<Box<RawValue>>);
 
 impl ToRpcParams for Params {
     fn to_rpc_params(self) -> Result<Option<Box<RawValue>>, serde_json::Error> {
@@ -65,4 +67,139 @@ impl RpcClientT for Client {
             Ok(RawRpcSubscription { stream, id })
         })
     }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        Box::pin(batch_request(self, requests))
+    }
+}
+
+/// Send a JSON-RPC batch request using some jsonrpsee client.
+pub(crate) async fn batch_request<C: ClientT>(
+    client: &C,
+    requests: Vec<(&str, Option<Box<RawValue>>)>,
+) -> Result<Vec<Result<Box<RawValue>, RpcError>>, RpcError> {
+    // jsonrpsee doesn't allow empty batches.
+    if requests.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut batch = BatchRequestBuilder::new();
+    for (method, params) in requests {
+        batch
+            .insert(method, Params(params))
+            .map_err(|e| RpcError::ClientError(Box::new(e)))?;
+    }
+
+    let responses = client
+        .batch_request::<Box<RawValue>>(batch)
+        .await
+        .map_err(|e| RpcError::ClientError(Box::new(e)))?;
+
+    let results = responses
+        .into_iter()
+        .map(|res| res.map_err(|e| RpcError::ClientError(Box::new(ClientError::Call(e.into_owned())))))
+        .collect();
+    Ok(results)
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use jsonrpsee::core::{
+        async_trait,
+        client::{ClientBuilder, ReceivedMessage, TransportReceiverT, TransportSenderT},
+    };
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    // A transport which answers each request with its params, except for the method
+    // "fail", and records the messages that were sent.
+    struct Sender {
+        sent: Arc<Mutex<Vec<Value>>>,
+        responses: mpsc::UnboundedSender<String>,
+    }
+
+    struct Receiver(mpsc::UnboundedReceiver<String>);
+
+    fn respond(request: &Value) -> Value {
+        let id = &request["id"];
+        if request["method"] == "fail" {
+            json!({ "jsonrpc": "2.0", "id": id, "error": { "code": 1, "message": "failed" } })
+        } else {
+            json!({ "jsonrpc": "2.0", "id": id, "result": request["params"] })
+        }
+    }
+
+    #[async_trait]
+    impl TransportSenderT for Sender {
+        type Error = std::io::Error;
+
+        async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+            let msg: Value = serde_json::from_str(&msg).unwrap();
+            let response = match &msg {
+                Value::Array(requests) => Value::Array(requests.iter().map(respond).collect()),
+                request => respond(request),
+            };
+            self.sent.lock().unwrap().push(msg);
+            self.responses.send(response.to_string()).unwrap();
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl TransportReceiverT for Receiver {
+        type Error = std::io::Error;
+
+        async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+            match self.0.recv().await {
+                Some(msg) => Ok(ReceivedMessage::Text(msg)),
+                None => Err(std::io::ErrorKind::BrokenPipe.into()),
+            }
+        }
+    }
+
+    fn params(value: Value) -> Option<Box<RawValue>> {
+        Some(serde_json::value::to_raw_value(&value).unwrap())
+    }
+
+    #[tokio::test]
+    async fn batch_requests_are_sent_in_one_message() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        let sender = Sender {
+            sent: sent.clone(),
+            responses: tx,
+        };
+        let client = ClientBuilder::default().build_with_tokio(sender, Receiver(rx));
+
+        let results = client
+            .batch_request_raw(vec![
+                ("first", params(json!([1]))),
+                ("fail", params(json!([2]))),
+                ("third", params(json!([3]))),
+            ])
+            .await
+            .unwrap();
+
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|res| res.map(|value| value.get().to_owned()))
+            .collect();
+        assert_eq!(results[0].as_ref().unwrap(), "[1]");
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), "[3]");
+
+        // All three requests were sent in a single batch.
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].as_array().unwrap().len(), 3);
+
+        // Empty batches aren't sent at all.
+        let results = client.batch_request_raw(Vec::new()).await.unwrap();
+        assert!(results.is_empty());
+    }
 }
@@ -0,0 +1,170 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An [`RpcClientT`] implementation which hands requests to closures, for testing the
+//! RPC based backends without a node.
+
+use super::{RawRpcFuture, RawRpcSubscription, RawValue, RpcClientT};
+use crate::error::RpcError;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type MethodHandler = Box<dyn Fn(Vec<Value>) -> Result<Value, RpcError> + Send + Sync>;
+type SubscriptionHandler = Box<
+    dyn Fn(Vec<Value>) -> Result<BoxStream<'static, Result<Value, RpcError>>, RpcError>
+        + Send
+        + Sync,
+>;
+
+/// Configure and build a [`MockRpcClient`].
+#[derive(Default)]
+pub struct MockRpcClientBuilder {
+    methods: HashMap<String, MethodHandler>,
+    subscriptions: HashMap<String, SubscriptionHandler>,
+}
+
+impl MockRpcClientBuilder {
+    /// Handle requests to the given method, which are given the (JSON array) params.
+    pub fn method(
+        mut self,
+        name: &str,
+        f: impl Fn(Vec<Value>) -> Result<Value, RpcError> + Send + Sync + 'static,
+    ) -> Self {
+        self.methods.insert(name.to_owned(), Box::new(f));
+        self
+    }
+
+    /// Handle subscriptions to the given method by returning a stream of items.
+    pub fn subscription<S>(
+        mut self,
+        name: &str,
+        f: impl Fn(Vec<Value>) -> Result<S, RpcError> + Send + Sync + 'static,
+    ) -> Self
+    where
+        S: futures::Stream<Item = Result<Value, RpcError>> + Send + 'static,
+    {
+        self.subscriptions.insert(
+            name.to_owned(),
+            Box::new(move |params| Ok(f(params)?.boxed())),
+        );
+        self
+    }
+
+    /// Build the [`MockRpcClient`].
+    pub fn build(self) -> MockRpcClient {
+        MockRpcClient {
+            inner: Arc::new(MockRpcClientInner {
+                methods: self.methods,
+                subscriptions: self.subscriptions,
+                requests: Mutex::new(Vec::new()),
+                batch_sizes: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+/// An [`RpcClientT`] which hands each request and subscription to the handler registered
+/// for its method, and errors if there is none. Cloning this is cheap, and the clones
+/// share the requests that have been made.
+#[derive(Clone)]
+pub struct MockRpcClient {
+    inner: Arc<MockRpcClientInner>,
+}
+
+struct MockRpcClientInner {
+    methods: HashMap<String, MethodHandler>,
+    subscriptions: HashMap<String, SubscriptionHandler>,
+    requests: Mutex<Vec<(String, Vec<Value>)>>,
+    batch_sizes: Mutex<Vec<usize>>,
+}
+
+impl MockRpcClient {
+    /// Configure and construct a [`MockRpcClient`].
+    pub fn builder() -> MockRpcClientBuilder {
+        MockRpcClientBuilder::default()
+    }
+
+    /// The methods and params of every request and subscription made so far, in order.
+    pub fn requests(&self) -> Vec<(String, Vec<Value>)> {
+        self.inner.requests.lock().unwrap().clone()
+    }
+
+    /// The number of requests in each batch request made so far, in order. The requests
+    /// in each batch are also included in [`MockRpcClient::requests`].
+    pub fn batch_sizes(&self) -> Vec<usize> {
+        self.inner.batch_sizes.lock().unwrap().clone()
+    }
+
+    fn record(&self, method: &str, params: Option<Box<RawValue>>) -> Vec<Value> {
+        let params: Vec<Value> = params
+            .map(|p| serde_json::from_str(p.get()).expect("params are a JSON array"))
+            .unwrap_or_default();
+        self.inner
+            .requests
+            .lock()
+            .unwrap()
+            .push((method.to_owned(), params.clone()));
+        params
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Box<RawValue>, RpcError> {
+        let params = self.record(method, params);
+        match self.inner.methods.get(method) {
+            Some(f) => f(params).map(to_raw_value),
+            None => Err(RpcError::request_rejected(format!(
+                "No handler for {method}"
+            ))),
+        }
+    }
+}
+
+impl RpcClientT for MockRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        futures::future::ready(self.request(method, params)).boxed()
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        let params = self.record(sub, params);
+        let res = match self.inner.subscriptions.get(sub) {
+            Some(f) => f(params).map(|stream| RawRpcSubscription {
+                stream: stream.map(|item| item.map(to_raw_value)).boxed(),
+                id: Some(sub.to_owned()),
+            }),
+            None => Err(RpcError::request_rejected(format!("No handler for {sub}"))),
+        };
+        futures::future::ready(res).boxed()
+    }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        self.inner.batch_sizes.lock().unwrap().push(requests.len());
+        let results = requests
+            .into_iter()
+            .map(|(method, params)| self.request(method, params))
+            .collect();
+        futures::future::ready(Ok(results)).boxed()
+    }
+}
+
+fn to_raw_value(value: Value) -> Box<RawValue> {
+    serde_json::value::to_raw_value(&value).expect("JSON values can be serialized")
+}
@@ -69,6 +69,18 @@ crate::macros::cfg_reconnecting_rpc_client! {
    pub mod reconnecting_rpc_client;
 }
 
+crate::macros::cfg_http_rpc_client! {
+    /// An RPC client which talks to nodes over HTTP.
+    pub mod http_rpc_client;
+}
+
+pub mod multi_rpc_client;
+
+pub mod recording_rpc_client;
+
+#[cfg(test)]
+pub(crate) mod mock_rpc_client;
+
 mod rpc_client;
 mod rpc_client_t;
 
@@ -0,0 +1,910 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An [`RpcClientT`] implementation which spreads requests across several other
+//! [`RpcClientT`] implementations, failing over to another endpoint when one
+//! stops working.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() {
+//! use subxt::backend::rpc::multi_rpc_client::{MultiRpcClient, RoutingStrategy};
+//! use subxt::backend::rpc::RpcClient;
+//! use subxt::{OnlineClient, PolkadotConfig};
+//!
+//! let client = MultiRpcClient::builder()
+//!     .routing_strategy(RoutingStrategy::LowestLatency)
+//!     .endpoint(RpcClient::from_url("wss://rpc.polkadot.io:443").await.unwrap())
+//!     .endpoint(RpcClient::from_url("wss://polkadot-rpc.dwellir.com:443").await.unwrap())
+//!     .build();
+//!
+//! let api = OnlineClient::<PolkadotConfig>::from_rpc_client(RpcClient::new(client))
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use super::{RawRpcFuture, RawRpcSubscription, RawValue, RpcClient, RpcClientT};
+use crate::error::RpcError;
+use futures::{Future, FutureExt, Stream, StreamExt};
+use instant::{Duration, Instant};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How requests are routed to the available endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoutingStrategy {
+    /// Send each request to the next healthy endpoint in turn.
+    #[default]
+    RoundRobin,
+    /// Send each request to the healthy endpoint which has responded the fastest
+    /// on average. Endpoints which we haven't measured yet are tried first.
+    LowestLatency,
+}
+
+/// Builder for a [`MultiRpcClient`].
+pub struct MultiRpcClientBuilder {
+    endpoints: Vec<RpcClient>,
+    routing_strategy: RoutingStrategy,
+    unhealthy_cooldown: Duration,
+    health_check_method: String,
+}
+
+impl Default for MultiRpcClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiRpcClientBuilder {
+    /// Create a new builder.
+    pub fn new() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            routing_strategy: RoutingStrategy::default(),
+            unhealthy_cooldown: Duration::from_secs(30),
+            health_check_method: "system_health".to_owned(),
+        }
+    }
+
+    /// Add an endpoint to send requests to.
+    pub fn endpoint(mut self, client: impl Into<RpcClient>) -> Self {
+        self.endpoints.push(client.into());
+        self
+    }
+
+    /// Configure how requests are routed to the available endpoints.
+    ///
+    /// Default: [`RoutingStrategy::RoundRobin`]
+    pub fn routing_strategy(mut self, routing_strategy: RoutingStrategy) -> Self {
+        self.routing_strategy = routing_strategy;
+        self
+    }
+
+    /// Configure how long an endpoint is avoided for after it fails. Once this has elapsed,
+    /// requests will be routed to it again. Unhealthy endpoints are still used as a last
+    /// resort if every endpoint is unhealthy.
+    ///
+    /// Default: 30 seconds
+    pub fn unhealthy_cooldown(mut self, cooldown: Duration) -> Self {
+        self.unhealthy_cooldown = cooldown;
+        self
+    }
+
+    /// Configure the method (which takes no parameters) that is called on each endpoint
+    /// in [`MultiRpcClient::check_health()`].
+    ///
+    /// Default: `system_health`
+    pub fn health_check_method(mut self, method: impl Into<String>) -> Self {
+        self.health_check_method = method.into();
+        self
+    }
+
+    /// Build the [`MultiRpcClient`].
+    pub fn build(self) -> MultiRpcClient {
+        let endpoints = self
+            .endpoints
+            .into_iter()
+            .map(|client| Endpoint {
+                client,
+                state: Mutex::new(EndpointState::default()),
+            })
+            .collect();
+
+        MultiRpcClient {
+            inner: Arc::new(Inner {
+                endpoints,
+                routing_strategy: self.routing_strategy,
+                unhealthy_cooldown: self.unhealthy_cooldown,
+                health_check_method: self.health_check_method,
+                next_endpoint: AtomicUsize::new(0),
+                subscriptions: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+}
+
+/// An RPC client which routes requests to several underlying [`RpcClientT`] implementations,
+/// failing over to another endpoint if one of them returns a transport error.
+///
+/// - Requests are routed according to the configured [`RoutingStrategy`], avoiding any endpoints
+///   which have recently failed.
+/// - Requests which pass a subscription ID as their first parameter (like the `chainHead_v1_*`
+///   methods) are always routed to the endpoint that the subscription lives on.
+/// - If a subscription fails (or ends, as happens when the connection to its endpoint drops),
+///   it's transparently re-established on another endpoint. Nothing is carried over from the
+///   old subscription, so depending on how far behind or ahead the new endpoint is, items may be
+///   repeated (for example the same block being reported twice) or missed (for example blocks
+///   produced while the new subscription was being set up).
+/// - `chainHead_v1_*` subscriptions can't be moved to another endpoint, because the subscription
+///   ID, and the blocks pinned and operations started with it, only exist on the node that it was
+///   made on. Instead, they return [`RpcError::DisconnectedWillReconnect`] when their endpoint
+///   fails. The [`crate::backend::unstable::UnstableBackend`] handles this by following the chain
+///   again (which will happen on another endpoint) and catching up from the new subscription's
+///   `initialized` event, as it does when the node stops a subscription.
+/// - Transaction submissions return the error rather than being re-established, since that would
+///   submit the transaction again.
+///
+/// Endpoints are marked as unhealthy when a request to them fails. To find failed endpoints
+/// sooner, and allow recovered ones to be used again, spawn [`MultiRpcClient::health_checks()`].
+#[derive(Clone)]
+pub struct MultiRpcClient {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for MultiRpcClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiRpcClient")
+            .field("endpoints", &self.inner.endpoints.len())
+            .field("routing_strategy", &self.inner.routing_strategy)
+            .finish()
+    }
+}
+
+impl MultiRpcClient {
+    /// Create a builder.
+    pub fn builder() -> MultiRpcClientBuilder {
+        MultiRpcClientBuilder::new()
+    }
+
+    /// Create a [`MultiRpcClient`] using the default configuration and the given endpoints.
+    pub fn new<C: Into<RpcClient>>(endpoints: impl IntoIterator<Item = C>) -> Self {
+        endpoints
+            .into_iter()
+            .fold(MultiRpcClientBuilder::new(), |builder, client| {
+                builder.endpoint(client)
+            })
+            .build()
+    }
+
+    /// Call the configured health check method on every endpoint, updating their measured
+    /// latencies and marking any that fail as unhealthy. Returns whether each endpoint (in the
+    /// order they were added) is healthy.
+    ///
+    /// Endpoints are also marked as unhealthy whenever a request to them fails, so calling this
+    /// isn't necessary, but doing so periodically will find failed endpoints sooner, and allow
+    /// recovered endpoints to be used again sooner.
+    pub async fn check_health(&self) -> Vec<bool> {
+        let checks = self.inner.endpoints.iter().map(|endpoint| async move {
+            let started = Instant::now();
+            match endpoint
+                .client
+                .request_raw(&self.inner.health_check_method, None)
+                .await
+            {
+                Ok(_) => {
+                    endpoint.record_success(started.elapsed());
+                    true
+                }
+                Err(_) => {
+                    endpoint.record_failure();
+                    false
+                }
+            }
+        });
+        futures::future::join_all(checks).await
+    }
+
+    /// Return a future which calls [`MultiRpcClient::check_health()`] every `interval`, until
+    /// this client, its clones and any subscriptions made with it have all been dropped. This
+    /// should be spawned onto whichever runtime is being used.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use std::time::Duration;
+    /// use subxt::backend::rpc::multi_rpc_client::MultiRpcClient;
+    /// use subxt::backend::rpc::RpcClient;
+    ///
+    /// let client = MultiRpcClient::new([
+    ///     RpcClient::from_url("wss://rpc.polkadot.io:443").await.unwrap(),
+    ///     RpcClient::from_url("wss://polkadot-rpc.dwellir.com:443").await.unwrap(),
+    /// ]);
+    ///
+    /// tokio::spawn(client.health_checks(Duration::from_secs(10)));
+    /// # }
+    /// ```
+    pub fn health_checks(&self, interval: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let inner = Arc::downgrade(&self.inner);
+        async move {
+            loop {
+                futures_timer::Delay::new(interval).await;
+                let Some(inner) = inner.upgrade() else {
+                    return;
+                };
+                MultiRpcClient { inner }.check_health().await;
+            }
+        }
+    }
+}
+
+impl RpcClientT for MultiRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        async move {
+            // Requests about a subscription must go to the endpoint that it lives on.
+            if let Some(idx) = self.inner.subscription_endpoint(params.as_deref()) {
+                let endpoint = &self.inner.endpoints[idx];
+                return endpoint.request(method, params).await.map_err(|e| {
+                    if is_endpoint_failure(&e) {
+                        RpcError::DisconnectedWillReconnect(e.to_string())
+                    } else {
+                        e
+                    }
+                });
+            }
+
+            let mut last_err = None;
+            for idx in self.inner.endpoint_order() {
+                match self.inner.endpoints[idx]
+                    .request(method, params.clone())
+                    .await
+                {
+                    Err(e) if is_endpoint_failure(&e) => last_err = Some(e),
+                    res => return res,
+                }
+            }
+            Err(last_err.unwrap_or_else(no_endpoints_error))
+        }
+        .boxed()
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        async move {
+            let (idx, subscription) = self.inner.subscribe(sub, params.clone(), unsub).await?;
+            let id = subscription.id.clone();
+
+            let state = SubscriptionState {
+                guard: SubscriptionGuard::new(self.inner.clone(), idx, id.clone()),
+                stream: Some(subscription.stream),
+                sub: sub.to_owned(),
+                params,
+                unsub: unsub.to_owned(),
+                empty_resubscriptions: 0,
+            };
+
+            let stream = futures::stream::unfold(state, |mut state| async move {
+                let item = state.next().await?;
+                Some((item, state))
+            });
+
+            Ok(RawRpcSubscription {
+                stream: Box::pin(stream),
+                id,
+            })
+        }
+        .boxed()
+    }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        async move {
+            let mut last_err = None;
+            for idx in self.inner.endpoint_order() {
+                let endpoint = &self.inner.endpoints[idx];
+                let started = Instant::now();
+                let res = match endpoint.client.batch_request_raw(requests.clone()).await {
+                    Ok(results) => batch_endpoint_failure(results),
+                    Err(e) => Err(e),
+                };
+                match res {
+                    Err(e) if is_endpoint_failure(&e) => {
+                        endpoint.record_failure();
+                        last_err = Some(e);
+                    }
+                    res => {
+                        endpoint.record_success(started.elapsed());
+                        return res;
+                    }
+                }
+            }
+            Err(last_err.unwrap_or_else(no_endpoints_error))
+        }
+        .boxed()
+    }
+}
+
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    routing_strategy: RoutingStrategy,
+    unhealthy_cooldown: Duration,
+    health_check_method: String,
+    next_endpoint: AtomicUsize,
+    // Which endpoint each of our active subscriptions lives on.
+    subscriptions: Mutex<HashMap<String, usize>>,
+}
+
+impl Inner {
+    /// The order in which to try endpoints for the next request. Healthy endpoints
+    /// are ordered according to the routing strategy, followed by unhealthy ones.
+    fn endpoint_order(&self) -> Vec<usize> {
+        let num_endpoints = self.endpoints.len();
+        let mut healthy = Vec::with_capacity(num_endpoints);
+        let mut unhealthy = Vec::new();
+
+        let start = match self.routing_strategy {
+            RoutingStrategy::RoundRobin => self.next_endpoint.fetch_add(1, Ordering::Relaxed),
+            RoutingStrategy::LowestLatency => 0,
+        };
+        for offset in 0..num_endpoints {
+            let idx = (start + offset) % num_endpoints;
+            let state = self.endpoints[idx].state.lock().unwrap();
+            match state.failed_at {
+                Some(failed_at) if failed_at.elapsed() < self.unhealthy_cooldown => {
+                    unhealthy.push((idx, failed_at))
+                }
+                _ => healthy.push((idx, state.latency)),
+            }
+        }
+
+        if self.routing_strategy == RoutingStrategy::LowestLatency {
+            // Unmeasured endpoints (with no latency) sort first.
+            healthy.sort_by_key(|(_, latency)| *latency);
+        }
+        // Try the endpoints which failed longest ago first.
+        unhealthy.sort_by_key(|(_, failed_at)| *failed_at);
+
+        healthy
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .chain(unhealthy.into_iter().map(|(idx, _)| idx))
+            .collect()
+    }
+
+    /// If the first parameter given is the ID of one of our subscriptions, return
+    /// the index of the endpoint that the subscription lives on.
+    fn subscription_endpoint(&self, params: Option<&RawValue>) -> Option<usize> {
+        let subscriptions = self.subscriptions.lock().unwrap();
+        if subscriptions.is_empty() {
+            return None;
+        }
+        let params: Vec<serde_json::Value> = serde_json::from_str(params?.get()).ok()?;
+        let id = params.first()?.as_str()?;
+        subscriptions.get(id).copied()
+    }
+
+    /// Subscribe on the first endpoint that we're able to, returning its index and the subscription.
+    async fn subscribe(
+        &self,
+        sub: &str,
+        params: Option<Box<RawValue>>,
+        unsub: &str,
+    ) -> Result<(usize, RawRpcSubscription), RpcError> {
+        let mut last_err = None;
+        for idx in self.endpoint_order() {
+            let endpoint = &self.endpoints[idx];
+            let started = Instant::now();
+            match endpoint
+                .client
+                .subscribe_raw(sub, params.clone(), unsub)
+                .await
+            {
+                Ok(subscription) => {
+                    endpoint.record_success(started.elapsed());
+                    return Ok((idx, subscription));
+                }
+                Err(e) if is_endpoint_failure(&e) => {
+                    endpoint.record_failure();
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(no_endpoints_error))
+    }
+}
+
+struct Endpoint {
+    client: RpcClient,
+    state: Mutex<EndpointState>,
+}
+
+#[derive(Default)]
+struct EndpointState {
+    // A moving average of how long requests to this endpoint take.
+    latency: Option<Duration>,
+    // When this endpoint last failed, if it hasn't succeeded since.
+    failed_at: Option<Instant>,
+}
+
+impl Endpoint {
+    async fn request(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Box<RawValue>, RpcError> {
+        let started = Instant::now();
+        let res = self.client.request_raw(method, params).await;
+        match &res {
+            Err(e) if is_endpoint_failure(e) => self.record_failure(),
+            _ => self.record_success(started.elapsed()),
+        }
+        res
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.failed_at = None;
+        state.latency = Some(match state.latency {
+            // Weight the newest measurement at 1/4.
+            Some(avg) => (avg * 3 + latency) / 4,
+            None => latency,
+        });
+    }
+
+    fn record_failure(&self) {
+        self.state.lock().unwrap().failed_at = Some(Instant::now());
+    }
+}
+
+/// Tracks the subscription ID of an active subscription so that requests using it
+/// are routed to the right endpoint, and forgets it again when dropped.
+struct SubscriptionGuard {
+    inner: Arc<Inner>,
+    endpoint: usize,
+    id: Option<String>,
+}
+
+impl SubscriptionGuard {
+    fn new(inner: Arc<Inner>, endpoint: usize, id: Option<String>) -> Self {
+        if let Some(id) = &id {
+            inner
+                .subscriptions
+                .lock()
+                .unwrap()
+                .insert(id.clone(), endpoint);
+        }
+        SubscriptionGuard {
+            inner,
+            endpoint,
+            id,
+        }
+    }
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id {
+            self.inner.subscriptions.lock().unwrap().remove(id);
+        }
+    }
+}
+
+type RawSubscriptionStream =
+    Pin<Box<dyn Stream<Item = Result<Box<RawValue>, RpcError>> + Send + 'static>>;
+
+struct SubscriptionState {
+    guard: SubscriptionGuard,
+    // This is `None` once the subscription has finished.
+    stream: Option<RawSubscriptionStream>,
+    sub: String,
+    params: Option<Box<RawValue>>,
+    unsub: String,
+    // How many times in a row we've resubscribed after the subscription ended, only for
+    // the new subscription to end without handing back any items.
+    empty_resubscriptions: usize,
+}
+
+impl SubscriptionState {
+    async fn next(&mut self) -> Option<Result<Box<RawValue>, RpcError>> {
+        loop {
+            let err = match self.stream.as_mut()?.next().await {
+                Some(Err(e)) if is_endpoint_failure(&e) => e,
+                Some(item) => {
+                    self.empty_resubscriptions = 0;
+                    return Some(item);
+                }
+                // Subscriptions end when the connection to the endpoint drops, so if we can
+                // move this one elsewhere, treat it ending as the endpoint failing. We stop
+                // once every endpoint has ended it without handing anything back.
+                None if can_resubscribe(&self.sub)
+                    && self.empty_resubscriptions < self.inner().endpoints.len() =>
+                {
+                    self.empty_resubscriptions += 1;
+                    RpcError::SubscriptionDropped
+                }
+                None => {
+                    self.stream = None;
+                    return None;
+                }
+            };
+
+            self.inner().endpoints[self.guard.endpoint].record_failure();
+            self.stream = None;
+
+            if !can_resubscribe(&self.sub) {
+                // chainHead subscriptions only exist on the node they were made on, so they
+                // can't be moved. Instead, the backend knows to follow the chain again (which
+                // will happen on a healthy endpoint) when it sees this error.
+                if self.sub.starts_with("chainHead_") {
+                    return Some(Err(RpcError::DisconnectedWillReconnect(err.to_string())));
+                }
+                return Some(Err(err));
+            }
+
+            tracing::debug!(
+                "Subscription {} failed ({err}); resubscribing on another endpoint",
+                self.sub
+            );
+            let inner = self.inner().clone();
+            match inner
+                .subscribe(&self.sub, self.params.clone(), &self.unsub)
+                .await
+            {
+                Ok((idx, subscription)) => {
+                    self.guard = SubscriptionGuard::new(inner, idx, subscription.id);
+                    self.stream = Some(subscription.stream);
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    fn inner(&self) -> &Arc<Inner> {
+        &self.guard.inner
+    }
+}
+
+/// Subscriptions which aren't tied to any state on the node that they were made
+/// on can be transparently re-established on another endpoint.
+fn can_resubscribe(sub: &str) -> bool {
+    !sub.starts_with("chainHead_")
+        && !sub.starts_with("transaction")
+        && sub != "author_submitAndWatchExtrinsic"
+}
+
+/// Did the request fail because of a problem with the endpoint (as opposed to
+/// the endpoint responding with an error)?
+pub(crate) fn is_endpoint_failure(err: &RpcError) -> bool {
+    match err {
+        RpcError::RequestRejected(_) => false,
+        #[cfg(feature = "jsonrpsee")]
+        RpcError::ClientError(e) => !matches!(
+            e.downcast_ref::<jsonrpsee::core::ClientError>(),
+            Some(jsonrpsee::core::ClientError::Call(_))
+        ),
+        _ => true,
+    }
+}
+
+/// Clients which don't support batch requests send each request individually, so a
+/// batch in which every request failed because of the endpoint is an endpoint failure.
+fn batch_endpoint_failure(
+    mut results: Vec<Result<Box<RawValue>, RpcError>>,
+) -> Result<Vec<Result<Box<RawValue>, RpcError>>, RpcError> {
+    let all_failed = !results.is_empty()
+        && results
+            .iter()
+            .all(|res| matches!(res, Err(e) if is_endpoint_failure(e)));
+    match results.pop() {
+        Some(Err(e)) if all_failed => Err(e),
+        Some(last) => {
+            results.push(last);
+            Ok(results)
+        }
+        None => Ok(results),
+    }
+}
+
+fn no_endpoints_error() -> RpcError {
+    RpcError::ClientError(Box::new(std::io::Error::new(
+        std::io::ErrorKind::NotConnected,
+        "no RPC endpoints were configured",
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::rpc::mock_rpc_client::MockRpcClient;
+    use std::sync::atomic::AtomicBool;
+
+    // A client which counts the requests it gets, and fails them when told to.
+    #[derive(Clone, Default)]
+    struct MockClient {
+        requests: Arc<AtomicUsize>,
+        failing: Arc<AtomicBool>,
+    }
+
+    impl RpcClientT for MockClient {
+        fn request_raw<'a>(
+            &'a self,
+            _method: &'a str,
+            _params: Option<Box<RawValue>>,
+        ) -> RawRpcFuture<'a, Box<RawValue>> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            let failing = self.failing.load(Ordering::SeqCst);
+            async move {
+                if failing {
+                    Err(RpcError::SubscriptionDropped)
+                } else {
+                    Ok(RawValue::from_string("null".to_owned()).unwrap())
+                }
+            }
+            .boxed()
+        }
+
+        fn subscribe_raw<'a>(
+            &'a self,
+            _sub: &'a str,
+            _params: Option<Box<RawValue>>,
+            _unsub: &'a str,
+        ) -> RawRpcFuture<'a, RawRpcSubscription> {
+            async { Err(RpcError::SubscriptionDropped) }.boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn round_robin_spreads_requests() {
+        let mocks = vec![MockClient::default(), MockClient::default()];
+        let client = MultiRpcClient::new(mocks.iter().cloned().map(RpcClient::new));
+
+        for _ in 0..4 {
+            client.request_raw("foo", None).await.unwrap();
+        }
+
+        assert_eq!(mocks[0].requests.load(Ordering::SeqCst), 2);
+        assert_eq!(mocks[1].requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_healthy_endpoint() {
+        let mocks = vec![MockClient::default(), MockClient::default()];
+        mocks[0].failing.store(true, Ordering::SeqCst);
+        let client = MultiRpcClient::new(mocks.iter().cloned().map(RpcClient::new));
+
+        for _ in 0..4 {
+            client.request_raw("foo", None).await.unwrap();
+        }
+
+        // The failing endpoint is tried once, and then avoided.
+        assert_eq!(mocks[0].requests.load(Ordering::SeqCst), 1);
+        assert_eq!(mocks[1].requests.load(Ordering::SeqCst), 4);
+        assert_eq!(client.check_health().await, vec![false, true]);
+    }
+
+    #[tokio::test]
+    async fn batches_fail_over_to_healthy_endpoint() {
+        let mocks = vec![MockClient::default(), MockClient::default()];
+        mocks[0].failing.store(true, Ordering::SeqCst);
+        let client = MultiRpcClient::new(mocks.iter().cloned().map(RpcClient::new));
+
+        let results = client
+            .batch_request_raw(vec![("foo", None), ("bar", None)])
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|res| res.is_ok()));
+        assert_eq!(mocks[0].requests.load(Ordering::SeqCst), 2);
+        assert_eq!(mocks[1].requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn errors_if_every_endpoint_fails() {
+        let mock = MockClient::default();
+        mock.failing.store(true, Ordering::SeqCst);
+        let client = MultiRpcClient::new([RpcClient::new(mock)]);
+
+        let err = client.request_raw("foo", None).await.unwrap_err();
+        assert!(matches!(err, RpcError::SubscriptionDropped));
+    }
+
+    #[tokio::test]
+    async fn health_checks_run_until_the_client_is_dropped() {
+        let mock = MockClient::default();
+        let client = MultiRpcClient::new([RpcClient::new(mock.clone())]);
+
+        let checks = tokio::spawn(client.health_checks(Duration::from_millis(10)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(mock.requests.load(Ordering::SeqCst) > 1);
+
+        drop(client);
+        tokio::time::timeout(Duration::from_secs(1), checks)
+            .await
+            .expect("health checks should stop once the client is dropped")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn health_checks_find_recovered_endpoints() {
+        let mocks = vec![MockClient::default(), MockClient::default()];
+        mocks[0].failing.store(true, Ordering::SeqCst);
+        let client = MultiRpcClient::new(mocks.iter().cloned().map(RpcClient::new));
+
+        // The first endpoint fails, and is then avoided..
+        client.request_raw("foo", None).await.unwrap();
+        assert_eq!(client.inner.endpoint_order(), vec![1, 0]);
+
+        // ..until a health check finds that it's working again.
+        mocks[0].failing.store(false, Ordering::SeqCst);
+        tokio::spawn(client.health_checks(Duration::from_millis(10)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(client.inner.endpoints[0]
+            .state
+            .lock()
+            .unwrap()
+            .failed_at
+            .is_none());
+    }
+
+    // A subscription which emits the given item and then fails as if the endpoint went away.
+    fn failing_subscription(
+        item: serde_json::Value,
+    ) -> impl Stream<Item = Result<serde_json::Value, RpcError>> {
+        futures::stream::iter([Ok(item), Err(RpcError::SubscriptionDropped)])
+    }
+
+    // A subscription which emits the given item and then ends, as a WebSocket
+    // subscription does when its connection drops.
+    fn ending_subscription(
+        item: serde_json::Value,
+    ) -> impl Stream<Item = Result<serde_json::Value, RpcError>> {
+        futures::stream::iter([Ok(item)])
+    }
+
+    // A subscription which emits the given item and then waits forever.
+    fn pending_subscription(
+        item: serde_json::Value,
+    ) -> impl Stream<Item = Result<serde_json::Value, RpcError>> {
+        futures::stream::iter([Ok(item)]).chain(futures::stream::pending())
+    }
+
+    #[tokio::test]
+    async fn subscriptions_are_reestablished_on_another_endpoint() {
+        let failing = MockRpcClient::builder()
+            .subscription("chain_subscribeNewHeads", |_| {
+                Ok(failing_subscription(serde_json::json!(1)))
+            })
+            .build();
+        let working = MockRpcClient::builder()
+            .subscription("chain_subscribeNewHeads", |_| {
+                Ok(pending_subscription(serde_json::json!(2)))
+            })
+            .build();
+        let client = MultiRpcClient::new([
+            RpcClient::new(failing.clone()),
+            RpcClient::new(working.clone()),
+        ]);
+
+        let mut sub = client
+            .subscribe_raw("chain_subscribeNewHeads", None, "chain_unsubscribeNewHeads")
+            .await
+            .unwrap();
+        assert_eq!(sub.stream.next().await.unwrap().unwrap().get(), "1");
+        assert_eq!(sub.stream.next().await.unwrap().unwrap().get(), "2");
+        assert_eq!(failing.requests().len(), 1);
+        assert_eq!(working.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn subscriptions_which_end_are_reestablished_on_another_endpoint() {
+        let ending = MockRpcClient::builder()
+            .subscription("chain_subscribeNewHeads", |_| {
+                Ok(ending_subscription(serde_json::json!(1)))
+            })
+            .build();
+        let working = MockRpcClient::builder()
+            .subscription("chain_subscribeNewHeads", |_| {
+                Ok(pending_subscription(serde_json::json!(2)))
+            })
+            .build();
+        let client = MultiRpcClient::new([
+            RpcClient::new(ending.clone()),
+            RpcClient::new(working.clone()),
+        ]);
+
+        let mut sub = client
+            .subscribe_raw("chain_subscribeNewHeads", None, "chain_unsubscribeNewHeads")
+            .await
+            .unwrap();
+        assert_eq!(sub.stream.next().await.unwrap().unwrap().get(), "1");
+        assert_eq!(sub.stream.next().await.unwrap().unwrap().get(), "2");
+        assert_eq!(ending.requests().len(), 1);
+        assert_eq!(working.requests().len(), 1);
+        assert_eq!(client.inner.endpoint_order(), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn subscriptions_end_if_every_endpoint_ends_them() {
+        let ending = MockRpcClient::builder()
+            .subscription("chain_subscribeNewHeads", |_| {
+                Ok(futures::stream::empty::<Result<serde_json::Value, RpcError>>())
+            })
+            .build();
+        let client = MultiRpcClient::new([
+            RpcClient::new(ending.clone()),
+            RpcClient::new(ending.clone()),
+        ]);
+
+        let mut sub = client
+            .subscribe_raw("chain_subscribeNewHeads", None, "chain_unsubscribeNewHeads")
+            .await
+            .unwrap();
+        assert!(sub.stream.next().await.is_none());
+        // The first subscription, and then one more on each endpoint.
+        assert_eq!(ending.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn chainhead_follow_is_restarted_on_another_endpoint() {
+        use crate::backend::unstable::follow_stream::FollowStream;
+        use crate::backend::unstable::rpc_methods::FollowEvent;
+        use crate::backend::unstable::UnstableRpcMethods;
+        use crate::SubstrateConfig;
+
+        fn initialized(hash: u8) -> serde_json::Value {
+            serde_json::json!({
+                "event": "initialized",
+                "finalizedBlockHashes": [format!("0x{}", hex::encode([hash; 32]))],
+            })
+        }
+
+        let failing = MockRpcClient::builder()
+            .subscription("chainHead_v1_follow", |_| {
+                Ok(failing_subscription(initialized(1)))
+            })
+            .build();
+        let working = MockRpcClient::builder()
+            .subscription("chainHead_v1_follow", |_| {
+                Ok(pending_subscription(initialized(2)))
+            })
+            .build();
+        let client = MultiRpcClient::new([RpcClient::new(failing), RpcClient::new(working)]);
+
+        let methods = UnstableRpcMethods::<SubstrateConfig>::new(RpcClient::new(client));
+        let events: Vec<_> = FollowStream::<crate::utils::H256>::from_methods(methods)
+            .take(5)
+            .map(|msg| msg.unwrap().into_event())
+            .collect()
+            .await;
+
+        // The endpoint failing doesn't end the stream; it's stopped and restarted instead.
+        let finalized = |events: &Option<FollowEvent<_>>| match events {
+            Some(FollowEvent::Initialized(init)) => init.finalized_block_hashes.clone(),
+            _ => panic!("expected an initialized event, got {events:?}"),
+        };
+        assert!(events[0].is_none());
+        assert_eq!(finalized(&events[1]), vec![[1; 32].into()]);
+        assert_eq!(events[2], Some(FollowEvent::Stop));
+        assert!(events[3].is_none());
+        assert_eq!(finalized(&events[4]), vec![[2; 32].into()]);
+    }
+}
@@ -0,0 +1,704 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! This module exposes a [`RecordingRpcClient`], which wraps some other RPC client and
+//! records every request and subscription made through it, and a [`ReplayRpcClient`], which
+//! serves a [`Recording`] back without connecting to anything. Together, these allow tests to
+//! be run once against a node, and then deterministically re-run offline.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # async fn foo() -> Result<(), subxt::Error> {
+//! use subxt::backend::rpc::recording_rpc_client::{RecordingRpcClient, ReplayRpcClient};
+//! use subxt::backend::rpc::RpcClient;
+//! use subxt::{OnlineClient, PolkadotConfig};
+//!
+//! // Record a session against a node:
+//! let recorder = RecordingRpcClient::new(RpcClient::from_url("ws://127.0.0.1:9944").await?);
+//! let api = OnlineClient::<PolkadotConfig>::from_rpc_client(recorder.clone()).await?;
+//! let block = api.blocks().at_latest().await?;
+//! recorder.recording().save_to_file("session.json")?;
+//!
+//! // Later, replay it without a node:
+//! let replayer = ReplayRpcClient::from_file("session.json")?;
+//! let api = OnlineClient::<PolkadotConfig>::from_rpc_client(replayer).await?;
+//! let same_block = api.blocks().at_latest().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{RawRpcFuture, RawRpcSubscription, RawValue, RpcClient, RpcClientT};
+use crate::error::{Error, RpcError};
+use futures::{future, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+/// The requests and subscriptions recorded by a [`RecordingRpcClient`], which can be
+/// replayed by a [`ReplayRpcClient`]. This can be saved to and loaded from a JSON file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    requests: Vec<RecordedRequest>,
+    subscriptions: Vec<RecordedSubscription>,
+}
+
+impl Recording {
+    /// Load a recording from the JSON file at the given path.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let recording = serde_json::from_reader(file)?;
+        Ok(recording)
+    }
+
+    /// Save this recording as JSON to the file at the given path.
+    /// Any existing file is overwritten.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// The number of requests that have been recorded.
+    pub fn num_requests(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// The number of subscriptions that have been recorded.
+    pub fn num_subscriptions(&self) -> usize {
+        self.subscriptions.len()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedRequest {
+    method: String,
+    params: Option<Box<RawValue>>,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedSubscription {
+    method: String,
+    params: Option<Box<RawValue>>,
+    // Either the ID of the subscription, or why subscribing failed.
+    subscription: Result<Option<String>, RecordedError>,
+    items: Vec<RecordedItem>,
+    // Whether the subscription ended while it was being recorded.
+    ended: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedItem {
+    // How many requests had been responded to when this item arrived. When replaying, we
+    // wait until this many requests have been responded to before handing the item back,
+    // so that items are never seen before the requests that led to them.
+    requests_before: usize,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RecordedResponse {
+    Result(Box<RawValue>),
+    Error(RecordedError),
+}
+
+impl From<&Result<Box<RawValue>, RpcError>> for RecordedResponse {
+    fn from(res: &Result<Box<RawValue>, RpcError>) -> Self {
+        match res {
+            Ok(value) => RecordedResponse::Result(value.clone()),
+            Err(e) => RecordedResponse::Error(e.into()),
+        }
+    }
+}
+
+impl From<RecordedResponse> for Result<Box<RawValue>, RpcError> {
+    fn from(res: RecordedResponse) -> Self {
+        match res {
+            RecordedResponse::Result(value) => Ok(value),
+            RecordedResponse::Error(e) => Err(e.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RecordedError {
+    RequestRejected(String),
+    SubscriptionDropped,
+    DisconnectedWillReconnect(String),
+    Other(String),
+}
+
+impl From<&RpcError> for RecordedError {
+    fn from(e: &RpcError) -> Self {
+        match e {
+            RpcError::RequestRejected(msg) => RecordedError::RequestRejected(msg.clone()),
+            RpcError::SubscriptionDropped => RecordedError::SubscriptionDropped,
+            RpcError::DisconnectedWillReconnect(msg) => {
+                RecordedError::DisconnectedWillReconnect(msg.clone())
+            }
+            RpcError::ClientError(_) | RpcError::InsecureUrl(_) => {
+                RecordedError::Other(e.to_string())
+            }
+        }
+    }
+}
+
+impl From<RecordedError> for RpcError {
+    fn from(e: RecordedError) -> Self {
+        match e {
+            RecordedError::RequestRejected(msg) => RpcError::RequestRejected(msg),
+            RecordedError::SubscriptionDropped => RpcError::SubscriptionDropped,
+            RecordedError::DisconnectedWillReconnect(msg) => {
+                RpcError::DisconnectedWillReconnect(msg)
+            }
+            RecordedError::Other(msg) => RpcError::ClientError(msg.into()),
+        }
+    }
+}
+
+/// An [`RpcClientT`] implementation which passes everything through to some other RPC
+/// client, recording each request and subscription (and the items it produces) along the
+/// way. Use [`RecordingRpcClient::recording`] to obtain what's been recorded so far.
+///
+/// Cloning this is cheap, and the clones share a single recording.
+#[derive(Debug, Clone)]
+pub struct RecordingRpcClient {
+    client: RpcClient,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl RecordingRpcClient {
+    /// Record the requests and subscriptions made through the given RPC client.
+    pub fn new(client: impl Into<RpcClient>) -> Self {
+        RecordingRpcClient {
+            client: client.into(),
+            recording: Default::default(),
+        }
+    }
+
+    /// Return everything that's been recorded so far. Subscriptions which are still
+    /// running will continue to record items, but these won't be seen in the returned
+    /// [`Recording`]; call this again to obtain them.
+    pub fn recording(&self) -> Recording {
+        self.recording.lock().unwrap().clone()
+    }
+
+    fn record_request(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+        res: &Result<Box<RawValue>, RpcError>,
+    ) {
+        self.recording
+            .lock()
+            .unwrap()
+            .requests
+            .push(RecordedRequest {
+                method: method.to_owned(),
+                params,
+                response: res.into(),
+            });
+    }
+}
+
+impl RpcClientT for RecordingRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        async move {
+            let res = self.client.request_raw(method, params.clone()).await;
+            self.record_request(method, params, &res);
+            res
+        }
+        .boxed()
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        async move {
+            let res = self.client.subscribe_raw(sub, params.clone(), unsub).await;
+
+            let mut recording = self.recording.lock().unwrap();
+            let idx = recording.subscriptions.len();
+            recording.subscriptions.push(RecordedSubscription {
+                method: sub.to_owned(),
+                params,
+                subscription: match &res {
+                    Ok(sub) => Ok(sub.id.clone()),
+                    Err(e) => Err(e.into()),
+                },
+                items: Vec::new(),
+                ended: false,
+            });
+            drop(recording);
+
+            let sub = res?;
+            let recording = self.recording.clone();
+            let recording2 = self.recording.clone();
+            let stream = sub
+                .stream
+                .map(move |item| {
+                    let mut recording = recording.lock().unwrap();
+                    let requests_before = recording.requests.len();
+                    recording.subscriptions[idx].items.push(RecordedItem {
+                        requests_before,
+                        response: (&item).into(),
+                    });
+                    item
+                })
+                .chain(
+                    // Once the subscription ends, record that it did so:
+                    futures::stream::once(async move {
+                        recording2.lock().unwrap().subscriptions[idx].ended = true;
+                    })
+                    .filter_map(|()| future::ready(None)),
+                );
+
+            Ok(RawRpcSubscription {
+                stream: Box::pin(stream),
+                id: sub.id,
+            })
+        }
+        .boxed()
+    }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        async move {
+            let responses = self.client.batch_request_raw(requests.clone()).await?;
+            // Each request in the batch is recorded individually, so that it can be
+            // replayed regardless of whether it's made in a batch or not.
+            for ((method, params), res) in requests.into_iter().zip(&responses) {
+                self.record_request(method, params, res);
+            }
+            Ok(responses)
+        }
+        .boxed()
+    }
+}
+
+/// An [`RpcClientT`] implementation which replays a [`Recording`], without connecting to
+/// anything. Requests and subscriptions are matched against those recorded by their method
+/// name and parameters:
+///
+/// - Requests are given the recorded responses in the order that they were recorded. If more
+///   identical requests are made than were recorded, the last recorded response is repeated.
+/// - Subscriptions are likewise given the recorded subscriptions in order, but each can be
+///   replayed only once. Their items are handed back in the order that they were recorded, but
+///   not before the requests that had been responded to when they were recorded have been
+///   replayed. Once all items have been handed back, the subscription ends if it ended when it
+///   was recorded, and never produces anything else otherwise.
+///
+/// An error is returned for any request or subscription that wasn't recorded.
+#[derive(Debug, Clone)]
+pub struct ReplayRpcClient {
+    inner: Arc<Mutex<ReplayState>>,
+}
+
+#[derive(Debug)]
+struct ReplayState {
+    requests: HashMap<RequestKey, ReplayResponses>,
+    subscriptions: HashMap<RequestKey, VecDeque<RecordedSubscription>>,
+    // How many requests have been responded to so far.
+    requests_served: usize,
+    // Subscriptions waiting for more requests to be responded to.
+    wakers: Vec<Waker>,
+}
+
+#[derive(Debug)]
+struct ReplayResponses {
+    responses: Vec<RecordedResponse>,
+    next: usize,
+}
+
+/// Requests are identified by their method name and serialized parameters.
+type RequestKey = (String, Option<String>);
+
+fn request_key(method: &str, params: Option<&RawValue>) -> RequestKey {
+    (method.to_owned(), params.map(|p| p.get().to_owned()))
+}
+
+impl ReplayRpcClient {
+    /// Replay the given recording.
+    pub fn new(recording: Recording) -> Self {
+        let mut requests: HashMap<RequestKey, ReplayResponses> = HashMap::new();
+        for req in recording.requests {
+            requests
+                .entry(request_key(&req.method, req.params.as_deref()))
+                .or_insert_with(|| ReplayResponses {
+                    responses: Vec::new(),
+                    next: 0,
+                })
+                .responses
+                .push(req.response);
+        }
+
+        let mut subscriptions: HashMap<RequestKey, VecDeque<RecordedSubscription>> = HashMap::new();
+        for sub in recording.subscriptions {
+            subscriptions
+                .entry(request_key(&sub.method, sub.params.as_deref()))
+                .or_default()
+                .push_back(sub);
+        }
+
+        ReplayRpcClient {
+            inner: Arc::new(Mutex::new(ReplayState {
+                requests,
+                subscriptions,
+                requests_served: 0,
+                wakers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Replay the recording saved as JSON to the file at the given path.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(ReplayRpcClient::new(Recording::from_file(path)?))
+    }
+}
+
+impl RpcClientT for ReplayRpcClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RawRpcFuture<'a, Box<RawValue>> {
+        let mut state = self.inner.lock().unwrap();
+        let key = request_key(method, params.as_deref());
+        let Some(replay) = state.requests.get_mut(&key) else {
+            return future::ready(Err(not_recorded("request", key))).boxed();
+        };
+
+        let idx = replay.next.min(replay.responses.len() - 1);
+        replay.next += 1;
+        let res = replay.responses[idx].clone().into();
+
+        // Let any subscriptions waiting on this response continue.
+        state.requests_served += 1;
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+
+        future::ready(res).boxed()
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RawRpcFuture<'a, RawRpcSubscription> {
+        let key = request_key(sub, params.as_deref());
+        let Some(recorded) = self
+            .inner
+            .lock()
+            .unwrap()
+            .subscriptions
+            .get_mut(&key)
+            .and_then(|subs| subs.pop_front())
+        else {
+            return future::ready(Err(not_recorded("subscription", key))).boxed();
+        };
+
+        let id = match recorded.subscription {
+            Ok(id) => id,
+            Err(e) => return future::ready(Err(e.into())).boxed(),
+        };
+
+        let inner = self.inner.clone();
+        let ended = recorded.ended;
+        let mut items = VecDeque::from(recorded.items);
+        let stream = futures::stream::poll_fn(move |cx| {
+            let Some(item) = items.front() else {
+                return if ended {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Pending
+                };
+            };
+
+            let mut state = inner.lock().unwrap();
+            if state.requests_served < item.requests_before {
+                state.wakers.push(cx.waker().clone());
+                return Poll::Pending;
+            }
+            drop(state);
+
+            let item = items.pop_front().expect("item exists; checked above; qed");
+            Poll::Ready(Some(item.response.into()))
+        });
+
+        future::ready(Ok(RawRpcSubscription {
+            stream: Box::pin(stream),
+            id,
+        }))
+        .boxed()
+    }
+}
+
+fn not_recorded(kind: &str, (method, params): RequestKey) -> RpcError {
+    RpcError::RequestRejected(format!(
+        "No {kind} to {method} with params {} was recorded",
+        params.as_deref().unwrap_or("[]")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::legacy::LegacyRpcMethods;
+    use crate::backend::mock::test_utils::header;
+    use crate::backend::rpc::mock_rpc_client::MockRpcClient;
+    use crate::backend::rpc::rpc_params;
+    use crate::backend::unstable::UnstableRpcMethods;
+    use crate::utils::H256;
+    use crate::SubstrateConfig;
+    use codec::Encode;
+    use futures::stream;
+    use serde_json::json;
+
+    // Responds to each request with its method name, and provides a
+    // subscription which produces a single item and then ends.
+    struct MockClient;
+
+    impl RpcClientT for MockClient {
+        fn request_raw<'a>(
+            &'a self,
+            method: &'a str,
+            _params: Option<Box<RawValue>>,
+        ) -> RawRpcFuture<'a, Box<RawValue>> {
+            let res = serde_json::value::to_raw_value(method).unwrap();
+            future::ready(Ok(res)).boxed()
+        }
+
+        fn subscribe_raw<'a>(
+            &'a self,
+            _sub: &'a str,
+            _params: Option<Box<RawValue>>,
+            _unsub: &'a str,
+        ) -> RawRpcFuture<'a, RawRpcSubscription> {
+            let item = RawValue::from_string("1".to_owned()).unwrap();
+            future::ready(Ok(RawRpcSubscription {
+                stream: Box::pin(stream::iter([Ok(item)])),
+                id: Some("sub_id".to_owned()),
+            }))
+            .boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_what_was_recorded() {
+        let recording_client = RecordingRpcClient::new(MockClient);
+        let recorder = RpcClient::new(recording_client.clone());
+        let foo: String = recorder.request("foo", rpc_params![1]).await.unwrap();
+        assert_eq!(foo, "foo");
+        let mut sub = recorder
+            .subscribe::<u32>("sub", rpc_params![], "unsub")
+            .await
+            .unwrap();
+        assert_eq!(sub.next().await.unwrap().unwrap(), 1);
+        assert!(sub.next().await.is_none());
+
+        // Round trip the recording through JSON:
+        let recording = recording_client.recording();
+        assert_eq!(recording.num_requests(), 1);
+        assert_eq!(recording.num_subscriptions(), 1);
+        let recording: Recording =
+            serde_json::from_str(&serde_json::to_string(&recording).unwrap()).unwrap();
+        let replayer = RpcClient::new(ReplayRpcClient::new(recording));
+
+        let foo: String = replayer.request("foo", rpc_params![1]).await.unwrap();
+        assert_eq!(foo, "foo");
+        // Params must match:
+        assert!(replayer
+            .request::<String>("foo", rpc_params![2])
+            .await
+            .is_err());
+
+        let mut sub = replayer
+            .subscribe::<u32>("sub", rpc_params![], "unsub")
+            .await
+            .unwrap();
+        assert_eq!(sub.subscription_id(), Some("sub_id"));
+        assert_eq!(sub.next().await.unwrap().unwrap(), 1);
+        assert!(sub.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn replayed_items_wait_for_earlier_requests() {
+        let item = |requests_before| RecordedItem {
+            requests_before,
+            response: RecordedResponse::Result(RawValue::from_string("1".to_owned()).unwrap()),
+        };
+        let recording = Recording {
+            requests: vec![RecordedRequest {
+                method: "foo".to_owned(),
+                params: None,
+                response: RecordedResponse::Result(
+                    RawValue::from_string("null".to_owned()).unwrap(),
+                ),
+            }],
+            subscriptions: vec![RecordedSubscription {
+                method: "sub".to_owned(),
+                params: None,
+                subscription: Ok(None),
+                items: vec![item(0), item(1)],
+                ended: true,
+            }],
+        };
+
+        let replayer = ReplayRpcClient::new(recording);
+        let mut sub = replayer.subscribe_raw("sub", None, "unsub").await.unwrap();
+
+        // The first item doesn't wait for any requests, but the second does:
+        assert!(sub.stream.next().await.is_some());
+        assert!(sub.stream.next().now_or_never().is_none());
+
+        replayer.request_raw("foo", None).await.unwrap();
+        assert!(sub.stream.next().await.is_some());
+        assert!(sub.stream.next().await.is_none());
+    }
+
+    // Record whatever `session` does against the given client, and then check that replaying
+    // the recording (after a round trip through a file) produces the same results offline.
+    async fn assert_replays_the_same<R, F, Fut>(
+        name: &str,
+        client: MockRpcClient,
+        session: F,
+    ) -> Recording
+    where
+        R: PartialEq + std::fmt::Debug,
+        F: Fn(RpcClient) -> Fut,
+        Fut: std::future::Future<Output = R>,
+    {
+        let recording_client = RecordingRpcClient::new(client);
+        let recorded = session(RpcClient::new(recording_client.clone())).await;
+
+        let path = std::env::temp_dir().join(format!(
+            "subxt-recording-test-{name}-{}.json",
+            std::process::id()
+        ));
+        recording_client.recording().save_to_file(&path).unwrap();
+        let recording = Recording::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let replayed = session(RpcClient::new(ReplayRpcClient::new(recording.clone()))).await;
+        assert_eq!(recorded, replayed);
+        recording
+    }
+
+    #[tokio::test]
+    async fn legacy_rpc_methods_can_be_replayed() {
+        let best = header(2, H256::repeat_byte(1));
+        let best_json = serde_json::to_value(&best).unwrap();
+
+        let client = MockRpcClient::builder()
+            .method("system_chain", |_| Ok(json!("Polkadot")))
+            .method("chain_getBlockHash", |params| match params[0].as_u64() {
+                Some(1) => Ok(json!(H256::repeat_byte(1))),
+                _ => Ok(json!(null)),
+            })
+            .method("state_getStorage", |params| match params[0].as_str() {
+                Some("0x0102") => Ok(json!("0x0304")),
+                _ => Ok(json!(null)),
+            })
+            .subscription("chain_subscribeNewHeads", move |_| {
+                Ok(stream::iter([Ok(best_json.clone())]))
+            })
+            .build();
+
+        let recording = assert_replays_the_same("legacy", client, |client| async move {
+            let methods = LegacyRpcMethods::<SubstrateConfig>::new(client);
+            let chain = methods.system_chain().await.unwrap();
+            let hash = methods
+                .chain_get_block_hash(Some(1u32.into()))
+                .await
+                .unwrap();
+            let missing_hash = methods
+                .chain_get_block_hash(Some(5u32.into()))
+                .await
+                .unwrap();
+            let value = methods.state_get_storage(&[1, 2], hash).await.unwrap();
+            let heads: Vec<_> = methods
+                .chain_subscribe_new_heads()
+                .await
+                .unwrap()
+                .map(|header| header.unwrap())
+                .collect()
+                .await;
+            (chain, hash, missing_hash, value, heads)
+        })
+        .await;
+
+        assert_eq!(recording.num_requests(), 4);
+        assert_eq!(recording.num_subscriptions(), 1);
+    }
+
+    #[tokio::test]
+    async fn unstable_rpc_methods_can_be_replayed() {
+        let block_hash = H256::repeat_byte(1);
+        let block_header = header(1, H256::zero());
+        let encoded_header = format!("0x{}", hex::encode(block_header.encode()));
+
+        let client = MockRpcClient::builder()
+            .method("chainSpec_v1_genesisHash", |_| Ok(json!(H256::zero())))
+            .method("chainHead_v1_header", move |_| Ok(json!(encoded_header)))
+            .method("chainHead_v1_body", |_| {
+                Ok(json!({ "result": "started", "operationId": "op" }))
+            })
+            .subscription("chainHead_v1_follow", move |_| {
+                Ok(stream::iter([
+                    Ok(json!({
+                        "event": "initialized",
+                        "finalizedBlockHashes": [block_hash],
+                    })),
+                    Ok(json!({
+                        "event": "operationBodyDone",
+                        "operationId": "op",
+                        "value": ["0x00"],
+                    })),
+                ])
+                .chain(stream::pending()))
+            })
+            .build();
+
+        let recording = assert_replays_the_same("unstable", client, |client| async move {
+            let methods = UnstableRpcMethods::<SubstrateConfig>::new(client);
+            let genesis_hash = methods.chainspec_v1_genesis_hash().await.unwrap();
+
+            let mut follow = methods.chainhead_v1_follow(true).await.unwrap();
+            let sub_id = follow.subscription_id().unwrap().to_owned();
+            let initialized = follow.next().await.unwrap().unwrap();
+            let block_header = methods
+                .chainhead_v1_header(&sub_id, block_hash)
+                .await
+                .unwrap();
+            let body = methods
+                .chainhead_v1_body(&sub_id, block_hash)
+                .await
+                .unwrap();
+            // When replaying, this is only handed back after the body request above.
+            let body_done = follow.next().await.unwrap().unwrap();
+
+            (genesis_hash, initialized, block_header, body, body_done)
+        })
+        .await;
+
+        assert_eq!(recording.num_requests(), 3);
+        assert_eq!(recording.num_subscriptions(), 1);
+    }
+}
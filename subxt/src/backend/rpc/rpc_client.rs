@@ -60,6 +60,32 @@ impl RpcClient {
         Ok(val)
     }
 
+    /// Make a batch of RPC requests, given the method name and parameters for each one,
+    /// and return the result of each request in the order that they were given. Clients
+    /// which support it will send these as a single JSON-RPC batch request.
+    ///
+    /// See [`RpcParams`] and the [`rpc_params!`] macro for an example of how to
+    /// construct the parameters.
+    pub async fn batch_request<'a, Res: DeserializeOwned>(
+        &self,
+        requests: impl IntoIterator<Item = (&'a str, RpcParams)>,
+    ) -> Result<Vec<Result<Res, Error>>, Error> {
+        let requests = requests
+            .into_iter()
+            .map(|(method, params)| (method, params.build()))
+            .collect();
+        let results = self.client.batch_request_raw(requests).await?;
+        let results = results
+            .into_iter()
+            .map(|res| {
+                let res = res?;
+                let val = serde_json::from_str(res.get())?;
+                Ok(val)
+            })
+            .collect();
+        Ok(results)
+    }
+
     /// Subscribe to an RPC endpoint, providing the parameters and the method to call to
     /// unsubscribe from it again.
     ///
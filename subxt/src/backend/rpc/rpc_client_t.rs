@@ -51,6 +51,27 @@ pub trait RpcClientT: Send + Sync + 'static {
         params: Option<Box<RawValue>>,
         unsub: &'a str,
     ) -> RawRpcFuture<'a, RawRpcSubscription>;
+
+    /// Make a batch of raw requests, returning the result of each request in the order
+    /// that they were given. The outer error is returned if the batch as a whole fails.
+    /// Implementations should expect that the params given for each request will either
+    /// be `None`, or be an already-serialized JSON array of parameters.
+    ///
+    /// The default implementation makes each request individually. Implementations which
+    /// are able to send JSON-RPC batch requests should override this.
+    ///
+    /// Prefer to use the interface provided on [`super::RpcClient`] where possible.
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        Box::pin(async move {
+            let requests = requests
+                .into_iter()
+                .map(|(method, params)| self.request_raw(method, params));
+            Ok(futures::future::join_all(requests).await)
+        })
+    }
 }
 
 /// A boxed future that is returned from the [`RpcClientT`] methods.
@@ -81,6 +102,13 @@ impl<T: RpcClientT> RpcClientT for std::sync::Arc<T> {
     ) -> RawRpcFuture<'a, RawRpcSubscription> {
         (**self).subscribe_raw(sub, params, unsub)
     }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        (**self).batch_request_raw(requests)
+    }
 }
 
 impl<T: RpcClientT> RpcClientT for Box<T> {
@@ -100,4 +128,11 @@ impl<T: RpcClientT> RpcClientT for Box<T> {
     ) -> RawRpcFuture<'a, RawRpcSubscription> {
         (**self).subscribe_raw(sub, params, unsub)
     }
+
+    fn batch_request_raw<'a>(
+        &'a self,
+        requests: Vec<(&'a str, Option<Box<RawValue>>)>,
+    ) -> RawRpcFuture<'a, Vec<Result<Box<RawValue>, RpcError>>> {
+        (**self).batch_request_raw(requests)
+    }
 }
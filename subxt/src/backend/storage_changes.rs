@@ -0,0 +1,136 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! A [`Backend::storage_fetch_changes`] implementation for backends which have no way
+//! to ask for storage changes directly, built on top of the other backend methods.
+
+use super::{Backend, StorageChanges, StreamOf, StreamOfResults};
+use crate::config::{Config, Header};
+use crate::error::{BlockError, Error};
+use futures::stream;
+use std::collections::HashMap;
+
+/// Fetch the values at the given keys in each block from `from` to `to` (inclusive) in which
+/// any of them changed, by comparing the values at each block.
+///
+/// The blocks in between are found by walking back from `to` via parent hashes, which happens
+/// before this returns; a header is fetched for every block in the range in doing so. The
+/// values in each block are then only fetched as the returned stream is polled, and the
+/// stream ends after the first error.
+pub(crate) async fn storage_changes_between<T, B>(
+    backend: B,
+    keys: Vec<Vec<u8>>,
+    from: T::Hash,
+    to: T::Hash,
+) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error>
+where
+    T: Config,
+    B: Backend<T> + Clone,
+{
+    let block_hashes = blocks_between(&backend, from, to).await?;
+
+    let state = ChangesState {
+        backend,
+        keys,
+        block_hashes: block_hashes.into_iter(),
+        last_values: HashMap::new(),
+    };
+
+    let changes = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        loop {
+            let block_hash = state.block_hashes.next()?;
+            match state.changes_at(block_hash).await {
+                Ok(changes) if changes.is_empty() => continue,
+                Ok(changes) => {
+                    let changes = StorageChanges {
+                        block_hash,
+                        changes,
+                    };
+                    return Some((Ok(changes), Some(state)));
+                }
+                Err(e) => return Some((Err(e), None)),
+            }
+        }
+    });
+
+    Ok(StreamOf::new(Box::pin(changes)))
+}
+
+/// The hashes of the blocks from `from` to `to` (inclusive), in ascending order.
+async fn blocks_between<T: Config, B: Backend<T>>(
+    backend: &B,
+    from: T::Hash,
+    to: T::Hash,
+) -> Result<Vec<T::Hash>, Error> {
+    let from_number: u64 = backend
+        .block_header(from)
+        .await?
+        .ok_or_else(|| Error::Block(BlockError::not_found(from)))?
+        .number()
+        .into();
+
+    let mut block_hashes = vec![to];
+    let mut block_hash = to;
+    while block_hash != from {
+        let header = backend
+            .block_header(block_hash)
+            .await?
+            .ok_or_else(|| Error::Block(BlockError::not_found(block_hash)))?;
+        if header.number().into() <= from_number {
+            return Err(Error::Other(format!(
+                "Block 0x{} is not an ancestor of block 0x{}",
+                hex::encode(from),
+                hex::encode(to)
+            )));
+        }
+
+        block_hash = header.parent_hash();
+        block_hashes.push(block_hash);
+    }
+    block_hashes.reverse();
+
+    Ok(block_hashes)
+}
+
+struct ChangesState<T: Config, B> {
+    backend: B,
+    keys: Vec<Vec<u8>>,
+    block_hashes: std::vec::IntoIter<T::Hash>,
+    last_values: HashMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<T: Config, B: Backend<T>> ChangesState<T, B> {
+    /// The keys whose values differ from the last block we looked at. For the first block,
+    /// this is every key.
+    async fn changes_at(
+        &mut self,
+        block_hash: T::Hash,
+    ) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>, Error> {
+        let mut values: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut responses = self
+            .backend
+            .storage_fetch_values(self.keys.clone(), block_hash)
+            .await?;
+        while let Some(response) = responses.next().await {
+            let response = response?;
+            values.insert(response.key, response.value);
+        }
+
+        let changes = self
+            .keys
+            .iter()
+            .filter_map(|key| {
+                let value = values.remove(key);
+                if self.last_values.get(key) == Some(&value) {
+                    return None;
+                }
+                self.last_values.insert(key.clone(), value.clone());
+                Some((key.clone(), value))
+            })
+            .collect();
+
+        Ok(changes)
+    }
+}
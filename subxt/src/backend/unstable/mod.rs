@@ -11,7 +11,7 @@
 //! Everything in this module is **unstable**, meaning that it could change without
 //! warning at any time.
 
-mod follow_stream;
+pub(crate) mod follow_stream;
 mod follow_stream_driver;
 mod follow_stream_unpin;
 mod storage_items;
@@ -23,16 +23,19 @@ use self::rpc_methods::{
     FollowEvent, MethodResponse, RuntimeEvent, StorageQuery, StorageQueryType, StorageResultType,
 };
 use crate::backend::{
-    rpc::RpcClient, utils::retry, Backend, BlockRef, BlockRefT, RuntimeVersion, StorageResponse,
-    StreamOf, StreamOfResults, TransactionStatus,
+    block_tree::BlockTree, legacy::LegacyRpcMethods, rpc::multi_rpc_client::is_endpoint_failure,
+    rpc::RpcClient, storage_changes::storage_changes_between, utils::retry, Backend, BlockRef,
+    BlockRefT, ChainEvent, RuntimeVersion, StorageChanges, StorageResponse, StreamOf,
+    StreamOfResults, TransactionStatus,
 };
 use crate::config::BlockHash;
 use crate::error::{Error, RpcError};
 use crate::Config;
 use async_trait::async_trait;
+use derive_where::derive_where;
 use follow_stream_driver::{FollowStreamDriver, FollowStreamDriverHandle};
 use futures::future::Either;
-use futures::{Stream, StreamExt};
+use futures::{FutureExt, Stream, StreamExt};
 use std::collections::HashMap;
 use std::task::Poll;
 use storage_items::StorageItems;
@@ -81,7 +84,8 @@ impl<T: Config> UnstableBackendBuilder<T> {
         client: impl Into<RpcClient>,
     ) -> (UnstableBackend<T>, UnstableBackendDriver<T>) {
         // Construct the underlying follow_stream layers:
-        let rpc_methods = UnstableRpcMethods::new(client.into());
+        let client = client.into();
+        let rpc_methods = UnstableRpcMethods::new(client.clone());
         let follow_stream =
             follow_stream::FollowStream::<T::Hash>::from_methods(rpc_methods.clone());
         let follow_stream_unpin = follow_stream_unpin::FollowStreamUnpin::<T::Hash>::from_methods(
@@ -94,6 +98,7 @@ impl<T: Config> UnstableBackendBuilder<T> {
         // Wrap these into the backend and driver that we'll expose.
         let backend = UnstableBackend {
             methods: rpc_methods,
+            legacy_methods: LegacyRpcMethods::new(client),
             follow_handle: follow_stream_driver.handle(),
         };
         let driver = UnstableBackendDriver {
@@ -122,10 +127,12 @@ impl<T: Config> Stream for UnstableBackendDriver<T> {
 }
 
 /// The unstable backend.
-#[derive(Debug, Clone)]
+#[derive_where(Debug, Clone)]
 pub struct UnstableBackend<T: Config> {
     // RPC methods we'll want to call:
     methods: UnstableRpcMethods<T>,
+    // Legacy RPC methods, used for the few things chainHead can't provide:
+    legacy_methods: LegacyRpcMethods<T>,
     // A handle to the chainHead_follow subscription:
     follow_handle: FollowStreamDriverHandle<T::Hash>,
 }
@@ -193,12 +200,11 @@ impl<Hash: BlockHash + 'static> From<follow_stream_unpin::BlockRef<Hash>> for Bl
     }
 }
 
-impl<T: Config> super::sealed::Sealed for UnstableBackend<T> {}
-
-#[async_trait]
-impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
-    async fn storage_fetch_values(
+impl<T: Config + Send + Sync + 'static> UnstableBackend<T> {
+    // Fetch values from the main trie, or the child trie with the given key.
+    async fn fetch_values(
         &self,
+        child_key: Option<&[u8]>,
         keys: Vec<Vec<u8>>,
         at: T::Hash,
     ) -> Result<StreamOfResults<StorageResponse>, Error> {
@@ -208,9 +214,14 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
                 query_type: StorageQueryType::Value,
             });
 
-            let storage_items =
-                StorageItems::from_methods(queries, at, &self.follow_handle, self.methods.clone())
-                    .await?;
+            let storage_items = StorageItems::from_methods(
+                queries,
+                child_key,
+                at,
+                &self.follow_handle,
+                self.methods.clone(),
+            )
+            .await?;
 
             let stream = storage_items.filter_map(|val| async move {
                 let val = match val {
@@ -232,8 +243,10 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
         .await
     }
 
-    async fn storage_fetch_descendant_keys(
+    // Fetch keys underneath the given key from the main trie, or the child trie with the given key.
+    async fn fetch_descendant_keys(
         &self,
+        child_key: Option<&[u8]>,
         key: Vec<u8>,
         at: T::Hash,
     ) -> Result<StreamOfResults<Vec<u8>>, Error> {
@@ -246,6 +259,7 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
 
             let storage_items = StorageItems::from_methods(
                 std::iter::once(query),
+                child_key,
                 at,
                 &self.follow_handle,
                 self.methods.clone(),
@@ -258,8 +272,10 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
         .await
     }
 
-    async fn storage_fetch_descendant_values(
+    // Fetch values underneath the given key from the main trie, or the child trie with the given key.
+    async fn fetch_descendant_values(
         &self,
+        child_key: Option<&[u8]>,
         key: Vec<u8>,
         at: T::Hash,
     ) -> Result<StreamOfResults<StorageResponse>, Error> {
@@ -271,6 +287,7 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
 
             let storage_items = StorageItems::from_methods(
                 std::iter::once(query),
+                child_key,
                 at,
                 &self.follow_handle,
                 self.methods.clone(),
@@ -296,11 +313,113 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
         })
         .await
     }
+}
+
+impl<T: Config> super::sealed::Sealed for UnstableBackend<T> {}
+
+#[async_trait]
+impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
+    async fn storage_fetch_values(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_values(None, keys, at).await
+    }
+
+    async fn storage_fetch_descendant_keys(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        self.fetch_descendant_keys(None, key, at).await
+    }
+
+    async fn storage_fetch_descendant_values(
+        &self,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_descendant_values(None, key, at).await
+    }
+
+    async fn storage_fetch_read_proof(
+        &self,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        // The chainHead methods only expose the merkle values of storage entries,
+        // which are not enough on their own to verify anything against the state root.
+        // So, we ask for a proof via the legacy methods where the node still exposes them.
+        retry(|| async {
+            let keys = keys.iter().map(|key| &**key);
+            match self
+                .legacy_methods
+                .state_get_read_proof(keys, Some(at))
+                .await
+            {
+                Ok(read_proof) => Ok(read_proof.proof.into_iter().map(|node| node.0).collect()),
+                Err(Error::Rpc(e)) if !is_endpoint_failure(&e) => Err(Error::ReadProofsUnsupported),
+                Err(e) => Err(e),
+            }
+        })
+        .await
+    }
+
+    async fn storage_fetch_changes(
+        &self,
+        keys: Vec<Vec<u8>>,
+        from: T::Hash,
+        to: T::Hash,
+    ) -> Result<StreamOfResults<StorageChanges<T::Hash>>, Error> {
+        // There is no chainHead equivalent to `state_queryStorage`, so we walk back from the `to`
+        // block to the `from` block via parent hashes, and then compare the values at each block.
+        // This only works for blocks which the chainHead subscription is able to access; blocks
+        // which are not pinned (ie older than the latest finalized block when the subscription
+        // started) cannot be fetched, and so this will return an error for them.
+        storage_changes_between(self.clone(), keys, from, to).await
+    }
+
+    async fn storage_fetch_child_values(
+        &self,
+        child_key: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_values(Some(&child_key), keys, at).await
+    }
+
+    async fn storage_fetch_child_descendant_keys(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<Vec<u8>>, Error> {
+        self.fetch_descendant_keys(Some(&child_key), key, at).await
+    }
+
+    async fn storage_fetch_child_descendant_values(
+        &self,
+        child_key: Vec<u8>,
+        key: Vec<u8>,
+        at: T::Hash,
+    ) -> Result<StreamOfResults<StorageResponse>, Error> {
+        self.fetch_descendant_values(Some(&child_key), key, at)
+            .await
+    }
 
     async fn genesis_hash(&self) -> Result<T::Hash, Error> {
         retry(|| self.methods.chainspec_v1_genesis_hash()).await
     }
 
+    async fn block_hash_at_height(&self, _height: u64) -> Result<Option<T::Hash>, Error> {
+        // The chainHead methods only give us access to the recent blocks that they
+        // report, and offer no way to look up older blocks by their height.
+        Err(Error::Other(
+            "Looking up blocks by height is not available via the chainHead RPC methods".into(),
+        ))
+    }
+
     async fn block_header(&self, at: T::Hash) -> Result<Option<T::Header>, Error> {
         retry(|| async {
             let sub_id = get_subscription_id(&self.follow_handle).await?;
@@ -360,6 +479,27 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
         next_ref.ok_or_else(|| RpcError::SubscriptionDropped.into())
     }
 
+    async fn latest_best_block_ref(&self) -> Result<BlockRef<T::Hash>, Error> {
+        // New subscriptions are handed the current finalized block followed by any changes to
+        // the best block since, which are all ready straight away, so the last of these is the
+        // current best block.
+        let mut events = self.follow_handle.subscribe().events();
+        let mut best_ref: Option<BlockRef<T::Hash>> = None;
+        while let Some(ev) = events.next().await {
+            if let FollowEvent::Initialized(init) = ev {
+                best_ref = init.finalized_block_hashes.last().map(|b| b.clone().into());
+                break;
+            }
+        }
+        while let Some(Some(ev)) = events.next().now_or_never() {
+            if let FollowEvent::BestBlockChanged(ev) = ev {
+                best_ref = Some(ev.best_block_hash.into());
+            }
+        }
+
+        best_ref.ok_or_else(|| RpcError::SubscriptionDropped.into())
+    }
+
     async fn current_runtime_version(&self) -> Result<RuntimeVersion, Error> {
         // Just start a stream of version infos, and return the first value we get from it.
         let runtime_version = self.stream_runtime_version().await?.next().await;
@@ -492,6 +632,105 @@ impl<T: Config + Send + Sync + 'static> Backend<T> for UnstableBackend<T> {
         .await
     }
 
+    async fn stream_chain_events(&self) -> Result<StreamOfResults<ChainEvent<T::Hash>>, Error> {
+        // The follow events tell us about new and finalized blocks directly, but only give
+        // the new best block, so we track block parents to work out how the best chain changed.
+        let mut tree: Option<BlockTree<T::Hash>> = None;
+
+        let events = self.follow_handle.subscribe().events().flat_map(move |ev| {
+            let mut events = Vec::new();
+            match (ev, &mut tree) {
+                (FollowEvent::Initialized(init), None) => {
+                    let Some(finalized) = init.finalized_block_hashes.last() else {
+                        return futures::stream::iter(events);
+                    };
+                    tree = Some(BlockTree::new(finalized.hash()));
+                    events.push(Ok(ChainEvent::Finalized {
+                        hashes: init
+                            .finalized_block_hashes
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        pruned: Vec::new(),
+                    }));
+                }
+                (FollowEvent::Initialized(init), Some(tree)) => {
+                    // The follow subscription was restarted, so catch up with any blocks
+                    // that were finalized in the meantime.
+                    let hashes: Vec<T::Hash> = init
+                        .finalized_block_hashes
+                        .iter()
+                        .map(|b| b.hash())
+                        .collect();
+                    let Some(pos) = hashes.iter().position(|h| *h == tree.finalized()) else {
+                        events.push(Err(RpcError::DisconnectedWillReconnect(
+                            "Missed at least one block when the connection was lost".to_owned(),
+                        )
+                        .into()));
+                        if let Some(&finalized) = hashes.last() {
+                            *tree = BlockTree::new(finalized);
+                            events.push(Ok(ChainEvent::Finalized {
+                                hashes: init
+                                    .finalized_block_hashes
+                                    .into_iter()
+                                    .map(Into::into)
+                                    .collect(),
+                                pruned: Vec::new(),
+                            }));
+                        }
+                        return futures::stream::iter(events);
+                    };
+
+                    for (block_ref, parent_hash) in init.finalized_block_hashes[pos + 1..]
+                        .iter()
+                        .zip(&hashes[pos..])
+                    {
+                        if !tree.contains(block_ref.hash()) {
+                            tree.add_block(block_ref.hash(), *parent_hash);
+                            events.push(Ok(ChainEvent::NewBlock {
+                                block: block_ref.clone().into(),
+                                parent_hash: *parent_hash,
+                            }));
+                        }
+                    }
+                    if let Some(&finalized) = hashes.last() {
+                        events.extend(tree.finalize_to(finalized).into_iter().map(Ok));
+                    }
+                }
+                (FollowEvent::NewBlock(ev), Some(tree)) if !tree.contains(ev.block_hash.hash()) => {
+                    let parent_hash = ev.parent_block_hash.hash();
+                    tree.add_block(ev.block_hash.hash(), parent_hash);
+                    events.push(Ok(ChainEvent::NewBlock {
+                        block: ev.block_hash.into(),
+                        parent_hash,
+                    }));
+                }
+                (FollowEvent::BestBlockChanged(ev), Some(tree)) => {
+                    events.extend(tree.set_best(ev.best_block_hash.hash()).map(Ok));
+                }
+                (FollowEvent::Finalized(ev), Some(tree)) => {
+                    let hashes: Vec<T::Hash> =
+                        ev.finalized_block_hashes.iter().map(|b| b.hash()).collect();
+                    let pruned: Vec<T::Hash> =
+                        ev.pruned_block_hashes.iter().map(|b| b.hash()).collect();
+                    tree.finalize(&hashes, &pruned);
+                    events.push(Ok(ChainEvent::Finalized {
+                        hashes: ev
+                            .finalized_block_hashes
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        pruned,
+                    }));
+                }
+                _ => {}
+            }
+            futures::stream::iter(events)
+        });
+
+        Ok(StreamOf(Box::pin(events)))
+    }
+
     async fn submit_transaction(
         &self,
         extrinsic: &[u8],
@@ -716,3 +955,135 @@ async fn get_subscription_id<Hash: BlockHash>(
 
     Ok(sub_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::rpc::mock_rpc_client::MockRpcClient;
+    use crate::utils::H256;
+    use crate::SubstrateConfig;
+    use futures::channel::mpsc;
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+
+    const CHILD_KEY: &[u8] = b"crowdloan";
+    // The keys in the child trie. The value of each is the key followed by 0x00.
+    const CHILD_KEYS: [&str; 3] = ["0x0101", "0x0102", "0x0201"];
+
+    // A backend which answers `chainHead_v1_storage` requests against the `CHILD_KEY`
+    // child trie by handing back the results on the follow subscription.
+    fn backend() -> UnstableBackend<SubstrateConfig> {
+        let (events_tx, events_rx) = mpsc::unbounded();
+        events_tx
+            .unbounded_send(Ok(json!({
+                "event": "initialized",
+                "finalizedBlockHashes": [H256::zero()],
+            })))
+            .unwrap();
+        let events_rx = Mutex::new(Some(events_rx));
+
+        let client = MockRpcClient::builder()
+            .subscription("chainHead_v1_follow", move |_| {
+                Ok(events_rx.lock().unwrap().take().expect("followed once"))
+            })
+            .method("chainHead_v1_unpin", |_| Ok(Value::Null))
+            .method("chainHead_v1_storage", move |params| {
+                assert_eq!(params[3], json!(format!("0x{}", hex::encode(CHILD_KEY))));
+
+                let items: Vec<Value> = params[2]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .flat_map(|query| {
+                        let key = query["key"].as_str().unwrap().to_owned();
+                        let query_type = query["type"].as_str().unwrap().to_owned();
+                        CHILD_KEYS
+                            .into_iter()
+                            .filter_map(move |k| match query_type.as_str() {
+                                "value" if k == key => {
+                                    Some(json!({ "key": k, "value": format!("{k}00") }))
+                                }
+                                "descendantsValues" if k.starts_with(&key) => {
+                                    Some(json!({ "key": k, "value": format!("{k}00") }))
+                                }
+                                "descendantsHashes" if k.starts_with(&key) => {
+                                    Some(json!({ "key": k, "hash": "0x00" }))
+                                }
+                                _ => None,
+                            })
+                    })
+                    .collect();
+
+                events_tx
+                    .unbounded_send(Ok(json!({
+                        "event": "operationStorageItems",
+                        "operationId": "op",
+                        "items": items,
+                    })))
+                    .unwrap();
+                events_tx
+                    .unbounded_send(Ok(json!({
+                        "event": "operationStorageDone",
+                        "operationId": "op",
+                    })))
+                    .unwrap();
+                Ok(json!({ "result": "started", "operationId": "op" }))
+            })
+            .build();
+
+        let (backend, mut driver) = UnstableBackend::builder().build(client);
+        tokio::spawn(async move { while driver.next().await.is_some() {} });
+        backend
+    }
+
+    async fn collect_values(values: StreamOfResults<StorageResponse>) -> Vec<(Vec<u8>, Vec<u8>)> {
+        values
+            .map(|res| {
+                let res = res.unwrap();
+                (res.key, res.value)
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn child_storage_values_are_fetched() {
+        let backend = backend();
+
+        let values = backend
+            .storage_fetch_child_values(
+                CHILD_KEY.to_vec(),
+                vec![vec![1, 2], vec![3, 3]],
+                H256::zero(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            collect_values(values).await,
+            vec![(vec![1, 2], vec![1, 2, 0])]
+        );
+    }
+
+    #[tokio::test]
+    async fn child_storage_can_be_iterated() {
+        let backend = backend();
+
+        let keys: Vec<Vec<u8>> = backend
+            .storage_fetch_child_descendant_keys(CHILD_KEY.to_vec(), vec![1], H256::zero())
+            .await
+            .unwrap()
+            .map(|key| key.unwrap())
+            .collect()
+            .await;
+        assert_eq!(keys, vec![vec![1, 1], vec![1, 2]]);
+
+        let values = backend
+            .storage_fetch_child_descendant_values(CHILD_KEY.to_vec(), vec![1], H256::zero())
+            .await
+            .unwrap();
+        assert_eq!(
+            collect_values(values).await,
+            vec![(vec![1, 1], vec![1, 1, 0]), (vec![1, 2], vec![1, 2, 0])]
+        );
+    }
+}
@@ -29,10 +29,11 @@ pub struct StorageItems<T: Config> {
 
 impl<T: Config> StorageItems<T> {
     // Subscribe to follow events, and return a stream of storage results
-    // given some storage queries. The stream will automatically resume as
-    // needed, and stop when done.
+    // given some storage queries, optionally against the child trie with the
+    // given key. The stream will automatically resume as needed, and stop when done.
     pub async fn from_methods(
         queries: impl Iterator<Item = StorageQuery<&[u8]>>,
+        child_key: Option<&[u8]>,
         at: T::Hash,
         follow_handle: &FollowStreamDriverHandle<T::Hash>,
         methods: UnstableRpcMethods<T>,
@@ -42,7 +43,7 @@ impl<T: Config> StorageItems<T> {
         // Subscribe to events and make the initial request to get an operation ID.
         let follow_events = follow_handle.subscribe().events();
         let status = methods
-            .chainhead_v1_storage(&sub_id, at, queries, None)
+            .chainhead_v1_storage(&sub_id, at, queries, child_key)
             .await?;
         let operation_id: Arc<str> = match status {
             MethodResponse::LimitReached => {
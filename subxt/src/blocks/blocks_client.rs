@@ -4,19 +4,23 @@
 
 use super::Block;
 use crate::{
-    backend::{BlockRef, StreamOfResults},
+    backend::{BlockRef, ChainEvent, StreamOfResults},
     client::OnlineClientT,
-    config::Config,
+    config::{Config, Header},
     error::{BlockError, Error},
     utils::PhantomDataSendSync,
 };
 use derive_where::derive_where;
-use futures::StreamExt;
+use futures::{future, stream, StreamExt};
 use std::future::Future;
+use std::ops::Range;
 
 type BlockStream<T> = StreamOfResults<T>;
 type BlockStreamRes<T> = Result<BlockStream<T>, Error>;
 
+/// The number of blocks that [`BlocksClient::range()`] will fetch at once.
+const DEFAULT_RANGE_CONCURRENCY: usize = 16;
+
 /// A client for working with blocks.
 #[derive_where(Clone; Client)]
 pub struct BlocksClient<T, Client> {
@@ -53,6 +57,68 @@ where
         self.at_or_latest(Some(block_ref.into()))
     }
 
+    /// Obtain block details of the block at the given height on the canonical chain.
+    ///
+    /// Not every backend supports looking up blocks by their height; in this case
+    /// an error will be returned.
+    ///
+    /// # Warning
+    ///
+    /// This call only supports blocks produced since the most recent
+    /// runtime upgrade. You can attempt to retrieve older blocks,
+    /// but may run into errors attempting to work with them.
+    pub fn at_number(
+        &self,
+        number: u64,
+    ) -> impl Future<Output = Result<Block<T, Client>, Error>> + Send + 'static {
+        let client = self.client.clone();
+        let this = self.clone();
+        async move {
+            let hash = match client.backend().block_hash_at_height(number).await? {
+                Some(hash) => hash,
+                None => return Err(BlockError::NotFoundAtHeight(number).into()),
+            };
+            this.at_or_latest(Some(BlockRef::from_hash(hash))).await
+        }
+    }
+
+    /// Obtain block details for each of the blocks in the given range of heights, in order.
+    /// Up to 16 blocks are fetched at once; see [`Self::range_with_concurrency()`] to
+    /// configure this.
+    ///
+    /// This is useful for backfilling historic blocks. The stream will hand back an error
+    /// for any block which cannot be fetched, and then carry on with the next block.
+    pub fn range(&self, range: Range<u64>) -> BlockStream<Block<T, Client>>
+    where
+        Client: Send + Sync + 'static,
+    {
+        self.range_with_concurrency(range, DEFAULT_RANGE_CONCURRENCY)
+    }
+
+    /// Like [`Self::range()`], but fetching up to `max_concurrency` blocks at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrency` is 0.
+    pub fn range_with_concurrency(
+        &self,
+        range: Range<u64>,
+        max_concurrency: usize,
+    ) -> BlockStream<Block<T, Client>>
+    where
+        Client: Send + Sync + 'static,
+    {
+        assert!(
+            max_concurrency > 0,
+            "max_concurrency must be greater than 0"
+        );
+        let this = self.clone();
+        let blocks = stream::iter(range)
+            .map(move |number| this.at_number(number))
+            .buffered(max_concurrency);
+        StreamOfResults::new(Box::pin(blocks))
+    }
+
     /// Obtain block details of the latest block hash.
     pub fn at_latest(
         &self,
@@ -130,6 +196,93 @@ where
             BlockStreamRes::Ok(stream)
         })
     }
+
+    /// Subscribe to [`ChainEvent`]s, which describe the blocks being imported and finalized,
+    /// and how the best chain changes as this happens.
+    ///
+    /// Unlike [`Self::subscribe_best()`], this makes it possible to tell when the best chain
+    /// switches to a different fork, so that any state built on the blocks which were retracted
+    /// can be rolled back. Use [`Self::at()`] to obtain the details of any new blocks.
+    pub fn subscribe_chain_events(
+        &self,
+    ) -> impl Future<Output = Result<BlockStream<ChainEvent<T::Hash>>, Error>> + Send + 'static
+    {
+        let client = self.client.clone();
+        async move { client.backend().stream_chain_events().await }
+    }
+
+    /// Subscribe to finalized blocks, starting from the finalized block at the given height.
+    ///
+    /// Historic blocks are fetched using [`Self::at_number()`] until we catch up with the
+    /// finalized blocks being reported by the node, and then those are handed back. Each
+    /// block is handed back exactly once and in order, and any gaps in the blocks being
+    /// reported are filled in. If the starting height is ahead of the latest finalized
+    /// block, then blocks are handed back once it's reached.
+    ///
+    /// The stream ends after the first error, so that no blocks are silently skipped. To resume
+    /// after the stream ends or is dropped, subscribe again from the height after the last
+    /// block that was handled.
+    ///
+    /// # Warning
+    ///
+    /// Backfilling relies on looking up blocks by their height, which the
+    /// [`crate::backend::unstable::UnstableBackend`] does not support. Using it, this will
+    /// error if any blocks before the first finalized block reported need to be fetched.
+    pub fn subscribe_finalized_from(
+        &self,
+        number: u64,
+    ) -> impl Future<Output = Result<BlockStream<Block<T, Client>>, Error>> + Send + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        let this = self.clone();
+        async move {
+            // Subscribe before backfilling, so that no blocks finalized in the meantime are missed.
+            let headers = this
+                .client
+                .backend()
+                .stream_finalized_block_headers()
+                .await?;
+
+            // The height of the next block that we should hand back.
+            let mut next_number = number;
+            let blocks = headers
+                .map(move |header_and_ref| {
+                    let (header, block_ref) = match header_and_ref {
+                        Ok(header_and_ref) => header_and_ref,
+                        Err(e) => {
+                            return StreamOfResults::new(Box::pin(stream::once(async { Err(e) })))
+                        }
+                    };
+
+                    let header_number: u64 = header.number().into();
+                    if header_number < next_number {
+                        // Already handed back, or before the starting height.
+                        return StreamOfResults::new(Box::pin(stream::empty()));
+                    }
+
+                    // Fill in any blocks between the last one handed back and this one.
+                    let missed_blocks = this.range(next_number..header_number);
+                    let block = Block::new(header, block_ref, this.client.clone());
+                    next_number = header_number + 1;
+
+                    StreamOfResults::new(Box::pin(
+                        missed_blocks.chain(stream::once(async { Ok(block) })),
+                    ))
+                })
+                .flatten();
+
+            // Hand back the first error, and then end the stream.
+            let mut errored = false;
+            let blocks = blocks.take_while(move |block| {
+                let keep_going = !errored;
+                errored = block.is_err();
+                future::ready(keep_going)
+            });
+
+            BlockStreamRes::Ok(StreamOfResults::new(Box::pin(blocks)))
+        }
+    }
 }
 
 /// Take a promise that will return a subscription to some block headers,
@@ -156,3 +309,60 @@ where
     });
     BlockStreamRes::Ok(StreamOfResults::new(Box::pin(sub)))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::config::Header;
+    use crate::error::{BlockError, Error};
+    use futures::StreamExt;
+
+    // A backend with blocks 0 to 5 (missing any heights given), whose block
+    // subscriptions start at block 3.
+    fn backend(missing: &[u32]) -> MockBackend<crate::SubstrateConfig> {
+        let mut builder = MockBackend::test_builder().stream_blocks_from(3);
+        let mut parent = test_utils::header(0, Default::default()).hash();
+        for number in 1..=5 {
+            let header = test_utils::header(number, parent);
+            parent = header.hash();
+            if !missing.contains(&number) {
+                builder = builder.block(header, vec![]);
+            }
+        }
+        builder.build()
+    }
+
+    #[tokio::test]
+    async fn finalized_blocks_are_backfilled_once_each() {
+        let api = test_utils::client(backend(&[])).await;
+
+        let numbers: Vec<u32> = api
+            .blocks()
+            .subscribe_finalized_from(1)
+            .await
+            .unwrap()
+            .map(|block| block.unwrap().number())
+            .collect()
+            .await;
+        assert_eq!(numbers, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test]
+    async fn finalized_blocks_end_after_backfill_error() {
+        let api = test_utils::client(backend(&[2])).await;
+
+        let blocks: Vec<_> = api
+            .blocks()
+            .subscribe_finalized_from(1)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].as_ref().unwrap().number(), 1);
+        assert!(matches!(
+            blocks[1],
+            Err(Error::Block(BlockError::NotFoundAtHeight(2)))
+        ));
+    }
+}
@@ -164,6 +164,21 @@ where
         self.inner.is_signed()
     }
 
+    /// See [`subxt_core::blocks::ExtrinsicDetails::is_general()`].
+    pub fn is_general(&self) -> bool {
+        self.inner.is_general()
+    }
+
+    /// See [`subxt_core::blocks::ExtrinsicDetails::version()`].
+    pub fn version(&self) -> u8 {
+        self.inner.version()
+    }
+
+    /// See [`subxt_core::blocks::ExtrinsicDetails::transaction_extension_version()`].
+    pub fn transaction_extension_version(&self) -> Option<u8> {
+        self.inner.transaction_extension_version()
+    }
+
     /// See [`subxt_core::blocks::ExtrinsicDetails::index()`].
     pub fn index(&self) -> u32 {
         self.inner.index()
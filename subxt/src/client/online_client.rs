@@ -13,6 +13,7 @@ use crate::{
     runtime_api::RuntimeApiClient,
     storage::StorageClient,
     tx::TxClient,
+    view_functions::ViewFunctionsClient,
     Config, Metadata,
 };
 use derive_where::derive_where;
@@ -349,6 +350,11 @@ impl<T: Config> OnlineClient<T> {
     pub fn runtime_api(&self) -> RuntimeApiClient<T, Self> {
         <Self as OfflineClientT<T>>::runtime_api(self)
     }
+
+    /// Work with pallet view functions.
+    pub fn view_functions(&self) -> ViewFunctionsClient<T, Self> {
+        <Self as OfflineClientT<T>>::view_functions(self)
+    }
 }
 
 impl<T: Config> OfflineClientT<T> for OnlineClient<T> {
@@ -21,7 +21,9 @@ pub use dispatch_error::{
 pub use crate::metadata::Metadata;
 pub use scale_decode::Error as DecodeError;
 pub use scale_encode::Error as EncodeError;
-pub use subxt_core::error::{ExtrinsicParamsError, MetadataError, StorageAddressError};
+pub use subxt_core::error::{
+    ExtrinsicParamsError, MetadataError, StorageAddressError, StorageProofError, ViewFunctionError,
+};
 pub use subxt_metadata::TryFromError as MetadataTryFromError;
 
 /// The underlying error enum, generic over the type held by the `Runtime`
@@ -69,6 +71,19 @@ pub enum Error {
     /// An error encoding a storage address.
     #[error("Error encoding storage address: {0}")]
     StorageAddress(#[from] StorageAddressError),
+    /// An error verifying a storage proof.
+    #[error("Error verifying storage proof: {0}")]
+    StorageProof(#[from] StorageProofError),
+    /// An error returned from a pallet view function call.
+    #[error("View function error: {0}")]
+    ViewFunction(#[from] ViewFunctionError),
+    /// A v5 transaction was signed with an address. These must be signed with an
+    /// account ID instead.
+    #[error("v5 transactions must be signed with an account ID rather than an address")]
+    SignedWithAddress,
+    /// The backend in use is unable to fetch storage read proofs.
+    #[error("Storage read proofs are not available via the backend in use")]
+    ReadProofsUnsupported,
     /// The bytes representing an error that we were unable to decode.
     #[error("An error occurred but it could not be decoded: {0:?}")]
     Unknown(Vec<u8>),
@@ -88,10 +103,13 @@ impl From<CoreError> for Error {
             CoreError::Codec(e) => Error::Codec(e),
             CoreError::Metadata(e) => Error::Metadata(e),
             CoreError::StorageAddress(e) => Error::StorageAddress(e),
+            CoreError::StorageProof(e) => Error::StorageProof(e),
             CoreError::Decode(e) => Error::Decode(e),
             CoreError::Encode(e) => Error::Encode(e),
             CoreError::ExtrinsicParams(e) => Error::ExtrinsicParams(e),
             CoreError::Block(e) => Error::Block(e.into()),
+            CoreError::ViewFunction(e) => Error::ViewFunction(e),
+            CoreError::SignedWithAddress => Error::SignedWithAddress,
         }
     }
 }
@@ -171,13 +189,19 @@ pub enum BlockError {
     /// An error containing the hash of the block that was not found.
     #[error("Could not find a block with hash {0} (perhaps it was on a non-finalized fork?)")]
     NotFound(String),
+    /// An error containing the height of the block that was not found.
+    #[error("Could not find a block at height {0}")]
+    NotFoundAtHeight(u64),
     /// Extrinsic type ID cannot be resolved with the provided metadata.
     #[error("Extrinsic type ID cannot be resolved with the provided metadata. Make sure this is a valid metadata")]
     MissingType,
     /// Unsupported signature.
-    #[error("Unsupported extrinsic version, only version 4 is supported currently")]
+    #[error("Unsupported extrinsic version, only versions 4 and 5 are supported currently")]
     /// The extrinsic has an unsupported version.
     UnsupportedVersion(u8),
+    /// The extrinsic uses a transaction extension version that is not in the metadata.
+    #[error("Unsupported transaction extension version {0}, which is not in the metadata")]
+    UnsupportedTransactionExtensionVersion(u8),
     /// Decoding error.
     #[error("Cannot decode extrinsic: {0}")]
     DecodingError(codec::Error),
@@ -188,6 +212,9 @@ impl From<CoreBlockError> for BlockError {
         match value {
             CoreBlockError::MissingType => BlockError::MissingType,
             CoreBlockError::UnsupportedVersion(n) => BlockError::UnsupportedVersion(n),
+            CoreBlockError::UnsupportedTransactionExtensionVersion(n) => {
+                BlockError::UnsupportedTransactionExtensionVersion(n)
+            }
             CoreBlockError::DecodingError(e) => BlockError::DecodingError(e),
         }
     }
@@ -53,6 +53,7 @@ pub mod runtime_api;
 pub mod storage;
 pub mod tx;
 pub mod utils;
+pub mod view_functions;
 
 /// This module provides a [`Config`] type, which is used to define various
 /// types that are important in order to speak to a particular chain.
@@ -63,8 +64,8 @@ pub mod config {
     pub use subxt_core::config::{
         polkadot, signed_extensions, substrate, BlockHash, Config, DefaultExtrinsicParams,
         DefaultExtrinsicParamsBuilder, ExtrinsicParams, ExtrinsicParamsEncoder, Hasher, Header,
-        PolkadotConfig, PolkadotExtrinsicParams, RefineParams, RefineParamsData, SignedExtension,
-        SubstrateConfig, SubstrateExtrinsicParams,
+        ManagedNonce, NonceManager, PolkadotConfig, PolkadotExtrinsicParams, RefineParams,
+        RefineParamsData, SignedExtension, SubstrateConfig, SubstrateExtrinsicParams,
     };
     pub use subxt_core::error::ExtrinsicParamsError;
 }
@@ -79,7 +80,8 @@ pub mod metadata {
 /// Submit dynamic transactions.
 pub mod dynamic {
     pub use subxt_core::dynamic::{
-        constant, runtime_api_call, storage, tx, At, DecodedValue, DecodedValueThunk, Value,
+        constant, runtime_api_call, storage, tx, view_function, At, DecodedValue,
+        DecodedValueThunk, Value,
     };
 }
 
@@ -24,6 +24,12 @@ macro_rules! cfg_unstable_light_client {
 	};
 }
 
+macro_rules! cfg_native {
+	($($item:item)*) => {
+		crate::macros::cfg_feature!("native", $($item)*);
+	};
+}
+
 macro_rules! cfg_jsonrpsee {
 	($($item:item)*) => {
 		crate::macros::cfg_feature!("jsonrpsee", $($item)*);
@@ -63,9 +69,20 @@ macro_rules! cfg_reconnecting_rpc_client {
 	}
 }
 
+#[allow(unused)]
+macro_rules! cfg_http_rpc_client {
+	($($item:item)*) => {
+		$(
+			#[cfg(feature = "http-rpc-client")]
+			#[cfg_attr(docsrs, doc(cfg(feature = "http-rpc-client")))]
+			$item
+		)*
+	}
+}
+
 pub(crate) use {
-    cfg_feature, cfg_jsonrpsee, cfg_reconnecting_rpc_client, cfg_substrate_compat,
-    cfg_unstable_light_client,
+    cfg_feature, cfg_http_rpc_client, cfg_jsonrpsee, cfg_native, cfg_reconnecting_rpc_client,
+    cfg_substrate_compat, cfg_unstable_light_client,
 };
 
 // Only used by light-client.
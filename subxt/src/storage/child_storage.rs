@@ -0,0 +1,165 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    backend::{BlockRef, StorageResponse, StreamOfResults},
+    client::OnlineClientT,
+    error::Error,
+    Config,
+};
+use derive_where::derive_where;
+use std::{future::Future, marker::PhantomData};
+
+/// The prefix that the storage keys of default child tries are stored under in the main trie.
+const DEFAULT_CHILD_STORAGE_KEY_PREFIX: &[u8] = b":child_storage:default:";
+
+/// Information needed to identify a child trie. Child tries are used by pallets
+/// such as `contracts` and `crowdloan` to store data outside of the main storage trie.
+///
+/// Only default child tries are supported, which is currently the only kind of child trie.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChildInfo {
+    storage_key: Vec<u8>,
+}
+
+impl ChildInfo {
+    /// Identify a default child trie given its (unprefixed) storage key.
+    pub fn new_default(storage_key: impl Into<Vec<u8>>) -> Self {
+        ChildInfo {
+            storage_key: storage_key.into(),
+        }
+    }
+
+    /// Identify a default child trie given its prefixed storage key, which is the
+    /// key that the child trie root is stored under in the main trie. This returns
+    /// `None` if the key does not begin with the default child trie prefix.
+    pub fn from_prefixed_key(prefixed_key: &[u8]) -> Option<Self> {
+        prefixed_key
+            .strip_prefix(DEFAULT_CHILD_STORAGE_KEY_PREFIX)
+            .map(ChildInfo::new_default)
+    }
+
+    /// The (unprefixed) storage key of the child trie.
+    pub fn storage_key(&self) -> &[u8] {
+        &self.storage_key
+    }
+
+    /// The prefixed storage key of the child trie, which is the key that the
+    /// child trie root is stored under in the main trie.
+    pub fn prefixed_storage_key(&self) -> Vec<u8> {
+        let mut key = DEFAULT_CHILD_STORAGE_KEY_PREFIX.to_vec();
+        key.extend_from_slice(&self.storage_key);
+        key
+    }
+}
+
+/// Query the storage of some child trie.
+#[derive_where(Clone; Client)]
+pub struct ChildStorage<T: Config, Client> {
+    client: Client,
+    block_ref: BlockRef<T::Hash>,
+    child_info: ChildInfo,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Config, Client> ChildStorage<T, Client> {
+    /// Create a new [`ChildStorage`]
+    pub(crate) fn new(client: Client, block_ref: BlockRef<T::Hash>, child_info: ChildInfo) -> Self {
+        Self {
+            client,
+            block_ref,
+            child_info,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The child trie that this is accessing.
+    pub fn child_info(&self) -> &ChildInfo {
+        &self.child_info
+    }
+}
+
+impl<T, Client> ChildStorage<T, Client>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    /// Fetch the raw encoded value at the key given in the child trie.
+    pub fn fetch_raw(
+        &self,
+        key: impl Into<Vec<u8>>,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, Error>> + 'static {
+        let client = self.client.clone();
+        let child_key = self.child_info.storage_key.clone();
+        let key = key.into();
+        // Keep this alive until the call is complete:
+        let block_ref = self.block_ref.clone();
+        // Manual future so lifetime not tied to api.storage().
+        async move {
+            let data = client
+                .backend()
+                .storage_fetch_child_values(child_key, vec![key], block_ref.hash())
+                .await?
+                .next()
+                .await
+                .transpose()?
+                .map(|res| res.value);
+            Ok(data)
+        }
+    }
+
+    /// Stream all of the raw keys underneath the key given in the child trie.
+    /// Pass an empty key to stream every key in the child trie.
+    pub fn fetch_raw_keys(
+        &self,
+        key: impl Into<Vec<u8>>,
+    ) -> impl Future<Output = Result<StreamOfResults<Vec<u8>>, Error>> + 'static {
+        let client = self.client.clone();
+        let child_key = self.child_info.storage_key.clone();
+        let block_hash = self.block_ref.hash();
+        let key = key.into();
+        // Manual future so lifetime not tied to api.storage().
+        async move {
+            let keys = client
+                .backend()
+                .storage_fetch_child_descendant_keys(child_key, key, block_hash)
+                .await?;
+            Ok(keys)
+        }
+    }
+
+    /// Stream all of the raw keys and values underneath the key given in the child trie.
+    /// Pass an empty key to stream every entry in the child trie.
+    pub fn iter_raw(
+        &self,
+        key: impl Into<Vec<u8>>,
+    ) -> impl Future<Output = Result<StreamOfResults<StorageResponse>, Error>> + 'static {
+        let client = self.client.clone();
+        let child_key = self.child_info.storage_key.clone();
+        let block_hash = self.block_ref.hash();
+        let key = key.into();
+        // Manual future so lifetime not tied to api.storage().
+        async move {
+            let entries = client
+                .backend()
+                .storage_fetch_child_descendant_values(child_key, key, block_hash)
+                .await?;
+            Ok(entries)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixed_storage_key_roundtrips() {
+        let child_info = ChildInfo::new_default(b"crowdloan".to_vec());
+        let prefixed = child_info.prefixed_storage_key();
+        assert_eq!(prefixed, b":child_storage:default:crowdloan".to_vec());
+        assert_eq!(ChildInfo::from_prefixed_key(&prefixed), Some(child_info));
+        assert_eq!(ChildInfo::from_prefixed_key(b"crowdloan"), None);
+    }
+}
@@ -4,11 +4,14 @@
 
 //! Types associated with accessing and working with storage items.
 
+mod child_storage;
 mod storage_client;
 mod storage_type;
 
-pub use storage_client::StorageClient;
+pub use child_storage::{ChildInfo, ChildStorage};
+pub use storage_client::{StorageChange, StorageClient};
 pub use storage_type::{Storage, StorageKeyValuePair};
 pub use subxt_core::storage::address::{
     dynamic, Address, DefaultAddress, DynamicAddress, StaticAddress, StaticStorageKey, StorageKey,
 };
+pub use subxt_core::storage::proof;
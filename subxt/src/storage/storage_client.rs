@@ -2,16 +2,19 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-use super::storage_type::Storage;
+use super::storage_type::{Storage, StreamOfResults};
 use crate::{
     backend::BlockRef,
     client::{OfflineClientT, OnlineClientT},
     error::Error,
+    metadata::DecodeWithMetadata,
     Config,
 };
 use derive_where::derive_where;
+use futures::StreamExt;
 use std::{future::Future, marker::PhantomData};
 use subxt_core::storage::address::Address;
+use subxt_core::utils::Yes;
 
 /// Query the runtime storage.
 #[derive_where(Clone; Client)]
@@ -83,4 +86,174 @@ where
             Ok(Storage::new(client, block_ref))
         }
     }
+
+    /// Stream the value at the given storage address for each block from `from_block` to
+    /// `to_block` (inclusive) in which it changed. The first item is the value at `from_block`,
+    /// and each subsequent item is a new value that was set in the given block. A value of `None`
+    /// means that no value exists at the address in that block.
+    ///
+    /// Values are decoded using the current metadata, so this may fail to decode values from
+    /// before a runtime upgrade which changed the storage entry.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use subxt::{ PolkadotConfig, OnlineClient };
+    ///
+    /// #[subxt::subxt(runtime_metadata_path = "../artifacts/polkadot_metadata_full.scale")]
+    /// pub mod polkadot {}
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let from_block = Default::default();
+    /// # let to_block = Default::default();
+    /// let api = OnlineClient::<PolkadotConfig>::new().await.unwrap();
+    ///
+    /// // Address to a storage entry we'd like to track.
+    /// let address = polkadot::storage().timestamp().now();
+    ///
+    /// // Stream each change to the value between the two blocks.
+    /// let mut changes = api
+    ///     .storage()
+    ///     .changes(&address, from_block, to_block)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// while let Some(change) = changes.next().await {
+    ///     let change = change.unwrap();
+    ///     println!("Value at block {:?}: {:?}", change.block_hash, change.value);
+    /// }
+    /// # }
+    /// ```
+    pub fn changes<Addr>(
+        &self,
+        address: &Addr,
+        from_block: T::Hash,
+        to_block: T::Hash,
+    ) -> impl Future<Output = Result<StreamOfResults<StorageChange<T::Hash, Addr>>, Error>> + 'static
+    where
+        Addr: Address<IsFetchable = Yes> + 'static,
+    {
+        let client = self.client.clone();
+        let metadata = client.metadata();
+        let lookup_bytes = subxt_core::storage::get_address_bytes(address, &metadata);
+        let return_type_id = subxt_core::storage::lookup_storage_entry_details(
+            address.pallet_name(),
+            address.entry_name(),
+            &metadata,
+        )
+        .map(|(_pallet, entry)| entry.entry_type().value_ty());
+        let validation = subxt_core::storage::validate(address, &metadata);
+
+        async move {
+            // Metadata validation checks whether the static address given
+            // is likely to actually correspond to a real storage entry or not.
+            // if not, it means static codegen doesn't line up with runtime
+            // metadata.
+            validation?;
+            let lookup_bytes = lookup_bytes?;
+            let return_type_id = return_type_id?;
+
+            let change_sets = client
+                .backend()
+                .storage_fetch_changes(vec![lookup_bytes], from_block, to_block)
+                .await?;
+
+            // Flatten the change sets into individual changes, and then decode each value.
+            let changes = change_sets
+                .flat_map(|change_set| {
+                    let changes: Vec<_> = match change_set {
+                        Err(e) => vec![Err(e)],
+                        Ok(change_set) => change_set
+                            .changes
+                            .into_iter()
+                            .map(|(key_bytes, value)| Ok((change_set.block_hash, key_bytes, value)))
+                            .collect(),
+                    };
+                    futures::stream::iter(changes)
+                })
+                .map(move |change| {
+                    let (block_hash, key_bytes, value) = change?;
+                    let value = value
+                        .map(|value| {
+                            Addr::Target::decode_with_metadata(
+                                &mut &*value,
+                                return_type_id,
+                                &metadata,
+                            )
+                        })
+                        .transpose()?;
+                    Ok(StorageChange {
+                        block_hash,
+                        key_bytes,
+                        value,
+                    })
+                });
+
+            Ok(StreamOfResults::new(Box::pin(changes)))
+        }
+    }
+}
+
+/// A change to the value at some storage address, as returned from [`StorageClient::changes()`].
+#[derive_where(Clone, Debug, PartialEq; Hash, Addr::Target)]
+pub struct StorageChange<Hash, Addr: Address> {
+    /// The hash of the block in which the value changed.
+    pub block_hash: Hash,
+    /// The bytes that make up the address of the storage entry.
+    pub key_bytes: Vec<u8>,
+    /// The new value of the storage entry, or `None` if no value exists there.
+    pub value: Option<Addr::Target>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::config::Header;
+    use codec::Encode;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn changes_are_found_by_walking_parent_hashes() {
+        let address = crate::dynamic::storage("System", "Number", ());
+        let key = subxt_core::storage::get_address_bytes(
+            &address,
+            &test_utils::metadata("polkadot_metadata_small"),
+        )
+        .unwrap();
+
+        let genesis = test_utils::header(0, Default::default());
+        let block1 = test_utils::header(1, genesis.hash());
+        let block2 = test_utils::header(2, block1.hash());
+        let block3 = test_utils::header(3, block2.hash());
+        let (hash1, hash2, hash3) = (block1.hash(), block2.hash(), block3.hash());
+
+        let backend = MockBackend::test_builder()
+            .block(block1, vec![])
+            .block(block2, vec![])
+            .block(block3, vec![])
+            .storage(hash1, key.clone(), 1u32.encode())
+            .storage(hash2, key.clone(), 1u32.encode())
+            .storage(hash3, key.clone(), 3u32.encode())
+            .build();
+        let api = test_utils::client(backend).await;
+
+        // Block 2 is skipped, because the value didn't change in it.
+        let changes: Vec<_> = api
+            .storage()
+            .changes(&address, hash1, hash3)
+            .await
+            .unwrap()
+            .map(|change| {
+                let change = change.unwrap();
+                let value = change.value.map(|v| v.as_type::<u32>().unwrap());
+                (change.block_hash, value)
+            })
+            .collect()
+            .await;
+        assert_eq!(changes, vec![(hash1, Some(1)), (hash3, Some(3))]);
+
+        // The blocks must be given in order.
+        assert!(api.storage().changes(&address, hash3, hash1).await.is_err());
+    }
 }
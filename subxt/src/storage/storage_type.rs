@@ -2,11 +2,13 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
+use super::child_storage::{ChildInfo, ChildStorage};
 use crate::{
     backend::{BackendExt, BlockRef},
     client::OnlineClientT,
-    error::{Error, MetadataError, StorageAddressError},
-    metadata::DecodeWithMetadata,
+    config::Header,
+    error::{BlockError, Error, MetadataError, StorageAddressError},
+    metadata::{DecodeWithMetadata, Metadata},
     Config,
 };
 use codec::Decode;
@@ -16,6 +18,10 @@ use std::{future::Future, marker::PhantomData};
 use subxt_core::storage::address::{Address, StorageHashers, StorageKey};
 use subxt_core::utils::Yes;
 
+/// The number of keys that we'll ask for a single read proof for when iterating
+/// over verified storage entries.
+const VERIFIED_ITER_PROOF_KEYS: usize = 256;
+
 /// This is returned from a couple of storage functions.
 pub use crate::backend::StreamOfResults;
 
@@ -43,6 +49,39 @@ where
     T: Config,
     Client: OnlineClientT<T>,
 {
+    /// Access the storage of the given child trie at this block.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use subxt::{ PolkadotConfig, OnlineClient };
+    /// use subxt::storage::ChildInfo;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let api = OnlineClient::<PolkadotConfig>::new().await.unwrap();
+    ///
+    /// // The child trie that we'd like to access.
+    /// let child_info = ChildInfo::new_default(b"some_child_trie".to_vec());
+    ///
+    /// // Fetch a raw value from it.
+    /// let value = api
+    ///     .storage()
+    ///     .at_latest()
+    ///     .await
+    ///     .unwrap()
+    ///     .child(child_info)
+    ///     .fetch_raw(b"some_key".to_vec())
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// println!("Value: {:?}", value);
+    /// # }
+    /// ```
+    pub fn child(&self, child_info: ChildInfo) -> ChildStorage<T, Client> {
+        ChildStorage::new(self.client.clone(), self.block_ref.clone(), child_info)
+    }
+
     /// Fetch the raw encoded value at the key given.
     pub fn fetch_raw(
         &self,
@@ -233,27 +272,145 @@ where
                         Ok(kv) => kv,
                         Err(e) => return Err(e),
                     };
-                    let value = Addr::Target::decode_with_metadata(
-                        &mut &*kv.value,
+                    decode_storage_key_value::<Addr>(
+                        kv.key,
+                        &kv.value,
+                        return_type_id,
+                        &hashers,
+                        &metadata,
+                    )
+                });
+
+            let s = StreamOfResults::new(Box::pin(s));
+            Ok(s)
+        }
+    }
+
+    /// Fetch a decoded value from storage at a given address, like [`Storage::fetch()`]. Rather
+    /// than trusting the node to return the correct value, this fetches a storage read proof and
+    /// verifies it against the state root of the block, returning the value that it proves exists.
+    ///
+    /// # Warning
+    ///
+    /// This relies on the backend being able to provide read proofs, and returns
+    /// [`Error::ReadProofsUnsupported`] if it can't. The chainHead RPC methods do not provide
+    /// them, so the [`crate::backend::unstable::UnstableBackend`] falls back to the legacy
+    /// `state_getReadProof` method, and this errors if the node doesn't expose it. The
+    /// [`crate::backend::archive::ArchiveBackend`] can't provide read proofs at all.
+    pub fn fetch_verified<'address, Addr>(
+        &self,
+        address: &'address Addr,
+    ) -> impl Future<Output = Result<Option<Addr::Target>, Error>> + 'address
+    where
+        Addr: Address<IsFetchable = Yes> + 'address,
+    {
+        let client = self.clone();
+        async move {
+            let metadata = client.client.metadata();
+
+            // Metadata validation checks whether the static address given
+            // is likely to actually correspond to a real storage entry or not.
+            // if not, it means static codegen doesn't line up with runtime
+            // metadata.
+            subxt_core::storage::validate(address, &metadata)?;
+
+            let state_root = client.verified_state_root().await?;
+            let lookup_bytes = subxt_core::storage::get_address_bytes(address, &metadata)?;
+            let proof = client
+                .client
+                .backend()
+                .storage_fetch_read_proof(vec![lookup_bytes], client.block_ref.hash())
+                .await?;
+
+            let val =
+                subxt_core::storage::proof::verify::<T, _>(address, state_root, &proof, &metadata)?;
+            Ok(val)
+        }
+    }
+
+    /// Returns an iterator of key value pairs, like [`Storage::iter()`]. Rather than trusting
+    /// the node to return the correct values, this fetches storage read proofs for each key and
+    /// verifies them against the state root of the block, returning the values that they prove exist.
+    ///
+    /// Note that while each value returned is verified, the node is still trusted to return
+    /// all of the keys underneath the given address. Keys are fetched as the stream is polled,
+    /// and a proof is fetched for each batch of them.
+    ///
+    /// # Warning
+    ///
+    /// This relies on the backend being able to provide read proofs, and returns
+    /// [`Error::ReadProofsUnsupported`] if it can't. The chainHead RPC methods do not provide
+    /// them, so the [`crate::backend::unstable::UnstableBackend`] falls back to the legacy
+    /// `state_getReadProof` method, and this errors if the node doesn't expose it. The
+    /// [`crate::backend::archive::ArchiveBackend`] can't provide read proofs at all.
+    pub fn iter_verified<Addr>(
+        &self,
+        address: Addr,
+    ) -> impl Future<Output = Result<StreamOfResults<StorageKeyValuePair<Addr>>, Error>> + 'static
+    where
+        Addr: Address<IsIterable = Yes> + 'static,
+        Addr::Keys: 'static + Sized,
+    {
+        let client = self.clone();
+        async move {
+            let metadata = client.client.metadata();
+            let (_pallet, entry) = subxt_core::storage::lookup_storage_entry_details(
+                address.pallet_name(),
+                address.entry_name(),
+                &metadata,
+            )?;
+
+            // Metadata validation checks whether the static address given
+            // is likely to actually correspond to a real storage entry or not.
+            // if not, it means static codegen doesn't line up with runtime
+            // metadata.
+            subxt_core::storage::validate(&address, &metadata)?;
+
+            let entry = entry.entry_type();
+            let return_type_id = entry.value_ty();
+            let hashers = StorageHashers::new(entry, metadata.types())?;
+
+            let state_root = client.verified_state_root().await?;
+            let block_hash = client.block_ref.hash();
+
+            let address_bytes = subxt_core::storage::get_address_bytes(&address, &metadata)?;
+            let keys = client
+                .client
+                .backend()
+                .storage_fetch_descendant_keys(address_bytes, block_hash)
+                .await?;
+
+            // Fetch proofs for batches of these keys as they arrive, and use them to obtain the values.
+            let s = keys
+                .chunks(VERIFIED_ITER_PROOF_KEYS)
+                .then(move |keys| {
+                    let client = client.clone();
+                    async move {
+                        let keys = keys.into_iter().collect::<Result<Vec<_>, Error>>()?;
+                        let proof = client
+                            .client
+                            .backend()
+                            .storage_fetch_read_proof(keys.clone(), block_hash)
+                            .await?;
+                        verify_values::<T>(state_root, &proof, keys)
+                    }
+                })
+                .flat_map(|kvs| {
+                    let kvs: Vec<Result<RawKeyValue, Error>> = match kvs {
+                        Ok(kvs) => kvs.into_iter().map(Ok).collect(),
+                        Err(e) => vec![Err(e)],
+                    };
+                    futures::stream::iter(kvs)
+                })
+                .map(move |kv| {
+                    let (key, value) = kv?;
+                    decode_storage_key_value::<Addr>(
+                        key,
+                        &value,
                         return_type_id,
+                        &hashers,
                         &metadata,
-                    )?;
-
-                    let key_bytes = kv.key;
-                    let cursor = &mut &key_bytes[..];
-                    strip_storage_address_root_bytes(cursor)?;
-
-                    let keys = <Addr::Keys as StorageKey>::decode_storage_key(
-                        cursor,
-                        &mut hashers.iter(),
-                        metadata.types(),
-                    )?;
-
-                    Ok(StorageKeyValuePair::<Addr> {
-                        keys,
-                        key_bytes,
-                        value,
-                    })
+                    )
                 });
 
             let s = StreamOfResults::new(Box::pin(s));
@@ -261,6 +418,27 @@ where
         }
     }
 
+    /// Fetch the header of the block that we're accessing storage at, check that it
+    /// hashes to the block hash, and return its state root.
+    async fn verified_state_root(&self) -> Result<T::Hash, Error> {
+        let block_hash = self.block_ref.hash();
+        let header = self
+            .client
+            .backend()
+            .block_header(block_hash)
+            .await?
+            .ok_or_else(|| Error::Block(BlockError::not_found(block_hash)))?;
+
+        if header.hash() != block_hash {
+            return Err(Error::Other(format!(
+                "The header returned for block 0x{} does not hash to the block hash",
+                hex::encode(block_hash)
+            )));
+        }
+
+        Ok(header.state_root())
+    }
+
     /// The storage version of a pallet.
     /// The storage version refers to the `frame_support::traits::Metadata::StorageVersion` type.
     pub async fn storage_version(&self, pallet_name: impl AsRef<str>) -> Result<u16, Error> {
@@ -300,6 +478,59 @@ where
     }
 }
 
+/// Decode the keys and value of some storage entry found underneath the given address.
+fn decode_storage_key_value<Addr>(
+    key_bytes: Vec<u8>,
+    value_bytes: &[u8],
+    return_type_id: u32,
+    hashers: &StorageHashers,
+    metadata: &Metadata,
+) -> Result<StorageKeyValuePair<Addr>, Error>
+where
+    Addr: Address,
+    Addr::Keys: 'static + Sized,
+{
+    let value = Addr::Target::decode_with_metadata(&mut &*value_bytes, return_type_id, metadata)?;
+
+    let cursor = &mut &key_bytes[..];
+    strip_storage_address_root_bytes(cursor)?;
+
+    let keys = <Addr::Keys as StorageKey>::decode_storage_key(
+        cursor,
+        &mut hashers.iter(),
+        metadata.types(),
+    )?;
+
+    Ok(StorageKeyValuePair::<Addr> {
+        keys,
+        key_bytes,
+        value,
+    })
+}
+
+/// A storage key and the value at it.
+type RawKeyValue = (Vec<u8>, Vec<u8>);
+
+/// Verify that values exist at each of the given keys using a storage proof, returning them.
+fn verify_values<T: Config>(
+    state_root: T::Hash,
+    proof: &[Vec<u8>],
+    keys: Vec<Vec<u8>>,
+) -> Result<Vec<RawKeyValue>, Error> {
+    keys.into_iter()
+        .map(|key| {
+            let value = subxt_core::storage::proof::verify_raw::<T>(state_root, proof, &key)?
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "The storage proof shows that no value exists at the returned key 0x{}",
+                        hex::encode(&key)
+                    ))
+                })?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
 /// Strips the first 32 bytes (16 for the pallet hash, 16 for the entry hash) off some storage address bytes.
 fn strip_storage_address_root_bytes(address_bytes: &mut &[u8]) -> Result<(), StorageAddressError> {
     if address_bytes.len() >= 32 {
@@ -321,3 +552,138 @@ pub struct StorageKeyValuePair<T: Address> {
     /// The value of the storage entry.
     pub value: T::Target,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::config::Header;
+    use crate::dynamic::Value;
+    use crate::utils::H256;
+    use codec::Encode;
+    use futures::StreamExt;
+    use sp_runtime::traits::BlakeTwo256;
+    use sp_trie::{LayoutV1, MemoryDB, TrieDBMutBuilder, TrieMut};
+
+    // Build a trie from the given entries, returning its root and every node in it.
+    fn trie(entries: &[(Vec<u8>, Vec<u8>)]) -> (H256, Vec<Vec<u8>>) {
+        let mut db = MemoryDB::<BlakeTwo256>::default();
+        let mut root = H256::zero();
+        {
+            let mut trie =
+                TrieDBMutBuilder::<LayoutV1<BlakeTwo256>>::new(&mut db, &mut root).build();
+            for (key, value) in entries {
+                trie.insert(key, value).unwrap();
+            }
+        }
+        let nodes = db.drain().into_values().map(|(node, _)| node).collect();
+        (root, nodes)
+    }
+
+    fn key(address: &impl subxt_core::storage::address::Address) -> Vec<u8> {
+        let metadata = test_utils::metadata("polkadot_metadata_small");
+        subxt_core::storage::get_address_bytes(address, &metadata).unwrap()
+    }
+
+    fn block_hash_key(number: u32) -> Vec<u8> {
+        key(&crate::dynamic::storage(
+            "System",
+            "BlockHash",
+            vec![Value::u128(number as u128)],
+        ))
+    }
+
+    // Set up a client whose latest block has the given storage, proven by the given proof.
+    async fn client_with_proof(
+        storage: &[(Vec<u8>, Vec<u8>)],
+        state_root: H256,
+        proof: Vec<Vec<u8>>,
+    ) -> (crate::OnlineClient<crate::SubstrateConfig>, H256) {
+        let genesis = test_utils::header(0, Default::default());
+        let mut block = test_utils::header(1, genesis.hash());
+        block.state_root = state_root;
+        let block_hash = block.hash();
+
+        let mut builder = MockBackend::test_builder()
+            .block(block, vec![])
+            .read_proof(block_hash, proof);
+        for (key, value) in storage {
+            builder = builder.storage(block_hash, key.clone(), value.clone());
+        }
+        (test_utils::client(builder.build()).await, block_hash)
+    }
+
+    #[tokio::test]
+    async fn verified_values_are_returned_from_valid_proofs() {
+        let number_address = crate::dynamic::storage("System", "Number", ());
+        let entries = vec![
+            (key(&number_address), 7u32.encode()),
+            (block_hash_key(1), H256::repeat_byte(1).encode()),
+            (block_hash_key(2), H256::repeat_byte(2).encode()),
+        ];
+        let (root, proof) = trie(&entries);
+        let (api, block_hash) = client_with_proof(&entries, root, proof).await;
+        let storage = api.storage().at(block_hash);
+
+        let number = storage.fetch_verified(&number_address).await.unwrap();
+        assert_eq!(number.unwrap().encoded(), 7u32.encode());
+
+        let mut block_hashes: Vec<_> = storage
+            .iter_verified(crate::dynamic::storage("System", "BlockHash", ()))
+            .await
+            .unwrap()
+            .map(|kv| kv.unwrap().value.encoded().to_vec())
+            .collect()
+            .await;
+        block_hashes.sort();
+        assert_eq!(
+            block_hashes,
+            vec![H256::repeat_byte(1).encode(), H256::repeat_byte(2).encode()]
+        );
+    }
+
+    #[tokio::test]
+    async fn tampered_proofs_are_rejected() {
+        let number_address = crate::dynamic::storage("System", "Number", ());
+        let entries = vec![(key(&number_address), 7u32.encode())];
+        let (root, mut proof) = trie(&entries);
+
+        // Change the value in the (only non-empty) node.
+        for node in &mut proof {
+            if let Some(last) = node.last_mut() {
+                *last = last.wrapping_add(1);
+            }
+        }
+
+        let (api, block_hash) = client_with_proof(&entries, root, proof).await;
+        let storage = api.storage().at(block_hash);
+
+        assert!(storage.fetch_verified(&number_address).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn missing_keys_are_proven_absent() {
+        let number_address = crate::dynamic::storage("System", "Number", ());
+        let entries = vec![(block_hash_key(1), H256::repeat_byte(1).encode())];
+        let (root, proof) = trie(&entries);
+
+        // The node claims that another key exists, but it isn't in the proven trie.
+        let mut storage_entries = entries.clone();
+        storage_entries.push((block_hash_key(2), H256::repeat_byte(2).encode()));
+
+        let (api, block_hash) = client_with_proof(&storage_entries, root, proof).await;
+        let storage = api.storage().at(block_hash);
+
+        let number = storage.fetch_verified(&number_address).await.unwrap();
+        assert!(number.is_none());
+
+        let results: Vec<_> = storage
+            .iter_verified(crate::dynamic::storage("System", "BlockHash", ()))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        // Values are verified in batches, and so the whole batch fails.
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+}
@@ -0,0 +1,470 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Dry run transactions using the `DryRunApi` runtime API, which (unlike the legacy
+//! `system_dryRun` RPC method) is available via any backend.
+
+use crate::{
+    client::OnlineClientT,
+    config::Config,
+    dynamic::DecodedValue,
+    error::{DispatchError, Error, MetadataError},
+    events::{Events, Phase},
+    Metadata,
+};
+use codec::{Compact, Encode};
+use derive_where::derive_where;
+use scale_encode::EncodeAsType;
+use scale_info::TypeDef;
+use scale_value::{At, Composite, Value, ValueDef};
+use subxt_metadata::RuntimeApiMethodMetadata;
+
+/// The effects of dry running a transaction via [`crate::tx::SubmittableExtrinsic::dry_run()`].
+#[derive_where(Debug)]
+pub struct DryRunEffects<T: Config> {
+    /// `Ok` if the call would dispatch successfully, or the error that it would fail with.
+    pub result: Result<(), DispatchError>,
+    /// The events that dispatching the call would emit.
+    pub events: Events<T>,
+    /// The XCM that would be executed locally, if any.
+    pub local_xcm: Option<DecodedValue>,
+    /// The XCMs that would be sent to other locations, alongside the location
+    /// that each is sent to.
+    pub forwarded_xcms: Vec<(DecodedValue, Vec<DecodedValue>)>,
+}
+
+impl<T: Config> DryRunEffects<T> {
+    /// Would the call dispatch successfully?
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Dry run the given encoded extrinsic at some block. This uses `DryRunApi_dry_run_extrinsic`
+/// if the runtime has it, and otherwise dry runs the call on behalf of the extrinsic signer
+/// via `DryRunApi_dry_run_call`.
+pub(crate) async fn dry_run<T: Config, C: OnlineClientT<T>>(
+    client: &C,
+    extrinsic: &[u8],
+    at: T::Hash,
+) -> Result<DryRunEffects<T>, Error> {
+    let metadata = client.metadata();
+    let api = metadata
+        .runtime_api_trait_by_name("DryRunApi")
+        .ok_or_else(|| MetadataError::RuntimeTraitNotFound("DryRunApi".into()))?;
+
+    let (method, params) = if let Some(method) = api.method_by_name("dry_run_extrinsic") {
+        (method, extrinsic.to_vec())
+    } else if let Some(method) = api.method_by_name("dry_run_call") {
+        (
+            method,
+            dry_run_call_params::<T>(extrinsic, method, &metadata)?,
+        )
+    } else {
+        return Err(MetadataError::RuntimeMethodNotFound("dry_run_call".into()).into());
+    };
+
+    let bytes = client
+        .backend()
+        .call(&format!("DryRunApi_{}", method.name()), Some(&params), at)
+        .await?;
+
+    decode_dry_run_effects(&bytes, method.output_ty(), &metadata)
+}
+
+/// Encode the parameters to pass to `DryRunApi_dry_run_call`; the origin, which is the signer
+/// of the extrinsic (if signed), then the call, then (for newer versions of the API) the XCM
+/// version to use in the results, which is the highest that the runtime supports.
+fn dry_run_call_params<T: Config>(
+    extrinsic: &[u8],
+    method: &RuntimeApiMethodMetadata,
+    metadata: &Metadata,
+) -> Result<Vec<u8>, Error> {
+    let extrinsics =
+        subxt_core::blocks::decode_from::<T>(vec![extrinsic.to_vec()], metadata.clone())
+            .map_err(subxt_core::Error::from)?;
+    let details = extrinsics
+        .iter()
+        .next()
+        .expect("one extrinsic was given; qed")?;
+
+    let origin = match details.address_bytes() {
+        Some(mut address_bytes) => {
+            let address = scale_value::scale::decode_as_type(
+                &mut address_bytes,
+                metadata.extrinsic().address_ty(),
+                metadata.types(),
+            )?;
+            // Addresses like `MultiAddress` wrap the account ID; use that if so. Other kinds of
+            // address (eg indexes) would need looking up to find the account they refer to.
+            let account = match address.value {
+                ValueDef::Variant(v) if v.name == "Id" && v.values.len() == 1 => {
+                    v.values.into_values().next().expect("one value; qed")
+                }
+                ValueDef::Variant(v) => {
+                    return Err(Error::Other(format!(
+                        "Unsupported address: cannot dry run a transaction signed with a '{}' address; only 'Id' addresses are supported",
+                        v.name
+                    )))
+                }
+                _ => address,
+            };
+            Value::unnamed_variant("Signed", [account.remove_context()])
+        }
+        None => Value::unnamed_variant("None", []),
+    };
+    let origin = Value::unnamed_variant("system", [origin]);
+
+    let origin_ty = method
+        .inputs()
+        .next()
+        .ok_or_else(|| Error::Other("DryRunApi_dry_run_call has no inputs".into()))?
+        .ty;
+
+    let mut params = origin.encode_as_type(origin_ty, metadata.types())?;
+    params.extend(details.call_bytes());
+    if method.inputs().len() > 2 {
+        highest_xcm_version(method, metadata)?.encode_to(&mut params);
+    }
+    Ok(params)
+}
+
+/// The highest XCM version that the runtime supports, going by the variants of the
+/// `VersionedXcm` that it returns as the `local_xcm` in the dry run results.
+fn highest_xcm_version(
+    method: &RuntimeApiMethodMetadata,
+    metadata: &Metadata,
+) -> Result<u32, Error> {
+    let types = metadata.types();
+    let variants = |id: u32| match &types.resolve(id)?.type_def {
+        TypeDef::Variant(v) => Some(&v.variants),
+        _ => None,
+    };
+    let variant_field = |id: u32, name: &str| {
+        let variant = variants(id)?.iter().find(|v| v.name == name)?;
+        Some(variant.fields.first()?.ty.id)
+    };
+
+    // Result<CallDryRunEffects, Error> -> CallDryRunEffects -> Option<VersionedXcm> -> VersionedXcm
+    let versioned_xcm = variant_field(method.output_ty(), "Ok")
+        .and_then(|effects| match &types.resolve(effects)?.type_def {
+            TypeDef::Composite(c) => c
+                .fields
+                .iter()
+                .find(|f| f.name.as_deref() == Some("local_xcm")),
+            _ => None,
+        })
+        .and_then(|local_xcm| variant_field(local_xcm.ty.id, "Some"));
+
+    versioned_xcm
+        .and_then(variants)
+        .and_then(|versions| {
+            versions
+                .iter()
+                .filter_map(|v| v.name.strip_prefix('V')?.parse::<u32>().ok())
+                .max()
+        })
+        .ok_or_else(|| unexpected_shape("local_xcm"))
+}
+
+/// Decode the `Result<CallDryRunEffects, Error>` returned from the `DryRunApi`.
+fn decode_dry_run_effects<T: Config>(
+    mut bytes: &[u8],
+    output_ty: u32,
+    metadata: &Metadata,
+) -> Result<DryRunEffects<T>, Error> {
+    let output = scale_value::scale::decode_as_type(&mut bytes, output_ty, metadata.types())?;
+    let effects = match output.value {
+        ValueDef::Variant(v) if v.name == "Ok" => v.values,
+        ValueDef::Variant(v) if v.name == "Err" => {
+            let reason = match v.values.at(0).map(|e| &e.value) {
+                Some(ValueDef::Variant(e)) => e.name.clone(),
+                _ => "unknown reason".into(),
+            };
+            return Err(Error::Other(format!(
+                "The transaction could not be dry run: {reason}"
+            )));
+        }
+        _ => return Err(unexpected_shape("result")),
+    };
+    let effects = effects.at(0).ok_or_else(|| unexpected_shape("effects"))?;
+
+    // `DispatchResultWithPostInfo`; the error is wrapped alongside some post dispatch info.
+    let result = match &effects.at("execution_result").map(|r| &r.value) {
+        Some(ValueDef::Variant(v)) if v.name == "Ok" => Ok(()),
+        Some(ValueDef::Variant(v)) if v.name == "Err" => {
+            let error = v
+                .values
+                .at(0)
+                .and_then(|e| e.at("error"))
+                .ok_or_else(|| unexpected_shape("execution_result"))?;
+            let error_bytes = error.encode_as_type(error.context, metadata.types())?;
+            Err(DispatchError::decode_from(error_bytes, metadata.clone())?)
+        }
+        _ => return Err(unexpected_shape("execution_result")),
+    };
+
+    // Events are returned without the usual `EventRecord` wrapping, so we add that back in
+    // (with the phase set to the first extrinsic, and no topics) in order to decode them.
+    // `Events` decodes each `frame_system::EventRecord` by hand rather than via the metadata,
+    // so the bytes here must match the layout that it expects: a `Phase`, then the event
+    // itself, then the topics as a `Vec<T::Hash>`. If that changes, this must change with it.
+    let emitted_events = values(effects.at("emitted_events"), "emitted_events")?;
+    let mut event_bytes = Compact(emitted_events.len() as u32).encode();
+    for event in emitted_events {
+        Phase::ApplyExtrinsic(0).encode_to(&mut event_bytes);
+        event.encode_as_type_to(event.context, metadata.types(), &mut event_bytes)?;
+        // No topics.
+        Vec::<T::Hash>::new().encode_to(&mut event_bytes);
+    }
+    let events = Events::decode_from(event_bytes, metadata.clone());
+
+    let local_xcm = match effects.at("local_xcm").map(|x| &x.value) {
+        Some(ValueDef::Variant(v)) if v.name == "Some" => v.values.at(0).cloned(),
+        Some(ValueDef::Variant(v)) if v.name == "None" => None,
+        _ => return Err(unexpected_shape("local_xcm")),
+    };
+
+    let forwarded_xcms = values(effects.at("forwarded_xcms"), "forwarded_xcms")?
+        .iter()
+        .map(|forwarded| {
+            let location = forwarded
+                .at(0)
+                .cloned()
+                .ok_or_else(|| unexpected_shape("forwarded_xcms"))?;
+            let xcms = values(forwarded.at(1), "forwarded_xcms")?.to_vec();
+            Ok((location, xcms))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(DryRunEffects {
+        result,
+        events,
+        local_xcm,
+        forwarded_xcms,
+    })
+}
+
+// The values in some sequence.
+fn values<'a>(value: Option<&'a DecodedValue>, what: &str) -> Result<&'a [DecodedValue], Error> {
+    match value.map(|v| &v.value) {
+        Some(ValueDef::Composite(Composite::Unnamed(values))) => Ok(values),
+        _ => Err(unexpected_shape(what)),
+    }
+}
+
+fn unexpected_shape(what: &str) -> Error {
+    Error::Other(format!(
+        "Unexpected shape of '{what}' in the DryRunApi results"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::tx::Signer;
+    use crate::utils::{AccountId32, MultiAddress, MultiSignature};
+    use crate::SubstrateConfig;
+    use frame_metadata::v15;
+
+    fn dry_run_call_method(metadata: &Metadata) -> &RuntimeApiMethodMetadata {
+        metadata
+            .runtime_api_trait_by_name("DryRunApi")
+            .unwrap()
+            .method_by_name("dry_run_call")
+            .unwrap()
+    }
+
+    // Encode some `CallDryRunEffects` as returned from `DryRunApi_dry_run_call`.
+    fn dry_run_call_output(metadata: &Metadata, account: [u8; 32]) -> Vec<u8> {
+        let method = dry_run_call_method(metadata);
+
+        let post_info = Value::named_composite([
+            ("actual_weight", Value::unnamed_variant("None", [])),
+            ("pays_fee", Value::unnamed_variant("Yes", [])),
+        ]);
+        let remarked = Value::unnamed_variant(
+            "System",
+            [Value::named_variant(
+                "Remarked",
+                [
+                    ("sender", Value::from_bytes(account)),
+                    ("hash", Value::from_bytes([1u8; 32])),
+                ],
+            )],
+        );
+        let effects = Value::named_composite([
+            (
+                "execution_result",
+                Value::unnamed_variant(
+                    "Err",
+                    [Value::named_composite([
+                        ("post_info", post_info),
+                        ("error", Value::unnamed_variant("BadOrigin", [])),
+                    ])],
+                ),
+            ),
+            ("emitted_events", Value::unnamed_composite([remarked])),
+            ("local_xcm", Value::unnamed_variant("None", [])),
+            ("forwarded_xcms", Value::unnamed_composite([])),
+        ]);
+
+        Value::unnamed_variant("Ok", [effects])
+            .encode_as_type(method.output_ty(), metadata.types())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn dry_runs_calls_on_behalf_of_the_signer() {
+        let metadata = test_utils::metadata("polkadot_metadata_small");
+        let signer = subxt_signer::sr25519::dev::alice();
+        let account = signer.public_key().0;
+
+        let backend = MockBackend::test_builder()
+            .call("AccountNonceApi_account_nonce", 0u32.encode())
+            .call(
+                "DryRunApi_dry_run_call",
+                dry_run_call_output(&metadata, account),
+            )
+            .build();
+        let api = test_utils::client(backend).await;
+
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let tx = api
+            .tx()
+            .create_signed(&call, &signer, Default::default())
+            .await
+            .unwrap();
+
+        // The origin is the signer, and the call is passed as is.
+        let method = dry_run_call_method(&metadata);
+        let params =
+            dry_run_call_params::<SubstrateConfig>(tx.encoded(), method, &metadata).unwrap();
+        let call_data = api.tx().call_data(&call).unwrap();
+        assert_eq!(params[..2], [0, 1]);
+        assert_eq!(params[2..34], account);
+        assert_eq!(params[34..], call_data);
+
+        let effects = tx.dry_run().await.unwrap();
+        assert!(matches!(effects.result, Err(DispatchError::BadOrigin)));
+        assert!(effects.local_xcm.is_none());
+        assert!(effects.forwarded_xcms.is_empty());
+
+        let events: Vec<_> = effects.events.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].pallet_name(), "System");
+        assert_eq!(events[0].variant_name(), "Remarked");
+        assert_eq!(events[0].phase(), Phase::ApplyExtrinsic(0));
+    }
+
+    /// The small Polkadot metadata, with a `DryRunApi.dry_run_extrinsic` method added which
+    /// returns the same effects as `dry_run_call`.
+    fn metadata_with_dry_run_extrinsic() -> Metadata {
+        let metadata = test_utils::metadata("polkadot_metadata_small");
+        let mut v15: v15::RuntimeMetadataV15 = (*metadata).clone().into();
+
+        let api = v15
+            .apis
+            .iter_mut()
+            .find(|api| api.name == "DryRunApi")
+            .unwrap();
+        let dry_run_call = api
+            .methods
+            .iter()
+            .find(|method| method.name == "dry_run_call")
+            .unwrap()
+            .clone();
+        api.methods.push(v15::RuntimeApiMethodMetadata {
+            name: "dry_run_extrinsic".into(),
+            inputs: vec![v15::RuntimeApiMethodParamMetadata {
+                name: "extrinsic".into(),
+                ty: dry_run_call.inputs[1].ty,
+            }],
+            output: dry_run_call.output,
+            docs: vec![],
+        });
+
+        subxt_metadata::Metadata::try_from(v15).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn dry_runs_extrinsics_if_the_runtime_can() {
+        let metadata = metadata_with_dry_run_extrinsic();
+        let signer = subxt_signer::sr25519::dev::alice();
+        let account = signer.public_key().0;
+
+        // There's no `DryRunApi_dry_run_call` response, so this fails if that's used instead.
+        let backend = MockBackend::test_builder()
+            .metadata(metadata.clone())
+            .call("AccountNonceApi_account_nonce", 0u32.encode())
+            .call(
+                "DryRunApi_dry_run_extrinsic",
+                dry_run_call_output(&metadata, account),
+            )
+            .build();
+        let api = test_utils::client(backend).await;
+
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let tx = api
+            .tx()
+            .create_signed(&call, &signer, Default::default())
+            .await
+            .unwrap();
+
+        let effects = tx.dry_run().await.unwrap();
+        assert!(matches!(effects.result, Err(DispatchError::BadOrigin)));
+        let events: Vec<_> = effects.events.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].variant_name(), "Remarked");
+    }
+
+    // Signs as Alice, but gives an address which isn't her account ID.
+    struct Address32Signer(subxt_signer::sr25519::Keypair);
+
+    impl Signer<SubstrateConfig> for Address32Signer {
+        fn account_id(&self) -> AccountId32 {
+            Signer::<SubstrateConfig>::account_id(&self.0)
+        }
+
+        fn address(&self) -> MultiAddress<AccountId32, u32> {
+            MultiAddress::Address32([1; 32])
+        }
+
+        fn sign(&self, signer_payload: &[u8]) -> MultiSignature {
+            Signer::<SubstrateConfig>::sign(&self.0, signer_payload)
+        }
+    }
+
+    #[tokio::test]
+    async fn only_account_id_addresses_can_be_dry_run() {
+        let metadata = test_utils::metadata("polkadot_metadata_small");
+        let backend = MockBackend::test_builder()
+            .call("AccountNonceApi_account_nonce", 0u32.encode())
+            .build();
+        let api = test_utils::client(backend).await;
+
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let signer = Address32Signer(subxt_signer::sr25519::dev::alice());
+        let tx = api
+            .tx()
+            .create_signed(&call, &signer, Default::default())
+            .await
+            .unwrap();
+
+        let method = dry_run_call_method(&metadata);
+        let err = dry_run_call_params::<SubstrateConfig>(tx.encoded(), method, &metadata)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Unsupported address"), "{err}");
+        assert!(err.contains("'Address32'"), "{err}");
+    }
+
+    #[test]
+    fn xcm_version_is_the_highest_the_runtime_supports() {
+        let metadata = test_utils::metadata("polkadot_metadata_small");
+        let method = dry_run_call_method(&metadata);
+        assert_eq!(highest_xcm_version(method, &metadata).unwrap(), 4);
+    }
+}
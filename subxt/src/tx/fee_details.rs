@@ -0,0 +1,176 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Types describing the fees and weight of a transaction, as returned from the
+//! `TransactionPaymentApi` runtime API.
+
+use codec::Decode;
+
+/// The fees that a transaction is expected to pay, and the weight that it's expected to
+/// consume, as returned from [`crate::tx::SubmittableExtrinsic::fee_details()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeDetails {
+    /// The weight of the transaction.
+    pub weight: Weight,
+    /// The class of the transaction.
+    pub class: DispatchClass,
+    /// The fee that the transaction is expected to pay, less any tip. This is the sum of
+    /// the [`InclusionFee`] parts.
+    pub partial_fee: u128,
+    /// A breakdown of the fee that must be paid for the transaction to be included in a
+    /// block. Unsigned transactions do not pay this, and so it will be `None` for them.
+    pub inclusion_fee: Option<InclusionFee>,
+    /// The tip that the transaction pays.
+    pub tip: u128,
+}
+
+/// The weight of a transaction.
+#[derive(Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Weight {
+    /// The computational time used to execute the transaction, in picoseconds.
+    #[codec(compact)]
+    pub ref_time: u64,
+    /// The size of the storage proof needed to execute the transaction, in bytes.
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+/// The class of a transaction, which determines how it's treated when building blocks.
+#[derive(Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DispatchClass {
+    /// A normal transaction.
+    Normal,
+    /// An operational transaction, which is given priority over normal ones.
+    Operational,
+    /// A mandatory transaction, which is always included in a block regardless of its weight.
+    Mandatory,
+}
+
+/// The breakdown of the fee that a transaction must pay to be included in a block.
+#[derive(Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InclusionFee {
+    /// The minimum fee that every transaction pays.
+    pub base_fee: u128,
+    /// The fee based on the length of the transaction in bytes.
+    pub len_fee: u128,
+    /// The fee based on the weight of the transaction, adjusted to account for how
+    /// congested the chain is.
+    pub adjusted_weight_fee: u128,
+}
+
+/// The `RuntimeDispatchInfo` returned from `TransactionPaymentApi_query_info`.
+#[derive(Decode, Debug)]
+pub(crate) struct RuntimeDispatchInfo {
+    pub weight: Weight,
+    pub class: DispatchClass,
+    pub partial_fee: u128,
+}
+
+/// The `FeeDetails` returned from `TransactionPaymentApi_query_fee_details`.
+#[derive(Decode, Debug)]
+pub(crate) struct RuntimeFeeDetails {
+    pub inclusion_fee: Option<InclusionFee>,
+    pub tip: u128,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::backend::BlockRef;
+    use crate::utils::{MultiSignature, H256};
+    use crate::{OnlineClient, SubstrateConfig};
+    use codec::{Compact, Encode};
+
+    fn expected_fee_details() -> FeeDetails {
+        FeeDetails {
+            weight: Weight {
+                ref_time: 1_000,
+                proof_size: 200,
+            },
+            class: DispatchClass::Operational,
+            partial_fee: 600,
+            inclusion_fee: Some(InclusionFee {
+                base_fee: 100,
+                len_fee: 200,
+                adjusted_weight_fee: 300,
+            }),
+            tip: 5,
+        }
+    }
+
+    // A client whose `TransactionPaymentApi` returns the `expected_fee_details()`.
+    async fn client() -> OnlineClient<SubstrateConfig> {
+        // RuntimeDispatchInfo { weight, class: Operational, partial_fee }
+        let query_info = (Compact(1_000u64), Compact(200u64), 1u8, 600u128).encode();
+        // FeeDetails { inclusion_fee: Some(InclusionFee { .. }), tip }
+        let query_fee_details = (Some((100u128, 200u128, 300u128)), 5u128).encode();
+
+        let backend = MockBackend::test_builder()
+            .call("AccountNonceApi_account_nonce", 0u32.encode())
+            .call("TransactionPaymentApi_query_info", query_info)
+            .call("TransactionPaymentApi_query_fee_details", query_fee_details)
+            .build();
+        test_utils::client(backend).await
+    }
+
+    #[tokio::test]
+    async fn fee_details_can_be_fetched_before_signing() {
+        let api = client().await;
+
+        let account_id = [0; 32].into();
+        let call = crate::dynamic::tx("System", "remark", vec![Vec::<u8>::new()]);
+        let partial = api
+            .tx()
+            .create_partial_signed(&call, &account_id, Default::default())
+            .await
+            .unwrap();
+
+        let signature = MultiSignature::Sr25519([0; 64]);
+        let fee_details = partial.fee_details(&account_id, &signature).await.unwrap();
+
+        assert_eq!(fee_details, expected_fee_details());
+    }
+
+    #[tokio::test]
+    async fn fee_details_can_be_fetched_at_a_block() {
+        let api = client().await;
+        let signer = subxt_signer::sr25519::dev::alice();
+
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let tx = api
+            .tx()
+            .create_signed(&call, &signer, Default::default())
+            .await
+            .unwrap();
+
+        let at = BlockRef::from_hash(H256::repeat_byte(1));
+        let fee_details = tx.fee_details_at(at).await.unwrap();
+
+        assert_eq!(fee_details, expected_fee_details());
+    }
+
+    #[tokio::test]
+    async fn fee_details_can_be_estimated_without_a_signature() {
+        let api = client().await;
+        let signer = subxt_signer::sr25519::dev::alice();
+        let account_id = signer.public_key().to_account_id();
+
+        let call = crate::dynamic::tx("System", "remark", vec![vec![1u8, 2, 3]]);
+        let partial = api
+            .tx()
+            .create_partial_signed(&call, &account_id, Default::default())
+            .await
+            .unwrap();
+
+        // The placeholder signature is the same size as a real one.
+        let placeholder = partial.with_placeholder_signature(&account_id).unwrap();
+        let signed = partial.sign(&signer);
+        assert_eq!(placeholder.encoded().len(), signed.encoded().len());
+        assert_ne!(placeholder.encoded(), signed.encoded());
+
+        let fee_details = partial.estimate_fee_details(&account_id).await.unwrap();
+        assert_eq!(fee_details, expected_fee_details());
+    }
+}
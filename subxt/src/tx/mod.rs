@@ -11,6 +11,9 @@
 
 use crate::macros::cfg_substrate_compat;
 
+mod dry_run;
+mod fee_details;
+mod submission_policy;
 mod tx_client;
 mod tx_progress;
 
@@ -20,6 +23,10 @@ cfg_substrate_compat! {
     pub use subxt_core::tx::signer::PairSigner;
 }
 
+pub use dry_run::DryRunEffects;
+pub use fee_details::{DispatchClass, FeeDetails, InclusionFee, Weight};
+pub use submission_policy::{SubmissionPolicy, SubmissionStatus, TxSubmission};
+pub use subxt_core::config::NonceManager;
 pub use subxt_core::tx::payload::{dynamic, DefaultPayload, DynamicPayload, Payload};
 pub use subxt_core::tx::signer::{self, Signer};
 pub use tx_client::{
@@ -0,0 +1,763 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Submit a transaction, and re-sign and resubmit it if it doesn't make it into a block.
+
+use crate::{
+    backend::{BlockRef, StreamOfResults},
+    client::OnlineClientT,
+    config::{Config, DefaultExtrinsicParams, DefaultExtrinsicParamsBuilder, Hasher, Header},
+    error::{BlockError, Error, RpcError, TransactionError},
+    tx::{
+        Payload, Signer as SignerT, TransactionInvalid, TxClient, TxInBlock, TxProgress, TxStatus,
+        ValidationResult,
+    },
+    utils::{strip_compact_prefix, Era},
+};
+use derive_where::derive_where;
+use futures::future::{self, Either};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type SubmissionStatusStream<T, C> =
+    Pin<Box<dyn Stream<Item = Result<SubmissionStatus<T, C>, Error>> + Send>>;
+type FinalizedBlocks<T> = StreamOfResults<(<T as Config>::Header, BlockRef<<T as Config>::Hash>)>;
+
+/// Configures how [`TxClient::submit_with_policy()`] resubmits a transaction which is
+/// dropped or found to be invalid before it makes it into a finalized block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmissionPolicy {
+    max_attempts: u32,
+    tip: u128,
+    tip_increase_percent: u32,
+    mortal_for_n_blocks: Option<u64>,
+}
+
+impl Default for SubmissionPolicy {
+    fn default() -> Self {
+        SubmissionPolicy {
+            max_attempts: 3,
+            tip: 0,
+            tip_increase_percent: 20,
+            mortal_for_n_blocks: Some(32),
+        }
+    }
+}
+
+impl SubmissionPolicy {
+    /// Create a new [`SubmissionPolicy`]. By default, a transaction is submitted up to 3 times,
+    /// starting with no tip and increasing it by 20% each time, and is mortal for 32 blocks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of times to submit the transaction, including the first time.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// The tip to give the block author when the transaction is first submitted.
+    pub fn tip(mut self, tip: u128) -> Self {
+        self.tip = tip;
+        self
+    }
+
+    /// How much to increase the tip by, as a percentage, each time the transaction is
+    /// resubmitted. The tip always increases by at least 1.
+    pub fn tip_increase_percent(mut self, percent: u32) -> Self {
+        self.tip_increase_percent = percent;
+        self
+    }
+
+    /// Make each submission mortal for (roughly) the given number of blocks from the latest
+    /// finalized block at the time, or immortal if `None`. A mortal submission is treated as
+    /// dropped once a block at which it's no longer valid is finalized.
+    pub fn mortal_for(mut self, n_blocks: Option<u64>) -> Self {
+        self.mortal_for_n_blocks = n_blocks;
+        self
+    }
+
+    fn next_tip(&self, tip: u128) -> u128 {
+        let increase = tip.saturating_mul(self.tip_increase_percent as u128) / 100;
+        tip.saturating_add(increase.max(1))
+    }
+}
+
+/// The progress of a transaction submitted via [`TxClient::submit_with_policy()`].
+#[derive_where(Debug; C)]
+pub enum SubmissionStatus<T: Config, C> {
+    /// The transaction was signed and submitted. This is emitted again each time that
+    /// the transaction is resubmitted, after which any statuses relate to the new submission.
+    Submitted {
+        /// Which attempt this is, starting from 1.
+        attempt: u32,
+        /// The hash of the transaction that was submitted.
+        extrinsic_hash: T::Hash,
+        /// The tip given to the block author.
+        tip: u128,
+    },
+    /// The status of the latest submission.
+    Status(TxStatus<T, C>),
+}
+
+/// A stream of [`SubmissionStatus`]es for a transaction submitted via
+/// [`TxClient::submit_with_policy()`]. This ends once the transaction is finalized, or once
+/// it fails in a way that the [`SubmissionPolicy`] won't retry.
+pub struct TxSubmission<T: Config, C> {
+    inner: SubmissionStatusStream<T, C>,
+}
+
+impl<T: Config, C> std::fmt::Debug for TxSubmission<T, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TxSubmission")
+            .field("inner", &"<stream>")
+            .finish()
+    }
+}
+
+impl<T: Config, C> Stream for TxSubmission<T, C> {
+    type Item = Result<SubmissionStatus<T, C>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl<T, C> TxSubmission<T, C>
+where
+    T: Config<ExtrinsicParams = DefaultExtrinsicParams<T>>,
+    T::AccountId: Send + Sync,
+    C: OnlineClientT<T> + Send + Sync + 'static,
+{
+    pub(crate) fn new<Call, Signer>(
+        client: C,
+        call: Call,
+        signer: Signer,
+        policy: SubmissionPolicy,
+    ) -> Self
+    where
+        Call: Payload + Send + Sync + 'static,
+        Signer: SignerT<T> + Send + Sync + 'static,
+    {
+        let state = SubmissionState {
+            client,
+            call,
+            signer,
+            policy,
+            nonce: None,
+            first_block_number: 0,
+            extrinsic_hashes: Vec::new(),
+            attempt: 0,
+            tip: policy.tip,
+            progress: None,
+            expiry: None,
+            done: false,
+        };
+
+        let inner = futures::stream::unfold(state, |mut state| async move {
+            let item = state.next().await?;
+            Some((item, state))
+        });
+
+        TxSubmission {
+            inner: Box::pin(inner),
+        }
+    }
+
+    /// Return the next status when it's emitted. This just delegates to the
+    /// [`futures::Stream`] implementation for [`TxSubmission`], but allows you to
+    /// avoid importing that trait if you don't otherwise need it.
+    pub async fn next(&mut self) -> Option<Result<SubmissionStatus<T, C>, Error>> {
+        StreamExt::next(self).await
+    }
+
+    /// Wait for the transaction to be finalized, resubmitting it according to the
+    /// [`SubmissionPolicy`], and return a [`TxInBlock`] instance when it is, or an
+    /// error if the transaction could not be finalized.
+    pub async fn wait_for_finalized(mut self) -> Result<TxInBlock<T, C>, Error> {
+        // Invalid and dropped transactions are resubmitted until we run out of attempts,
+        // so these only lead to an error if they are the last thing we see.
+        let mut last_failure = None;
+        while let Some(status) = self.next().await {
+            match status? {
+                SubmissionStatus::Status(TxStatus::InFinalizedBlock(s)) => return Ok(s),
+                SubmissionStatus::Status(TxStatus::Error { message }) => {
+                    return Err(TransactionError::Error(message).into())
+                }
+                SubmissionStatus::Status(TxStatus::Invalid { message }) => {
+                    last_failure = Some(TransactionError::Invalid(message));
+                }
+                SubmissionStatus::Status(TxStatus::Dropped { message }) => {
+                    last_failure = Some(TransactionError::Dropped(message));
+                }
+                _ => continue,
+            }
+        }
+        match last_failure {
+            Some(e) => Err(e.into()),
+            None => Err(RpcError::SubscriptionDropped.into()),
+        }
+    }
+}
+
+struct SubmissionState<T: Config, C, Call, Signer> {
+    client: C,
+    call: Call,
+    signer: Signer,
+    policy: SubmissionPolicy,
+    // Every submission uses the same nonce, so that only one of them can make it into a block.
+    nonce: Option<u64>,
+    // The latest finalized block number when we first submitted. None of the submissions
+    // can be in this block or any before it.
+    first_block_number: u64,
+    // The hashes of every submission so far.
+    extrinsic_hashes: Vec<T::Hash>,
+    attempt: u32,
+    tip: u128,
+    progress: Option<TxProgress<T, C>>,
+    // The first block number at which the latest submission is no longer valid, and a stream
+    // of finalized blocks to watch for it. This is `None` for immortal submissions.
+    expiry: Option<(u64, FinalizedBlocks<T>)>,
+    done: bool,
+}
+
+impl<T, C, Call, Signer> SubmissionState<T, C, Call, Signer>
+where
+    T: Config<ExtrinsicParams = DefaultExtrinsicParams<T>>,
+    C: OnlineClientT<T>,
+    Call: Payload,
+    Signer: SignerT<T>,
+{
+    async fn next(&mut self) -> Option<Result<SubmissionStatus<T, C>, Error>> {
+        if self.done {
+            return None;
+        }
+
+        let item = self.next_item().await.transpose();
+        match &item {
+            None
+            | Some(Err(_))
+            | Some(Ok(SubmissionStatus::Status(
+                TxStatus::InFinalizedBlock(_) | TxStatus::Error { .. },
+            ))) => self.done = true,
+            _ => {}
+        }
+        item
+    }
+
+    async fn next_item(&mut self) -> Result<Option<SubmissionStatus<T, C>>, Error> {
+        if self.progress.is_none() {
+            if self.attempt > 0 {
+                // The last submission was dropped or is invalid, but an earlier one may have
+                // made it into a block regardless, so look for it before trying again.
+                if let Some(tx_in_block) = self.find_earlier_submission().await? {
+                    let status = TxStatus::InFinalizedBlock(tx_in_block);
+                    return Ok(Some(SubmissionStatus::Status(status)));
+                }
+                if self.attempt >= self.policy.max_attempts {
+                    return Ok(None);
+                }
+                // Resubmit with a higher tip.
+                self.tip = self.policy.next_tip(self.tip);
+            }
+            return self.submit().await.map(Some);
+        }
+
+        let status = self.next_status().await?;
+        if let TxStatus::Invalid { .. } | TxStatus::Dropped { .. } = status {
+            self.progress = None;
+            self.expiry = None;
+        }
+        Ok(Some(SubmissionStatus::Status(status)))
+    }
+
+    // The next status of the latest submission. If the submission is mortal, this is also
+    // `Dropped` once its mortality runs out, since the node might not tell us about that.
+    async fn next_status(&mut self) -> Result<TxStatus<T, C>, Error> {
+        let progress = self
+            .progress
+            .as_mut()
+            .expect("progress is set when a transaction is submitted; qed");
+
+        loop {
+            let Some((death, finalized_blocks)) = &mut self.expiry else {
+                return StreamExt::next(progress)
+                    .await
+                    .unwrap_or_else(|| Err(RpcError::SubscriptionDropped.into()));
+            };
+
+            match future::select(StreamExt::next(progress), StreamExt::next(finalized_blocks)).await
+            {
+                Either::Left((status, _)) => {
+                    return status.unwrap_or_else(|| Err(RpcError::SubscriptionDropped.into()));
+                }
+                Either::Right((Some(block), _)) => {
+                    let (header, _) = block?;
+                    if header.number().into() >= *death {
+                        let message = format!(
+                            "The transaction was not included before its mortality ended at block {death}"
+                        );
+                        return Ok(TxStatus::Dropped { message });
+                    }
+                }
+                // We can't keep track of the mortality any more, but can still wait for
+                // the node to tell us what happened.
+                Either::Right((None, _)) => self.expiry = None,
+            }
+        }
+    }
+
+    // Sign and submit the transaction with a fresh mortality and the current tip.
+    async fn submit(&mut self) -> Result<SubmissionStatus<T, C>, Error> {
+        let client = &self.client;
+        let tx_client = TxClient::<T, C>::new(client.clone());
+
+        let block_ref = client.backend().latest_finalized_block_ref().await?;
+        let header = client
+            .backend()
+            .block_header(block_ref.hash())
+            .await?
+            .ok_or_else(|| BlockError::not_found(block_ref.hash()))?;
+        let block_number: u64 = header.number().into();
+
+        let nonce = match self.nonce {
+            Some(nonce) => nonce,
+            None => {
+                // Take into account transactions from the account in blocks which aren't
+                // finalized yet, so that the nonce isn't already used.
+                let account_id = self.signer.account_id();
+                let nonce = tx_client.best_block_account_nonce(&account_id).await?;
+                self.first_block_number = block_number;
+                *self.nonce.insert(nonce)
+            }
+        };
+
+        let mut params = DefaultExtrinsicParamsBuilder::<T>::new()
+            .nonce(nonce)
+            .tip(self.tip);
+        let mut death = None;
+        if let Some(n_blocks) = self.policy.mortal_for_n_blocks {
+            params = params.mortal(&header, n_blocks);
+            death = Some(Era::mortal(n_blocks, block_number).death(block_number));
+        }
+
+        let tx = tx_client
+            .create_signed(&self.call, &self.signer, params.build())
+            .await?;
+
+        // There's no point in resubmitting a transaction which will never be valid, for
+        // instance because the account can't pay for it.
+        if self.attempt > 0 {
+            match tx.validate().await? {
+                ValidationResult::Valid(_)
+                | ValidationResult::Invalid(
+                    TransactionInvalid::Future | TransactionInvalid::ExhaustsResources,
+                ) => {}
+                ValidationResult::Invalid(e) => {
+                    let message = format!("Not resubmitting the transaction: {e:?}");
+                    return Err(TransactionError::Invalid(message).into());
+                }
+                ValidationResult::Unknown(e) => {
+                    let message = format!("Not resubmitting the transaction: {e:?}");
+                    return Err(TransactionError::Invalid(message).into());
+                }
+            }
+        }
+
+        let progress = tx.submit_and_watch().await?;
+        self.expiry = match death {
+            Some(death) => {
+                let finalized_blocks = client.backend().stream_finalized_block_headers().await?;
+                Some((death, finalized_blocks))
+            }
+            None => None,
+        };
+
+        self.attempt += 1;
+        let extrinsic_hash = progress.extrinsic_hash();
+        self.extrinsic_hashes.push(extrinsic_hash);
+        self.progress = Some(progress);
+
+        Ok(SubmissionStatus::Submitted {
+            attempt: self.attempt,
+            extrinsic_hash,
+            tip: self.tip,
+        })
+    }
+
+    // Look for any of our submissions in the blocks finalized since we first submitted. This
+    // is only worth doing once the account nonce is past ours. If that's only the case in a
+    // best block, we wait for that block to be finalized (or for it to be retracted).
+    //
+    // An error is returned if the nonce was used by a transaction that isn't ours.
+    async fn find_earlier_submission(&mut self) -> Result<Option<TxInBlock<T, C>>, Error> {
+        let nonce = self
+            .nonce
+            .expect("nonce is set when a transaction is submitted; qed");
+        let account_id = self.signer.account_id();
+        let tx_client = TxClient::<T, C>::new(self.client.clone());
+
+        let mut finalized_blocks = self
+            .client
+            .backend()
+            .stream_finalized_block_headers()
+            .await?;
+        while let Some(block) = finalized_blocks.next().await {
+            let (header, block_ref) = block?;
+
+            let finalized_nonce =
+                crate::blocks::get_account_nonce(&self.client, &account_id, block_ref.hash())
+                    .await?;
+            if finalized_nonce > nonce {
+                return match self.find_in_blocks_up_to(header).await? {
+                    Some(tx_in_block) => Ok(Some(tx_in_block)),
+                    None => {
+                        let message = format!("Nonce {nonce} was used by another transaction");
+                        Err(TransactionError::Invalid(message).into())
+                    }
+                };
+            }
+
+            if tx_client.best_block_account_nonce(&account_id).await? <= nonce {
+                return Ok(None);
+            }
+        }
+
+        Err(RpcError::SubscriptionDropped.into())
+    }
+
+    // Walk back from the given block to the block we first submitted at, looking for
+    // any of our submissions.
+    async fn find_in_blocks_up_to(
+        &mut self,
+        mut header: T::Header,
+    ) -> Result<Option<TxInBlock<T, C>>, Error> {
+        while header.number().into() > self.first_block_number {
+            let block_hash = header.hash();
+            let body = self
+                .client
+                .backend()
+                .block_body(block_hash)
+                .await?
+                .ok_or_else(|| BlockError::not_found(block_hash))?;
+
+            for ext in body {
+                let Ok((_, stripped)) = strip_compact_prefix(&ext) else {
+                    continue;
+                };
+                let ext_hash = T::Hasher::hash_of(&stripped);
+                if self.extrinsic_hashes.contains(&ext_hash) {
+                    let block_ref = BlockRef::from_hash(block_hash);
+                    return Ok(Some(TxInBlock::new(
+                        block_ref,
+                        ext_hash,
+                        self.client.clone(),
+                    )));
+                }
+            }
+
+            header = self
+                .client
+                .backend()
+                .block_header(header.parent_hash())
+                .await?
+                .ok_or_else(|| BlockError::not_found(header.parent_hash()))?;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::backend::rpc::{mock_rpc_client::MockRpcClient, RpcClient};
+    use crate::backend::TransactionStatus;
+    use crate::client::RuntimeVersion;
+    use crate::utils::H256;
+    use crate::{OnlineClient, SubstrateConfig};
+    use codec::Encode;
+    use serde_json::{json, Value};
+    use sp_runtime::transaction_validity::{
+        InvalidTransaction, TransactionValidity, ValidTransaction,
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn valid() -> Vec<u8> {
+        TransactionValidity::Ok(ValidTransaction::default()).encode()
+    }
+
+    // A block header, along with the extrinsics and the account nonce in the block.
+    type Block = (<SubstrateConfig as Config>::Header, Vec<Vec<u8>>, u32);
+
+    // A chain for the legacy backend to talk to, in which every block is finalized.
+    // Submitted transactions are handed to a closure, which can add blocks to the
+    // chain and returns the statuses to send back for the transaction.
+    #[derive(Default)]
+    struct Chain {
+        blocks: Vec<Block>,
+        submitted: Vec<Vec<u8>>,
+    }
+
+    impl Chain {
+        fn add_block(&mut self, extrinsics: Vec<Vec<u8>>, account_nonce: u32) -> H256 {
+            let parent_hash = self.blocks.last().map(|b| b.0.hash()).unwrap_or_default();
+            let header = test_utils::header(self.blocks.len() as u32, parent_hash);
+            let hash = header.hash();
+            self.blocks.push((header, extrinsics, account_nonce));
+            hash
+        }
+
+        fn last(&self) -> &Block {
+            self.blocks.last().expect("the chain has a genesis block")
+        }
+
+        fn block(&self, hash: &Value) -> &Block {
+            let hash: H256 = serde_json::from_value(hash.clone()).unwrap();
+            self.blocks.iter().find(|b| b.0.hash() == hash).unwrap()
+        }
+    }
+
+    fn chain_client(
+        account_nonce: u32,
+        on_submit: impl Fn(&mut Chain, &[u8]) -> Vec<Value> + Send + Sync + 'static,
+    ) -> (OnlineClient<SubstrateConfig>, Arc<Mutex<Chain>>) {
+        let chain = Arc::new(Mutex::new(Chain::default()));
+        let genesis_hash = chain.lock().unwrap().add_block(vec![], account_nonce);
+
+        let c = chain.clone();
+        let rpc_client = MockRpcClient::builder().method("chain_getBlockHash", move |params| {
+            let chain = c.lock().unwrap();
+            let block = match params[0].as_u64() {
+                Some(n) => chain.blocks.get(n as usize),
+                None => chain.blocks.last(),
+            };
+            Ok(json!(block.map(|b| b.0.hash())))
+        });
+        let c = chain.clone();
+        let rpc_client = rpc_client.method("chain_getFinalizedHead", move |_| {
+            Ok(json!(c.lock().unwrap().last().0.hash()))
+        });
+        let c = chain.clone();
+        let rpc_client = rpc_client.method("chain_getHeader", move |params| {
+            Ok(json!(c.lock().unwrap().block(&params[0]).0))
+        });
+        let c = chain.clone();
+        let rpc_client = rpc_client.method("chain_getBlock", move |params| {
+            let chain = c.lock().unwrap();
+            let (header, extrinsics, _) = chain.block(&params[0]);
+            let extrinsics: Vec<_> = extrinsics.iter().map(|e| format!("0x{}", hex::encode(e))).collect();
+            Ok(json!({ "block": { "header": header, "extrinsics": extrinsics }, "justifications": null }))
+        });
+        let c = chain.clone();
+        let rpc_client = rpc_client.method("state_call", move |params| {
+            let response = match params[0].as_str().unwrap() {
+                "AccountNonceApi_account_nonce" => c.lock().unwrap().block(&params[2]).2.encode(),
+                "TaggedTransactionQueue_validate_transaction" => valid(),
+                method => panic!("unexpected runtime API call {method}"),
+            };
+            Ok(json!(format!("0x{}", hex::encode(response))))
+        });
+        let c = chain.clone();
+        let rpc_client = rpc_client.subscription("chain_subscribeFinalizedHeads", move |_| {
+            let header = json!(c.lock().unwrap().last().0);
+            Ok(futures::stream::iter([Ok(header)]).chain(futures::stream::pending()))
+        });
+        let c = chain.clone();
+        let rpc_client = rpc_client
+            .subscription("author_submitAndWatchExtrinsic", move |params| {
+                let bytes =
+                    hex::decode(params[0].as_str().unwrap().trim_start_matches("0x")).unwrap();
+                let mut chain = c.lock().unwrap();
+                chain.submitted.push(bytes.clone());
+                let statuses = on_submit(&mut chain, &bytes);
+                Ok(futures::stream::iter(statuses.into_iter().map(Ok))
+                    .chain(futures::stream::pending()))
+            })
+            .build();
+
+        let runtime_version = RuntimeVersion {
+            spec_version: 0,
+            transaction_version: 0,
+        };
+        let api = OnlineClient::from_rpc_client_with(
+            genesis_hash,
+            runtime_version,
+            test_utils::metadata("polkadot_metadata_small"),
+            RpcClient::new(rpc_client),
+        )
+        .unwrap();
+        (api, chain)
+    }
+
+    fn remark() -> crate::tx::DynamicPayload {
+        crate::dynamic::tx("System", "remark", vec![Vec::<u8>::new()])
+    }
+
+    #[tokio::test]
+    async fn resubmits_dropped_transactions() {
+        let block_hash = test_utils::header(0, Default::default()).hash();
+
+        let dropped = TransactionStatus::Dropped {
+            message: "dropped".into(),
+        };
+        let backend = MockBackend::test_builder()
+            .call("AccountNonceApi_account_nonce", 7u32.encode())
+            .call("TaggedTransactionQueue_validate_transaction", valid())
+            .transaction_statuses(vec![TransactionStatus::Validated, dropped.clone()])
+            .transaction_statuses(vec![dropped])
+            .transaction_statuses(vec![TransactionStatus::InFinalizedBlock {
+                hash: block_hash.into(),
+            }])
+            .build();
+        let api = test_utils::client(backend.clone()).await;
+
+        let signer = subxt_signer::sr25519::dev::alice();
+        let policy = SubmissionPolicy::new().tip(100);
+
+        let mut submission = api.tx().submit_with_policy(remark(), signer, policy);
+        let mut tips = Vec::new();
+        while let Some(status) = submission.next().await {
+            if let SubmissionStatus::Submitted { attempt, tip, .. } = status.unwrap() {
+                tips.push((attempt, tip));
+            }
+        }
+
+        assert_eq!(tips, vec![(1, 100), (2, 120), (3, 144)]);
+        assert_eq!(backend.submitted_transactions().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_resubmit_transactions_which_will_never_be_valid() {
+        let cannot_pay = TransactionValidity::Err(InvalidTransaction::Payment.into());
+        let backend = MockBackend::test_builder()
+            .call("AccountNonceApi_account_nonce", 7u32.encode())
+            .call(
+                "TaggedTransactionQueue_validate_transaction",
+                cannot_pay.encode(),
+            )
+            .transaction_statuses(vec![TransactionStatus::Invalid {
+                message: "invalid".into(),
+            }])
+            .build();
+        let api = test_utils::client(backend.clone()).await;
+
+        let signer = subxt_signer::sr25519::dev::alice();
+        let submission = api
+            .tx()
+            .submit_with_policy(remark(), signer, SubmissionPolicy::new());
+
+        let err = submission.wait_for_finalized().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::Transaction(TransactionError::Invalid(m)) if m.contains("Payment")),
+            "{err:?}"
+        );
+        assert_eq!(backend.submitted_transactions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn finds_earlier_submissions_which_made_it_into_a_block() {
+        // The transaction is dropped, but makes it into a block anyway.
+        let (api, chain) = chain_client(7, |chain, bytes| {
+            chain.add_block(vec![bytes.to_vec()], 8);
+            vec![json!("ready"), json!("dropped")]
+        });
+
+        let signer = subxt_signer::sr25519::dev::alice();
+        let mut submission = api
+            .tx()
+            .submit_with_policy(remark(), signer, SubmissionPolicy::new());
+
+        let mut extrinsic_hashes = Vec::new();
+        let mut finalized = None;
+        while let Some(status) = submission.next().await {
+            match status.unwrap() {
+                SubmissionStatus::Submitted { extrinsic_hash, .. } => {
+                    extrinsic_hashes.push(extrinsic_hash);
+                }
+                SubmissionStatus::Status(TxStatus::InFinalizedBlock(tx)) => finalized = Some(tx),
+                _ => {}
+            }
+        }
+
+        let chain = chain.lock().unwrap();
+        let tx = finalized.expect("the transaction was found");
+        assert_eq!(extrinsic_hashes.len(), 1);
+        assert_eq!(tx.extrinsic_hash(), extrinsic_hashes[0]);
+        assert_eq!(tx.block_hash(), chain.last().0.hash());
+        assert_eq!(chain.submitted.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_if_another_transaction_used_the_nonce() {
+        let (api, chain) = chain_client(7, |chain, _| {
+            chain.add_block(vec![vec![12, 1, 2, 3]], 8);
+            vec![json!("ready"), json!("invalid")]
+        });
+
+        let signer = subxt_signer::sr25519::dev::alice();
+        let submission = api
+            .tx()
+            .submit_with_policy(remark(), signer, SubmissionPolicy::new());
+
+        let err = submission.wait_for_finalized().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::Transaction(TransactionError::Invalid(m)) if m.contains("Nonce 7")),
+            "{err:?}"
+        );
+        assert_eq!(chain.lock().unwrap().submitted.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resubmits_transactions_whose_mortality_ends() {
+        // The node never tells us what happened to the first submission, but its mortality
+        // ends as more blocks are finalized. The second submission makes it into a block.
+        let (api, chain) = chain_client(7, |chain, bytes| {
+            if chain.submitted.len() == 1 {
+                for _ in 0..8 {
+                    chain.add_block(vec![], 7);
+                }
+                vec![json!("ready")]
+            } else {
+                let hash = chain.add_block(vec![bytes.to_vec()], 8);
+                vec![json!("ready"), json!({ "finalized": hash })]
+            }
+        });
+
+        let signer = subxt_signer::sr25519::dev::alice();
+        let policy = SubmissionPolicy::new().mortal_for(Some(4));
+        let mut submission = api.tx().submit_with_policy(remark(), signer, policy);
+
+        let mut dropped = Vec::new();
+        let mut finalized = false;
+        while let Some(status) = submission.next().await {
+            match status.unwrap() {
+                SubmissionStatus::Status(TxStatus::Dropped { message }) => dropped.push(message),
+                SubmissionStatus::Status(TxStatus::InFinalizedBlock(_)) => finalized = true,
+                _ => {}
+            }
+        }
+
+        assert!(finalized);
+        assert_eq!(
+            dropped,
+            vec!["The transaction was not included before its mortality ended at block 4"]
+        );
+        assert_eq!(chain.lock().unwrap().submitted.len(), 2);
+    }
+
+    #[test]
+    fn tip_always_increases() {
+        let policy = SubmissionPolicy::new().tip_increase_percent(50);
+        assert_eq!(policy.next_tip(0), 1);
+        assert_eq!(policy.next_tip(1), 2);
+        assert_eq!(policy.next_tip(100), 150);
+        assert_eq!(policy.next_tip(u128::MAX), u128::MAX);
+
+        let policy = SubmissionPolicy::new().tip_increase_percent(0);
+        assert_eq!(policy.next_tip(100), 101);
+    }
+}
@@ -5,13 +5,22 @@
 use crate::{
     backend::{BackendExt, BlockRef, TransactionStatus},
     client::{OfflineClientT, OnlineClientT},
-    config::{Config, ExtrinsicParams, Header, RefineParams, RefineParamsData},
+    config::{
+        Config, DefaultExtrinsicParams, ExtrinsicParams, Header, ManagedNonce, RefineParams,
+        RefineParamsData,
+    },
     error::{BlockError, Error},
-    tx::{Payload, Signer as SignerT, TxProgress},
+    tx::{
+        dry_run,
+        fee_details::{RuntimeDispatchInfo, RuntimeFeeDetails},
+        DryRunEffects, FeeDetails, Payload, Signer as SignerT, SubmissionPolicy, TxProgress,
+        TxSubmission,
+    },
     utils::PhantomDataSendSync,
 };
 use codec::{Compact, Decode, Encode};
 use derive_where::derive_where;
+use subxt_metadata::{ExtrinsicProof, MerkleizeError, MerkleizedMetadata, MetadataDigestInfo};
 
 /// A client for working with transactions.
 #[derive_where(Clone; Client)]
@@ -59,6 +68,7 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
             .map(|tx| SubmittableExtrinsic {
                 client: self.client.clone(),
                 inner: tx,
+                managed_nonce: None,
             })
             .map_err(Into::into)
     }
@@ -75,12 +85,21 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
     where
         Call: Payload,
     {
-        subxt_core::tx::create_partial_signed(call, &self.client.client_state(), params)
-            .map(|tx| PartialExtrinsic {
+        // If the params were refined using a nonce manager, the nonce is given back if we fail.
+        let managed_nonce = params.managed_nonce();
+        match subxt_core::tx::create_partial_signed(call, &self.client.client_state(), params) {
+            Ok(tx) => Ok(PartialExtrinsic {
                 client: self.client.clone(),
                 inner: tx,
-            })
-            .map_err(Into::into)
+                managed_nonce,
+            }),
+            Err(e) => {
+                if let Some(managed_nonce) = managed_nonce {
+                    managed_nonce.release();
+                }
+                Err(e.into())
+            }
+        }
     }
 
     /// Creates a signed extrinsic without submitting it.
@@ -101,6 +120,7 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
             .map(|tx| SubmittableExtrinsic {
                 client: self.client.clone(),
                 inner: tx,
+                managed_nonce: None,
             })
             .map_err(Into::into)
     }
@@ -111,21 +131,34 @@ where
     T: Config,
     C: OnlineClientT<T>,
 {
-    /// Fetch the latest block header and account nonce from the backend and use them to refine [`ExtrinsicParams::Params`].
+    /// Fetch the latest finalized block header and the account nonce from the backend and use
+    /// them to refine [`ExtrinsicParams::Params`]. If the params hand out a nonce from a
+    /// [`crate::tx::NonceManager`], the account nonce comes from the latest best block so that
+    /// transactions from the account in blocks which are not yet finalized are taken into
+    /// account. Otherwise, it comes from the latest finalized block.
     async fn refine_params(
         &self,
         account_id: &T::AccountId,
         params: &mut <T::ExtrinsicParams as ExtrinsicParams<T>>::Params,
     ) -> Result<(), Error> {
-        let block_ref = self.client.backend().latest_finalized_block_ref().await?;
-        let block_header = self
-            .client
-            .backend()
-            .block_header(block_ref.hash())
-            .await?
-            .ok_or_else(|| Error::Block(BlockError::not_found(block_ref.hash())))?;
-        let account_nonce =
-            crate::blocks::get_account_nonce(&self.client, account_id, block_ref.hash()).await?;
+        let block_header = async {
+            let block_ref = self.client.backend().latest_finalized_block_ref().await?;
+            self.client
+                .backend()
+                .block_header(block_ref.hash())
+                .await?
+                .ok_or_else(|| Error::Block(BlockError::not_found(block_ref.hash())))
+        };
+        let uses_nonce_manager = params.uses_nonce_manager();
+        let account_nonce = async {
+            if uses_nonce_manager {
+                self.best_block_account_nonce(account_id).await
+            } else {
+                self.account_nonce(account_id).await
+            }
+        };
+        let (block_header, account_nonce) =
+            futures::future::try_join(block_header, account_nonce).await?;
 
         params.refine(&RefineParamsData::new(
             account_nonce,
@@ -141,6 +174,15 @@ where
         crate::blocks::get_account_nonce(&self.client, account_id, block_ref.hash()).await
     }
 
+    /// Get the account nonce for a given account ID at the latest best block.
+    pub(crate) async fn best_block_account_nonce(
+        &self,
+        account_id: &T::AccountId,
+    ) -> Result<u64, Error> {
+        let block_ref = self.client.backend().latest_best_block_ref().await?;
+        crate::blocks::get_account_nonce(&self.client, account_id, block_ref.hash()).await
+    }
+
     /// Creates a partial signed extrinsic, without submitting it.
     pub async fn create_partial_signed<Call>(
         &self,
@@ -158,6 +200,12 @@ where
     }
 
     /// Creates a signed extrinsic, without submitting it.
+    ///
+    /// To create many transactions from the same account without waiting for each to make
+    /// it into a block, give the params a [`crate::tx::NonceManager`] (for instance via
+    /// [`crate::config::DefaultExtrinsicParamsBuilder::nonce_manager()`]). If the extrinsic
+    /// then can't be submitted, or [`TxProgress`] reports that it's invalid, has been dropped
+    /// or has errored, its nonce is given back to the [`crate::tx::NonceManager`].
     pub async fn create_signed<Call, Signer>(
         &self,
         call: &Call,
@@ -269,10 +317,43 @@ where
     }
 }
 
+impl<T, C> TxClient<T, C>
+where
+    T: Config<ExtrinsicParams = DefaultExtrinsicParams<T>>,
+    T::AccountId: Send + Sync,
+    C: OnlineClientT<T> + Send + Sync + 'static,
+{
+    /// Sign and submit a transaction, and then re-sign and resubmit it according to the given
+    /// [`SubmissionPolicy`] if it's dropped or found to be invalid (for instance because its
+    /// mortal era expired) before it makes it into a block.
+    ///
+    /// Each submission uses the same nonce, so at most one of them can make it into a block,
+    /// and is given a higher tip and a mortality starting from the latest finalized block.
+    /// Before resubmitting, we check whether an earlier submission made it into a block after
+    /// all, and we give up if the nonce was used by some other transaction or if the new
+    /// submission would never be valid (for instance because the account can't pay for it).
+    ///
+    /// Returns a [`TxSubmission`], which can be used to track the progress of each submission
+    /// until the transaction is finalized or we give up.
+    pub fn submit_with_policy<Call, Signer>(
+        &self,
+        call: Call,
+        signer: Signer,
+        policy: SubmissionPolicy,
+    ) -> TxSubmission<T, C>
+    where
+        Call: Payload + Send + Sync + 'static,
+        Signer: SignerT<T> + Send + Sync + 'static,
+    {
+        TxSubmission::new(self.client.clone(), call, signer, policy)
+    }
+}
+
 /// This payload contains the information needed to produce an extrinsic.
 pub struct PartialExtrinsic<T: Config, C> {
     client: C,
     inner: subxt_core::tx::PartialTransaction<T>,
+    managed_nonce: Option<ManagedNonce>,
 }
 
 impl<T, C> PartialExtrinsic<T, C>
@@ -286,12 +367,34 @@ where
         self.inner.signer_payload()
     }
 
+    /// The extrinsic version (4 or 5) that this extrinsic will be built as.
+    pub fn version(&self) -> u8 {
+        self.inner.version()
+    }
+
     /// Return the bytes representing the call data for this partially constructed
     /// extrinsic.
     pub fn call_data(&self) -> &[u8] {
         self.inner.call_data()
     }
 
+    /// Return a proof containing the type information needed to decode this extrinsic, as
+    /// described in RFC-0078. Offline and hardware signers can use this, alongside the
+    /// [`PartialExtrinsic::signer_payload()`], to display the extrinsic being signed and
+    /// to check it against the metadata hash.
+    ///
+    /// The [`MerkleizedMetadata`] can be obtained via
+    /// [`subxt_metadata::Metadata::merkleize()`], and should be reused across
+    /// transactions where possible. The `info` should match the details used to compute
+    /// the metadata hash, and is included in the proof.
+    pub fn metadata_proof(
+        &self,
+        metadata: &MerkleizedMetadata,
+        info: MetadataDigestInfo,
+    ) -> Result<ExtrinsicProof, MerkleizeError> {
+        self.inner.metadata_proof(metadata, info)
+    }
+
     /// Convert this [`PartialExtrinsic`] into a [`SubmittableExtrinsic`], ready to submit.
     /// The provided `signer` is responsible for providing the "from" address for the transaction,
     /// as well as providing a signature to attach to it.
@@ -302,31 +405,151 @@ where
         SubmittableExtrinsic {
             client: self.client.clone(),
             inner: self.inner.sign(signer),
+            managed_nonce: self.managed_nonce.clone(),
         }
     }
 
     /// Convert this [`PartialExtrinsic`] into a [`SubmittableExtrinsic`], ready to submit.
-    /// An address, and something representing a signature that can be SCALE encoded, are both
+    /// An account ID, and something representing a signature that can be SCALE encoded, are both
     /// needed in order to construct it. If you have a `Signer` to hand, you can use
     /// [`PartialExtrinsic::sign()`] instead.
-    pub fn sign_with_address_and_signature(
+    pub fn sign_with_account_and_signature(
         &self,
-        address: &T::Address,
+        account_id: &T::AccountId,
         signature: &T::Signature,
     ) -> SubmittableExtrinsic<T, C> {
         SubmittableExtrinsic {
             client: self.client.clone(),
             inner: self
                 .inner
-                .sign_with_address_and_signature(address, signature),
+                .sign_with_account_and_signature(account_id, signature),
+            managed_nonce: self.managed_nonce.clone(),
         }
     }
+
+    /// Convert this [`PartialExtrinsic`] into a [`SubmittableExtrinsic`], ready to submit.
+    /// An address, and something representing a signature that can be SCALE encoded, are both
+    /// needed in order to construct it. If you have a `Signer` to hand, you can use
+    /// [`PartialExtrinsic::sign()`] instead.
+    ///
+    /// Only v4 extrinsics can be signed with an address, and so this returns
+    /// [`Error::SignedWithAddress`] if [`PartialExtrinsic::version()`] is 5. Prefer
+    /// [`PartialExtrinsic::sign_with_account_and_signature()`], which builds whichever
+    /// version the runtime supports.
+    pub fn sign_with_address_and_signature(
+        &self,
+        address: &T::Address,
+        signature: &T::Signature,
+    ) -> Result<SubmittableExtrinsic<T, C>, Error> {
+        Ok(SubmittableExtrinsic {
+            client: self.client.clone(),
+            inner: self
+                .inner
+                .sign_with_address_and_signature(address, signature)?,
+            managed_nonce: self.managed_nonce.clone(),
+        })
+    }
+}
+
+impl<T, C> PartialExtrinsic<T, C>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+{
+    /// Return the weight, class and a breakdown of the fees that this extrinsic is expected
+    /// to have once it's signed by the given account, so that they can be shown before asking
+    /// for a signature. See [`SubmittableExtrinsic::fee_details()`] for more details.
+    ///
+    /// The fees depend on the length of the extrinsic, and so a signature of the right size
+    /// must be given here. It isn't checked, and so a placeholder (for instance, one which is
+    /// all zeroes) can be used.
+    pub async fn fee_details(
+        &self,
+        account_id: &T::AccountId,
+        signature: &T::Signature,
+    ) -> Result<FeeDetails, Error> {
+        self.sign_with_account_and_signature(account_id, signature)
+            .fee_details()
+            .await
+    }
+
+    /// Return the weight, class and a breakdown of the fees that this extrinsic is expected
+    /// to have at the given block once it's signed by the given account. See
+    /// [`PartialExtrinsic::fee_details()`] for more details.
+    pub async fn fee_details_at(
+        &self,
+        account_id: &T::AccountId,
+        signature: &T::Signature,
+        at: impl Into<BlockRef<T::Hash>>,
+    ) -> Result<FeeDetails, Error> {
+        self.sign_with_account_and_signature(account_id, signature)
+            .fee_details_at(at)
+            .await
+    }
+
+    /// Return the weight, class and a breakdown of the fees that this extrinsic is expected
+    /// to have once it's signed by the given account. Unlike [`PartialExtrinsic::fee_details()`],
+    /// no signature is needed; a placeholder of the right size is used instead.
+    ///
+    /// The size of the placeholder comes from the signature type in the metadata. If this is
+    /// an enum like `MultiSignature`, the first variant is used, which (as an ed25519
+    /// signature) is the same size as an sr25519 signature, but a byte smaller than an ecdsa
+    /// one. Use [`PartialExtrinsic::fee_details()`] with a real signature if this matters.
+    pub async fn estimate_fee_details(
+        &self,
+        account_id: &T::AccountId,
+    ) -> Result<FeeDetails, Error> {
+        self.with_placeholder_signature(account_id)?
+            .fee_details()
+            .await
+    }
+
+    /// Return the weight, class and a breakdown of the fees that this extrinsic is expected
+    /// to have at the given block once it's signed by the given account. See
+    /// [`PartialExtrinsic::estimate_fee_details()`] for more details.
+    pub async fn estimate_fee_details_at(
+        &self,
+        account_id: &T::AccountId,
+        at: impl Into<BlockRef<T::Hash>>,
+    ) -> Result<FeeDetails, Error> {
+        self.with_placeholder_signature(account_id)?
+            .fee_details_at(at)
+            .await
+    }
+
+    // Sign this extrinsic with an all zero signature of the size given by the metadata. This
+    // won't be valid, but is the same length as a valid extrinsic, which is what fees depend on.
+    pub(crate) fn with_placeholder_signature(
+        &self,
+        account_id: &T::AccountId,
+    ) -> Result<SubmittableExtrinsic<T, C>, Error> {
+        let metadata = self.client.metadata();
+        // Zeroes decode as the first variant of any enum and as empty sequences, so decoding
+        // them tells us how many bytes a signature of that shape takes up.
+        let zeroes = [0u8; 1024];
+        let mut cursor = &zeroes[..];
+        scale_value::scale::decode_as_type(
+            &mut cursor,
+            metadata.extrinsic().signature_ty(),
+            metadata.types(),
+        )?;
+        let signature = &zeroes[..zeroes.len() - cursor.len()];
+
+        Ok(SubmittableExtrinsic {
+            client: self.client.clone(),
+            inner: self
+                .inner
+                .sign_with_account_and_encoded_signature(account_id, signature),
+            managed_nonce: None,
+        })
+    }
 }
 
 /// This represents an extrinsic that has been signed and is ready to submit.
 pub struct SubmittableExtrinsic<T, C> {
     client: C,
     inner: subxt_core::tx::Transaction<T>,
+    managed_nonce: Option<ManagedNonce>,
 }
 
 impl<T, C> SubmittableExtrinsic<T, C>
@@ -345,6 +568,7 @@ where
         Self {
             client,
             inner: subxt_core::tx::Transaction::from_bytes(tx_bytes),
+            managed_nonce: None,
         }
     }
 
@@ -363,6 +587,14 @@ where
     pub fn into_encoded(self) -> Vec<u8> {
         self.inner.into_encoded()
     }
+
+    // Give back the nonce that this was created with, if it came from a nonce manager,
+    // because the extrinsic won't make it into a block.
+    fn release_nonce(&self) {
+        if let Some(managed_nonce) = &self.managed_nonce {
+            managed_nonce.release();
+        }
+    }
 }
 
 impl<T, C> SubmittableExtrinsic<T, C>
@@ -383,9 +615,14 @@ where
             .client
             .backend()
             .submit_transaction(self.encoded())
-            .await?;
+            .await
+            .map_err(|e| {
+                self.release_nonce();
+                e
+            })?;
 
-        Ok(TxProgress::new(sub, self.client.clone(), ext_hash))
+        Ok(TxProgress::new(sub, self.client.clone(), ext_hash)
+            .with_managed_nonce(self.managed_nonce.clone()))
     }
 
     /// Submits the extrinsic to the chain for block inclusion.
@@ -394,6 +631,13 @@ where
     /// submission and whether it's eventually successful or not. This call does not guarantee
     /// success, and is just sending the transaction to the chain.
     pub async fn submit(&self) -> Result<T::Hash, Error> {
+        self.submit_inner().await.map_err(|e| {
+            self.release_nonce();
+            e
+        })
+    }
+
+    async fn submit_inner(&self) -> Result<T::Hash, Error> {
         let ext_hash = self.hash();
         let mut sub = self
             .client
@@ -464,6 +708,28 @@ where
         ValidationResult::try_from_bytes(res)
     }
 
+    /// Dry run the transaction using the `DryRunApi` runtime API, returning whether the call
+    /// would dispatch successfully, along with the events and XCMs that it would emit.
+    ///
+    /// Unlike [`crate::backend::legacy::LegacyRpcMethods::dry_run()`], this works with any
+    /// backend, but it requires the runtime to expose the `DryRunApi`. If the runtime only has
+    /// `DryRunApi_dry_run_call`, the call is dry run on behalf of the signer, and so the
+    /// transaction must be unsigned or signed with an address which is an account ID (like
+    /// `MultiAddress::Id`).
+    pub async fn dry_run(&self) -> Result<DryRunEffects<T>, Error> {
+        let latest_block_ref = self.client.backend().latest_finalized_block_ref().await?;
+        self.dry_run_at(latest_block_ref).await
+    }
+
+    /// Dry run the transaction at the given block using the `DryRunApi` runtime API. See
+    /// [`SubmittableExtrinsic::dry_run()`] for more details.
+    pub async fn dry_run_at(
+        &self,
+        at: impl Into<BlockRef<T::Hash>>,
+    ) -> Result<DryRunEffects<T>, Error> {
+        dry_run::dry_run(&self.client, self.encoded(), at.into().hash()).await
+    }
+
     /// This returns an estimate for what the extrinsic is expected to cost to execute, less any tips.
     /// The actual amount paid can vary from block to block based on node traffic and other factors.
     pub async fn partial_fee_estimate(&self) -> Result<u128, Error> {
@@ -484,6 +750,48 @@ where
             .await?;
         Ok(partial_fee)
     }
+
+    /// Return the weight, class and a breakdown of the fees that the extrinsic is expected to
+    /// pay, using the `TransactionPaymentApi` runtime API at the latest finalized block. The
+    /// actual amount paid can vary from block to block based on node traffic and other factors.
+    pub async fn fee_details(&self) -> Result<FeeDetails, Error> {
+        let latest_block_ref = self.client.backend().latest_finalized_block_ref().await?;
+        self.fee_details_at(latest_block_ref).await
+    }
+
+    /// Return the weight, class and a breakdown of the fees that the extrinsic is expected to
+    /// pay, using the `TransactionPaymentApi` runtime API at the given block.
+    pub async fn fee_details_at(
+        &self,
+        at: impl Into<BlockRef<T::Hash>>,
+    ) -> Result<FeeDetails, Error> {
+        let block_hash = at.into().hash();
+        let mut params = self.encoded().to_vec();
+        (self.encoded().len() as u32).encode_to(&mut params);
+
+        let backend = self.client.backend();
+        let (info, details): (RuntimeDispatchInfo, RuntimeFeeDetails) = futures::future::try_join(
+            backend.call_decoding(
+                "TransactionPaymentApi_query_info",
+                Some(&params),
+                block_hash,
+            ),
+            backend.call_decoding(
+                "TransactionPaymentApi_query_fee_details",
+                Some(&params),
+                block_hash,
+            ),
+        )
+        .await?;
+
+        Ok(FeeDetails {
+            weight: info.weight,
+            class: info.class,
+            partial_fee: info.partial_fee,
+            inclusion_fee: details.inclusion_fee,
+            tip: details.tip,
+        })
+    }
 }
 
 impl ValidationResult {
@@ -494,7 +802,7 @@ impl ValidationResult {
         // We copy some of the inner types and put the three states (valid, invalid, unknown) into one enum,
         // because from our perspective, the call was successful regardless.
         if bytes.get(0) == Some(&0) {
-            // ok: valid. Decode but, for now we discard most of the information
+            // ok: valid.
             let res = TransactionValid::decode(&mut &bytes[1..])?;
             Ok(ValidationResult::Valid(res))
         } else if bytes.get(0) == Some(&1) && bytes.get(1) == Some(&0) {
@@ -621,6 +929,10 @@ pub enum TransactionInvalid {
     MandatoryValidation,
     /// The sending address is disabled or known to be invalid.
     BadSigner,
+    /// Some of the implicit data needed to validate the transaction could not be determined.
+    IndeterminateImplicit,
+    /// The transaction extension did not authorize any origin.
+    UnknownOrigin,
 }
 
 #[cfg(test)]
@@ -746,4 +1058,143 @@ mod test {
             assert_eq!(decoded, validation_result);
         }
     }
+
+    #[test]
+    fn transaction_validity_decoding_keeps_tags() {
+        use sp_runtime::transaction_validity as sp;
+
+        let valid: sp::TransactionValidity = Ok(sp::ValidTransaction {
+            priority: 10,
+            requires: vec![vec![1, 2], vec![3]],
+            provides: vec![vec![4, 5, 6]],
+            longevity: 64,
+            propagate: false,
+        });
+
+        let decoded = ValidationResult::try_from_bytes(valid.encode()).expect("should decode OK");
+        assert_eq!(
+            decoded,
+            ValidationResult::Valid(TransactionValid {
+                priority: 10,
+                requires: vec![vec![1, 2], vec![3]],
+                provides: vec![vec![4, 5, 6]],
+                longevity: 64,
+                propagate: false,
+            })
+        );
+    }
+
+    #[test]
+    fn transaction_validity_decoding_newer_invalid_variants() {
+        // Newer runtimes can return these, which our version of `sp_runtime` doesn't know about.
+        let decoded = ValidationResult::try_from_bytes(vec![1, 0, 11]).expect("should decode OK");
+        assert_eq!(
+            decoded,
+            ValidationResult::Invalid(TransactionInvalid::IndeterminateImplicit)
+        );
+
+        let decoded = ValidationResult::try_from_bytes(vec![1, 0, 12]).expect("should decode OK");
+        assert_eq!(
+            decoded,
+            ValidationResult::Invalid(TransactionInvalid::UnknownOrigin)
+        );
+    }
+
+    mod nonce_manager {
+        use super::*;
+        use crate::backend::mock::{test_utils, MockBackend};
+        use crate::config::DefaultExtrinsicParamsBuilder;
+        use crate::tx::NonceManager;
+        use crate::{OnlineClient, SubstrateConfig};
+
+        type Extrinsic = SubmittableExtrinsic<SubstrateConfig, OnlineClient<SubstrateConfig>>;
+
+        async fn create_signed(
+            api: &OnlineClient<SubstrateConfig>,
+            nonce_manager: &NonceManager,
+        ) -> Extrinsic {
+            let call = crate::dynamic::tx("System", "remark", vec![Vec::<u8>::new()]);
+            let signer = subxt_signer::sr25519::dev::alice();
+            let params = DefaultExtrinsicParamsBuilder::new()
+                .nonce_manager(nonce_manager.clone())
+                .build();
+            api.tx()
+                .create_signed(&call, &signer, params)
+                .await
+                .unwrap()
+        }
+
+        fn nonce_of(api: &OnlineClient<SubstrateConfig>, tx: &Extrinsic) -> u64 {
+            let extrinsics = subxt_core::blocks::decode_from::<SubstrateConfig>(
+                vec![tx.encoded().to_vec()],
+                api.metadata(),
+            )
+            .unwrap();
+            let extrinsic = extrinsics.iter().next().unwrap().unwrap();
+            extrinsic.signed_extensions().unwrap().nonce().unwrap()
+        }
+
+        #[tokio::test]
+        async fn nonces_are_handed_out_when_refining_params() {
+            let backend = MockBackend::test_builder()
+                .call("AccountNonceApi_account_nonce", 5u32.encode())
+                .build();
+            let api = test_utils::client(backend).await;
+            let nonce_manager = NonceManager::new();
+
+            for expected in 5..8 {
+                let tx = create_signed(&api, &nonce_manager).await;
+                assert_eq!(nonce_of(&api, &tx), expected);
+            }
+        }
+
+        #[tokio::test]
+        async fn nonces_are_released_if_transactions_fail() {
+            let failures = || {
+                vec![
+                    Ok(TransactionStatus::Invalid {
+                        message: "invalid".into(),
+                    }),
+                    Ok(TransactionStatus::Dropped {
+                        message: "dropped".into(),
+                    }),
+                    Ok(TransactionStatus::Error {
+                        message: "error".into(),
+                    }),
+                    Err(Error::Other("subscription failed".into())),
+                ]
+            };
+            let mut builder =
+                MockBackend::test_builder().call("AccountNonceApi_account_nonce", 5u32.encode());
+            for failure in failures() {
+                builder = builder
+                    .transaction_status_results(vec![Ok(TransactionStatus::Validated), failure]);
+            }
+            let api = test_utils::client(builder.build()).await;
+            let nonce_manager = NonceManager::new();
+
+            let first = create_signed(&api, &nonce_manager).await;
+            assert_eq!(nonce_of(&api, &first), 5);
+
+            // Each transaction fails, and so its nonce is handed out again.
+            for failure in failures() {
+                let tx = create_signed(&api, &nonce_manager).await;
+                assert_eq!(
+                    nonce_of(&api, &tx),
+                    6,
+                    "nonce not released after {failure:?}"
+                );
+                let mut progress = tx.submit_and_watch().await.unwrap();
+                while progress.next().await.is_some() {}
+            }
+            let next = create_signed(&api, &nonce_manager).await;
+            assert_eq!(nonce_of(&api, &next), 6);
+
+            // There are no statuses left to hand back, so submitting fails, and because the
+            // first nonce leaves a gap, we go back to the account nonce from the chain.
+            assert!(first.submit().await.is_err());
+            let last = create_signed(&api, &nonce_manager).await;
+            assert_eq!(nonce_of(&api, &last), 5);
+        }
+    }
 }
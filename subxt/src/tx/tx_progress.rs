@@ -9,6 +9,7 @@ use std::task::Poll;
 use crate::{
     backend::{BlockRef, StreamOfResults, TransactionStatus as BackendTxStatus},
     client::OnlineClientT,
+    config::ManagedNonce,
     error::{DispatchError, Error, RpcError, TransactionError},
     events::EventsClient,
     utils::strip_compact_prefix,
@@ -22,6 +23,7 @@ pub struct TxProgress<T: Config, C> {
     sub: Option<StreamOfResults<BackendTxStatus<T::Hash>>>,
     ext_hash: T::Hash,
     client: C,
+    managed_nonce: Option<ManagedNonce>,
 }
 
 impl<T: Config, C> std::fmt::Debug for TxProgress<T, C> {
@@ -50,13 +52,27 @@ impl<T: Config, C> TxProgress<T, C> {
             sub: Some(sub),
             client,
             ext_hash,
+            managed_nonce: None,
         }
     }
 
+    /// Give the nonce back to the [`crate::tx::NonceManager`] that it came from if the
+    /// transaction is found to be invalid, is dropped or errors, or if the subscription fails.
+    pub(crate) fn with_managed_nonce(mut self, managed_nonce: Option<ManagedNonce>) -> Self {
+        self.managed_nonce = managed_nonce;
+        self
+    }
+
     /// Return the hash of the extrinsic.
     pub fn extrinsic_hash(&self) -> T::Hash {
         self.ext_hash
     }
+
+    fn release_nonce(&mut self) {
+        if let Some(managed_nonce) = self.managed_nonce.take() {
+            managed_nonce.release();
+        }
+    }
 }
 
 impl<T, C> TxProgress<T, C>
@@ -132,7 +148,13 @@ impl<T: Config, C: Clone> Stream for TxProgress<T, C> {
             None => return Poll::Ready(None),
         };
 
-        sub.poll_next_unpin(cx).map_ok(|status| {
+        let res = sub.poll_next_unpin(cx);
+        // If the subscription fails, we won't find out what happened to the transaction.
+        if let Poll::Ready(Some(Err(_))) = &res {
+            self.release_nonce();
+        }
+
+        res.map_ok(|status| {
             match status {
                 BackendTxStatus::Validated => TxStatus::Validated,
                 BackendTxStatus::Broadcasted { num_peers } => TxStatus::Broadcasted { num_peers },
@@ -151,14 +173,17 @@ impl<T: Config, C: Clone> Stream for TxProgress<T, C> {
                 }
                 BackendTxStatus::Error { message } => {
                     self.sub = None;
+                    self.release_nonce();
                     TxStatus::Error { message }
                 }
                 BackendTxStatus::Invalid { message } => {
                     self.sub = None;
+                    self.release_nonce();
                     TxStatus::Invalid { message }
                 }
                 BackendTxStatus::Dropped { message } => {
                     self.sub = None;
+                    self.release_nonce();
                     TxStatus::Dropped { message }
                 }
             }
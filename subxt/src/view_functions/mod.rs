@@ -0,0 +1,14 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Types associated with executing pallet view functions.
+
+mod view_function_types;
+mod view_functions_client;
+
+pub use subxt_core::view_functions::payload::{
+    dynamic, DefaultPayload, DynamicPayload, Payload, StaticPayload,
+};
+pub use view_function_types::ViewFunctionsApi;
+pub use view_functions_client::ViewFunctionsClient;
@@ -0,0 +1,156 @@
+// Copyright 2019-2024 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::Payload;
+use crate::{backend::BlockRef, client::OnlineClientT, error::Error, Config};
+use derive_where::derive_where;
+use std::{future::Future, marker::PhantomData};
+
+/// Execute pallet view functions.
+#[derive_where(Clone; Client)]
+pub struct ViewFunctionsApi<T: Config, Client> {
+    client: Client,
+    block_ref: BlockRef<T::Hash>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Config, Client> ViewFunctionsApi<T, Client> {
+    /// Create a new [`ViewFunctionsApi`]
+    pub(crate) fn new(client: Client, block_ref: BlockRef<T::Hash>) -> Self {
+        Self {
+            client,
+            block_ref,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, Client> ViewFunctionsApi<T, Client>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    /// Run the validation logic against some view function payload you'd like to use. Returns `Ok(())`
+    /// if the payload is valid (or if it's not possible to check since the payload has no validation hash).
+    /// Return an error if the payload was not valid or something went wrong trying to validate it (ie
+    /// the view function in question does not exist at all)
+    pub fn validate<Call: Payload>(&self, payload: &Call) -> Result<(), Error> {
+        subxt_core::view_functions::validate(payload, &self.client.metadata()).map_err(Into::into)
+    }
+
+    /// Execute a view function.
+    pub fn call<Call: Payload>(
+        &self,
+        payload: Call,
+    ) -> impl Future<Output = Result<Call::ReturnType, Error>> {
+        let client = self.client.clone();
+        let block_hash = self.block_ref.hash();
+        // Ensure that the returned future doesn't have a lifetime tied to api.view_functions(),
+        // which is a temporary thing we'll be throwing away quickly:
+        async move {
+            let metadata = client.metadata();
+
+            // Validate the view function payload hash against the compile hash from codegen.
+            subxt_core::view_functions::validate(&payload, &metadata)?;
+
+            // Encode the view function ID and arguments for the runtime call.
+            let call_name = subxt_core::view_functions::call_name();
+            let call_args = subxt_core::view_functions::call_args(&payload, &metadata)?;
+
+            // Make the call.
+            let bytes = client
+                .backend()
+                .call(call_name, Some(call_args.as_slice()), block_hash)
+                .await?;
+
+            // Decode the response.
+            let value =
+                subxt_core::view_functions::decode_value(&mut &*bytes, &payload, &metadata)?;
+            Ok(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::mock::{test_utils, MockBackend};
+    use crate::client::RuntimeVersion;
+    use crate::{Metadata, OnlineClient, SubstrateConfig};
+    use codec::Encode;
+    use frame_metadata::v16;
+    use scale_info::{TypeDef, TypeDefPrimitive};
+    use scale_value::Value;
+    use std::sync::Arc;
+    use subxt_core::error::ViewFunctionError;
+
+    /// The small Polkadot metadata, with a `System.nonce_plus(n: u32) -> u64` view function.
+    fn metadata() -> Metadata {
+        let metadata = test_utils::metadata("polkadot_metadata_small");
+        let primitive_id = |primitive: TypeDefPrimitive| {
+            metadata
+                .types()
+                .types
+                .iter()
+                .find(|ty| ty.ty.type_def == TypeDef::Primitive(primitive.clone()))
+                .unwrap()
+                .id
+        };
+        let view_function = v16::PalletViewFunctionMetadata {
+            name: "nonce_plus".into(),
+            id: [7; 32],
+            inputs: vec![v16::PalletViewFunctionParamMetadata {
+                name: "n".into(),
+                ty: primitive_id(TypeDefPrimitive::U32).into(),
+            }],
+            output: primitive_id(TypeDefPrimitive::U64).into(),
+            docs: vec![],
+            deprecation_info: v16::DeprecationStatus::NotDeprecated,
+        };
+
+        let mut v16: v16::RuntimeMetadataV16 = (*metadata).clone().into();
+        v16.pallets
+            .iter_mut()
+            .find(|p| p.name == "System")
+            .unwrap()
+            .view_functions
+            .push(view_function);
+        subxt_metadata::Metadata::try_from(v16).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn view_functions_are_executed_via_the_runtime_api() {
+        let backend = MockBackend::test_builder()
+            .call(
+                "RuntimeViewFunction_execute_view_function",
+                Ok::<_, ViewFunctionError>(6u64.encode()).encode(),
+            )
+            .build();
+        // The mock backend hands back V15 metadata, which has no view functions, so
+        // the V16 metadata is given to the client directly.
+        let api = OnlineClient::<SubstrateConfig>::from_backend_with(
+            Default::default(),
+            RuntimeVersion {
+                spec_version: 0,
+                transaction_version: 0,
+            },
+            metadata(),
+            Arc::new(backend),
+        )
+        .unwrap();
+
+        let payload = crate::dynamic::view_function("System", "nonce_plus", vec![Value::u128(5)]);
+        let value = api
+            .view_functions()
+            .at_latest()
+            .await
+            .unwrap()
+            .call(payload)
+            .await
+            .unwrap()
+            .to_value()
+            .unwrap();
+
+        assert_eq!(value.as_u128(), Some(6));
+    }
+}
@@ -1,4 +1,4 @@
-#[allow(dead_code, unused_imports, non_camel_case_types)]
+#[allow(dead_code, unused_imports, non_camel_case_types, deprecated)]
 #[allow(clippy::all)]
 #[allow(rustdoc::broken_intra_doc_links)]
 pub mod api {
@@ -116,6 +116,9 @@ pub mod api {
     pub fn apis() -> runtime_apis::RuntimeApi {
         runtime_apis::RuntimeApi
     }
+    pub fn view_functions() -> ViewFunctionsApi {
+        ViewFunctionsApi
+    }
     pub mod runtime_apis {
         use super::root_mod;
         use super::runtime_types;
@@ -4995,6 +4998,8 @@ pub mod api {
             sudo::calls::TransactionApi
         }
     }
+    pub struct ViewFunctionsApi;
+    impl ViewFunctionsApi {}
     #[doc = r" check whether the metadata provided is aligned with this statically generated code."]
     pub fn is_codegen_valid_for(metadata: &::subxt::ext::subxt_core::Metadata) -> bool {
         let runtime_metadata_hash = metadata